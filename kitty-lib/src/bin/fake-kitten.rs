@@ -0,0 +1,6 @@
+//! The `kitten` binary used with `kitty_lib::testing::FakeKitty`
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    std::process::exit(kitty_lib::testing::run_kitten_shim(&args));
+}
@@ -1,10 +1,32 @@
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::TabId;
+
 #[derive(Debug, Clone)]
 pub struct KittenCloseTabCommand {
-    pub tab_id: u32,
+    pub tab_id: TabId,
 }
 
 impl KittenCloseTabCommand {
-    pub fn new(tab_id: u32) -> Self {
-        Self { tab_id }
+    pub fn new(tab_id: impl Into<TabId>) -> Self {
+        Self {
+            tab_id: tab_id.into(),
+        }
+    }
+}
+
+impl KittenCommand for KittenCloseTabCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "close-tab"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        Ok(vec![format!("--match=id:{}", self.tab_id)])
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
     }
 }
@@ -0,0 +1,41 @@
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::WindowId;
+
+#[derive(Debug, Clone)]
+pub struct KittenCloseWindowCommand {
+    /// Window match expression; every matching window is closed
+    pub match_pattern: String,
+}
+
+impl KittenCloseWindowCommand {
+    pub fn new(window_id: impl Into<WindowId>) -> Self {
+        let window_id: WindowId = window_id.into();
+        Self {
+            match_pattern: format!("id:{}", window_id),
+        }
+    }
+
+    /// Close every window matching the given pattern
+    pub fn with_match(pattern: impl Into<String>) -> Self {
+        Self {
+            match_pattern: pattern.into(),
+        }
+    }
+}
+
+impl KittenCommand for KittenCloseWindowCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "close-window"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        Ok(vec![format!("--match={}", self.match_pattern)])
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
+    }
+}
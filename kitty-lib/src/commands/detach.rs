@@ -0,0 +1,124 @@
+use crate::commands::KittenCommand;
+use crate::error::{KittyError, KittyResult};
+use crate::types::{TabId, WindowId};
+
+/// Where detached windows or tabs are moved to (`--target-tab`)
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum DetachTarget {
+    /// A new OS window
+    #[default]
+    NewOsWindow,
+    /// A new tab in the current OS window (windows only)
+    NewTab,
+    /// Next to the tab matching the expression: into that tab for windows, into its OS window
+    /// for tabs
+    Tab(String),
+}
+
+/// Move the windows matching `match_pattern` to another tab or OS window
+#[derive(Debug, Clone)]
+pub struct KittenDetachWindowCommand {
+    pub match_pattern: String,
+    pub target: DetachTarget,
+}
+
+impl KittenDetachWindowCommand {
+    pub fn new(window_id: impl Into<WindowId>) -> Self {
+        let window_id: WindowId = window_id.into();
+        Self {
+            match_pattern: format!("id:{}", window_id),
+            target: DetachTarget::NewOsWindow,
+        }
+    }
+
+    pub fn with_match(pattern: impl Into<String>) -> Self {
+        Self {
+            match_pattern: pattern.into(),
+            target: DetachTarget::NewOsWindow,
+        }
+    }
+
+    pub fn target(mut self, target: DetachTarget) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+/// Move the tabs matching `match_tab` to another OS window
+#[derive(Debug, Clone)]
+pub struct KittenDetachTabCommand {
+    pub match_tab: String,
+    pub target: DetachTarget,
+}
+
+impl KittenDetachTabCommand {
+    pub fn new(tab_id: impl Into<TabId>) -> Self {
+        let tab_id: TabId = tab_id.into();
+        Self {
+            match_tab: format!("id:{}", tab_id),
+            target: DetachTarget::NewOsWindow,
+        }
+    }
+
+    pub fn with_match(pattern: impl Into<String>) -> Self {
+        Self {
+            match_tab: pattern.into(),
+            target: DetachTarget::NewOsWindow,
+        }
+    }
+
+    pub fn target(mut self, target: DetachTarget) -> Self {
+        self.target = target;
+        self
+    }
+}
+
+/// kitty answers with nothing, not even where the window went, so there is nothing to return
+impl KittenCommand for KittenDetachWindowCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "detach-window"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        let mut args = vec![format!("--match={}", self.match_pattern)];
+        match &self.target {
+            DetachTarget::NewOsWindow => {}
+            DetachTarget::NewTab => args.push("--target-tab=new".to_string()),
+            DetachTarget::Tab(match_tab) => args.push(format!("--target-tab={}", match_tab)),
+        }
+        Ok(args)
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
+    }
+}
+
+/// kitty answers with nothing, not even where the tab went, so there is nothing to return
+impl KittenCommand for KittenDetachTabCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "detach-tab"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        let mut args = vec![format!("--match={}", self.match_tab)];
+        match &self.target {
+            DetachTarget::NewOsWindow => {}
+            DetachTarget::NewTab => {
+                return Err(KittyError::InvalidCommand(
+                    "Tabs can only be detached to an OS window".to_string(),
+                ));
+            }
+            DetachTarget::Tab(match_tab) => args.push(format!("--target-tab={}", match_tab)),
+        }
+        Ok(args)
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
+    }
+}
@@ -1,10 +1,36 @@
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::TabId;
+
 #[derive(Debug, Clone)]
 pub struct KittenFocusTabCommand {
-    pub tab_id: u32,
+    pub tab_id: TabId,
 }
 
 impl KittenFocusTabCommand {
-    pub fn new(tab_id: u32) -> Self {
-        Self { tab_id }
+    pub fn new(tab_id: impl Into<TabId>) -> Self {
+        Self {
+            tab_id: tab_id.into(),
+        }
+    }
+}
+
+impl KittenCommand for KittenFocusTabCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "focus-tab"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        Ok(vec![format!("--match=id:{}", self.tab_id)])
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
     }
 }
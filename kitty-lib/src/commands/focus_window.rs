@@ -0,0 +1,45 @@
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::WindowId;
+
+#[derive(Debug, Clone)]
+pub struct KittenFocusWindowCommand {
+    /// Window match expression; kitty focuses the first matching window
+    pub match_pattern: String,
+}
+
+impl KittenFocusWindowCommand {
+    pub fn new(window_id: impl Into<WindowId>) -> Self {
+        let window_id: WindowId = window_id.into();
+        Self {
+            match_pattern: format!("id:{}", window_id),
+        }
+    }
+
+    /// Focus the first window matching the given pattern
+    pub fn with_match(pattern: impl Into<String>) -> Self {
+        Self {
+            match_pattern: pattern.into(),
+        }
+    }
+}
+
+impl KittenCommand for KittenFocusWindowCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "focus-window"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        Ok(vec![format!("--match={}", self.match_pattern)])
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
+    }
+}
@@ -0,0 +1,131 @@
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::WindowId;
+use std::fmt;
+use std::str::FromStr;
+
+/// Which text `kitten @ get-text` returns (`--extent`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextExtent {
+    /// The text currently on screen
+    #[default]
+    Screen,
+    /// The screen plus the whole scrollback
+    All,
+    /// The current selection
+    Selection,
+    /// The output of the last command run in the shell (needs shell integration)
+    LastCmdOutput,
+}
+
+impl fmt::Display for TextExtent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TextExtent::Screen => "screen",
+            TextExtent::All => "all",
+            TextExtent::Selection => "selection",
+            TextExtent::LastCmdOutput => "last_cmd_output",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for TextExtent {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "screen" => Ok(TextExtent::Screen),
+            "all" => Ok(TextExtent::All),
+            "selection" => Ok(TextExtent::Selection),
+            "last_cmd_output" | "last-cmd-output" => Ok(TextExtent::LastCmdOutput),
+            other => Err(format!(
+                "unknown extent '{}' (expected screen, all, selection or last_cmd_output)",
+                other
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KittenGetTextCommand {
+    /// Window match expression (`--match`); kitty reads the first matching window
+    pub match_pattern: Option<String>,
+    pub extent: TextExtent,
+    /// Keep the ANSI formatting escape codes in the text (`--ansi`)
+    pub ansi: bool,
+}
+
+impl Default for KittenGetTextCommand {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl KittenGetTextCommand {
+    /// Create a get-text command that reads the screen of the active window
+    pub fn new() -> Self {
+        Self {
+            match_pattern: None,
+            extent: TextExtent::Screen,
+            ansi: false,
+        }
+    }
+
+    /// Read the first window matching the given pattern
+    pub fn with_match(mut self, pattern: impl Into<String>) -> Self {
+        self.match_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Read a specific window by ID
+    pub fn for_window_id(mut self, window_id: impl Into<WindowId>) -> Self {
+        let window_id: WindowId = window_id.into();
+        self.match_pattern = Some(format!("id:{}", window_id));
+        self
+    }
+
+    pub fn extent(mut self, extent: TextExtent) -> Self {
+        self.extent = extent;
+        self
+    }
+
+    pub fn ansi(mut self) -> Self {
+        self.ansi = true;
+        self
+    }
+}
+
+impl KittenCommand for KittenGetTextCommand {
+    type Response = String;
+
+    fn name(&self) -> &'static str {
+        "get-text"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        let mut args = vec![format!("--extent={}", self.extent)];
+
+        if let Some(match_pattern) = &self.match_pattern {
+            args.push(format!("--match={}", match_pattern));
+        }
+
+        if self.ansi {
+            args.push("--ansi".to_string());
+        }
+
+        Ok(args)
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn parse_response(&self, stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(String::from_utf8_lossy(stdout).into_owned())
+    }
+}
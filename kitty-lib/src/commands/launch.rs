@@ -1,10 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt;
+
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::KittyLaunchResponse;
+use crate::utils::SESSION_USER_VAR;
+
+/// Where `kitten @ launch` opens the new window (`--type`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LaunchType {
+    #[default]
+    Tab,
+    Window,
+    OsWindow,
+    Overlay,
+}
+
+impl fmt::Display for LaunchType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LaunchType::Tab => "tab",
+            LaunchType::Window => "window",
+            LaunchType::OsWindow => "os-window",
+            LaunchType::Overlay => "overlay",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Placement of a new window relative to the active one (`--location`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WindowLocation {
+    Default,
+    First,
+    Last,
+    After,
+    Before,
+    Neighbor,
+    Split,
+    Hsplit,
+    Vsplit,
+}
+
+impl fmt::Display for WindowLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            WindowLocation::Default => "default",
+            WindowLocation::First => "first",
+            WindowLocation::Last => "last",
+            WindowLocation::After => "after",
+            WindowLocation::Before => "before",
+            WindowLocation::Neighbor => "neighbor",
+            WindowLocation::Split => "split",
+            WindowLocation::Hsplit => "hsplit",
+            WindowLocation::Vsplit => "vsplit",
+        };
+        write!(f, "{}", name)
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct KittenLaunchCommand {
-    pub launch_type: String,
+    pub launch_type: LaunchType,
     pub cwd: Option<String>,
-    pub env: Option<String>,
+    pub env: Vec<(String, String)>,
     pub tab_title: Option<String>,
     pub inherit_session: bool,
+    /// Program and arguments to run instead of the default shell
+    pub args: Vec<String>,
+    /// Title of the new window (`--title`)
+    pub window_title: Option<String>,
+    /// Keep the window open after the program exits (`--hold`)
+    pub hold: bool,
+    /// Copy the environment of the launching process (`--copy-env`)
+    pub copy_env: bool,
+    /// Leave focus on the currently active window (`--keep-focus`)
+    pub keep_focus: bool,
+    pub location: Option<WindowLocation>,
+    /// Match expression for the window to place the new window next to (`--next-to`)
+    pub next_to: Option<String>,
+    /// Share of the available space given to the new window, as a percentage (`--bias`)
+    pub bias: Option<f64>,
+    /// User variables attached to the new window (`--var`)
+    pub vars: Vec<(String, String)>,
 }
 
 impl Default for KittenLaunchCommand {
@@ -16,16 +97,25 @@ impl Default for KittenLaunchCommand {
 impl KittenLaunchCommand {
     pub fn new() -> Self {
         Self {
-            launch_type: "tab".to_string(),
+            launch_type: LaunchType::Tab,
             cwd: None,
-            env: None,
+            env: Vec::new(),
             tab_title: None,
             inherit_session: false,
+            args: Vec::new(),
+            window_title: None,
+            hold: false,
+            copy_env: false,
+            keep_focus: false,
+            location: None,
+            next_to: None,
+            bias: None,
+            vars: Vec::new(),
         }
     }
 
-    pub fn launch_type(mut self, launch_type: &str) -> Self {
-        self.launch_type = launch_type.to_string();
+    pub fn launch_type(mut self, launch_type: LaunchType) -> Self {
+        self.launch_type = launch_type;
         self
     }
 
@@ -34,19 +124,187 @@ impl KittenLaunchCommand {
         self
     }
 
+    /// Add an environment variable; later entries for the same name take precedence
     pub fn env(mut self, env_var: &str, value: &str) -> Self {
-        self.env = Some(format!("{}={}", env_var, value));
+        self.env.push((env_var.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add several environment variables, preserving their order
+    pub fn envs<I, K, V>(mut self, vars: I) -> Self
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        self.env
+            .extend(vars.into_iter().map(|(k, v)| (k.into(), v.into())));
         self
     }
 
+    /// The effective value of an environment variable, honouring later overrides
+    pub fn env_value(&self, env_var: &str) -> Option<&str> {
+        self.env
+            .iter()
+            .rev()
+            .find(|(key, _)| key == env_var)
+            .map(|(_, value)| value.as_str())
+    }
+
     pub fn tab_title(mut self, title: &str) -> Self {
         self.tab_title = Some(title.to_string());
         self
     }
 
+    /// Run the given program and arguments instead of the default shell
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.args = args.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn window_title(mut self, title: &str) -> Self {
+        self.window_title = Some(title.to_string());
+        self
+    }
+
+    pub fn hold(mut self) -> Self {
+        self.hold = true;
+        self
+    }
+
+    pub fn copy_env(mut self) -> Self {
+        self.copy_env = true;
+        self
+    }
+
+    pub fn keep_focus(mut self) -> Self {
+        self.keep_focus = true;
+        self
+    }
+
+    pub fn location(mut self, location: WindowLocation) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    /// Place the new window next to the window matched by `match_expression`, e.g. `id:3`
+    pub fn next_to(mut self, match_expression: &str) -> Self {
+        self.next_to = Some(match_expression.to_string());
+        self
+    }
+
+    pub fn bias(mut self, bias: f64) -> Self {
+        self.bias = Some(bias);
+        self
+    }
+
+    /// Attach a user variable to the new window; later entries for the same name take precedence
+    pub fn var(mut self, name: &str, value: &str) -> Self {
+        self.vars.push((name.to_string(), value.to_string()));
+        self
+    }
+
     /// Enable automatic session inheritance from the current environment
     pub fn inherit_current_session(mut self) -> Self {
         self.inherit_session = true;
         self
     }
 }
+
+impl KittenCommand for KittenLaunchCommand {
+    type Response = KittyLaunchResponse;
+
+    fn name(&self) -> &'static str {
+        "launch"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        let mut args = vec![format!("--type={}", self.launch_type)];
+
+        if let Some(cwd) = &self.cwd {
+            args.push(format!("--cwd={}", cwd));
+        }
+
+        let mut effective_env = self.env.clone();
+        let mut effective_vars = self.vars.clone();
+
+        // Handle session inheritance
+        if self.inherit_session
+            && self.env_value("KITTY_SESSION_PROJECT").is_none()
+            && let Ok(session_project) = env::var("KITTY_SESSION_PROJECT")
+            && !session_project.is_empty()
+        {
+            if !effective_vars
+                .iter()
+                .any(|(name, _)| name == SESSION_USER_VAR)
+            {
+                effective_vars.push((SESSION_USER_VAR.to_string(), session_project.clone()));
+            }
+            effective_env.push(("KITTY_SESSION_PROJECT".to_string(), session_project));
+        }
+
+        args.extend(
+            effective_env
+                .iter()
+                .map(|(key, value)| format!("--env={}={}", key, value)),
+        );
+
+        if let Some(tab_title) = &self.tab_title {
+            args.push(format!("--tab-title={}", tab_title));
+        }
+
+        if let Some(window_title) = &self.window_title {
+            args.push(format!("--title={}", window_title));
+        }
+
+        if self.hold {
+            args.push("--hold".to_string());
+        }
+
+        if self.copy_env {
+            args.push("--copy-env".to_string());
+        }
+
+        if self.keep_focus {
+            args.push("--keep-focus".to_string());
+        }
+
+        if let Some(location) = self.location {
+            args.push(format!("--location={}", location));
+        }
+
+        if let Some(next_to) = &self.next_to {
+            args.push(format!("--next-to={}", next_to));
+        }
+
+        if let Some(bias) = self.bias {
+            args.push(format!("--bias={}", bias));
+        }
+
+        args.extend(
+            effective_vars
+                .iter()
+                .map(|(name, value)| format!("--var={}={}", name, value)),
+        );
+
+        // Program args that start with `-` must not be taken for launch options
+        if !self.args.is_empty() {
+            args.push("--".to_string());
+            args.extend(self.args.iter().cloned());
+        }
+
+        Ok(args)
+    }
+
+    /// `kitten @ launch` prints the id of the new window
+    fn parse_response(&self, stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(KittyLaunchResponse {
+            tab_id: None,
+            window_id: String::from_utf8_lossy(stdout).trim().parse().ok(),
+        })
+    }
+}
@@ -0,0 +1,143 @@
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::TabId;
+use std::fmt;
+use std::str::FromStr;
+
+/// One of kitty's window layouts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KittyLayout {
+    Fat,
+    Grid,
+    Horizontal,
+    Splits,
+    Stack,
+    Tall,
+    Vertical,
+}
+
+impl fmt::Display for KittyLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            KittyLayout::Fat => "fat",
+            KittyLayout::Grid => "grid",
+            KittyLayout::Horizontal => "horizontal",
+            KittyLayout::Splits => "splits",
+            KittyLayout::Stack => "stack",
+            KittyLayout::Tall => "tall",
+            KittyLayout::Vertical => "vertical",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for KittyLayout {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Layout options such as `tall:bias=70` only select the base layout here
+        match s.split(':').next().unwrap_or_default() {
+            "fat" => Ok(KittyLayout::Fat),
+            "grid" => Ok(KittyLayout::Grid),
+            "horizontal" => Ok(KittyLayout::Horizontal),
+            "splits" => Ok(KittyLayout::Splits),
+            "stack" => Ok(KittyLayout::Stack),
+            "tall" => Ok(KittyLayout::Tall),
+            "vertical" => Ok(KittyLayout::Vertical),
+            other => Err(format!("unknown layout '{}'", other)),
+        }
+    }
+}
+
+/// Switch the layout of the active tab, or of the tabs matching `match_tab`
+#[derive(Debug, Clone)]
+pub struct KittenGotoLayoutCommand {
+    pub layout: KittyLayout,
+    pub match_tab: Option<String>,
+}
+
+impl KittenGotoLayoutCommand {
+    pub fn new(layout: KittyLayout) -> Self {
+        Self {
+            layout,
+            match_tab: None,
+        }
+    }
+
+    pub fn with_tab_match(mut self, pattern: impl Into<String>) -> Self {
+        self.match_tab = Some(pattern.into());
+        self
+    }
+
+    pub fn for_tab_id(mut self, tab_id: impl Into<TabId>) -> Self {
+        let tab_id: TabId = tab_id.into();
+        self.match_tab = Some(format!("id:{}", tab_id));
+        self
+    }
+}
+
+/// Switch the active tab, or the tabs matching `match_tab`, back to their previous layout
+#[derive(Debug, Clone, Default)]
+pub struct KittenLastUsedLayoutCommand {
+    pub match_tab: Option<String>,
+}
+
+impl KittenLastUsedLayoutCommand {
+    pub fn new() -> Self {
+        Self { match_tab: None }
+    }
+
+    pub fn with_tab_match(mut self, pattern: impl Into<String>) -> Self {
+        self.match_tab = Some(pattern.into());
+        self
+    }
+
+    pub fn for_tab_id(mut self, tab_id: impl Into<TabId>) -> Self {
+        let tab_id: TabId = tab_id.into();
+        self.match_tab = Some(format!("id:{}", tab_id));
+        self
+    }
+}
+
+/// kitty answers with nothing; an unknown layout fails the call instead
+impl KittenCommand for KittenGotoLayoutCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "goto-layout"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        let mut args = Vec::new();
+        if let Some(match_tab) = &self.match_tab {
+            args.push(format!("--match={}", match_tab));
+        }
+        args.push(self.layout.to_string());
+        Ok(args)
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
+    }
+}
+
+/// kitty answers with nothing, not even which layout it switched to
+impl KittenCommand for KittenLastUsedLayoutCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "last-used-layout"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        Ok(self
+            .match_tab
+            .iter()
+            .map(|match_tab| format!("--match={}", match_tab))
+            .collect())
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
+    }
+}
@@ -1,3 +1,6 @@
+use crate::commands::KittenCommand;
+use crate::error::{KittyError, KittyResult};
+use crate::types::KittyLsResponse;
 #[derive(Debug, Clone)]
 pub struct KittenLsCommand {
     pub match_arg: Option<String>,
@@ -29,9 +32,51 @@ impl KittenLsCommand {
         self
     }
 
+    pub fn match_tab_var(mut self, name: &str, value: &str) -> Self {
+        self.match_arg = Some(format!("var:{}={}", name, value));
+        self.use_tab_match = true;
+        self
+    }
+
     pub fn match_tab_title(mut self, title_pattern: &str) -> Self {
         self.match_arg = Some(format!("title:{}", title_pattern));
         self.use_tab_match = true;
         self
     }
 }
+
+impl KittenCommand for KittenLsCommand {
+    type Response = KittyLsResponse;
+
+    fn name(&self) -> &'static str {
+        "ls"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        let mut args = Vec::new();
+        if let Some(match_arg) = &self.match_arg {
+            let match_flag = if self.use_tab_match {
+                "--match-tab"
+            } else {
+                "--match"
+            };
+            args.push(format!("{}={}", match_flag, match_arg));
+        }
+        Ok(args)
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+
+    fn is_read_only(&self) -> bool {
+        true
+    }
+
+    fn parse_response(&self, stdout: &[u8]) -> KittyResult<Self::Response> {
+        serde_json::from_slice(stdout).map_err(|e| KittyError::InvalidResponse {
+            command: "ls".to_string(),
+            message: e.to_string(),
+        })
+    }
+}
@@ -1,6 +1,55 @@
 pub mod close_tab;
+pub mod close_window;
+pub mod detach;
 pub mod focus_tab;
+pub mod focus_window;
+pub mod get_text;
 pub mod launch;
+pub mod layout;
 pub mod ls;
 pub mod navigate_tab;
+pub mod resize_window;
+pub mod send_text;
 pub mod set_tab_title;
+pub mod set_user_vars;
+pub mod set_window_title;
+
+use crate::error::KittyResult;
+
+/// A remote control command run as `kitten @ <name> <args>`
+///
+/// Every built-in command implements this, and executors run any implementation through
+/// [`CommandExecutor::execute`](crate::CommandExecutor::execute). Implement it for commands
+/// kitty-lib doesn't wrap, such as `set-colors` or `scroll-window`.
+pub trait KittenCommand {
+    /// What the command returns on success
+    type Response;
+
+    /// The command name after `kitten @`, e.g. `focus-tab`
+    fn name(&self) -> &'static str;
+
+    /// Arguments following the command name
+    ///
+    /// Fails with [`KittyError::InvalidCommand`](crate::KittyError::InvalidCommand) when the
+    /// command can't be expressed.
+    fn args(&self) -> KittyResult<Vec<String>>;
+
+    /// Text to feed kitten on stdin, for commands passing `--stdin`
+    fn stdin(&self) -> Option<&str> {
+        None
+    }
+
+    /// Whether running the command twice is harmless, so it may be retried after transient
+    /// failures
+    fn is_idempotent(&self) -> bool {
+        false
+    }
+
+    /// Whether the command only reads kitty's state, so a dry run may still send it
+    fn is_read_only(&self) -> bool {
+        false
+    }
+
+    /// Build the response from what kitten printed on stdout
+    fn parse_response(&self, stdout: &[u8]) -> KittyResult<Self::Response>;
+}
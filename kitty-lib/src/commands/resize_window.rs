@@ -0,0 +1,95 @@
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::WindowId;
+use std::fmt;
+
+/// The direction `kitten @ resize-window` grows or shrinks a window in (`--axis`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ResizeAxis {
+    #[default]
+    Horizontal,
+    Vertical,
+    /// Reset every window in the tab to its default size
+    Reset,
+}
+
+impl fmt::Display for ResizeAxis {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ResizeAxis::Horizontal => "horizontal",
+            ResizeAxis::Vertical => "vertical",
+            ResizeAxis::Reset => "reset",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct KittenResizeWindowCommand {
+    pub match_pattern: Option<String>,
+    /// Number of cells to grow by; negative values shrink the window
+    pub increment: i32,
+    pub axis: ResizeAxis,
+}
+
+impl KittenResizeWindowCommand {
+    /// Resize the active window by `increment` cells along `axis`
+    pub fn new(increment: i32, axis: ResizeAxis) -> Self {
+        Self {
+            match_pattern: None,
+            increment,
+            axis,
+        }
+    }
+
+    /// Reset the sizes of all windows in the tab
+    pub fn reset() -> Self {
+        Self::new(0, ResizeAxis::Reset)
+    }
+
+    /// Resize the first window matching the given pattern
+    pub fn with_match(mut self, pattern: impl Into<String>) -> Self {
+        self.match_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Resize a specific window by ID
+    pub fn for_window_id(mut self, window_id: impl Into<WindowId>) -> Self {
+        let window_id: WindowId = window_id.into();
+        self.match_pattern = Some(format!("id:{}", window_id));
+        self
+    }
+}
+
+/// Whether kitty resized the window
+///
+/// kitty answers `False` or an error such as `Could not resize` when the layout has no room to
+/// change the window, e.g. for the only window of a tab, without failing the call.
+impl KittenCommand for KittenResizeWindowCommand {
+    type Response = bool;
+
+    fn name(&self) -> &'static str {
+        "resize-window"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        let mut args = vec![
+            format!("--increment={}", self.increment),
+            format!("--axis={}", self.axis),
+        ];
+        if let Some(match_pattern) = &self.match_pattern {
+            args.push(format!("--match={}", match_pattern));
+        }
+        Ok(args)
+    }
+
+    fn parse_response(&self, stdout: &[u8]) -> KittyResult<Self::Response> {
+        // Resetting always succeeds, though kitty answers `False` for it
+        if self.axis == ResizeAxis::Reset {
+            return Ok(true);
+        }
+        let response = String::from_utf8_lossy(stdout);
+        let response = response.trim();
+        Ok(response.is_empty() || response.eq_ignore_ascii_case("true"))
+    }
+}
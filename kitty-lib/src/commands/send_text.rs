@@ -0,0 +1,112 @@
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::WindowId;
+
+#[derive(Debug, Clone)]
+pub struct KittenSendTextCommand {
+    pub text: String,
+    /// Window match expression (`--match`), e.g. `id:3` or `env:KITTY_SESSION_PROJECT=api`
+    pub match_pattern: Option<String>,
+    /// Tab match expression (`--match-tab`), sending to every window of the matched tabs
+    pub match_tab: Option<String>,
+    /// Pass the text on stdin instead of as an argument, so it is sent without escape processing
+    pub from_stdin: bool,
+    /// Wrap the text in bracketed paste codes so shells do not run it line by line
+    pub bracketed_paste: bool,
+    /// Skip the active window even when it matches (`--exclude-active`)
+    pub exclude_active: bool,
+}
+
+impl KittenSendTextCommand {
+    /// Create a send-text command that sends to the active window
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            match_pattern: None,
+            match_tab: None,
+            from_stdin: false,
+            bracketed_paste: false,
+            exclude_active: false,
+        }
+    }
+
+    /// Send to the windows matching the given pattern
+    pub fn with_match(mut self, pattern: impl Into<String>) -> Self {
+        self.match_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Send to a specific window by ID
+    pub fn for_window_id(mut self, window_id: impl Into<WindowId>) -> Self {
+        let window_id: WindowId = window_id.into();
+        self.match_pattern = Some(format!("id:{}", window_id));
+        self
+    }
+
+    /// Send to every window of the tabs matching the given pattern
+    pub fn with_tab_match(mut self, pattern: impl Into<String>) -> Self {
+        self.match_tab = Some(pattern.into());
+        self
+    }
+
+    pub fn from_stdin(mut self) -> Self {
+        self.from_stdin = true;
+        self
+    }
+
+    pub fn bracketed_paste(mut self) -> Self {
+        self.bracketed_paste = true;
+        self
+    }
+
+    pub fn exclude_active(mut self) -> Self {
+        self.exclude_active = true;
+        self
+    }
+}
+
+impl KittenCommand for KittenSendTextCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "send-text"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        let mut args = Vec::new();
+
+        if let Some(match_pattern) = &self.match_pattern {
+            args.push(format!("--match={}", match_pattern));
+        }
+
+        if let Some(match_tab) = &self.match_tab {
+            args.push(format!("--match-tab={}", match_tab));
+        }
+
+        if self.exclude_active {
+            args.push("--exclude-active".to_string());
+        }
+
+        if self.bracketed_paste {
+            args.push("--bracketed-paste=enable".to_string());
+        }
+
+        if self.from_stdin {
+            args.push("--stdin".to_string());
+        } else {
+            // Text starting with `-` would otherwise be taken for an option
+            args.push("--".to_string());
+            args.push(self.text.clone());
+        }
+
+        Ok(args)
+    }
+
+    fn stdin(&self) -> Option<&str> {
+        self.from_stdin.then_some(self.text.as_str())
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
+    }
+}
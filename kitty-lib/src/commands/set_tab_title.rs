@@ -1,3 +1,7 @@
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::TabId;
+
 #[derive(Debug, Clone)]
 pub struct KittenSetTabTitleCommand {
     pub title: String,
@@ -20,8 +24,30 @@ impl KittenSetTabTitleCommand {
     }
 
     /// Set the title for a specific tab by ID
-    pub fn for_tab_id(mut self, tab_id: u32) -> Self {
+    pub fn for_tab_id(mut self, tab_id: impl Into<TabId>) -> Self {
+        let tab_id: TabId = tab_id.into();
         self.match_pattern = Some(format!("id:{}", tab_id));
         self
     }
 }
+
+impl KittenCommand for KittenSetTabTitleCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "set-tab-title"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        let mut args = Vec::new();
+        if let Some(match_pattern) = &self.match_pattern {
+            args.push(format!("--match={}", match_pattern));
+        }
+        args.push(self.title.clone());
+        Ok(args)
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
+    }
+}
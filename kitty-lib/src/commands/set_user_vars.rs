@@ -0,0 +1,64 @@
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::WindowId;
+
+#[derive(Debug, Clone, Default)]
+pub struct KittenSetUserVarsCommand {
+    /// Variables to set; an empty value unsets the variable
+    pub vars: Vec<(String, String)>,
+    pub match_pattern: Option<String>,
+}
+
+impl KittenSetUserVarsCommand {
+    /// Create a new set user vars command that applies to the active window
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set a user variable; later entries for the same name take precedence
+    pub fn var(mut self, name: &str, value: &str) -> Self {
+        self.vars.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Set the variables on the windows matching the given pattern
+    pub fn with_match(mut self, pattern: impl Into<String>) -> Self {
+        self.match_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Set the variables on a specific window by ID
+    pub fn for_window_id(mut self, window_id: impl Into<WindowId>) -> Self {
+        let window_id: WindowId = window_id.into();
+        self.match_pattern = Some(format!("id:{}", window_id));
+        self
+    }
+}
+
+impl KittenCommand for KittenSetUserVarsCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "set-user-vars"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        let mut args = Vec::new();
+        if let Some(match_pattern) = &self.match_pattern {
+            args.push(format!("--match={}", match_pattern));
+        }
+        // A bare name without `=` removes the variable
+        args.extend(self.vars.iter().map(|(name, value)| {
+            if value.is_empty() {
+                name.clone()
+            } else {
+                format!("{}={}", name, value)
+            }
+        }));
+        Ok(args)
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
+    }
+}
@@ -0,0 +1,53 @@
+use crate::commands::KittenCommand;
+use crate::error::KittyResult;
+use crate::types::WindowId;
+
+#[derive(Debug, Clone)]
+pub struct KittenSetWindowTitleCommand {
+    pub title: String,
+    pub match_pattern: Option<String>,
+}
+
+impl KittenSetWindowTitleCommand {
+    /// Create a new set window title command that will set the title of the active window
+    pub fn new(title: impl Into<String>) -> Self {
+        Self {
+            title: title.into(),
+            match_pattern: None,
+        }
+    }
+
+    /// Set the title of the windows matching the given pattern
+    pub fn with_match(mut self, pattern: impl Into<String>) -> Self {
+        self.match_pattern = Some(pattern.into());
+        self
+    }
+
+    /// Set the title of a specific window by ID
+    pub fn for_window_id(mut self, window_id: impl Into<WindowId>) -> Self {
+        let window_id: WindowId = window_id.into();
+        self.match_pattern = Some(format!("id:{}", window_id));
+        self
+    }
+}
+
+impl KittenCommand for KittenSetWindowTitleCommand {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "set-window-title"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        let mut args = Vec::new();
+        if let Some(match_pattern) = &self.match_pattern {
+            args.push(format!("--match={}", match_pattern));
+        }
+        args.push(self.title.clone());
+        Ok(args)
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<Self::Response> {
+        Ok(())
+    }
+}
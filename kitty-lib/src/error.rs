@@ -0,0 +1,357 @@
+use std::fmt;
+use std::io;
+use std::process::Output;
+use std::time::Duration;
+
+use crate::version::{Capability, KittyVersion};
+
+/// Result type returned by every [`CommandExecutor`](crate::CommandExecutor) method
+pub type KittyResult<T> = std::result::Result<T, KittyError>;
+
+/// Why a kitty remote control command failed
+///
+/// Failures reported by `kitten` itself carry its exit code and stderr so callers can show what
+/// kitty said.
+#[derive(Debug)]
+pub enum KittyError {
+    /// The `kitten` binary is not installed or not on `PATH`
+    KittenNotFound,
+    /// kitten could not connect to the remote control socket
+    SocketUnreachable {
+        socket: String,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+    /// No kitty accepted connections on any of the sockets discovery tried
+    NoSocket { tried: Vec<String> },
+    /// kitty refused the command because remote control is not enabled
+    RemoteControlDisabled {
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+    /// kitty rejected the remote control password, or it doesn't allow this command
+    PermissionDenied {
+        command: String,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+    /// No tab or window matched the command's match expression
+    NoMatch {
+        command: String,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+    /// kitten exited with a non-zero status for another reason
+    CommandFailed {
+        command: String,
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+    /// kitten didn't finish in time and was killed
+    Timeout { command: String, timeout: Duration },
+    /// kitten succeeded but its output could not be parsed
+    InvalidResponse { command: String, message: String },
+    /// The command can't be expressed as a kitten invocation
+    InvalidCommand(String),
+    /// The running kitty is older than the first release with a feature the command needs
+    Unsupported {
+        capability: Capability,
+        found: KittyVersion,
+    },
+    /// The version of the kitty behind a socket can't be found out from this machine
+    VersionUnknown { socket: String, reason: String },
+    /// Running kitten failed for a reason other than it being missing
+    Io(io::Error),
+    /// A [`ReplayExecutor`](crate::ReplayExecutor) got a call its recording doesn't have next
+    UnexpectedCall {
+        call: String,
+        expected: Option<String>,
+    },
+}
+
+impl KittyError {
+    /// Classify a failed kitten invocation by its stderr
+    pub fn from_output(command: &str, socket: &str, output: &Output) -> Self {
+        Self::from_stderr(
+            command,
+            socket,
+            output.status.code(),
+            &String::from_utf8_lossy(&output.stderr),
+        )
+    }
+
+    /// Classify a failed kitten invocation from its exit code and stderr
+    pub fn from_stderr(command: &str, socket: &str, exit_code: Option<i32>, stderr: &str) -> Self {
+        let stderr = stderr.trim().to_string();
+        let lowercase = stderr.to_lowercase();
+
+        if lowercase.contains("remote control is disabled")
+            || lowercase.contains("allow_remote_control")
+        {
+            Self::RemoteControlDisabled { exit_code, stderr }
+        } else if lowercase.contains("permission denied")
+            || lowercase.contains("not allowed")
+            || lowercase.contains("disallowed")
+            || lowercase.contains("access denied")
+        {
+            Self::PermissionDenied {
+                command: command.to_string(),
+                exit_code,
+                stderr,
+            }
+        } else if lowercase.contains("failed to connect")
+            || lowercase.contains("connection refused")
+            || lowercase.contains("connect: no such file")
+        {
+            Self::SocketUnreachable {
+                socket: socket.to_string(),
+                exit_code,
+                stderr,
+            }
+        } else if lowercase.contains("no matching") {
+            Self::NoMatch {
+                command: command.to_string(),
+                exit_code,
+                stderr,
+            }
+        } else {
+            Self::CommandFailed {
+                command: command.to_string(),
+                exit_code,
+                stderr,
+            }
+        }
+    }
+
+    /// Map an error from spawning kitten, recognising a missing binary
+    pub fn spawn(error: io::Error) -> Self {
+        if error.kind() == io::ErrorKind::NotFound {
+            Self::KittenNotFound
+        } else {
+            Self::Io(error)
+        }
+    }
+
+    /// A [`KittyError::NoMatch`] worded the way kitten reports it
+    pub fn no_match(command: &str, kind: &str, expression: &str) -> Self {
+        Self::NoMatch {
+            command: command.to_string(),
+            exit_code: Some(1),
+            stderr: format!("Error: No matching {} for expression: {}", kind, expression),
+        }
+    }
+
+    /// Whether trying the same command again might succeed
+    ///
+    /// A [`KittyError::Timeout`] is not: a kitty that hung once is likely to hang again, and
+    /// waiting out the timeout on every attempt would freeze the caller instead of failing fast.
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::SocketUnreachable { .. } | Self::Io(_))
+    }
+
+    /// The exit code kitten returned, when the failure came from kitten
+    pub fn exit_code(&self) -> Option<i32> {
+        match self {
+            Self::SocketUnreachable { exit_code, .. }
+            | Self::RemoteControlDisabled { exit_code, .. }
+            | Self::PermissionDenied { exit_code, .. }
+            | Self::NoMatch { exit_code, .. }
+            | Self::CommandFailed { exit_code, .. } => *exit_code,
+            _ => None,
+        }
+    }
+
+    /// What kitten printed on stderr, when the failure came from kitten
+    pub fn stderr(&self) -> Option<&str> {
+        match self {
+            Self::SocketUnreachable { stderr, .. }
+            | Self::RemoteControlDisabled { stderr, .. }
+            | Self::PermissionDenied { stderr, .. }
+            | Self::NoMatch { stderr, .. }
+            | Self::CommandFailed { stderr, .. } => Some(stderr),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for KittyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KittenNotFound => write!(
+                f,
+                "kitten was not found on PATH; install kitty or add its bin directory to PATH"
+            ),
+            Self::SocketUnreachable { socket, stderr, .. } => write!(
+                f,
+                "Could not reach kitty at {} ({}); is kitty running with listen_on set?",
+                socket, stderr
+            ),
+            Self::NoSocket { tried } if tried.is_empty() => write!(
+                f,
+                "No kitty socket found; set listen_on in kitty.conf or pass the socket explicitly"
+            ),
+            Self::NoSocket { tried } => write!(
+                f,
+                "No running kitty found on {}; set listen_on in kitty.conf or pass the socket explicitly",
+                tried.join(", ")
+            ),
+            Self::RemoteControlDisabled { .. } => write!(
+                f,
+                "Remote control is disabled in kitty; add `allow_remote_control yes` to kitty.conf"
+            ),
+            Self::PermissionDenied {
+                command, stderr, ..
+            } => write!(
+                f,
+                "kitty denied {} ({}); check remote_control_password in kitty.conf and the password passed to kitten",
+                command, stderr
+            ),
+            Self::NoMatch {
+                command, stderr, ..
+            } => write!(f, "kitten {}: {}", command, stderr),
+            Self::CommandFailed {
+                command,
+                exit_code,
+                stderr,
+            } => {
+                write!(f, "kitten {} failed", command)?;
+                if let Some(code) = exit_code {
+                    write!(f, " with exit code {}", code)?;
+                }
+                if !stderr.is_empty() {
+                    write!(f, ": {}", stderr)?;
+                }
+                Ok(())
+            }
+            Self::Timeout { command, timeout } => write!(
+                f,
+                "kitten {} timed out after {:.1}s; kitty may be hung or its socket stale",
+                command,
+                timeout.as_secs_f64()
+            ),
+            Self::InvalidResponse { command, message } => {
+                write!(f, "Could not parse kitten {} output: {}", command, message)
+            }
+            Self::InvalidCommand(message) => write!(f, "{}", message),
+            Self::Unsupported { capability, found } => write!(
+                f,
+                "kitty {} lacks {}; upgrade kitty to {} or newer",
+                found,
+                capability,
+                capability.since()
+            ),
+            Self::VersionUnknown { socket, reason } => write!(
+                f,
+                "Could not find out the version of the kitty at {}: {}",
+                socket, reason
+            ),
+            Self::Io(error) => write!(f, "Failed to run kitten: {}", error),
+            Self::UnexpectedCall {
+                call,
+                expected: Some(expected),
+            } => write!(
+                f,
+                "Unexpected kitten call `{}`; the recording expects `{}` next",
+                call, expected
+            ),
+            Self::UnexpectedCall {
+                call,
+                expected: None,
+            } => write!(
+                f,
+                "Unexpected kitten call `{}`; the recording has no calls left",
+                call
+            ),
+        }
+    }
+}
+
+impl std::error::Error for KittyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for KittyError {
+    fn from(error: io::Error) -> Self {
+        Self::Io(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    fn failed(stderr: &str) -> Output {
+        Output {
+            status: ExitStatus::from_raw(1 << 8),
+            stdout: Vec::new(),
+            stderr: stderr.as_bytes().to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_from_output_classifies_stderr() {
+        let socket = "unix:/tmp/kitty";
+
+        let error = KittyError::from_output(
+            "ls",
+            socket,
+            &failed("Error: dial unix /tmp/kitty: connect: no such file or directory\n"),
+        );
+        assert!(
+            matches!(error, KittyError::SocketUnreachable { ref socket, .. } if socket == "unix:/tmp/kitty")
+        );
+        assert_eq!(error.exit_code(), Some(1));
+
+        let error = KittyError::from_output(
+            "ls",
+            socket,
+            &failed(
+                "Error: Remote control is disabled. Add allow_remote_control to your kitty.conf",
+            ),
+        );
+        assert!(matches!(error, KittyError::RemoteControlDisabled { .. }));
+
+        let error = KittyError::from_output(
+            "focus-tab",
+            socket,
+            &failed("Error: No matching tabs for expression: id:42"),
+        );
+        assert!(matches!(error, KittyError::NoMatch { .. }));
+        assert_eq!(
+            error.stderr(),
+            Some("Error: No matching tabs for expression: id:42")
+        );
+
+        let error = KittyError::from_output(
+            "close-tab",
+            socket,
+            &failed("Error: The close-tab command is not allowed by the remote control password"),
+        );
+        assert!(
+            matches!(error, KittyError::PermissionDenied { ref command, .. } if command == "close-tab")
+        );
+
+        let error = KittyError::from_output("launch", socket, &failed("boom"));
+        assert_eq!(
+            error.to_string(),
+            "kitten launch failed with exit code 1: boom"
+        );
+    }
+
+    #[test]
+    fn test_spawn_recognises_missing_kitten() {
+        let error = KittyError::spawn(io::Error::from(io::ErrorKind::NotFound));
+        assert!(matches!(error, KittyError::KittenNotFound));
+
+        let error = KittyError::spawn(io::Error::from(io::ErrorKind::PermissionDenied));
+        assert!(matches!(error, KittyError::Io(_)));
+        assert_eq!(error.exit_code(), None);
+    }
+}
@@ -0,0 +1,112 @@
+use std::future::Future;
+
+use crate::commands::KittenCommand;
+use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::close_window::KittenCloseWindowCommand;
+use crate::commands::detach::{KittenDetachTabCommand, KittenDetachWindowCommand};
+use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::focus_window::KittenFocusWindowCommand;
+use crate::commands::get_text::KittenGetTextCommand;
+use crate::commands::launch::KittenLaunchCommand;
+use crate::commands::layout::{KittenGotoLayoutCommand, KittenLastUsedLayoutCommand};
+use crate::commands::ls::KittenLsCommand;
+use crate::commands::navigate_tab::KittenNavigateTabCommand;
+use crate::commands::resize_window::KittenResizeWindowCommand;
+use crate::commands::send_text::KittenSendTextCommand;
+use crate::commands::set_tab_title::KittenSetTabTitleCommand;
+use crate::commands::set_user_vars::KittenSetUserVarsCommand;
+use crate::commands::set_window_title::KittenSetWindowTitleCommand;
+use crate::error::KittyResult;
+use crate::types::{KittyLaunchResponse, KittyLsResponse};
+use crate::version::KittyCapabilities;
+
+/// Runs kitty remote control commands without blocking the caller's async runtime
+///
+/// The async counterpart of [`CommandExecutor`](crate::CommandExecutor), with the same
+/// commands and errors. Futures are `Send`, so calls can be spawned, raced or wrapped in a
+/// timeout; dropping one cancels the command.
+pub trait AsyncCommandExecutor {
+    fn ls(
+        &self,
+        command: KittenLsCommand,
+    ) -> impl Future<Output = KittyResult<KittyLsResponse>> + Send;
+    fn focus_tab(
+        &self,
+        command: KittenFocusTabCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn close_tab(
+        &self,
+        command: KittenCloseTabCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn launch(
+        &self,
+        command: KittenLaunchCommand,
+    ) -> impl Future<Output = KittyResult<KittyLaunchResponse>> + Send;
+    fn navigate_tab(
+        &self,
+        command: KittenNavigateTabCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn set_tab_title(
+        &self,
+        command: KittenSetTabTitleCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn send_text(
+        &self,
+        command: KittenSendTextCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn get_text(
+        &self,
+        command: KittenGetTextCommand,
+    ) -> impl Future<Output = KittyResult<String>> + Send;
+    fn focus_window(
+        &self,
+        command: KittenFocusWindowCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn close_window(
+        &self,
+        command: KittenCloseWindowCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn set_window_title(
+        &self,
+        command: KittenSetWindowTitleCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn resize_window(
+        &self,
+        command: KittenResizeWindowCommand,
+    ) -> impl Future<Output = KittyResult<bool>> + Send;
+    fn goto_layout(
+        &self,
+        command: KittenGotoLayoutCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn last_used_layout(
+        &self,
+        command: KittenLastUsedLayoutCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn detach_window(
+        &self,
+        command: KittenDetachWindowCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn detach_tab(
+        &self,
+        command: KittenDetachTabCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+    fn set_user_vars(
+        &self,
+        command: KittenSetUserVarsCommand,
+    ) -> impl Future<Output = KittyResult<()>> + Send;
+
+    /// What the kitty behind the executor supports, as for
+    /// [`CommandExecutor::capabilities`](crate::CommandExecutor::capabilities)
+    ///
+    /// Executors that can't tell answer [`KittyCapabilities::unknown`], which supports
+    /// everything.
+    fn capabilities(&self) -> impl Future<Output = KittyResult<KittyCapabilities>> + Send {
+        async { Ok(KittyCapabilities::unknown()) }
+    }
+
+    /// Run any [`KittenCommand`], including ones defined outside kitty-lib
+    fn execute<C>(&self, command: C) -> impl Future<Output = KittyResult<C::Response>> + Send
+    where
+        C: KittenCommand + Send,
+        C::Response: Send;
+}
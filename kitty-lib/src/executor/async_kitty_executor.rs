@@ -0,0 +1,277 @@
+use std::io;
+use std::process::Output;
+
+use tokio::io::AsyncWriteExt;
+
+use crate::commands::KittenCommand;
+use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::close_window::KittenCloseWindowCommand;
+use crate::commands::detach::{KittenDetachTabCommand, KittenDetachWindowCommand};
+use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::focus_window::KittenFocusWindowCommand;
+use crate::commands::get_text::KittenGetTextCommand;
+use crate::commands::launch::KittenLaunchCommand;
+use crate::commands::layout::{KittenGotoLayoutCommand, KittenLastUsedLayoutCommand};
+use crate::commands::ls::KittenLsCommand;
+use crate::commands::navigate_tab::KittenNavigateTabCommand;
+use crate::commands::resize_window::KittenResizeWindowCommand;
+use crate::commands::send_text::KittenSendTextCommand;
+use crate::commands::set_tab_title::KittenSetTabTitleCommand;
+use crate::commands::set_user_vars::KittenSetUserVarsCommand;
+use crate::commands::set_window_title::KittenSetWindowTitleCommand;
+use crate::error::{KittyError, KittyResult};
+use crate::executor::AsyncCommandExecutor;
+use crate::executor::CommandExecutor;
+use crate::executor::KittyExecutor;
+use crate::executor::RetryPolicy;
+use crate::executor::invocation::{self, Invocation};
+use crate::snapshot::KittySnapshot;
+use crate::types::{KittyLaunchResponse, KittyLsResponse};
+use crate::version::KittyCapabilities;
+
+/// [`AsyncCommandExecutor`] running `kitten` through tokio's process support
+///
+/// Sockets, passwords, timeouts and retries are configured as for [`KittyExecutor`]; socket
+/// discovery happens once, when the executor is created, and blocks briefly while candidates
+/// are probed. Failures are blamed on an outdated kitty as [`KittyExecutor`] does, with kitty's
+/// version found out on tokio's blocking pool. Dropping a call's future kills its `kitten`
+/// process.
+#[derive(Debug, Clone, Default)]
+pub struct AsyncKittyExecutor {
+    inner: KittyExecutor,
+}
+
+impl AsyncKittyExecutor {
+    /// Talk to the kitty [`KittyExecutor::new`] would find
+    pub fn new() -> Self {
+        Self {
+            inner: KittyExecutor::new(),
+        }
+    }
+
+    /// The socket commands are sent to, if a kitty was found
+    pub fn socket(&self) -> Option<&str> {
+        self.inner.socket()
+    }
+
+    /// Run `invocation`, retrying it after transient failures when it is idempotent
+    async fn run(&self, invocation: &Invocation) -> KittyResult<Output> {
+        let retry = if invocation.idempotent {
+            self.inner.retry_policy()
+        } else {
+            RetryPolicy::none()
+        };
+
+        let mut attempt = 0;
+        loop {
+            match self.run_once(invocation).await {
+                Err(error) if retry.should_retry(attempt, &error) => {
+                    tokio::time::sleep(retry.delay(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Run `invocation` once, killing kitten when it outlives the executor's timeout
+    async fn run_once(&self, invocation: &Invocation) -> KittyResult<Output> {
+        let run = async {
+            let mut kitten = tokio::process::Command::from(self.inner.command(invocation)?);
+            kitten.kill_on_drop(true);
+            let mut child = kitten.spawn().map_err(KittyError::spawn)?;
+
+            if let Some(text) = &invocation.stdin
+                && let Some(mut pipe) = child.stdin.take()
+            {
+                pipe.write_all(text.as_bytes()).await?;
+            }
+
+            Ok::<_, KittyError>(child.wait_with_output().await?)
+        };
+
+        let output =
+            match self.inner.timeout() {
+                Some(timeout) => tokio::time::timeout(timeout, run).await.map_err(|_| {
+                    KittyError::Timeout {
+                        command: invocation.command.to_string(),
+                        timeout,
+                    }
+                })??,
+                None => run.await?,
+            };
+        self.inner.check(invocation, output)
+    }
+
+    /// Blame a failed `invocation` on kitty's version when kitty lacks a feature it needs, as
+    /// [`KittyExecutor`] does
+    async fn explain(&self, invocation: &Invocation, error: KittyError) -> KittyError {
+        if !invocation.may_need_newer_kitty(&error) {
+            return error;
+        }
+        match self.capabilities().await {
+            Ok(capabilities) => invocation.explain(error, &capabilities),
+            Err(_) => error,
+        }
+    }
+}
+
+impl From<KittyExecutor> for AsyncKittyExecutor {
+    fn from(inner: KittyExecutor) -> Self {
+        Self { inner }
+    }
+}
+
+impl AsyncCommandExecutor for AsyncKittyExecutor {
+    async fn ls(&self, command: KittenLsCommand) -> KittyResult<KittyLsResponse> {
+        self.execute(command).await
+    }
+
+    async fn focus_tab(&self, command: KittenFocusTabCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    async fn close_tab(&self, command: KittenCloseTabCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    async fn launch(&self, command: KittenLaunchCommand) -> KittyResult<KittyLaunchResponse> {
+        self.execute(command).await
+    }
+
+    async fn navigate_tab(&self, command: KittenNavigateTabCommand) -> KittyResult<()> {
+        let snapshot = KittySnapshot::new(self.ls(KittenLsCommand::new()).await?);
+        let session_tabs = invocation::session_tabs(&snapshot, &command);
+
+        match invocation::navigation_target(&command, &session_tabs)? {
+            Some(tab_id) => self.focus_tab(KittenFocusTabCommand::new(tab_id)).await,
+            None => Ok(()),
+        }
+    }
+
+    async fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    async fn send_text(&self, command: KittenSendTextCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    async fn get_text(&self, command: KittenGetTextCommand) -> KittyResult<String> {
+        self.execute(command).await
+    }
+
+    async fn focus_window(&self, command: KittenFocusWindowCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    async fn close_window(&self, command: KittenCloseWindowCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    async fn set_window_title(&self, command: KittenSetWindowTitleCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    async fn resize_window(&self, command: KittenResizeWindowCommand) -> KittyResult<bool> {
+        self.execute(command).await
+    }
+
+    async fn goto_layout(&self, command: KittenGotoLayoutCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    async fn last_used_layout(&self, command: KittenLastUsedLayoutCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    async fn detach_window(&self, command: KittenDetachWindowCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    async fn detach_tab(&self, command: KittenDetachTabCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    async fn set_user_vars(&self, command: KittenSetUserVarsCommand) -> KittyResult<()> {
+        self.execute(command).await
+    }
+
+    /// Probes on tokio's blocking pool, since the probe waits on `kitten`, `ps` and kitty's binary
+    async fn capabilities(&self) -> KittyResult<KittyCapabilities> {
+        let inner = self.inner.clone();
+        tokio::task::spawn_blocking(move || inner.capabilities())
+            .await
+            .map_err(|error| KittyError::Io(io::Error::other(error)))?
+    }
+
+    async fn execute<C>(&self, command: C) -> KittyResult<C::Response>
+    where
+        C: KittenCommand + Send,
+        C::Response: Send,
+    {
+        let invocation = Invocation::of(&command)?;
+        let output = match self.run(&invocation).await {
+            Ok(output) => output,
+            Err(error) => return Err(self.explain(&invocation, error).await),
+        };
+        command.parse_response(&output.stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::version::KittyVersion;
+
+    #[tokio::test]
+    async fn test_unreachable_kitty_fails_without_running_kitten() {
+        let executor = AsyncKittyExecutor::from(KittyExecutor::discover(
+            &crate::SocketDiscovery::new().listen_on_env("unix:/nonexistent/ksm-test-socket"),
+        ));
+
+        let error = executor.ls(KittenLsCommand::new()).await.unwrap_err();
+        match error {
+            KittyError::NoSocket { tried } => {
+                assert_eq!(tried, vec!["unix:/nonexistent/ksm-test-socket"])
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_blamed_on_an_outdated_kitty() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let kitten = dir.path().join("kitten");
+        std::fs::write(
+            &kitten,
+            "#!/bin/sh\necho 'Error: unknown flag: --match-tab' >&2\nexit 1\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&kitten, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let socket = format!("unix:/tmp/kitty-async-outdated-{}", std::process::id());
+        crate::executor::kitty_executor::remember_version(&socket, KittyVersion::new(0, 26, 5));
+        let executor =
+            AsyncKittyExecutor::from(KittyExecutor::with_socket(&socket).with_kitten(kitten));
+
+        assert_eq!(
+            executor.capabilities().await.unwrap().version(),
+            Some(KittyVersion::new(0, 26, 5))
+        );
+        let error = executor
+            .ls(KittenLsCommand::new().match_tab_title("session:api"))
+            .await
+            .unwrap_err();
+        assert!(
+            error.to_string().contains("upgrade kitty to 0.30.0"),
+            "{}",
+            error
+        );
+        assert!(matches!(
+            executor.focus_tab(KittenFocusTabCommand::new(3)).await,
+            Err(KittyError::CommandFailed { .. })
+        ));
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+use crate::commands::KittenCommand;
+use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::close_window::KittenCloseWindowCommand;
+use crate::commands::detach::{KittenDetachTabCommand, KittenDetachWindowCommand};
+use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::focus_window::KittenFocusWindowCommand;
+use crate::commands::get_text::KittenGetTextCommand;
+use crate::commands::launch::KittenLaunchCommand;
+use crate::commands::layout::{KittenGotoLayoutCommand, KittenLastUsedLayoutCommand};
+use crate::commands::ls::KittenLsCommand;
+use crate::commands::navigate_tab::KittenNavigateTabCommand;
+use crate::commands::resize_window::KittenResizeWindowCommand;
+use crate::commands::send_text::KittenSendTextCommand;
+use crate::commands::set_tab_title::KittenSetTabTitleCommand;
+use crate::commands::set_user_vars::KittenSetUserVarsCommand;
+use crate::commands::set_window_title::KittenSetWindowTitleCommand;
+use crate::error::KittyResult;
+use crate::executor::{AsyncCommandExecutor, CommandExecutor, MockExecutor};
+use crate::types::{KittyLaunchResponse, KittyLsResponse};
+use crate::version::KittyCapabilities;
+
+/// [`AsyncCommandExecutor`] answering from a [`MockExecutor`]
+///
+/// Queue responses and inspect calls on the wrapped mock through [`AsyncMockExecutor::mock`].
+/// An optional latency delays every answer, to exercise timeouts and concurrency.
+#[derive(Default)]
+pub struct AsyncMockExecutor {
+    mock: Mutex<MockExecutor>,
+    latency: Option<Duration>,
+}
+
+impl AsyncMockExecutor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answer from an already configured mock
+    pub fn from_mock(mock: MockExecutor) -> Self {
+        Self {
+            mock: Mutex::new(mock),
+            latency: None,
+        }
+    }
+
+    /// Wait `latency` before answering each call
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = Some(latency);
+        self
+    }
+
+    /// The wrapped mock, for queuing responses and verifying calls
+    pub fn mock(&self) -> MutexGuard<'_, MockExecutor> {
+        self.mock
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    async fn respond<T>(
+        &self,
+        answer: impl FnOnce(&MockExecutor) -> KittyResult<T>,
+    ) -> KittyResult<T> {
+        if let Some(latency) = self.latency {
+            tokio::time::sleep(latency).await;
+        }
+        answer(&self.mock())
+    }
+}
+
+impl AsyncCommandExecutor for AsyncMockExecutor {
+    async fn ls(&self, command: KittenLsCommand) -> KittyResult<KittyLsResponse> {
+        self.respond(|mock| mock.ls(command)).await
+    }
+
+    async fn focus_tab(&self, command: KittenFocusTabCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.focus_tab(command)).await
+    }
+
+    async fn close_tab(&self, command: KittenCloseTabCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.close_tab(command)).await
+    }
+
+    async fn launch(&self, command: KittenLaunchCommand) -> KittyResult<KittyLaunchResponse> {
+        self.respond(|mock| mock.launch(command)).await
+    }
+
+    async fn navigate_tab(&self, command: KittenNavigateTabCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.navigate_tab(command)).await
+    }
+
+    async fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.set_tab_title(command)).await
+    }
+
+    async fn send_text(&self, command: KittenSendTextCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.send_text(command)).await
+    }
+
+    async fn get_text(&self, command: KittenGetTextCommand) -> KittyResult<String> {
+        self.respond(|mock| mock.get_text(command)).await
+    }
+
+    async fn focus_window(&self, command: KittenFocusWindowCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.focus_window(command)).await
+    }
+
+    async fn close_window(&self, command: KittenCloseWindowCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.close_window(command)).await
+    }
+
+    async fn set_window_title(&self, command: KittenSetWindowTitleCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.set_window_title(command)).await
+    }
+
+    async fn resize_window(&self, command: KittenResizeWindowCommand) -> KittyResult<bool> {
+        self.respond(|mock| mock.resize_window(command)).await
+    }
+
+    async fn goto_layout(&self, command: KittenGotoLayoutCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.goto_layout(command)).await
+    }
+
+    async fn last_used_layout(&self, command: KittenLastUsedLayoutCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.last_used_layout(command)).await
+    }
+
+    async fn detach_window(&self, command: KittenDetachWindowCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.detach_window(command)).await
+    }
+
+    async fn detach_tab(&self, command: KittenDetachTabCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.detach_tab(command)).await
+    }
+
+    async fn set_user_vars(&self, command: KittenSetUserVarsCommand) -> KittyResult<()> {
+        self.respond(|mock| mock.set_user_vars(command)).await
+    }
+
+    async fn capabilities(&self) -> KittyResult<KittyCapabilities> {
+        self.respond(|mock| mock.capabilities()).await
+    }
+
+    async fn execute<C>(&self, command: C) -> KittyResult<C::Response>
+    where
+        C: KittenCommand + Send,
+        C::Response: Send,
+    {
+        self.respond(|mock| mock.execute(command)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::KittyError;
+    use crate::types::TabId;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn test_async_mock_answers_concurrent_calls() {
+        let executor = Arc::new(AsyncMockExecutor::new());
+        let target = {
+            let mock = executor.mock();
+            let mut layout = mock.layout.borrow_mut();
+            layout.add_tab_with_session("api", None);
+            layout.add_tab_with_session("web", None)
+        };
+
+        let ls = tokio::spawn({
+            let executor = Arc::clone(&executor);
+            async move { executor.ls(KittenLsCommand::new()).await }
+        });
+        let focus = tokio::spawn({
+            let executor = Arc::clone(&executor);
+            async move { executor.focus_tab(KittenFocusTabCommand::new(target)).await }
+        });
+
+        assert_eq!(ls.await.unwrap().unwrap()[0].tabs.len(), 2);
+        focus.await.unwrap().unwrap();
+        assert_eq!(executor.mock().focus_tab_call_count(), 1);
+
+        let error = executor
+            .close_tab(KittenCloseTabCommand::new(TabId(9)))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, KittyError::NoMatch { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_latency_lets_callers_time_out() {
+        let executor = AsyncMockExecutor::new().with_latency(Duration::from_secs(5));
+
+        let result = tokio::time::timeout(
+            Duration::from_millis(20),
+            executor.ls(KittenLsCommand::new()),
+        )
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(executor.mock().ls_call_count(), 0);
+    }
+}
@@ -0,0 +1,419 @@
+use regex::Regex;
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::io::Write;
+use std::sync::LazyLock;
+
+use crate::commands::KittenCommand;
+use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::close_window::KittenCloseWindowCommand;
+use crate::commands::detach::{KittenDetachTabCommand, KittenDetachWindowCommand};
+use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::focus_window::KittenFocusWindowCommand;
+use crate::commands::get_text::KittenGetTextCommand;
+use crate::commands::launch::KittenLaunchCommand;
+use crate::commands::layout::{KittenGotoLayoutCommand, KittenLastUsedLayoutCommand};
+use crate::commands::ls::KittenLsCommand;
+use crate::commands::navigate_tab::KittenNavigateTabCommand;
+use crate::commands::resize_window::KittenResizeWindowCommand;
+use crate::commands::send_text::KittenSendTextCommand;
+use crate::commands::set_tab_title::KittenSetTabTitleCommand;
+use crate::commands::set_user_vars::KittenSetUserVarsCommand;
+use crate::commands::set_window_title::KittenSetWindowTitleCommand;
+use crate::error::KittyResult;
+use crate::executor::invocation::{self, Invocation};
+use crate::executor::{CommandExecutor, KittyExecutor};
+use crate::types::{KittyLaunchResponse, KittyLsResponse};
+use crate::version::KittyCapabilities;
+
+/// Window ids handed out for launches that didn't happen start above this, far past any id
+/// kitty assigns
+const PLANNED_WINDOW_IDS: u32 = 4_000_000_000;
+
+/// Whole numbers in an argument, checked for planned window ids
+static NUMBER: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"\d+").expect("valid regex"));
+
+/// [`KittyExecutor`] that reads from kitty but only writes down the commands that would change it
+///
+/// Read-only commands such as `ls` and `get-text` still go to kitty, so callers decide what to
+/// do from the live layout. Every other command becomes a line of a `sh` script instead, which
+/// [`DryRunExecutor::script`] returns and [`DryRunExecutor::print_to`] streams as it grows.
+/// Running the script later makes the same calls.
+///
+/// Launches answer with a made-up window id and store kitty's real one in a shell variable,
+/// `window_1`, `window_2` and so on; later commands mentioning the made-up id use the
+/// variable. Tabs that were only planned don't show up in `ls`.
+pub struct DryRunExecutor {
+    inner: KittyExecutor,
+    lines: RefCell<Vec<String>>,
+    launches: Cell<u32>,
+    output: RefCell<Option<Box<dyn Write>>>,
+}
+
+impl DryRunExecutor {
+    /// Read from kitty through `inner` and plan everything else
+    pub fn new(inner: KittyExecutor) -> Self {
+        Self {
+            inner,
+            lines: RefCell::new(Vec::new()),
+            launches: Cell::new(0),
+            output: RefCell::new(None),
+        }
+    }
+
+    /// Also write the script to `output`, e.g. stdout: its header right away, then one
+    /// command at a time
+    ///
+    /// Callers may write lines of their own in between, such as the local commands a real run
+    /// would have made.
+    pub fn print_to(self, mut output: impl Write + 'static) -> KittyResult<Self> {
+        output.write_all(self.script().as_bytes())?;
+        output.flush()?;
+        self.output.replace(Some(Box::new(output)));
+        Ok(self)
+    }
+
+    /// The script planned so far, runnable with `sh`
+    pub fn script(&self) -> String {
+        let mut script = self.header();
+        for line in self.lines.borrow().iter() {
+            script.push_str(line);
+            script.push('\n');
+        }
+        script
+    }
+
+    /// The planned commands, one shell command line each
+    pub fn commands(&self) -> Vec<String> {
+        self.lines.borrow().clone()
+    }
+
+    fn header(&self) -> String {
+        let mut header = String::from("#!/bin/sh\nset -e\n");
+        // kitten reads a password from kitty.conf only through the environment
+        if let Some(name) = self.inner.password_env() {
+            header.push_str(&format!(
+                "# Set {} to kitty's remote_control_password before running\n",
+                name
+            ));
+        }
+        header
+    }
+
+    /// Add the shell command for `invocation` to the script, returning what kitten would
+    /// print: a planned window id for launches, nothing otherwise
+    fn plan(&self, invocation: &Invocation) -> KittyResult<String> {
+        let args = self.inner.kitten_args(invocation)?;
+        let mut line = std::iter::once(self.inner.kitten().display().to_string())
+            .chain(args)
+            .map(|arg| self.shell_word(&arg))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        if let Some(text) = &invocation.stdin {
+            line = format!("printf '%s' {} | {}", shell_quote(text), line);
+        }
+
+        let stdout = if invocation.command == "launch" {
+            let launch = self.launches.get() + 1;
+            self.launches.set(launch);
+            line = format!("window_{}=$({})", launch, line);
+            (PLANNED_WINDOW_IDS + launch).to_string()
+        } else {
+            String::new()
+        };
+
+        if let Some(output) = self.output.borrow_mut().as_mut() {
+            writeln!(output, "{}", line)?;
+            output.flush()?;
+        }
+        self.lines.borrow_mut().push(line);
+
+        Ok(stdout)
+    }
+
+    /// `arg` quoted for `sh`, with planned window ids replaced by their variables
+    fn shell_word(&self, arg: &str) -> String {
+        let mut word = String::new();
+        let mut literal_start = 0;
+        for number in NUMBER.find_iter(arg) {
+            let Some(launch) = self.planned_launch(number.as_str()) else {
+                continue;
+            };
+            if number.start() > literal_start {
+                word.push_str(&shell_quote(&arg[literal_start..number.start()]));
+            }
+            word.push_str(&format!("\"$window_{}\"", launch));
+            literal_start = number.end();
+        }
+
+        if literal_start == 0 {
+            return shell_quote(arg);
+        }
+        if literal_start < arg.len() {
+            word.push_str(&shell_quote(&arg[literal_start..]));
+        }
+        word
+    }
+
+    /// Which launch handed out `number` as its window id, if any
+    fn planned_launch(&self, number: &str) -> Option<u32> {
+        let launch = number
+            .parse::<u32>()
+            .ok()?
+            .checked_sub(PLANNED_WINDOW_IDS)?;
+        (1..=self.launches.get())
+            .contains(&launch)
+            .then_some(launch)
+    }
+}
+
+impl fmt::Debug for DryRunExecutor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DryRunExecutor")
+            .field("inner", &self.inner)
+            .field("lines", &self.lines)
+            .field("launches", &self.launches)
+            .finish_non_exhaustive()
+    }
+}
+
+/// `text` as a single `sh` word, quoted only when it has to be
+pub fn shell_quote(text: &str) -> String {
+    let plain = !text.is_empty()
+        && text
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c));
+    if plain {
+        text.to_string()
+    } else {
+        format!("'{}'", text.replace('\'', r"'\''"))
+    }
+}
+
+impl CommandExecutor for DryRunExecutor {
+    fn ls(&self, command: KittenLsCommand) -> KittyResult<KittyLsResponse> {
+        self.execute(command)
+    }
+
+    fn focus_tab(&self, command: KittenFocusTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn close_tab(&self, command: KittenCloseTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn launch(&self, command: KittenLaunchCommand) -> KittyResult<KittyLaunchResponse> {
+        self.execute(command)
+    }
+
+    fn navigate_tab(&self, command: KittenNavigateTabCommand) -> KittyResult<()> {
+        invocation::navigate_tab(self, command)
+    }
+
+    fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn send_text(&self, command: KittenSendTextCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn get_text(&self, command: KittenGetTextCommand) -> KittyResult<String> {
+        self.execute(command)
+    }
+
+    fn focus_window(&self, command: KittenFocusWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn close_window(&self, command: KittenCloseWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn set_window_title(&self, command: KittenSetWindowTitleCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn resize_window(&self, command: KittenResizeWindowCommand) -> KittyResult<bool> {
+        self.execute(command)
+    }
+
+    fn goto_layout(&self, command: KittenGotoLayoutCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn last_used_layout(&self, command: KittenLastUsedLayoutCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn detach_window(&self, command: KittenDetachWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn detach_tab(&self, command: KittenDetachTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn set_user_vars(&self, command: KittenSetUserVarsCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn capabilities(&self) -> KittyResult<KittyCapabilities> {
+        self.inner.capabilities()
+    }
+
+    fn round_trips(&self) -> Option<usize> {
+        self.inner.round_trips()
+    }
+
+    fn execute<C: KittenCommand>(&self, command: C) -> KittyResult<C::Response> {
+        if command.is_read_only() {
+            return self.inner.execute(command);
+        }
+
+        let stdout = self.plan(&Invocation::of(&command)?)?;
+        command.parse_response(stdout.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::KittyError;
+    use crate::password::RemoteControlPassword;
+    use crate::types::{TabId, WindowId};
+    use std::rc::Rc;
+
+    fn dry_run() -> DryRunExecutor {
+        DryRunExecutor::new(KittyExecutor::with_socket("unix:/tmp/kitty"))
+    }
+
+    /// Collects what [`DryRunExecutor::print_to`] writes
+    #[derive(Clone, Default)]
+    struct Captured(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Captured {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_mutating_commands_become_script_lines() {
+        let executor = dry_run();
+
+        executor
+            .focus_tab(KittenFocusTabCommand::new(TabId(3)))
+            .unwrap();
+        executor
+            .set_tab_title(KittenSetTabTitleCommand::new("it's api").for_tab_id(TabId(3)))
+            .unwrap();
+        executor
+            .close_tab(KittenCloseTabCommand::new(TabId(3)))
+            .unwrap();
+
+        assert_eq!(
+            executor.commands(),
+            [
+                "kitten @ --to=unix:/tmp/kitty focus-tab --match=id:3",
+                r"kitten @ --to=unix:/tmp/kitty set-tab-title --match=id:3 'it'\''s api'",
+                "kitten @ --to=unix:/tmp/kitty close-tab --match=id:3",
+            ]
+        );
+        assert!(executor.script().starts_with("#!/bin/sh\nset -e\nkitten @"));
+    }
+
+    #[test]
+    fn test_launched_windows_are_referred_to_by_variable() {
+        let executor = dry_run();
+
+        let launched = executor
+            .launch(KittenLaunchCommand::new().tab_title("api"))
+            .unwrap();
+        let window_id = launched.window_id.expect("a planned window id");
+        assert_eq!(window_id, WindowId(PLANNED_WINDOW_IDS + 1));
+
+        executor
+            .send_text(
+                KittenSendTextCommand::new("make test\n")
+                    .for_window_id(window_id)
+                    .from_stdin(),
+            )
+            .unwrap();
+
+        let commands = executor.commands();
+        assert!(
+            commands[0].starts_with("window_1=$(kitten @ --to=unix:/tmp/kitty launch"),
+            "{}",
+            commands[0]
+        );
+        assert!(commands[0].ends_with(')'));
+        assert_eq!(
+            commands[1],
+            "printf '%s' 'make test\n' | kitten @ --to=unix:/tmp/kitty send-text \
+             --match=id:\"$window_1\" --stdin"
+        );
+    }
+
+    #[test]
+    fn test_only_planned_ids_become_variables() {
+        let executor = dry_run();
+        executor.launch(KittenLaunchCommand::new()).unwrap();
+
+        assert_eq!(executor.shell_word("id:4000000001"), "id:\"$window_1\"");
+        assert_eq!(
+            executor.shell_word("id:4000000001 or id:7"),
+            "id:\"$window_1\"' or id:7'"
+        );
+        assert_eq!(executor.shell_word("id:4000000002"), "id:4000000002");
+        assert_eq!(executor.shell_word("id:14000000001"), "id:14000000001");
+        assert_eq!(executor.shell_word(""), "''");
+    }
+
+    #[test]
+    fn test_read_only_commands_reach_kitty() {
+        let executor = DryRunExecutor::new(
+            KittyExecutor::with_socket("unix:/tmp/kitty").with_kitten("/nonexistent/kitten"),
+        );
+
+        let error = executor.ls(KittenLsCommand::new()).unwrap_err();
+        assert!(matches!(error, KittyError::KittenNotFound), "{:?}", error);
+        assert!(executor.commands().is_empty());
+    }
+
+    #[test]
+    fn test_print_to_streams_the_script() {
+        let captured = Captured::default();
+        let executor = dry_run().print_to(captured.clone()).unwrap();
+        assert_eq!(*captured.0.borrow(), b"#!/bin/sh\nset -e\n");
+
+        executor
+            .focus_window(KittenFocusWindowCommand::new(WindowId(5)))
+            .unwrap();
+        let printed = String::from_utf8(captured.0.borrow().clone()).unwrap();
+        assert_eq!(printed, executor.script());
+    }
+
+    #[test]
+    fn test_password_from_kitty_conf_is_left_to_the_environment() {
+        let executor = DryRunExecutor::new(
+            KittyExecutor::with_socket("unix:/tmp/kitty")
+                .with_password(RemoteControlPassword::Literal("secret".to_string())),
+        );
+        executor
+            .close_tab(KittenCloseTabCommand::new(TabId(1)))
+            .unwrap();
+
+        let script = executor.script();
+        assert!(!script.contains("secret"));
+        assert!(script.contains("# Set KITTY_RC_PASSWORD"));
+        assert!(script.contains("--password-env=KITTY_RC_PASSWORD close-tab"));
+    }
+}
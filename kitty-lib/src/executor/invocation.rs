@@ -0,0 +1,233 @@
+//! `kitten @` invocations and tab navigation shared by the blocking and async executors
+
+use crate::commands::KittenCommand;
+use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::navigate_tab::{KittenNavigateTabCommand, TabNavigationDirection};
+use crate::error::{KittyError, KittyResult};
+use crate::executor::CommandExecutor;
+use crate::snapshot::KittySnapshot;
+use crate::types::{KittyTab, TabId};
+use crate::version::KittyCapabilities;
+
+/// One `kitten @ <command> <args>` run, with text to feed on stdin
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Invocation {
+    pub command: &'static str,
+    pub args: Vec<String>,
+    pub stdin: Option<String>,
+    /// Whether running this twice is harmless, so it may be retried
+    pub idempotent: bool,
+}
+
+impl Invocation {
+    pub fn of<C: KittenCommand + ?Sized>(command: &C) -> KittyResult<Self> {
+        Ok(Self {
+            command: command.name(),
+            args: command.args()?,
+            stdin: command.stdin().map(str::to_string),
+            idempotent: command.is_idempotent(),
+        })
+    }
+
+    /// Whether kitty lacking a feature could be behind `error`, so kitty's version is worth
+    /// finding out
+    ///
+    /// Only failures kitten couldn't explain itself qualify, so the version is only probed once
+    /// something went wrong.
+    pub fn may_need_newer_kitty(&self, error: &KittyError) -> bool {
+        matches!(error, KittyError::CommandFailed { .. })
+    }
+
+    /// `error`, or [`KittyError::Unsupported`] when `capabilities` lack a feature this needs
+    pub fn explain(&self, error: KittyError, capabilities: &KittyCapabilities) -> KittyError {
+        capabilities
+            .require_for(self.command, &self.args)
+            .err()
+            .unwrap_or(error)
+    }
+}
+
+/// Move between the tabs of a session with one `ls` and a `focus-tab` call on `executor`
+///
+/// For executors that run the real kitten calls; kitty has no `navigate-tab` of its own.
+pub(crate) fn navigate_tab<E: CommandExecutor>(
+    executor: &E,
+    command: KittenNavigateTabCommand,
+) -> KittyResult<()> {
+    let snapshot = KittySnapshot::fetch(executor)?;
+
+    match navigation_target(&command, &session_tabs(&snapshot, &command))? {
+        Some(tab_id) => executor.focus_tab(KittenFocusTabCommand::new(tab_id)),
+        None => Ok(()),
+    }
+}
+
+/// The tabs `command` moves between, sorted by id
+///
+/// Sessions are those [`crate::snapshot::classify_tab`] assigns, so navigation agrees with every
+/// other session query about which tabs belong where; without a session name these are the
+/// tabs outside every session.
+pub(crate) fn session_tabs<'a>(
+    snapshot: &'a KittySnapshot,
+    command: &KittenNavigateTabCommand,
+) -> Vec<&'a KittyTab> {
+    match command.session_name.as_deref() {
+        Some(session_name) if session_name != "unnamed" => snapshot.session_tabs(session_name),
+        _ => {
+            let mut tabs = snapshot.unnamed_tabs();
+            tabs.sort_by_key(|tab| tab.id);
+            tabs
+        }
+    }
+}
+
+/// The tab `command` moves to among `session_tabs`, or `None` when focus stays put
+pub(crate) fn navigation_target(
+    command: &KittenNavigateTabCommand,
+    session_tabs: &[&KittyTab],
+) -> KittyResult<Option<TabId>> {
+    let session_name = command.session_name.as_deref().unwrap_or("unnamed");
+
+    if session_tabs.is_empty() {
+        return Err(KittyError::no_match(
+            "navigate-tab",
+            "tabs",
+            &format!("session:{}", session_name),
+        ));
+    }
+
+    if session_tabs.len() == 1 {
+        // Only one tab, nothing to navigate to
+        return Ok(None);
+    }
+
+    // Find the current tab; every OS window has an active tab, but only one has focus
+    let current_index = session_tabs
+        .iter()
+        .position(|t| t.is_focused)
+        .or_else(|| session_tabs.iter().position(|t| t.is_active))
+        .unwrap_or(0);
+
+    // Calculate next index based on direction
+    let next_index = match command.direction {
+        TabNavigationDirection::Next => {
+            if current_index + 1 >= session_tabs.len() {
+                if command.allow_wrap { 0 } else { current_index }
+            } else {
+                current_index + 1
+            }
+        }
+        TabNavigationDirection::Previous => {
+            if current_index == 0 {
+                if command.allow_wrap {
+                    session_tabs.len() - 1
+                } else {
+                    0
+                }
+            } else {
+                current_index - 1
+            }
+        }
+    };
+
+    // If no change needed due to no-wrap
+    if next_index == current_index {
+        return Ok(None);
+    }
+
+    log::debug!(
+        "Navigating {:?} in session '{}' from tab {} to tab {}",
+        command.direction,
+        session_name,
+        session_tabs[current_index].id,
+        session_tabs[next_index].id
+    );
+
+    Ok(Some(session_tabs[next_index].id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::commands::detach::{DetachTarget, KittenDetachTabCommand};
+    use crate::commands::ls::KittenLsCommand;
+    use crate::commands::send_text::KittenSendTextCommand;
+    use crate::executor::MockExecutor;
+    use crate::version::KittyVersion;
+
+    #[test]
+    fn test_invocation_args() {
+        let ls = Invocation::of(&KittenLsCommand::new().match_tab_title("session:api")).unwrap();
+        assert_eq!(ls.command, "ls");
+        assert_eq!(ls.args, vec!["--match-tab=title:session:api"]);
+        assert!(ls.idempotent);
+
+        let send = Invocation::of(
+            &KittenSendTextCommand::new("echo hi\n")
+                .with_match("id:3")
+                .from_stdin(),
+        )
+        .unwrap();
+        assert_eq!(send.args, vec!["--match=id:3", "--stdin"]);
+        assert_eq!(send.stdin.as_deref(), Some("echo hi\n"));
+        assert!(!send.idempotent);
+
+        let dashed = Invocation::of(&KittenSendTextCommand::new("-rf\n")).unwrap();
+        assert_eq!(dashed.args, vec!["--", "-rf\n"]);
+
+        let detach =
+            Invocation::of(&KittenDetachTabCommand::new(TabId(1)).target(DetachTarget::NewTab));
+        assert!(matches!(detach, Err(KittyError::InvalidCommand(_))));
+    }
+
+    #[test]
+    fn test_navigate_tab_follows_focus_across_os_windows() {
+        let mock = MockExecutor::new();
+        let (first, second, other) = {
+            let mut layout = mock.layout.borrow_mut();
+            let first = layout.add_tab_with_session("api", None);
+            let second = layout.add_tab_with_session("api", None);
+            // Matched by kitty's `var:`/`env:` regexes for "api" but another session
+            layout.add_tab_with_session("api-v2", None);
+            let other = layout.add_os_window(Some("api"), None);
+            layout.set_active_tab(other);
+            (first, second, other)
+        };
+        // `first` is still the active tab of its OS window, but `other` has focus
+        assert!(mock.layout.borrow().tab(first).unwrap().is_active);
+
+        navigate_tab(
+            &mock,
+            KittenNavigateTabCommand::previous().with_session("api"),
+        )
+        .unwrap();
+        assert_eq!(mock.get_active_tab_id(), Some(second));
+
+        navigate_tab(&mock, KittenNavigateTabCommand::next().with_session("api")).unwrap();
+        assert_eq!(mock.get_active_tab_id(), Some(other));
+    }
+
+    #[test]
+    fn test_navigate_tab_classifies_tabs_from_one_ls() {
+        let mock = MockExecutor::new();
+        let first = {
+            let mut layout = mock.layout.borrow_mut();
+            let first = layout.add_tab_with_session("web", None);
+            let second = layout.add_tab_with_session("web", None);
+            // Its environment says `web`, but the stronger title marker puts it in `api`
+            layout.add_tab_with_session("web", Some("session:api".to_string()));
+            layout.set_active_tab(second);
+            first
+        };
+        mock.set_kitty_version(KittyVersion::new(0, 26, 5));
+
+        navigate_tab(&mock, KittenNavigateTabCommand::next().with_session("web")).unwrap();
+
+        // Wraps past the `api` tab, after one plain `ls` that any kitty understands
+        assert_eq!(mock.get_active_tab_id(), Some(first));
+        let ls_calls = mock.get_ls_calls();
+        assert_eq!(ls_calls.len(), 1);
+        assert_eq!(ls_calls[0].match_arg, None);
+    }
+}
@@ -1,341 +1,883 @@
-use anyhow::Result;
-use log::{debug, error};
-use std::env;
-use std::process::Command;
-
+use log::debug;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Output, Stdio};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::commands::KittenCommand;
 use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::close_window::KittenCloseWindowCommand;
+use crate::commands::detach::{KittenDetachTabCommand, KittenDetachWindowCommand};
 use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::focus_window::KittenFocusWindowCommand;
+use crate::commands::get_text::KittenGetTextCommand;
 use crate::commands::launch::KittenLaunchCommand;
+use crate::commands::layout::{KittenGotoLayoutCommand, KittenLastUsedLayoutCommand};
 use crate::commands::ls::KittenLsCommand;
-use crate::commands::navigate_tab::{KittenNavigateTabCommand, TabNavigationDirection};
+use crate::commands::navigate_tab::KittenNavigateTabCommand;
+use crate::commands::resize_window::KittenResizeWindowCommand;
+use crate::commands::send_text::KittenSendTextCommand;
 use crate::commands::set_tab_title::KittenSetTabTitleCommand;
+use crate::commands::set_user_vars::KittenSetUserVarsCommand;
+use crate::commands::set_window_title::KittenSetWindowTitleCommand;
+use crate::error::{KittyError, KittyResult};
 use crate::executor::CommandExecutor;
-use crate::types::{KittyCommandResult, KittyLaunchResponse, KittyLsResponse};
-use crate::utils::get_kitty_socket;
+use crate::executor::invocation::{self, Invocation};
+use crate::executor::retry::{DEFAULT_TIMEOUT, RetryPolicy};
+use crate::password::RemoteControlPassword;
+use crate::socket::SocketDiscovery;
+use crate::types::{KittyLaunchResponse, KittyLsResponse};
+use crate::version::{KittyCapabilities, KittyVersion};
+
+/// Where a kitty version was found out: the kitty behind a socket, or the local kitten binary
+/// when the executor has no socket
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum ProbeTarget {
+    Socket(String),
+    Kitten(PathBuf),
+}
+
+/// Versions already probed, shared by every executor in the process
+static PROBED_VERSIONS: LazyLock<Mutex<HashMap<ProbeTarget, KittyVersion>>> =
+    LazyLock::new(Mutex::default);
+
+fn probed_versions() -> MutexGuard<'static, HashMap<ProbeTarget, KittyVersion>> {
+    PROBED_VERSIONS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Answer version probes for `socket` with `version`, as if kitty had been asked already
+#[cfg(test)]
+pub(crate) fn remember_version(socket: &str, version: KittyVersion) {
+    probed_versions().insert(ProbeTarget::Socket(socket.to_string()), version);
+}
+
+/// How often to check whether a `kitten` call with a timeout has finished
+const POLL_INTERVAL: Duration = Duration::from_millis(5);
+
+/// The kitten binary run unless [`KittyExecutor::with_kitten`] says otherwise, found on `PATH`
+const KITTEN: &str = "kitten";
 
+#[derive(Debug, Clone)]
 pub struct KittyExecutor {
-    socket: String,
+    /// The socket in use, or every address discovery tried when no kitty answered
+    socket: Result<String, Vec<String>>,
+    password: Option<RemoteControlPassword>,
+    timeout: Option<Duration>,
+    retry: RetryPolicy,
+    kitten: PathBuf,
+    /// Requests sent so far, shared with clones
+    round_trips: Arc<AtomicUsize>,
 }
 
 impl KittyExecutor {
+    /// Talk to the kitty found by [`SocketDiscovery::from_env`]
+    ///
+    /// When no kitty is reachable every command fails with [`KittyError::NoSocket`]. The
+    /// password comes from `KITTY_RC_PASSWORD` or kitty.conf, see
+    /// [`RemoteControlPassword::discover`].
     pub fn new() -> Self {
-        let socket = get_kitty_socket();
-        Self { socket }
+        Self::discover(&SocketDiscovery::from_env())
     }
-}
 
-impl Default for KittyExecutor {
-    fn default() -> Self {
-        Self::new()
+    /// Talk to the kitty `discovery` finds
+    pub fn discover(discovery: &SocketDiscovery) -> Self {
+        let socket = match discovery.discover() {
+            Ok(socket) => Ok(socket),
+            Err(KittyError::NoSocket { tried }) => Err(tried),
+            Err(error) => Err(vec![error.to_string()]),
+        };
+        Self {
+            socket,
+            password: RemoteControlPassword::discover(discovery.kitty_conf()),
+            timeout: Some(DEFAULT_TIMEOUT),
+            retry: RetryPolicy::default(),
+            kitten: PathBuf::from(KITTEN),
+            round_trips: Arc::default(),
+        }
     }
-}
 
-impl CommandExecutor for KittyExecutor {
-    fn ls(&self, command: KittenLsCommand) -> Result<KittyLsResponse> {
-        let socket_arg = format!("--to={}", self.socket);
-        let mut args = vec!["@", &socket_arg, "ls"];
-
-        let match_formatted;
-        if let Some(match_arg) = &command.match_arg {
-            let match_flag = if command.use_tab_match {
-                "--match-tab"
-            } else {
-                "--match"
-            };
-            match_formatted = format!("{}={}", match_flag, match_arg);
-            args.push(&match_formatted);
-            debug!(
-                "Running kitten @ --to={} ls {}={}",
-                self.socket, match_flag, match_arg
-            );
-        } else {
-            debug!("Running kitten @ --to={} ls", self.socket);
+    /// Talk to the kitty listening on `socket`, e.g. `unix:/tmp/mykitty-1234`
+    pub fn with_socket(socket: impl Into<String>) -> Self {
+        Self {
+            socket: Ok(socket.into()),
+            password: None,
+            timeout: Some(DEFAULT_TIMEOUT),
+            retry: RetryPolicy::default(),
+            kitten: PathBuf::from(KITTEN),
+            round_trips: Arc::default(),
         }
+    }
 
-        let output = Command::new("kitten").args(&args).output()?;
+    /// Authenticate with `password`, for kitty configured with `remote_control_password`
+    pub fn with_password(mut self, password: RemoteControlPassword) -> Self {
+        self.password = Some(password);
+        self
+    }
 
-        if !output.status.success() {
-            debug!("kitten ls command failed with non-zero exit status");
-            return Ok(Vec::new());
-        }
+    /// Run the kitten binary at `kitten` instead of the one on `PATH`
+    pub fn with_kitten(mut self, kitten: impl Into<PathBuf>) -> Self {
+        self.kitten = kitten.into();
+        self
+    }
 
-        let response: KittyLsResponse = serde_json::from_slice(&output.stdout).map_err(|e| {
-            error!("Failed to parse kitten ls output: {}", e);
-            e
-        })?;
+    /// Kill `kitten` calls running longer than `timeout`, failing with [`KittyError::Timeout`]
+    ///
+    /// Defaults to [`DEFAULT_TIMEOUT`].
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
 
-        Ok(response)
+    /// Wait for `kitten` calls however long they take
+    pub fn without_timeout(mut self) -> Self {
+        self.timeout = None;
+        self
     }
 
-    fn focus_tab(&self, command: KittenFocusTabCommand) -> Result<KittyCommandResult<()>> {
-        let socket_arg = format!("--to={}", self.socket);
-        let match_arg = format!("--match=id:{}", command.tab_id);
-        let args = ["@", &socket_arg, "focus-tab", &match_arg];
+    /// Retry idempotent commands after transient failures as `retry` says
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = retry;
+        self
+    }
 
-        debug!(
-            "Running kitten @ --to={} focus-tab --match=id:{}",
-            self.socket, command.tab_id
-        );
+    /// How long a single `kitten` call may run
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
 
-        let status = Command::new("kitten").args(args).status()?;
+    /// How idempotent commands are retried
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry
+    }
 
-        if status.success() {
-            Ok(KittyCommandResult::success_empty())
-        } else {
-            Ok(KittyCommandResult::error(format!(
-                "Failed to focus tab {}",
-                command.tab_id
-            )))
+    /// The socket commands are sent to, if a kitty was found
+    pub fn socket(&self) -> Option<&str> {
+        self.socket.as_deref().ok()
+    }
+
+    /// The kitten binary commands are run with
+    pub(crate) fn kitten(&self) -> &Path {
+        &self.kitten
+    }
+
+    /// The environment variable kitten reads the password from, when it is set directly
+    pub(crate) fn password_env(&self) -> Option<&'static str> {
+        let (_, env) = self.password.as_ref()?.kitten_args();
+        env.map(|(name, _)| name)
+    }
+
+    /// The arguments after `kitten` for `invocation`: `@ --to=<socket> <command> <args>`
+    pub(crate) fn kitten_args(&self, invocation: &Invocation) -> KittyResult<Vec<String>> {
+        let socket = self.socket.as_ref().map_err(|tried| KittyError::NoSocket {
+            tried: tried.clone(),
+        })?;
+
+        let mut args = vec!["@".to_string(), format!("--to={}", socket)];
+        if let Some(password) = &self.password {
+            let (password_arg, _) = password.kitten_args();
+            args.push(password_arg);
         }
+        args.push(invocation.command.to_string());
+        args.extend(invocation.args.iter().cloned());
+        Ok(args)
     }
 
-    fn close_tab(&self, command: KittenCloseTabCommand) -> Result<KittyCommandResult<()>> {
-        let socket_arg = format!("--to={}", self.socket);
-        let match_arg = format!("--match=id:{}", command.tab_id);
-        let args = ["@", &socket_arg, "close-tab", &match_arg];
+    /// `kitten @ --to=<socket> <command> <args>` for `invocation`, ready to spawn
+    pub(crate) fn command(&self, invocation: &Invocation) -> KittyResult<Command> {
+        let args = self.kitten_args(invocation)?;
 
         debug!(
-            "Running kitten @ --to={} close-tab --match=id:{}",
-            self.socket, command.tab_id
+            "Running kitten @ {} {}{}",
+            invocation.command,
+            invocation.args.join(" "),
+            invocation
+                .stdin
+                .as_ref()
+                .map(|text| format!(" ({} bytes on stdin)", text.len()))
+                .unwrap_or_default()
         );
 
-        let status = Command::new("kitten").args(args).status()?;
-
-        if status.success() {
-            Ok(KittyCommandResult::success_empty())
-        } else {
-            Ok(KittyCommandResult::error(format!(
-                "Failed to close tab {}",
-                command.tab_id
-            )))
+        let mut kitten = Command::new(&self.kitten);
+        kitten.args(args);
+        if let Some(password) = &self.password
+            && let (_, Some((name, value))) = password.kitten_args()
+        {
+            kitten.env(name, value);
         }
-    }
+        kitten
+            .stdin(if invocation.stdin.is_some() {
+                Stdio::piped()
+            } else {
+                Stdio::null()
+            })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
 
-    fn launch(
-        &self,
-        command: KittenLaunchCommand,
-    ) -> Result<KittyCommandResult<KittyLaunchResponse>> {
-        let socket_arg = format!("--to={}", self.socket);
-        let type_arg = format!("--type={}", command.launch_type);
-        let mut args = vec!["@", &socket_arg, "launch", &type_arg];
+        Ok(kitten)
+    }
 
-        let cwd_formatted;
-        if let Some(cwd) = &command.cwd {
-            cwd_formatted = format!("--cwd={}", cwd);
-            args.push(&cwd_formatted);
+    /// Turn a non-zero exit into a [`KittyError`] classified from kitten's stderr
+    pub(crate) fn check(&self, invocation: &Invocation, output: Output) -> KittyResult<Output> {
+        if output.status.success() {
+            return Ok(output);
         }
 
-        let env_formatted;
-        let mut effective_env = command.env.clone();
-
-        // Handle session inheritance
-        if command.inherit_session {
-            if let Ok(session_project) = env::var("KITTY_SESSION_PROJECT") {
-                if !session_project.is_empty() {
-                    // Check if env is already set, if not add the session
-                    if effective_env.is_none() {
-                        effective_env = Some(format!("KITTY_SESSION_PROJECT={}", session_project));
-                    } else if let Some(ref current_env) = effective_env {
-                        // If env is set but doesn't contain session, append it
-                        if !current_env.contains("KITTY_SESSION_PROJECT=") {
-                            effective_env = Some(format!(
-                                "{},KITTY_SESSION_PROJECT={}",
-                                current_env, session_project
-                            ));
-                        }
-                    }
+        let socket = self.socket().unwrap_or_default();
+        let error = KittyError::from_output(invocation.command, socket, &output);
+        debug!("kitten {} failed: {}", invocation.command, error);
+        Err(error)
+    }
+
+    /// Run `invocation` against the socket, retrying it after transient failures when it is
+    /// idempotent
+    pub(crate) fn run(&self, invocation: &Invocation) -> KittyResult<Output> {
+        let retry = if invocation.idempotent {
+            self.retry
+        } else {
+            RetryPolicy::none()
+        };
+
+        let mut attempt = 0;
+        loop {
+            match self.run_once(invocation) {
+                Err(error) if retry.should_retry(attempt, &error) => {
+                    let delay = retry.delay(attempt);
+                    debug!(
+                        "Retrying kitten {} in {:?} after: {}",
+                        invocation.command, delay, error
+                    );
+                    thread::sleep(delay);
+                    attempt += 1;
                 }
+                result => return result,
             }
         }
+    }
 
-        if let Some(env) = &effective_env {
-            env_formatted = format!("--env={}", env);
-            args.push(&env_formatted);
+    /// The version of the kitty behind the socket, asked once per socket and process
+    ///
+    /// kitty has no remote control command reporting its version, so an `ls` through the socket
+    /// finds kitty's process and that process' own binary is asked for its version. This is the
+    /// running kitty's version even when kitty was upgraded after it started. A kitty reached
+    /// over TCP may run on another machine and fails with [`KittyError::VersionUnknown`]. Only
+    /// an executor without a socket falls back to the local kitten binary.
+    pub fn kitty_version(&self) -> KittyResult<KittyVersion> {
+        let target = match &self.socket {
+            Ok(socket) => ProbeTarget::Socket(socket.clone()),
+            Err(_) => ProbeTarget::Kitten(self.kitten.clone()),
+        };
+        if let Some(version) = probed_versions().get(&target) {
+            return Ok(*version);
         }
 
-        if let Some(tab_title) = &command.tab_title {
-            args.push("--tab-title");
-            args.push(tab_title);
+        // Probe without holding the lock, so a hung kitty only stalls this call
+        let version = match &target {
+            ProbeTarget::Socket(socket) => self.probe_socket(socket)?,
+            ProbeTarget::Kitten(kitten) => self.probe_binary(kitten)?,
+        };
+        probed_versions().insert(target, version);
+        Ok(version)
+    }
+
+    /// Find the kitty process behind `socket` with an `ls` and ask its binary for the version
+    fn probe_socket(&self, socket: &str) -> KittyResult<KittyVersion> {
+        let unknown = |reason: &str| KittyError::VersionUnknown {
+            socket: socket.to_string(),
+            reason: reason.to_string(),
+        };
+        if socket.starts_with("tcp:") || socket.starts_with("tcp6:") {
+            return Err(unknown("kitty may run on another machine"));
         }
 
+        // Run directly rather than through `execute`, whose error handling asks for the version
+        let ls = KittenLsCommand::new();
+        let os_windows = ls.parse_response(&self.run(&Invocation::of(&ls)?)?.stdout)?;
+        let pid = kitty_pid(&os_windows).ok_or_else(|| unknown("no process found for kitty"))?;
+        let binary = process_binary(pid)
+            .ok_or_else(|| unknown(&format!("no binary found for kitty's process {}", pid)))?;
         debug!(
-            "Running kitten @ --to={} launch --type={} {}{}{}{}",
-            self.socket,
-            command.launch_type,
-            command
-                .cwd
-                .as_ref()
-                .map(|c| format!("--cwd={} ", c))
-                .unwrap_or_default(),
-            effective_env
-                .as_ref()
-                .map(|e| format!("--env={} ", e))
-                .unwrap_or_default(),
-            command
-                .tab_title
-                .as_ref()
-                .map(|t| format!("--tab-title={} ", t))
-                .unwrap_or_default(),
-            if command.inherit_session {
-                "(inherit_session)"
-            } else {
-                ""
-            }
+            "kitty at {} is process {} running {}",
+            socket,
+            pid,
+            binary.display()
         );
+        self.probe_binary(&binary)
+    }
 
-        let status = Command::new("kitten").args(&args).status()?;
+    /// Ask a kitty or kitten binary for its version, within the executor's timeout
+    fn probe_binary(&self, binary: &Path) -> KittyResult<KittyVersion> {
+        let child = Command::new(binary)
+            .arg("--version")
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(KittyError::spawn)?;
+        self.round_trips.fetch_add(1, Ordering::Relaxed);
+        let output = match self.timeout {
+            Some(timeout) => {
+                wait_with_timeout(child, timeout)?.ok_or_else(|| KittyError::Timeout {
+                    command: "--version".to_string(),
+                    timeout,
+                })?
+            }
+            None => child.wait_with_output()?,
+        };
+        if !output.status.success() {
+            let socket = self.socket().unwrap_or_default();
+            return Err(KittyError::from_output("--version", socket, &output));
+        }
 
-        if status.success() {
-            // Note: kitten launch doesn't typically return the ID of the created tab/window
-            // This would need to be enhanced if we need the actual IDs
-            Ok(KittyCommandResult::success(KittyLaunchResponse {
-                tab_id: None,
-                window_id: None,
-            }))
-        } else {
-            Ok(KittyCommandResult::error("Failed to launch tab"))
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let version = KittyVersion::parse(&stdout).ok_or_else(|| KittyError::InvalidResponse {
+            command: "--version".to_string(),
+            message: format!("no version in {:?}", stdout.trim()),
+        })?;
+        debug!("{} is version {}", binary.display(), version);
+        Ok(version)
+    }
+
+    /// Blame a failed `invocation` on kitty's version when kitty lacks a feature it needs
+    pub(crate) fn explain(&self, invocation: &Invocation, error: KittyError) -> KittyError {
+        if !invocation.may_need_newer_kitty(&error) {
+            return error;
+        }
+        match self.capabilities() {
+            Ok(capabilities) => invocation.explain(error, &capabilities),
+            Err(_) => error,
         }
     }
 
-    fn navigate_tab(&self, command: KittenNavigateTabCommand) -> Result<KittyCommandResult<()>> {
-        let session_name = command.session_name.as_deref().unwrap_or("unnamed");
+    /// Run `invocation` once, feeding its stdin text when given and killing kitten when it
+    /// outlives the timeout
+    fn run_once(&self, invocation: &Invocation) -> KittyResult<Output> {
+        let mut child = self
+            .command(invocation)?
+            .spawn()
+            .map_err(KittyError::spawn)?;
+        self.round_trips.fetch_add(1, Ordering::Relaxed);
+
+        let writer = invocation
+            .stdin
+            .clone()
+            .zip(child.stdin.take())
+            .map(|(text, pipe)| write_stdin(pipe, text));
+
+        let output = match self.timeout {
+            Some(timeout) => {
+                wait_with_timeout(child, timeout)?.ok_or_else(|| KittyError::Timeout {
+                    command: invocation.command.to_string(),
+                    timeout,
+                })?
+            }
+            None => child.wait_with_output()?,
+        };
 
-        // Get all tabs for the session
-        let mut session_tabs = Vec::new();
+        // A kitten that exits without reading all of its input is reported by `check`
+        if let Some(writer) = writer
+            && let Ok(Err(error)) = writer.join()
+            && error.kind() != io::ErrorKind::BrokenPipe
+        {
+            return Err(error.into());
+        }
+        self.check(invocation, output)
+    }
+}
 
-        if session_name != "unnamed" {
-            // First try tab title matching for named sessions
-            let session_title_pattern = format!("session:{}", session_name);
-            let ls_command_title = KittenLsCommand::new().match_tab_title(&session_title_pattern);
+/// The pid of the kitty an `ls` came from: the `KITTY_PID` kitty puts in its windows'
+/// environment, or the parent of a window's process when `ls` leaves the environment out
+fn kitty_pid(os_windows: &KittyLsResponse) -> Option<u32> {
+    let windows = || {
+        os_windows
+            .iter()
+            .flat_map(|os_window| &os_window.tabs)
+            .flat_map(|tab| &tab.windows)
+    };
+    windows()
+        .find_map(|window| window.env.get("KITTY_PID")?.parse().ok())
+        .or_else(|| windows().find_map(|window| ps(window.pid, "ppid=")?.parse().ok()))
+}
 
-            if let Ok(os_windows) = self.ls(ls_command_title) {
-                for os_window in os_windows {
-                    session_tabs.extend(os_window.tabs);
-                }
-            }
+/// The binary a local process runs
+///
+/// Where there is a `/proc`, its `exe` link runs the very binary the process started from, even
+/// after it was replaced on disk; elsewhere, e.g. on macOS, `ps` reports the full path.
+fn process_binary(pid: u32) -> Option<PathBuf> {
+    let exe = PathBuf::from(format!("/proc/{}/exe", pid));
+    if exe.exists() {
+        return Some(exe);
+    }
+    ps(pid, "comm=")
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+}
 
-            // Also include tabs matched by environment variable for backward compatibility
-            let ls_command_env =
-                KittenLsCommand::new().match_tab_env("KITTY_SESSION_PROJECT", session_name);
-
-            if let Ok(os_windows) = self.ls(ls_command_env) {
-                for os_window in os_windows {
-                    for tab in os_window.tabs {
-                        // Only add if not already included (check by ID)
-                        if !session_tabs.iter().any(|existing| existing.id == tab.id) {
-                            session_tabs.push(tab);
-                        }
-                    }
-                }
-            }
-        } else {
-            // For unnamed session, get all tabs and filter out those with session env var or session title
-            let ls_command = KittenLsCommand::new();
-            let os_windows = self.ls(ls_command)?;
-
-            for os_window in os_windows {
-                for tab in os_window.tabs {
-                    let has_session_env = tab
-                        .windows
-                        .iter()
-                        .any(|w| w.env.contains_key("KITTY_SESSION_PROJECT"));
-                    let has_session_title = tab.title.starts_with("session:");
-                    if !has_session_env && !has_session_title {
-                        session_tabs.push(tab);
-                    }
-                }
+/// One `ps -o <field>` column for a process
+fn ps(pid: u32, field: &str) -> Option<String> {
+    let output = Command::new("ps")
+        .args(["-o", field, "-p", &pid.to_string()])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+    let value = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!value.is_empty()).then_some(value)
+}
+
+/// Write `text` to a child's stdin on its own thread, closing the pipe once it is written
+///
+/// Writing from the calling thread would block before the timed wait starts whenever the text
+/// is larger than the pipe buffer and the kitten isn't reading.
+fn write_stdin(
+    mut pipe: impl Write + Send + 'static,
+    text: String,
+) -> thread::JoinHandle<io::Result<()>> {
+    thread::spawn(move || pipe.write_all(text.as_bytes()))
+}
+
+/// Wait for `child` to exit, or kill it once `timeout` has passed and return `None`
+///
+/// stdout and stderr are drained on their own threads so a chatty kitten can't block on a
+/// full pipe while we wait.
+fn wait_with_timeout(mut child: Child, timeout: Duration) -> io::Result<Option<Output>> {
+    fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+        thread::spawn(move || {
+            let mut buffer = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buffer);
             }
-        }
+            buffer
+        })
+    }
 
-        // Sort tabs by ID to maintain consistent order
-        session_tabs.sort_by_key(|t| t.id);
+    let stdout = drain(child.stdout.take());
+    let stderr = drain(child.stderr.take());
+    let deadline = Instant::now() + timeout;
 
-        if session_tabs.is_empty() {
-            return Ok(KittyCommandResult::error(format!(
-                "No tabs found in session '{}'",
-                session_name
-            )));
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
         }
-
-        if session_tabs.len() == 1 {
-            // Only one tab, nothing to navigate to
-            return Ok(KittyCommandResult::success_empty());
+        if Instant::now() >= deadline {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Ok(None);
         }
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    Ok(Some(Output {
+        status,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+    }))
+}
 
-        // Sort tabs by ID to maintain consistent order
-        session_tabs.sort_by_key(|t| t.id);
+impl Default for KittyExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
-        // Find the currently active tab
-        let current_active = session_tabs.iter().position(|t| t.is_active);
+impl CommandExecutor for KittyExecutor {
+    fn ls(&self, command: KittenLsCommand) -> KittyResult<KittyLsResponse> {
+        self.execute(command)
+    }
 
-        let current_index = current_active.unwrap_or(0);
+    fn focus_tab(&self, command: KittenFocusTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
 
-        // Calculate next index based on direction
-        let next_index = match command.direction {
-            TabNavigationDirection::Next => {
-                if current_index + 1 >= session_tabs.len() {
-                    if command.allow_wrap { 0 } else { current_index }
-                } else {
-                    current_index + 1
-                }
-            }
-            TabNavigationDirection::Previous => {
-                if current_index == 0 {
-                    if command.allow_wrap {
-                        session_tabs.len() - 1
-                    } else {
-                        0
-                    }
-                } else {
-                    current_index - 1
-                }
-            }
-        };
+    fn close_tab(&self, command: KittenCloseTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
 
-        // If no change needed due to no-wrap
-        if next_index == current_index {
-            return Ok(KittyCommandResult::success_empty());
-        }
+    fn launch(&self, command: KittenLaunchCommand) -> KittyResult<KittyLaunchResponse> {
+        self.execute(command)
+    }
 
-        let target_tab_id = session_tabs[next_index].id;
+    fn navigate_tab(&self, command: KittenNavigateTabCommand) -> KittyResult<()> {
+        invocation::navigate_tab(self, command)
+    }
 
-        debug!(
-            "Navigating {:?} in session '{}' from tab {} to tab {}",
-            command.direction, session_name, session_tabs[current_index].id, target_tab_id
+    fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn send_text(&self, command: KittenSendTextCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn get_text(&self, command: KittenGetTextCommand) -> KittyResult<String> {
+        self.execute(command)
+    }
+
+    fn focus_window(&self, command: KittenFocusWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn close_window(&self, command: KittenCloseWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn set_window_title(&self, command: KittenSetWindowTitleCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn resize_window(&self, command: KittenResizeWindowCommand) -> KittyResult<bool> {
+        self.execute(command)
+    }
+
+    fn goto_layout(&self, command: KittenGotoLayoutCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn last_used_layout(&self, command: KittenLastUsedLayoutCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn detach_window(&self, command: KittenDetachWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn detach_tab(&self, command: KittenDetachTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn set_user_vars(&self, command: KittenSetUserVarsCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn capabilities(&self) -> KittyResult<KittyCapabilities> {
+        self.kitty_version().map(KittyCapabilities::of)
+    }
+
+    fn round_trips(&self) -> Option<usize> {
+        Some(self.round_trips.load(Ordering::Relaxed))
+    }
+
+    fn execute<C: KittenCommand>(&self, command: C) -> KittyResult<C::Response> {
+        let invocation = Invocation::of(&command)?;
+        let output = self
+            .run(&invocation)
+            .map_err(|error| self.explain(&invocation, error))?;
+        command.parse_response(&output.stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(program: &str, args: &[&str]) -> Child {
+        Command::new(program)
+            .args(args)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_wait_with_timeout_kills_hung_process() {
+        let started = Instant::now();
+        let output = wait_with_timeout(spawn("sleep", &["5"]), Duration::from_millis(50)).unwrap();
+
+        assert!(output.is_none());
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_wait_with_timeout_collects_output() {
+        let child = spawn("sh", &["-c", "echo out; echo err >&2; exit 3"]);
+        let output = wait_with_timeout(child, Duration::from_secs(5))
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn test_timeout_covers_writing_stdin() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A kitten that never reads its input, so a large send-text fills the pipe
+        let dir = tempfile::tempdir().unwrap();
+        let kitten = dir.path().join("kitten");
+        std::fs::write(&kitten, "#!/bin/sh\nsleep 5\n").unwrap();
+        std::fs::set_permissions(&kitten, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let executor = KittyExecutor::with_socket("unix:/tmp/kitty-stdin-test")
+            .with_kitten(&kitten)
+            .with_timeout(Duration::from_millis(200));
+        let text = "x".repeat(4 * 1024 * 1024);
+        let invocation = Invocation::of(&KittenSendTextCommand::new(text).from_stdin()).unwrap();
+
+        let started = Instant::now();
+        let result = executor.run_once(&invocation);
+
+        assert!(matches!(result, Err(KittyError::Timeout { .. })));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_resize_window_reports_whether_kitty_resized() {
+        use crate::commands::resize_window::ResizeAxis;
+        use std::os::unix::fs::PermissionsExt;
+
+        // kitty can't resize vertically here, but leaves the call successful
+        let dir = tempfile::tempdir().unwrap();
+        let kitten = dir.path().join("kitten");
+        std::fs::write(
+            &kitten,
+            "#!/bin/sh\ncase \"$*\" in *vertical*) echo 'Could not resize' ;; esac\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&kitten, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let executor =
+            KittyExecutor::with_socket("unix:/tmp/kitty-resize-test").with_kitten(&kitten);
+
+        let resize = |axis| executor.resize_window(KittenResizeWindowCommand::new(2, axis));
+        assert!(resize(ResizeAxis::Horizontal).unwrap());
+        assert!(!resize(ResizeAxis::Vertical).unwrap());
+        assert!(resize(ResizeAxis::Reset).unwrap());
+    }
+
+    #[test]
+    fn test_timed_out_calls_are_not_retried() {
+        use std::os::unix::fs::PermissionsExt;
+
+        // A hung kitten that leaves a line behind for every run
+        let dir = tempfile::tempdir().unwrap();
+        let runs = dir.path().join("runs");
+        let kitten = dir.path().join("kitten");
+        std::fs::write(
+            &kitten,
+            format!("#!/bin/sh\necho run >> '{}'\nsleep 5\n", runs.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&kitten, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let executor = KittyExecutor::with_socket("unix:/tmp/kitty-retry-test")
+            .with_kitten(&kitten)
+            .with_timeout(Duration::from_millis(200))
+            .with_retry(RetryPolicy::new(2, Duration::from_millis(10)));
+        let ls = Invocation::of(&KittenLsCommand::new()).unwrap();
+        assert!(ls.idempotent);
+
+        let started = Instant::now();
+        let result = executor.run(&ls);
+
+        assert!(matches!(result, Err(KittyError::Timeout { .. })));
+        assert_eq!(std::fs::read_to_string(&runs).unwrap().lines().count(), 1);
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_middleware_sees_every_round_trip() {
+        use crate::executor::{CallTimer, LayeredExecutor};
+        use std::os::unix::fs::PermissionsExt;
+        use std::rc::Rc;
+
+        // Fails to connect on its first run, then answers `ls` and `--version`
+        let dir = tempfile::tempdir().unwrap();
+        let runs = dir.path().join("runs");
+        let kitten = dir.path().join("kitten");
+        std::fs::write(
+            &kitten,
+            format!(
+                "#!/bin/sh\n\
+                 [ \"$1\" = --version ] && {{ echo 'kitten 0.31.2'; exit; }}\n\
+                 [ -e '{runs}' ] || {{ touch '{runs}'; echo 'Failed to connect' >&2; exit 1; }}\n\
+                 echo '[]'\n",
+                runs = runs.display()
+            ),
+        )
+        .unwrap();
+        std::fs::set_permissions(&kitten, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let timer = Rc::new(CallTimer::new());
+        let executor = LayeredExecutor::new(
+            KittyExecutor::with_socket("unix:/tmp/kitty-round-trip-test")
+                .with_kitten(&kitten)
+                .with_retry(RetryPolicy::new(2, Duration::from_millis(10))),
+            Rc::clone(&timer),
+        );
+        executor.ls(KittenLsCommand::new()).unwrap();
+
+        let without_socket = LayeredExecutor::new(
+            KittyExecutor::discover(
+                &SocketDiscovery::new().listen_on_env("unix:/nonexistent/ksm-round-trip-test"),
+            )
+            .with_kitten(&kitten),
+            Rc::clone(&timer),
         );
+        without_socket.capabilities().unwrap();
+        // A cached version sends nothing and is no call
+        without_socket.capabilities().unwrap();
+
+        let calls: Vec<_> = timer
+            .timings()
+            .iter()
+            .map(|timing| (timing.call.command, timing.round_trips()))
+            .collect();
+        assert_eq!(calls, [("ls", 2), ("kitty-version", 1)]);
+        assert_eq!(timer.round_trips(), 3);
+    }
 
-        // Focus the target tab
-        let focus_command = KittenFocusTabCommand::new(target_tab_id);
-        self.focus_tab(focus_command)
+    #[test]
+    fn test_kitty_version_probe_times_out() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let kitten = dir.path().join("kitten");
+        std::fs::write(&kitten, "#!/bin/sh\nsleep 5\n").unwrap();
+        std::fs::set_permissions(&kitten, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let socket = format!("unix:/tmp/kitty-hung-{}", std::process::id());
+        let executor = KittyExecutor::with_socket(&socket)
+            .with_kitten(&kitten)
+            .with_timeout(Duration::from_millis(200))
+            .with_retry(RetryPolicy::none());
+
+        let started = Instant::now();
+        assert!(matches!(
+            executor.kitty_version(),
+            Err(KittyError::Timeout { .. })
+        ));
+        assert!(started.elapsed() < Duration::from_secs(2));
+        assert!(!probed_versions().contains_key(&ProbeTarget::Socket(socket)));
     }
 
-    fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> Result<KittyCommandResult<()>> {
-        let socket_arg = format!("--to={}", self.socket);
-        let mut args = vec!["@", &socket_arg, "set-tab-title"];
+    #[test]
+    fn test_kitty_version_is_cached_per_socket() {
+        let socket = |name: &str| format!("unix:/tmp/kitty-{}-{}", name, std::process::id());
+        remember_version(&socket("old"), KittyVersion::new(0, 26, 5));
+        let kitten = PathBuf::from("/nonexistent/kitten");
+
+        let old = KittyExecutor::with_socket(socket("old")).with_kitten(&kitten);
+        assert_eq!(old.kitty_version().unwrap(), KittyVersion::new(0, 26, 5));
+
+        // Same kitten, another kitty: probed on its own rather than answered from the cache
+        let other = KittyExecutor::with_socket(socket("other")).with_kitten(&kitten);
+        assert!(matches!(
+            other.kitty_version(),
+            Err(KittyError::KittenNotFound)
+        ));
+    }
 
-        let match_formatted;
-        if let Some(match_pattern) = &command.match_pattern {
-            match_formatted = format!("--match={}", match_pattern);
-            args.push(&match_formatted);
-            debug!(
-                "Running kitten @ --to={} set-tab-title --match={} '{}'",
-                self.socket, match_pattern, command.title
-            );
-        } else {
-            debug!(
-                "Running kitten @ --to={} set-tab-title '{}'",
-                self.socket, command.title
-            );
-        }
+    #[test]
+    fn test_kitty_version_over_tcp_is_unknown() {
+        // The kitten would fail with KittenNotFound if it were run
+        let executor =
+            KittyExecutor::with_socket("tcp:build-host:12345").with_kitten("/nonexistent/kitten");
+
+        assert!(matches!(
+            executor.kitty_version(),
+            Err(KittyError::VersionUnknown { socket, .. }) if socket == "tcp:build-host:12345"
+        ));
+        assert!(executor.capabilities().is_err());
+    }
+
+    #[test]
+    fn test_kitty_version_without_socket_asks_local_kitten() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let kitten = dir.path().join("kitten");
+        std::fs::write(
+            &kitten,
+            "#!/bin/sh\necho 'kitten 0.31.2 created by Kovid Goyal'\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&kitten, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let executor = KittyExecutor::discover(
+            &SocketDiscovery::new().listen_on_env("unix:/nonexistent/ksm-version-test"),
+        )
+        .with_kitten(&kitten);
+
+        assert_eq!(executor.socket(), None);
+        assert_eq!(
+            executor.kitty_version().unwrap(),
+            KittyVersion::new(0, 31, 2)
+        );
+    }
 
-        args.push(&command.title);
+    #[test]
+    fn test_kitty_pid_falls_back_to_the_window_parent() {
+        let window = |pid: u32, env: serde_json::Value| serde_json::json!({"id": 1, "title": "zsh", "pid": pid, "cwd": "/", "cmdline": [], "env": env});
+        let ls = |windows: Vec<serde_json::Value>| -> KittyLsResponse {
+            serde_json::from_value(serde_json::json!([{
+                "id": 1, "is_focused": true,
+                "tabs": [{"id": 1, "title": "zsh", "is_active": true, "windows": windows}]
+            }]))
+            .unwrap()
+        };
 
-        let status = Command::new("kitten").args(args).status()?;
+        let mut child = spawn("sleep", &["5"]);
+        let with_env = ls(vec![window(
+            child.id(),
+            serde_json::json!({"KITTY_PID": "4242"}),
+        )]);
+        let without_env = ls(vec![window(child.id(), serde_json::json!({}))]);
+
+        assert_eq!(kitty_pid(&with_env), Some(4242));
+        assert_eq!(kitty_pid(&without_env), Some(std::process::id()));
+        assert!(process_binary(child.id()).is_some());
+        child.kill().unwrap();
+        child.wait().unwrap();
+    }
 
-        if status.success() {
-            Ok(KittyCommandResult::success_empty())
-        } else {
-            Ok(KittyCommandResult::error("Failed to set tab title"))
-        }
+    #[test]
+    fn test_builder_configures_timeout_and_retry() {
+        let executor = KittyExecutor::with_socket("unix:/tmp/kitty");
+        assert_eq!(executor.timeout(), Some(DEFAULT_TIMEOUT));
+        assert_eq!(executor.retry_policy(), RetryPolicy::default());
+
+        let executor = executor.without_timeout().with_retry(RetryPolicy::none());
+        assert_eq!(executor.timeout(), None);
+        assert_eq!(executor.retry_policy().retries, 0);
+    }
+
+    #[test]
+    fn test_failures_are_blamed_on_an_outdated_kitty() {
+        let socket = format!("unix:/tmp/kitty-outdated-{}", std::process::id());
+        remember_version(&socket, KittyVersion::new(0, 26, 5));
+        let executor = KittyExecutor::with_socket(socket);
+        let failed = || KittyError::CommandFailed {
+            command: "ls".to_string(),
+            exit_code: Some(1),
+            stderr: "Error: unknown flag: --match-tab".to_string(),
+        };
+
+        let ls = Invocation::of(&KittenLsCommand::new().match_tab_title("session:api")).unwrap();
+        assert!(matches!(
+            executor.explain(&ls, failed()),
+            KittyError::Unsupported {
+                capability: crate::Capability::LsMatch,
+                found
+            } if found == KittyVersion::new(0, 26, 5)
+        ));
+
+        // Nothing version dependent, or kitten already said what went wrong
+        let focus = Invocation::of(&KittenFocusTabCommand::new(3)).unwrap();
+        assert!(matches!(
+            executor.explain(&focus, failed()),
+            KittyError::CommandFailed { .. }
+        ));
+        assert!(matches!(
+            executor.explain(&ls, KittyError::no_match("ls", "tabs", "title:session:api")),
+            KittyError::NoMatch { .. }
+        ));
     }
 }
@@ -0,0 +1,306 @@
+//! kitty's `--match` / `--match-tab` expressions, for evaluating them against [`MockLayout`]
+//!
+//! An expression is `field:pattern` terms combined with `and`, `or`, `not` and parentheses;
+//! adjacent terms without an operator are joined with `and`, and `all` on its own matches
+//! everything. Patterns may be quoted with `"` to include spaces or parentheses. Like kitty,
+//! text patterns are regular expressions found anywhere in the value, so `title:api` matches
+//! `session:api-v2` too.
+//!
+//! [`MockLayout`]: super::mock_executor::MockLayout
+
+use regex::Regex;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum MatchExpression {
+    All,
+    Term { field: String, pattern: String },
+    Not(Box<MatchExpression>),
+    And(Box<MatchExpression>, Box<MatchExpression>),
+    Or(Box<MatchExpression>, Box<MatchExpression>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Word(String),
+    /// A word that was quoted in whole or in part, so never an operator
+    Quoted(String),
+}
+
+impl MatchExpression {
+    pub(crate) fn parse(expression: &str) -> Result<Self, String> {
+        if expression.trim() == "all" {
+            return Ok(Self::All);
+        }
+
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let parsed = parser.or()?;
+        match parser.peek() {
+            None => Ok(parsed),
+            Some(token) => Err(format!("unexpected {:?} in '{}'", token, expression)),
+        }
+    }
+
+    /// Whether the expression holds, given whether each `field:pattern` term does
+    pub(crate) fn evaluate(&self, term: &mut impl FnMut(&str, &str) -> bool) -> bool {
+        match self {
+            Self::All => true,
+            Self::Term { field, pattern } => term(field, pattern),
+            Self::Not(inner) => !inner.evaluate(term),
+            Self::And(left, right) => left.evaluate(term) && right.evaluate(term),
+            Self::Or(left, right) => left.evaluate(term) || right.evaluate(term),
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                let mut word = String::new();
+                let mut quoted = false;
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    chars.next();
+                    if c != '"' {
+                        word.push(c);
+                        continue;
+                    }
+
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some('\\') => word.extend(chars.next()),
+                            Some(c) => word.push(c),
+                            None => return Err(format!("unterminated quote in '{}'", expression)),
+                        }
+                    }
+                }
+                tokens.push(if quoted {
+                    Token::Quoted(word)
+                } else {
+                    Token::Word(word)
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive descent over `or` < `and` < `not`
+struct Parser<'a> {
+    tokens: &'a [Token],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.position);
+        self.position += 1;
+        token
+    }
+
+    fn at_operator(&self, operator: &str) -> bool {
+        matches!(self.peek(), Some(Token::Word(word)) if word.eq_ignore_ascii_case(operator))
+    }
+
+    fn or(&mut self) -> Result<MatchExpression, String> {
+        let mut left = self.and()?;
+        while self.at_operator("or") {
+            self.next();
+            left = MatchExpression::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+
+    fn and(&mut self) -> Result<MatchExpression, String> {
+        let mut left = self.not()?;
+        loop {
+            if self.at_operator("and") {
+                self.next();
+            } else if self.peek().is_none()
+                || self.peek() == Some(&Token::Close)
+                || self.at_operator("or")
+            {
+                return Ok(left);
+            }
+            left = MatchExpression::And(Box::new(left), Box::new(self.not()?));
+        }
+    }
+
+    fn not(&mut self) -> Result<MatchExpression, String> {
+        if self.at_operator("not") {
+            self.next();
+            return Ok(MatchExpression::Not(Box::new(self.not()?)));
+        }
+
+        match self.next().cloned() {
+            Some(Token::Open) => {
+                let inner = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err("missing closing parenthesis".to_string()),
+                }
+            }
+            Some(Token::Word(word) | Token::Quoted(word)) => match word.split_once(':') {
+                Some((field, pattern)) => Ok(MatchExpression::Term {
+                    field: field.to_string(),
+                    pattern: pattern.to_string(),
+                }),
+                None => Err(format!("'{}' is not a field:pattern term", word)),
+            },
+            Some(Token::Close) => Err("unexpected closing parenthesis".to_string()),
+            None => Err("expression ended early".to_string()),
+        }
+    }
+}
+
+/// Whether `pattern`, a regular expression, is found anywhere in `value`
+///
+/// Invalid patterns match nothing.
+pub(crate) fn regex_search(pattern: &str, value: &str) -> bool {
+    Regex::new(pattern).is_ok_and(|regex| regex.is_match(value))
+}
+
+/// Evaluate an `env:` or `var:` pattern, `KEY[=VALUE]`, against name/value pairs
+///
+/// Both halves are regular expressions; without `=VALUE` any value matches.
+pub(crate) fn key_value_search<'a>(
+    pattern: &str,
+    items: impl IntoIterator<Item = (&'a String, &'a String)>,
+) -> bool {
+    let (key_pattern, value_pattern) = match pattern.split_once('=') {
+        Some((key, value)) => (key, Some(value)),
+        None => (pattern, None),
+    };
+    let Ok(key_regex) = Regex::new(key_pattern) else {
+        return false;
+    };
+    let value_regex = match value_pattern.map(Regex::new).transpose() {
+        Ok(value_regex) => value_regex,
+        Err(_) => return false,
+    };
+
+    items.into_iter().any(|(key, value)| {
+        key_regex.is_match(key)
+            && value_regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn term(field: &str, pattern: &str) -> MatchExpression {
+        MatchExpression::Term {
+            field: field.to_string(),
+            pattern: pattern.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_precedence_and_grouping() {
+        let parsed = MatchExpression::parse("id:1 or title:a and not state:active").unwrap();
+        assert_eq!(
+            parsed,
+            MatchExpression::Or(
+                Box::new(term("id", "1")),
+                Box::new(MatchExpression::And(
+                    Box::new(term("title", "a")),
+                    Box::new(MatchExpression::Not(Box::new(term("state", "active")))),
+                )),
+            )
+        );
+
+        let grouped = MatchExpression::parse("(id:1 or id:2) var:k").unwrap();
+        assert_eq!(
+            grouped,
+            MatchExpression::And(
+                Box::new(MatchExpression::Or(
+                    Box::new(term("id", "1")),
+                    Box::new(term("id", "2")),
+                )),
+                Box::new(term("var", "k")),
+            )
+        );
+
+        assert_eq!(
+            MatchExpression::parse(r#"title:"session:api - (tests)""#).unwrap(),
+            term("title", "session:api - (tests)")
+        );
+        assert_eq!(
+            MatchExpression::parse("title:session:api").unwrap(),
+            term("title", "session:api")
+        );
+        assert_eq!(
+            MatchExpression::parse(" all ").unwrap(),
+            MatchExpression::All
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(MatchExpression::parse("").is_err());
+        assert!(MatchExpression::parse("(id:1").is_err());
+        assert!(MatchExpression::parse("id:1)").is_err());
+        assert!(MatchExpression::parse("id:1 or").is_err());
+        assert!(MatchExpression::parse("api").is_err());
+        assert!(MatchExpression::parse(r#"title:"open"#).is_err());
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let expression = MatchExpression::parse("not (id:1 or id:2) and id:3").unwrap();
+        assert!(expression.evaluate(&mut |_, pattern| pattern == "3"));
+        assert!(!expression.evaluate(&mut |_, pattern| pattern != "4"));
+    }
+
+    #[test]
+    fn test_regex_search() {
+        assert!(regex_search("api", "session:api-v2"));
+        assert!(regex_search("^session:api( - |$)", "session:api - tests"));
+        assert!(!regex_search("^session:api( - |$)", "session:api-v2"));
+        assert!(!regex_search("(", "("));
+    }
+
+    #[test]
+    fn test_key_value_search() {
+        let vars = HashMap::from([("ksm_session".to_string(), "api-v2".to_string())]);
+
+        assert!(key_value_search("ksm_session=api", &vars));
+        assert!(!key_value_search("ksm_session=^api$", &vars));
+        assert!(key_value_search("ksm_session", &vars));
+        assert!(key_value_search("ksm", &vars));
+        assert!(!key_value_search("other=api", &vars));
+    }
+}
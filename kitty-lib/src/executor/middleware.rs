@@ -0,0 +1,627 @@
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use log::debug;
+
+use crate::commands::KittenCommand;
+use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::close_window::KittenCloseWindowCommand;
+use crate::commands::detach::{KittenDetachTabCommand, KittenDetachWindowCommand};
+use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::focus_window::KittenFocusWindowCommand;
+use crate::commands::get_text::KittenGetTextCommand;
+use crate::commands::launch::KittenLaunchCommand;
+use crate::commands::layout::{KittenGotoLayoutCommand, KittenLastUsedLayoutCommand};
+use crate::commands::ls::KittenLsCommand;
+use crate::commands::navigate_tab::KittenNavigateTabCommand;
+use crate::commands::resize_window::KittenResizeWindowCommand;
+use crate::commands::send_text::KittenSendTextCommand;
+use crate::commands::set_tab_title::KittenSetTabTitleCommand;
+use crate::commands::set_user_vars::KittenSetUserVarsCommand;
+use crate::commands::set_window_title::KittenSetWindowTitleCommand;
+use crate::error::{KittyError, KittyResult};
+use crate::executor::CommandExecutor;
+use crate::executor::invocation;
+use crate::executor::recording::RecordedCall;
+use crate::types::{KittyLaunchResponse, KittyLsResponse};
+use crate::version::KittyCapabilities;
+
+/// One kitten call as middleware sees it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call {
+    pub command: &'static str,
+    pub args: Vec<String>,
+    /// Requests the call sent to kitty, counting retries and any version probe it triggered
+    ///
+    /// 1 until the call returned, and left at 1 when the inner executor doesn't count them.
+    pub round_trips: usize,
+}
+
+impl Call {
+    /// The call `command` turns into; invalid arguments leave `args` empty
+    pub fn of<C: KittenCommand + ?Sized>(command: &C) -> Self {
+        Self {
+            command: command.name(),
+            args: command.args().unwrap_or_default(),
+            round_trips: 1,
+        }
+    }
+
+    /// The `--match` or `--match-tab` expression selecting what the call acts on
+    pub fn match_expression(&self) -> Option<&str> {
+        self.args.iter().find_map(|arg| {
+            arg.strip_prefix("--match=")
+                .or_else(|| arg.strip_prefix("--match-tab="))
+        })
+    }
+}
+
+/// The call as a kitten command line, e.g. `focus-tab --match=id:3`
+impl fmt::Display for Call {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&RecordedCall::describe(self.command, &self.args))
+    }
+}
+
+/// Observes the calls going through a [`LayeredExecutor`]
+///
+/// Both hooks default to doing nothing, so a layer only implements the ones it needs.
+pub trait Middleware {
+    /// Called right before `call` is sent to the inner executor
+    fn before(&self, _call: &Call) {}
+
+    /// Called once `call` returned after `elapsed`, with the error it failed with
+    fn after(&self, _call: &Call, _elapsed: Duration, _error: Option<&KittyError>) {}
+}
+
+impl<M: Middleware + ?Sized> Middleware for &M {
+    fn before(&self, call: &Call) {
+        (**self).before(call)
+    }
+
+    fn after(&self, call: &Call, elapsed: Duration, error: Option<&KittyError>) {
+        (**self).after(call, elapsed, error)
+    }
+}
+
+/// Lets callers keep a handle on a layer, e.g. to read a [`CallTimer`] once a command ran
+impl<M: Middleware + ?Sized> Middleware for Rc<M> {
+    fn before(&self, call: &Call) {
+        (**self).before(call)
+    }
+
+    fn after(&self, call: &Call, elapsed: Duration, error: Option<&KittyError>) {
+        (**self).after(call, elapsed, error)
+    }
+}
+
+/// A layer that is only switched on by configuration, doing nothing when `None`
+impl<M: Middleware> Middleware for Option<M> {
+    fn before(&self, call: &Call) {
+        if let Some(middleware) = self {
+            middleware.before(call);
+        }
+    }
+
+    fn after(&self, call: &Call, elapsed: Duration, error: Option<&KittyError>) {
+        if let Some(middleware) = self {
+            middleware.after(call, elapsed, error);
+        }
+    }
+}
+
+/// Two layers in one, the first wrapping the second: `before` runs first to last and
+/// `after` last to first
+impl<A: Middleware, B: Middleware> Middleware for (A, B) {
+    fn before(&self, call: &Call) {
+        self.0.before(call);
+        self.1.before(call);
+    }
+
+    fn after(&self, call: &Call, elapsed: Duration, error: Option<&KittyError>) {
+        self.1.after(call, elapsed, error);
+        self.0.after(call, elapsed, error);
+    }
+}
+
+/// [`CommandExecutor`] that runs every call of `inner` through a [`Middleware`]
+///
+/// `navigate_tab` is observed as the `ls` and `focus-tab` calls it is made of. Middleware sees
+/// each call once however often the inner executor retries it, with the requests it took in
+/// [`Call::round_trips`], and a version probe as a `kitty-version` call.
+/// Stack layers with [`layer`](Self::layer) or by passing a tuple.
+///
+/// ```
+/// use kitty_lib::{CallCounter, CallLogger, CommandExecutor, KittenLsCommand, LayeredExecutor};
+/// use kitty_lib::MockExecutor;
+///
+/// let executor = LayeredExecutor::new(MockExecutor::new(), CallCounter::new()).layer(CallLogger);
+/// executor.ls(KittenLsCommand::new()).unwrap();
+/// assert_eq!(executor.inner().middleware().count("ls"), 1);
+/// ```
+#[derive(Debug)]
+pub struct LayeredExecutor<E, M> {
+    inner: E,
+    middleware: M,
+}
+
+impl<E: CommandExecutor, M: Middleware> LayeredExecutor<E, M> {
+    pub fn new(inner: E, middleware: M) -> Self {
+        Self { inner, middleware }
+    }
+
+    /// Wrap this executor in one more layer, which sees every call before this one does
+    pub fn layer<N: Middleware>(self, middleware: N) -> LayeredExecutor<Self, N> {
+        LayeredExecutor::new(self, middleware)
+    }
+
+    pub fn inner(&self) -> &E {
+        &self.inner
+    }
+
+    pub fn middleware(&self) -> &M {
+        &self.middleware
+    }
+
+    fn observe<T>(&self, mut call: Call, run: impl FnOnce() -> KittyResult<T>) -> KittyResult<T> {
+        self.middleware.before(&call);
+        let round_trips = self.inner.round_trips();
+        let started = Instant::now();
+        let result = run();
+        let elapsed = started.elapsed();
+        if let (Some(before), Some(after)) = (round_trips, self.inner.round_trips()) {
+            call.round_trips = after - before;
+        }
+        self.middleware.after(&call, elapsed, result.as_ref().err());
+        result
+    }
+}
+
+impl<E: CommandExecutor, M: Middleware> CommandExecutor for LayeredExecutor<E, M> {
+    fn ls(&self, command: KittenLsCommand) -> KittyResult<KittyLsResponse> {
+        self.observe(Call::of(&command), || self.inner.ls(command))
+    }
+
+    fn focus_tab(&self, command: KittenFocusTabCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.focus_tab(command))
+    }
+
+    fn close_tab(&self, command: KittenCloseTabCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.close_tab(command))
+    }
+
+    fn launch(&self, command: KittenLaunchCommand) -> KittyResult<KittyLaunchResponse> {
+        self.observe(Call::of(&command), || self.inner.launch(command))
+    }
+
+    fn navigate_tab(&self, command: KittenNavigateTabCommand) -> KittyResult<()> {
+        invocation::navigate_tab(self, command)
+    }
+
+    fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.set_tab_title(command))
+    }
+
+    fn send_text(&self, command: KittenSendTextCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.send_text(command))
+    }
+
+    fn get_text(&self, command: KittenGetTextCommand) -> KittyResult<String> {
+        self.observe(Call::of(&command), || self.inner.get_text(command))
+    }
+
+    fn focus_window(&self, command: KittenFocusWindowCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.focus_window(command))
+    }
+
+    fn close_window(&self, command: KittenCloseWindowCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.close_window(command))
+    }
+
+    fn set_window_title(&self, command: KittenSetWindowTitleCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.set_window_title(command))
+    }
+
+    fn resize_window(&self, command: KittenResizeWindowCommand) -> KittyResult<bool> {
+        self.observe(Call::of(&command), || self.inner.resize_window(command))
+    }
+
+    fn goto_layout(&self, command: KittenGotoLayoutCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.goto_layout(command))
+    }
+
+    fn last_used_layout(&self, command: KittenLastUsedLayoutCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.last_used_layout(command))
+    }
+
+    fn detach_window(&self, command: KittenDetachWindowCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.detach_window(command))
+    }
+
+    fn detach_tab(&self, command: KittenDetachTabCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.detach_tab(command))
+    }
+
+    fn set_user_vars(&self, command: KittenSetUserVarsCommand) -> KittyResult<()> {
+        self.observe(Call::of(&command), || self.inner.set_user_vars(command))
+    }
+
+    /// A version probe that reached kitty is observed as a `kitty-version` call once it
+    /// returned; cached or assumed capabilities are not calls
+    fn capabilities(&self) -> KittyResult<KittyCapabilities> {
+        let round_trips = self.inner.round_trips();
+        let started = Instant::now();
+        let result = self.inner.capabilities();
+        let elapsed = started.elapsed();
+
+        if let (Some(before), Some(after)) = (round_trips, self.inner.round_trips())
+            && after > before
+        {
+            let call = Call {
+                command: "kitty-version",
+                args: Vec::new(),
+                round_trips: after - before,
+            };
+            self.middleware.before(&call);
+            self.middleware.after(&call, elapsed, result.as_ref().err());
+        }
+        result
+    }
+
+    fn round_trips(&self) -> Option<usize> {
+        self.inner.round_trips()
+    }
+
+    fn execute<C: KittenCommand>(&self, command: C) -> KittyResult<C::Response> {
+        self.observe(Call::of(&command), || self.inner.execute(command))
+    }
+}
+
+/// Logs every call with how long it took, at debug level
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CallLogger;
+
+impl Middleware for CallLogger {
+    fn after(&self, call: &Call, elapsed: Duration, error: Option<&KittyError>) {
+        match error {
+            None => debug!("kitten {} took {:.1?}", call, elapsed),
+            Some(error) => debug!("kitten {} failed after {:.1?}: {}", call, elapsed, error),
+        }
+    }
+}
+
+/// How long one call took, as measured by a [`CallTimer`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallTiming {
+    pub call: Call,
+    pub elapsed: Duration,
+    pub succeeded: bool,
+}
+
+impl CallTiming {
+    /// Requests the call sent to kitty, see [`Call::round_trips`]
+    pub fn round_trips(&self) -> usize {
+        self.call.round_trips
+    }
+}
+
+/// Remembers how long each call took, in the order they were made
+#[derive(Debug, Default)]
+pub struct CallTimer {
+    timings: RefCell<Vec<CallTiming>>,
+}
+
+impl CallTimer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The calls timed so far
+    pub fn timings(&self) -> Vec<CallTiming> {
+        self.timings.borrow().clone()
+    }
+
+    /// The requests all calls together sent to kitty
+    pub fn round_trips(&self) -> usize {
+        self.timings
+            .borrow()
+            .iter()
+            .map(CallTiming::round_trips)
+            .sum()
+    }
+
+    /// The time spent in all calls together
+    pub fn total(&self) -> Duration {
+        self.timings
+            .borrow()
+            .iter()
+            .map(|timing| timing.elapsed)
+            .sum()
+    }
+}
+
+impl Middleware for CallTimer {
+    fn after(&self, call: &Call, elapsed: Duration, error: Option<&KittyError>) {
+        self.timings.borrow_mut().push(CallTiming {
+            call: call.clone(),
+            elapsed,
+            succeeded: error.is_none(),
+        });
+    }
+}
+
+/// Counts the calls made, per kitten command
+#[derive(Debug, Default)]
+pub struct CallCounter {
+    counts: RefCell<BTreeMap<&'static str, usize>>,
+}
+
+impl CallCounter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// How often `command`, e.g. `"focus-tab"`, was called
+    pub fn count(&self, command: &str) -> usize {
+        self.counts.borrow().get(command).copied().unwrap_or(0)
+    }
+
+    /// The number of calls made, whatever the command
+    pub fn total(&self) -> usize {
+        self.counts.borrow().values().sum()
+    }
+
+    /// The number of calls per command
+    pub fn counts(&self) -> BTreeMap<&'static str, usize> {
+        self.counts.borrow().clone()
+    }
+}
+
+impl Middleware for CallCounter {
+    fn before(&self, call: &Call) {
+        *self.counts.borrow_mut().entry(call.command).or_default() += 1;
+    }
+}
+
+/// Runs every call in a `kitten` span carrying the command and its match expression
+///
+/// The span records `elapsed_ms` when the call returns, and `error` if it failed.
+#[cfg(feature = "tracing")]
+#[derive(Debug, Default)]
+pub struct CallTracer {
+    spans: RefCell<Vec<tracing::span::EnteredSpan>>,
+}
+
+#[cfg(feature = "tracing")]
+impl CallTracer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg(feature = "tracing")]
+impl Middleware for CallTracer {
+    fn before(&self, call: &Call) {
+        let span = tracing::debug_span!(
+            "kitten",
+            command = call.command,
+            match_expression = call.match_expression(),
+            call = %call,
+            elapsed_ms = tracing::field::Empty,
+            error = tracing::field::Empty,
+        );
+        self.spans.borrow_mut().push(span.entered());
+    }
+
+    fn after(&self, _call: &Call, elapsed: Duration, error: Option<&KittyError>) {
+        let Some(span) = self.spans.borrow_mut().pop() else {
+            return;
+        };
+        span.record("elapsed_ms", elapsed.as_secs_f64() * 1000.0);
+        if let Some(error) = error {
+            span.record("error", tracing::field::display(error));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::executor::MockExecutor;
+
+    /// Writes down the hooks it sees, tagged with its name
+    struct Journal {
+        name: &'static str,
+        entries: Rc<RefCell<Vec<String>>>,
+    }
+
+    impl Middleware for Journal {
+        fn before(&self, call: &Call) {
+            self.entries
+                .borrow_mut()
+                .push(format!("{} before {}", self.name, call.command));
+        }
+
+        fn after(&self, call: &Call, _elapsed: Duration, error: Option<&KittyError>) {
+            self.entries.borrow_mut().push(format!(
+                "{} after {}{}",
+                self.name,
+                call.command,
+                if error.is_some() { " (failed)" } else { "" }
+            ));
+        }
+    }
+
+    fn journal(name: &'static str, entries: &Rc<RefCell<Vec<String>>>) -> Journal {
+        Journal {
+            name,
+            entries: Rc::clone(entries),
+        }
+    }
+
+    #[test]
+    fn test_call_describes_the_kitten_command_line() {
+        let call = Call::of(&KittenFocusTabCommand::new(3));
+        assert_eq!(call.to_string(), "focus-tab --match=id:3");
+        assert_eq!(call.match_expression(), Some("id:3"));
+
+        let call = Call::of(&KittenLsCommand::new().match_tab_title("api"));
+        assert_eq!(call.match_expression(), Some("title:api"));
+
+        assert_eq!(Call::of(&KittenLsCommand::new()).match_expression(), None);
+    }
+
+    #[test]
+    fn test_timer_records_every_call_in_order() {
+        let mock = MockExecutor::new();
+        let tab_id = mock.layout.borrow_mut().add_unnamed_tab(None);
+        let timer = Rc::new(CallTimer::new());
+        let executor = LayeredExecutor::new(&mock, Rc::clone(&timer));
+
+        executor.ls(KittenLsCommand::new()).unwrap();
+        executor
+            .execute(KittenSetTabTitleCommand::new("renamed").for_tab_id(tab_id))
+            .unwrap();
+
+        let calls: Vec<_> = timer
+            .timings()
+            .into_iter()
+            .map(|timing| (timing.call.command, timing.succeeded))
+            .collect();
+        assert_eq!(calls, [("ls", true), ("set-tab-title", true)]);
+        assert_eq!(
+            timer.total(),
+            timer.timings().iter().map(|timing| timing.elapsed).sum()
+        );
+    }
+
+    #[test]
+    fn test_failed_calls_reach_the_middleware_with_their_error() {
+        let mock = MockExecutor::new();
+        mock.expect_focus_tab_response(Err(KittyError::NoMatch {
+            command: "focus-tab".to_string(),
+            exit_code: Some(1),
+            stderr: "No matching tabs".to_string(),
+        }));
+        let entries = Rc::default();
+        let executor = LayeredExecutor::new(&mock, journal("log", &entries));
+
+        assert!(executor.focus_tab(KittenFocusTabCommand::new(9)).is_err());
+        assert_eq!(
+            *entries.borrow(),
+            ["log before focus-tab", "log after focus-tab (failed)"]
+        );
+    }
+
+    #[test]
+    fn test_navigation_is_observed_as_its_kitten_calls() {
+        let mock = MockExecutor::new();
+        let first = mock.layout.borrow_mut().add_tab_with_session("api", None);
+        mock.layout.borrow_mut().add_tab_with_session("api", None);
+        mock.layout.borrow_mut().set_active_tab(first);
+        let counter = CallCounter::new();
+        let executor = LayeredExecutor::new(&mock, &counter);
+
+        executor
+            .navigate_tab(KittenNavigateTabCommand::next().with_session("api"))
+            .unwrap();
+
+        assert_eq!(counter.count("ls"), 1);
+        assert_eq!(counter.count("focus-tab"), 1);
+        assert_eq!(counter.total(), 2);
+        assert_eq!(counter.count("navigate-tab"), 0);
+    }
+
+    #[test]
+    fn test_outer_layers_wrap_inner_ones() {
+        let entries = Rc::default();
+        let executor = LayeredExecutor::new(MockExecutor::new(), journal("inner", &entries))
+            .layer(journal("outer", &entries));
+
+        executor.ls(KittenLsCommand::new()).unwrap();
+
+        assert_eq!(
+            *entries.borrow(),
+            [
+                "outer before ls",
+                "inner before ls",
+                "inner after ls",
+                "outer after ls"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_tuples_nest_like_layers_and_none_stays_silent() {
+        let entries = Rc::default();
+        let layers = (
+            journal("first", &entries),
+            (None::<Journal>, journal("second", &entries)),
+        );
+        let executor = LayeredExecutor::new(MockExecutor::new(), layers);
+
+        executor.ls(KittenLsCommand::new()).unwrap();
+
+        assert_eq!(
+            *entries.borrow(),
+            [
+                "first before ls",
+                "second before ls",
+                "second after ls",
+                "first after ls"
+            ]
+        );
+    }
+
+    #[cfg(feature = "tracing")]
+    #[test]
+    fn test_tracer_spans_carry_the_match_expression() {
+        use std::io;
+        use std::sync::{Arc, Mutex};
+        use tracing_subscriber::fmt::format::FmtSpan;
+
+        let output = Arc::new(Mutex::new(Vec::new()));
+        let writer = Arc::clone(&output);
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_span_events(FmtSpan::CLOSE)
+            .with_ansi(false)
+            .with_writer(move || SharedBuffer(Arc::clone(&writer)))
+            .finish();
+
+        let mock = MockExecutor::new();
+        let tab_id = mock.layout.borrow_mut().add_unnamed_tab(None);
+        tracing::subscriber::with_default(subscriber, || {
+            let executor = LayeredExecutor::new(&mock, CallTracer::new());
+            executor
+                .focus_tab(KittenFocusTabCommand::new(tab_id))
+                .unwrap();
+        });
+
+        let output = String::from_utf8(output.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("kitten{"), "{}", output);
+        assert!(output.contains("command=\"focus-tab\""), "{}", output);
+        assert!(
+            output.contains(&format!("match_expression=\"id:{}\"", tab_id)),
+            "{}",
+            output
+        );
+        assert!(output.contains("elapsed_ms="), "{}", output);
+
+        struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+        impl io::Write for SharedBuffer {
+            fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(bytes)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+    }
+}
@@ -1,26 +1,52 @@
-use anyhow::Result;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::collections::HashMap;
 
+use crate::commands::KittenCommand;
 use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::close_window::KittenCloseWindowCommand;
+use crate::commands::detach::{DetachTarget, KittenDetachTabCommand, KittenDetachWindowCommand};
 use crate::commands::focus_tab::KittenFocusTabCommand;
-use crate::commands::launch::KittenLaunchCommand;
+use crate::commands::focus_window::KittenFocusWindowCommand;
+use crate::commands::get_text::KittenGetTextCommand;
+use crate::commands::launch::{KittenLaunchCommand, LaunchType};
+use crate::commands::layout::{KittenGotoLayoutCommand, KittenLastUsedLayoutCommand};
 use crate::commands::ls::KittenLsCommand;
 use crate::commands::navigate_tab::{KittenNavigateTabCommand, TabNavigationDirection};
+use crate::commands::resize_window::KittenResizeWindowCommand;
+use crate::commands::send_text::KittenSendTextCommand;
 use crate::commands::set_tab_title::KittenSetTabTitleCommand;
+use crate::commands::set_user_vars::KittenSetUserVarsCommand;
+use crate::commands::set_window_title::KittenSetWindowTitleCommand;
+use crate::error::{KittyError, KittyResult};
 use crate::executor::CommandExecutor;
+use crate::executor::match_expression::{MatchExpression, key_value_search, regex_search};
+use crate::snapshot::parse_session_title;
 use crate::types::{
-    KittyCommandResult, KittyLaunchResponse, KittyLsResponse, KittyOsWindow, KittyTab, KittyWindow,
+    KittyLaunchResponse, KittyLsResponse, KittyOsWindow, KittyTab, KittyWindow, OsWindowId, TabId,
+    WindowId,
 };
+use crate::utils::SESSION_USER_VAR;
+use crate::version::{KittyCapabilities, KittyVersion};
+
+/// Layout new mock tabs start with
+pub const DEFAULT_LAYOUT: &str = "tall";
 
 /// In-memory state for simulating Kitty's tab/window layout
 #[derive(Debug, Clone)]
 pub struct MockLayout {
     pub os_windows: Vec<KittyOsWindow>,
-    pub active_tab_id: Option<u32>,
+    pub active_tab_id: Option<TabId>,
     pub next_tab_id: u32,
     pub next_window_id: u32,
     pub next_os_window_id: u32,
+    /// Text received by each window through send-text, in order
+    pub sent_text: Vec<(WindowId, String)>,
+    /// Screen and scrollback contents returned by get-text, per window
+    pub window_text: HashMap<WindowId, String>,
+    /// The layout each tab used before its current one, for last-used-layout
+    pub previous_layouts: HashMap<TabId, String>,
+    /// Tabs from least to most recently focused, ending with the active tab
+    pub tab_history: Vec<TabId>,
 }
 
 impl MockLayout {
@@ -31,154 +57,207 @@ impl MockLayout {
             next_tab_id: 1,
             next_window_id: 1,
             next_os_window_id: 1,
+            sent_text: Vec::new(),
+            window_text: HashMap::new(),
+            previous_layouts: HashMap::new(),
+            tab_history: Vec::new(),
         }
     }
 
     /// Add a tab with the given session context
-    pub fn add_tab_with_session(&mut self, session_name: &str, tab_title: Option<String>) -> u32 {
-        let tab_id = self.next_tab_id;
-        self.next_tab_id += 1;
-
-        let window_id = self.next_window_id;
-        self.next_window_id += 1;
-
-        let mut env = HashMap::new();
-        env.insert(
-            "KITTY_SESSION_PROJECT".to_string(),
-            session_name.to_string(),
-        );
-
-        let window = KittyWindow {
-            id: window_id,
-            title: "shell".to_string(),
-            pid: 12345 + window_id,
-            cwd: format!("/tmp/{}", session_name),
-            cmdline: vec!["zsh".to_string()],
-            env,
-            is_self: true,
-            state: Some("active".to_string()),
-            num: Some(0),
-            recent: Some(0),
-        };
-
-        let tab = KittyTab {
-            id: tab_id,
-            index: Some(0),
-            title: tab_title.unwrap_or_else(|| format!("Tab {}", tab_id)),
-            windows: vec![window],
-            is_active: false, // Will be set later by set_active_tab
-            is_focused: false,
-            state: Some("active".to_string()),
-            recent: Some(0),
-        };
+    ///
+    /// Like `launch --type=tab`, the tab opens in the OS window holding the active tab.
+    pub fn add_tab_with_session(&mut self, session_name: &str, tab_title: Option<String>) -> TabId {
+        let tab = self.new_tab(Some(session_name), tab_title);
+        self.insert_tab(tab)
+    }
 
-        // Find or create an OS window
-        if self.os_windows.is_empty() {
-            let os_window_id = self.next_os_window_id;
-            self.next_os_window_id += 1;
+    /// Add a tab without any session context (no KITTY_SESSION_PROJECT environment variable)
+    pub fn add_unnamed_tab(&mut self, tab_title: Option<String>) -> TabId {
+        let tab = self.new_tab(None, tab_title);
+        self.insert_tab(tab)
+    }
 
-            let os_window = KittyOsWindow {
-                id: os_window_id,
-                tabs: vec![tab],
-                title: Some("Kitty".to_string()),
-                state: Some("active".to_string()),
-            };
-            self.os_windows.push(os_window);
-        } else {
-            self.os_windows[0].tabs.push(tab);
-        }
+    /// Open a new OS window holding one tab, with the session context when one is given
+    ///
+    /// The OS window is not focused; focus one of its tabs to open later tabs next to it.
+    pub fn add_os_window(
+        &mut self,
+        session_name: Option<&str>,
+        tab_title: Option<String>,
+    ) -> TabId {
+        let tab = self.new_tab(session_name, tab_title);
+        let tab_id = tab.id;
+        self.push_os_window(vec![tab]);
 
-        // Set as active if it's the first tab
         if self.active_tab_id.is_none() {
             self.set_active_tab(tab_id);
         }
-
+        self.sync_focus();
         tab_id
     }
 
-    /// Add a tab without any session context (no KITTY_SESSION_PROJECT environment variable)
-    pub fn add_unnamed_tab(&mut self, tab_title: Option<String>) -> u32 {
-        let tab_id = self.next_tab_id;
+    /// A tab with one shell window, in the session's directory and environment when given
+    fn new_tab(&mut self, session_name: Option<&str>, tab_title: Option<String>) -> KittyTab {
+        let tab_id = TabId(self.next_tab_id);
         self.next_tab_id += 1;
 
-        let window_id = self.next_window_id;
+        let window_id = WindowId(self.next_window_id);
         self.next_window_id += 1;
 
-        // No session environment variable for unnamed tabs
-        let env = HashMap::new();
+        let mut env = HashMap::new();
+        if let Some(session_name) = session_name {
+            env.insert(
+                "KITTY_SESSION_PROJECT".to_string(),
+                session_name.to_string(),
+            );
+        }
 
         let window = KittyWindow {
             id: window_id,
             title: "shell".to_string(),
-            pid: 12345 + window_id,
-            cwd: "/tmp/default".to_string(),
+            pid: 12345 + window_id.get(),
+            cwd: format!("/tmp/{}", session_name.unwrap_or("default")),
             cmdline: vec!["zsh".to_string()],
             env,
-            is_self: true,
+            is_self: false,
             state: Some("active".to_string()),
             num: Some(0),
             recent: Some(0),
+            user_vars: HashMap::new(),
+            is_active: true,
+            is_focused: false,
+            foreground_processes: Vec::new(),
+            at_prompt: true,
+            last_reported_cmdline: None,
+            columns: 80,
+            lines: 24,
+        };
+
+        let default_title = match session_name {
+            Some(_) => format!("Tab {}", tab_id),
+            None => format!("Unnamed Tab {}", tab_id),
         };
 
-        let tab = KittyTab {
+        KittyTab {
             id: tab_id,
             index: Some(0),
-            title: tab_title.unwrap_or_else(|| format!("Unnamed Tab {}", tab_id)),
+            title: tab_title.unwrap_or(default_title),
             windows: vec![window],
             is_active: false, // Will be set later by set_active_tab
             is_focused: false,
             state: Some("active".to_string()),
             recent: Some(0),
-        };
+            layout: Some(DEFAULT_LAYOUT.to_string()),
+            enabled_layouts: Vec::new(),
+            active_window_history: Vec::new(),
+        }
+    }
 
-        // Find or create the first OS window
-        if self.os_windows.is_empty() {
-            let os_window_id = self.next_os_window_id;
-            self.next_os_window_id += 1;
+    /// Add a tab to the OS window of the active tab, or the first OS window
+    fn insert_tab(&mut self, tab: KittyTab) -> TabId {
+        let tab_id = tab.id;
+        let os_index = self
+            .active_tab_id
+            .and_then(|active| self.os_window_index_for_tab(active))
+            .or((!self.os_windows.is_empty()).then_some(0));
 
-            let os_window = KittyOsWindow {
-                id: os_window_id,
-                tabs: vec![tab],
-                title: Some("Kitty".to_string()),
-                state: Some("active".to_string()),
-            };
-            self.os_windows.push(os_window);
-        } else {
-            self.os_windows[0].tabs.push(tab);
+        match os_index {
+            Some(index) => self.os_windows[index].tabs.push(tab),
+            None => {
+                self.push_os_window(vec![tab]);
+            }
         }
 
         // Set as active if it's the first tab
         if self.active_tab_id.is_none() {
             self.set_active_tab(tab_id);
         }
-
+        self.sync_focus();
         tab_id
     }
 
-    /// Set the active tab
-    pub fn set_active_tab(&mut self, tab_id: u32) -> bool {
-        // Check if the tab exists and update is_active flags
-        let mut found = false;
-        for os_window in &mut self.os_windows {
-            for tab in &mut os_window.tabs {
-                if tab.id == tab_id {
+    fn push_os_window(&mut self, tabs: Vec<KittyTab>) -> OsWindowId {
+        let os_window_id = OsWindowId(self.next_os_window_id);
+        self.next_os_window_id += 1;
+
+        self.os_windows.push(KittyOsWindow {
+            id: os_window_id,
+            tabs,
+            title: Some("Kitty".to_string()),
+            state: Some("active".to_string()),
+            is_focused: false,
+            last_focused: false,
+            platform_window_id: None,
+        });
+        os_window_id
+    }
+
+    /// Focus a tab: it becomes the active tab of its OS window, which gets keyboard focus
+    pub fn set_active_tab(&mut self, tab_id: TabId) -> bool {
+        let Some(os_index) = self.os_window_index_for_tab(tab_id) else {
+            return false;
+        };
+
+        for tab in &mut self.os_windows[os_index].tabs {
+            tab.is_active = tab.id == tab_id;
+        }
+        self.tab_history.retain(|&id| id != tab_id);
+        self.tab_history.push(tab_id);
+        self.active_tab_id = Some(tab_id);
+        self.sync_focus();
+        true
+    }
+
+    /// Recompute the focus flags kitty reports from `active_tab_id`
+    ///
+    /// Every OS window keeps one active tab and every tab one active window, falling back to
+    /// the most recently focused one. Only the OS window holding the active tab is focused,
+    /// and within it only the active tab and that tab's active window.
+    fn sync_focus(&mut self) {
+        let focused_os_index = self
+            .active_tab_id
+            .and_then(|tab_id| self.os_window_index_for_tab(tab_id));
+
+        for (index, os_window) in self.os_windows.iter_mut().enumerate() {
+            let os_focused = focused_os_index == Some(index);
+            os_window.is_focused = os_focused;
+            os_window.last_focused = os_focused;
+
+            if !os_window.tabs.iter().any(|tab| tab.is_active) {
+                let fallback = self
+                    .tab_history
+                    .iter()
+                    .rev()
+                    .find_map(|&id| os_window.tabs.iter().position(|tab| tab.id == id))
+                    .unwrap_or(0);
+                if let Some(tab) = os_window.tabs.get_mut(fallback) {
                     tab.is_active = true;
-                    tab.is_focused = true;
-                    found = true;
-                } else {
-                    tab.is_active = false;
-                    tab.is_focused = false;
                 }
             }
-        }
 
-        if found {
-            self.active_tab_id = Some(tab_id);
+            for tab in &mut os_window.tabs {
+                tab.is_focused = os_focused && tab.is_active;
+
+                if !tab.windows.iter().any(|window| window.is_active) {
+                    let fallback = tab
+                        .active_window_history
+                        .iter()
+                        .rev()
+                        .find_map(|&id| tab.windows.iter().position(|window| window.id == id))
+                        .unwrap_or(tab.windows.len().saturating_sub(1));
+                    if let Some(window) = tab.windows.get_mut(fallback) {
+                        window.is_active = true;
+                    }
+                }
+                for window in &mut tab.windows {
+                    window.is_focused = tab.is_focused && window.is_active;
+                }
+            }
         }
-        found
     }
 
-    /// Get tabs filtered by session (environment variable or tab title)
+    /// Get tabs filtered by session (session user variable, tab title or environment variable)
     pub fn get_tabs_for_session(&self, session_name: &str) -> Vec<KittyTab> {
         let mut matching_tabs = Vec::new();
 
@@ -187,23 +266,21 @@ impl MockLayout {
                 let mut tab_matches = false;
 
                 // Check tab title first for session: prefix
-                if tab.title.starts_with("session:") {
-                    if let Some(title_session) = self.parse_session_from_title(&tab.title) {
-                        if title_session == session_name {
-                            matching_tabs.push(tab.clone());
-                            tab_matches = true;
-                        }
-                    }
+                if parse_session_title(&tab.title) == Some(session_name) {
+                    matching_tabs.push(tab.clone());
+                    tab_matches = true;
                 }
 
-                // Also check environment variable for backward compatibility
+                // Also check the session user variable and the environment variable
                 if !tab_matches {
                     for window in &tab.windows {
-                        if let Some(env_session) = window.env.get("KITTY_SESSION_PROJECT") {
-                            if env_session == session_name {
-                                matching_tabs.push(tab.clone());
-                                break; // Found matching window in this tab, move to next tab
-                            }
+                        let var_session = window.user_vars.get(SESSION_USER_VAR);
+                        let env_session = window.env.get("KITTY_SESSION_PROJECT");
+                        if var_session.is_some_and(|name| name == session_name)
+                            || env_session.is_some_and(|name| name == session_name)
+                        {
+                            matching_tabs.push(tab.clone());
+                            break; // Found matching window in this tab, move to next tab
                         }
                     }
                 }
@@ -215,22 +292,6 @@ impl MockLayout {
         matching_tabs
     }
 
-    /// Parses a session name from a tab title with the format "session:<name>[ - description]"
-    fn parse_session_from_title(&self, title: &str) -> Option<String> {
-        if let Some(after_prefix) = title.strip_prefix("session:") {
-            // Split on " - " to handle optional description
-            let session_name = after_prefix.split(" - ").next()?;
-
-            if !session_name.is_empty() {
-                Some(session_name.to_string())
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    }
-
     /// Get all tabs in the current layout
     pub fn get_all_tabs(&self) -> Vec<KittyTab> {
         let mut all_tabs = Vec::new();
@@ -247,16 +308,16 @@ impl MockLayout {
         session_name: &str,
         direction: TabNavigationDirection,
         allow_wrap: bool,
-    ) -> Option<u32> {
+    ) -> Option<TabId> {
         let session_tabs = if session_name == "unnamed" {
             // For unnamed session, get tabs without session env var or session title
             let mut unnamed_tabs = Vec::new();
             for os_window in &self.os_windows {
                 for tab in &os_window.tabs {
-                    let has_session_env = tab
-                        .windows
-                        .iter()
-                        .any(|w| w.env.contains_key("KITTY_SESSION_PROJECT"));
+                    let has_session_env = tab.windows.iter().any(|w| {
+                        w.env.contains_key("KITTY_SESSION_PROJECT")
+                            || w.user_vars.contains_key(SESSION_USER_VAR)
+                    });
                     let has_session_title = tab.title.starts_with("session:");
                     if !has_session_env && !has_session_title {
                         unnamed_tabs.push(tab.clone());
@@ -321,21 +382,42 @@ impl MockLayout {
         Some(target_tab_id)
     }
 
-    /// Remove a specific tab by ID
-    pub fn remove_tab(&mut self, tab_id: u32) -> bool {
-        for os_window in &mut self.os_windows {
-            if let Some(pos) = os_window.tabs.iter().position(|t| t.id == tab_id) {
-                os_window.tabs.remove(pos);
-
-                // If this was the active tab, clear active tab ID
-                if self.active_tab_id == Some(tab_id) {
-                    self.active_tab_id = None;
-                }
+    /// Remove a specific tab by ID, closing its OS window when it was the last tab there
+    ///
+    /// When the active tab closes, focus moves to the most recently focused remaining tab,
+    /// preferring the same OS window.
+    pub fn remove_tab(&mut self, tab_id: TabId) -> bool {
+        let Some(os_index) = self.os_window_index_for_tab(tab_id) else {
+            return false;
+        };
 
-                return true;
+        self.os_windows[os_index].tabs.retain(|t| t.id != tab_id);
+        let neighbors: Vec<TabId> = self.os_windows[os_index]
+            .tabs
+            .iter()
+            .map(|t| t.id)
+            .collect();
+        if neighbors.is_empty() {
+            self.os_windows.remove(os_index);
+        }
+        self.tab_history.retain(|&id| id != tab_id);
+
+        if self.active_tab_id == Some(tab_id) {
+            self.active_tab_id = None;
+            let next = self
+                .tab_history
+                .iter()
+                .rev()
+                .find(|id| neighbors.contains(id))
+                .or(neighbors.first())
+                .or(self.tab_history.last())
+                .copied();
+            if let Some(next) = next {
+                self.set_active_tab(next);
             }
         }
-        false
+        self.sync_focus();
+        true
     }
 
     /// Clear all tabs and reset state
@@ -345,10 +427,14 @@ impl MockLayout {
         self.next_tab_id = 1;
         self.next_window_id = 1;
         self.next_os_window_id = 1;
+        self.sent_text.clear();
+        self.window_text.clear();
+        self.previous_layouts.clear();
+        self.tab_history.clear();
     }
 
     /// Set the title of a tab by ID
-    pub fn set_tab_title(&mut self, tab_id: u32, title: &str) -> bool {
+    pub fn set_tab_title(&mut self, tab_id: TabId, title: &str) -> bool {
         for os_window in &mut self.os_windows {
             for tab in &mut os_window.tabs {
                 if tab.id == tab_id {
@@ -360,6 +446,520 @@ impl MockLayout {
         false
     }
 
+    /// Apply environment variables to every window in a tab, in order
+    pub fn extend_tab_env(&mut self, tab_id: TabId, env: &[(String, String)]) -> bool {
+        for os_window in &mut self.os_windows {
+            for tab in &mut os_window.tabs {
+                if tab.id == tab_id {
+                    for window in &mut tab.windows {
+                        window.env.extend(env.iter().cloned());
+                    }
+                    return true;
+                }
+            }
+        }
+        false
+    }
+
+    /// Add a window to an existing tab, returning the new window ID
+    pub fn add_window(&mut self, tab_id: TabId, env: &[(String, String)]) -> Option<WindowId> {
+        let window_id = WindowId(self.next_window_id);
+
+        let tab = self
+            .os_windows
+            .iter_mut()
+            .flat_map(|os_window| os_window.tabs.iter_mut())
+            .find(|tab| tab.id == tab_id)?;
+
+        let cwd = tab
+            .windows
+            .first()
+            .map(|w| w.cwd.clone())
+            .unwrap_or_else(|| "/tmp/default".to_string());
+
+        tab.windows.push(KittyWindow {
+            id: window_id,
+            title: "shell".to_string(),
+            pid: 12345 + window_id.get(),
+            cwd,
+            cmdline: vec!["zsh".to_string()],
+            env: env.iter().cloned().collect(),
+            is_self: false,
+            state: Some("active".to_string()),
+            num: Some(tab.windows.len() as u32),
+            recent: Some(0),
+            user_vars: HashMap::new(),
+            is_active: false,
+            is_focused: false,
+            foreground_processes: Vec::new(),
+            at_prompt: true,
+            last_reported_cmdline: None,
+            columns: 80,
+            lines: 24,
+        });
+
+        self.next_window_id += 1;
+        Some(window_id)
+    }
+
+    /// Move a tab into a new OS window, returning the new OS window ID
+    pub fn detach_tab(&mut self, tab_id: TabId) -> Option<OsWindowId> {
+        let os_index = self.os_window_index_for_tab(tab_id)?;
+        let tab_index = self.os_windows[os_index]
+            .tabs
+            .iter()
+            .position(|t| t.id == tab_id)?;
+
+        let tab = self.os_windows[os_index].tabs.remove(tab_index);
+        let os_window_id = self.push_os_window(vec![tab]);
+        self.close_empty_os_windows();
+        Some(os_window_id)
+    }
+
+    /// Close OS windows left without tabs, like kitty does
+    fn close_empty_os_windows(&mut self) {
+        self.os_windows
+            .retain(|os_window| !os_window.tabs.is_empty());
+        self.sync_focus();
+    }
+
+    /// Find the ID of the tab containing a window
+    pub fn tab_id_for_window(&self, window_id: WindowId) -> Option<TabId> {
+        self.os_windows
+            .iter()
+            .flat_map(|os_window| &os_window.tabs)
+            .find(|tab| tab.windows.iter().any(|w| w.id == window_id))
+            .map(|tab| tab.id)
+    }
+
+    /// Get a mutable reference to a window by ID
+    pub fn window_mut(&mut self, window_id: WindowId) -> Option<&mut KittyWindow> {
+        self.os_windows
+            .iter_mut()
+            .flat_map(|os_window| os_window.tabs.iter_mut())
+            .flat_map(|tab| tab.windows.iter_mut())
+            .find(|w| w.id == window_id)
+    }
+
+    /// Mark the window `kitten @` runs in, which `ls --self` and `state:self` resolve to
+    ///
+    /// No window is the invoking one until a test says so, like ksm run outside kitty.
+    pub fn set_self_window(&mut self, window_id: WindowId) -> bool {
+        if self.window_mut(window_id).is_none() {
+            return false;
+        }
+        for window in self
+            .os_windows
+            .iter_mut()
+            .flat_map(|os_window| os_window.tabs.iter_mut())
+            .flat_map(|tab| tab.windows.iter_mut())
+        {
+            window.is_self = window.id == window_id;
+        }
+        true
+    }
+
+    /// The active window: the active window of the active tab, falling back to its first window
+    pub fn active_window_id(&self) -> Option<WindowId> {
+        let tab = self.tab(self.active_tab_id?)?;
+        tab.windows
+            .iter()
+            .find(|window| window.is_active)
+            .or(tab.windows.first())
+            .map(|window| window.id)
+    }
+
+    /// Get a tab by ID
+    pub fn tab(&self, tab_id: TabId) -> Option<&KittyTab> {
+        self.os_windows
+            .iter()
+            .flat_map(|os_window| &os_window.tabs)
+            .find(|tab| tab.id == tab_id)
+    }
+
+    /// Get a mutable reference to a tab by ID
+    pub fn tab_mut(&mut self, tab_id: TabId) -> Option<&mut KittyTab> {
+        self.os_windows
+            .iter_mut()
+            .flat_map(|os_window| os_window.tabs.iter_mut())
+            .find(|tab| tab.id == tab_id)
+    }
+
+    /// Make a window the active window of its tab and focus that tab
+    ///
+    /// The window it replaces is appended to the tab's `active_window_history`.
+    pub fn focus_window(&mut self, window_id: WindowId) -> bool {
+        let Some(tab_id) = self.tab_id_for_window(window_id) else {
+            return false;
+        };
+
+        if let Some(tab) = self.tab_mut(tab_id) {
+            let previous = tab
+                .windows
+                .iter()
+                .find(|window| window.is_active && window.id != window_id)
+                .map(|window| window.id);
+            if let Some(previous) = previous {
+                tab.active_window_history
+                    .retain(|&id| id != previous && id != window_id);
+                tab.active_window_history.push(previous);
+            }
+            for window in &mut tab.windows {
+                window.is_active = window.id == window_id;
+            }
+        }
+        self.set_active_tab(tab_id)
+    }
+
+    /// Remove a window, closing its tab when it was the last window there
+    pub fn remove_window(&mut self, window_id: WindowId) -> bool {
+        let Some(window) = self.take_window(window_id) else {
+            return false;
+        };
+        self.window_text.remove(&window.id);
+        true
+    }
+
+    /// Set the title of a window by ID
+    pub fn set_window_title(&mut self, window_id: WindowId, title: &str) -> bool {
+        match self.window_mut(window_id) {
+            Some(window) => {
+                window.title = title.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Switch a tab to another layout, remembering the current one for last-used-layout
+    pub fn set_tab_layout(&mut self, tab_id: TabId, layout: &str) -> bool {
+        let Some(tab) = self.tab_mut(tab_id) else {
+            return false;
+        };
+
+        let previous = tab.layout.replace(layout.to_string());
+        if let Some(previous) = previous
+            && previous != layout
+        {
+            self.previous_layouts.insert(tab_id, previous);
+        }
+        true
+    }
+
+    /// Switch a tab back to the layout it used before its current one
+    pub fn last_used_layout(&mut self, tab_id: TabId) -> bool {
+        match self.previous_layouts.get(&tab_id).cloned() {
+            Some(previous) => self.set_tab_layout(tab_id, &previous),
+            None => false,
+        }
+    }
+
+    /// Resolve a kitty `--match-tab` expression to tab IDs
+    ///
+    /// Supports `id:`, `index:`, `title:`, `recent:` and `state:` terms for the tab itself, and
+    /// `window_id:`, `window_title:`, `pid:`, `cwd:`, `cmdline:`, `env:` and `var:` terms that
+    /// hold when any window of the tab matches. `index:` and `recent:` count within the focused
+    /// OS window, like kitty.
+    pub fn match_tabs(&self, expression: &str) -> Vec<TabId> {
+        let Ok(expression) = MatchExpression::parse(expression) else {
+            return Vec::new();
+        };
+
+        self.os_windows
+            .iter()
+            .flat_map(|os_window| os_window.tabs.iter().map(move |tab| (os_window, tab)))
+            .filter(|(os_window, tab)| {
+                expression.evaluate(&mut |field, pattern| {
+                    self.tab_matches_term(os_window, tab, field, pattern)
+                })
+            })
+            .map(|(_, tab)| tab.id)
+            .collect()
+    }
+
+    fn tab_matches_term(
+        &self,
+        os_window: &KittyOsWindow,
+        tab: &KittyTab,
+        field: &str,
+        pattern: &str,
+    ) -> bool {
+        match field {
+            "id" => pattern.parse::<u32>().is_ok_and(|id| tab.id == id),
+            "title" => regex_search(pattern, &tab.title),
+            "index" => {
+                os_window.is_focused
+                    && pattern.parse::<usize>().is_ok_and(|index| {
+                        os_window.tabs.get(index).is_some_and(|t| t.id == tab.id)
+                    })
+            }
+            "recent" => pattern
+                .parse::<usize>()
+                .is_ok_and(|n| self.recent_tab(n) == Some(tab.id)),
+            "state" => match pattern {
+                "active" => tab.is_active,
+                "focused" => tab.is_focused,
+                "parent_active" => os_window.last_focused,
+                "parent_focused" => os_window.is_focused,
+                "self" => tab.windows.iter().any(|w| w.is_self),
+                // needs_attention is not simulated
+                _ => false,
+            },
+            "window_id" => tab
+                .windows
+                .iter()
+                .any(|w| window_field_matches(w, "id", pattern)),
+            "window_title" => tab
+                .windows
+                .iter()
+                .any(|w| window_field_matches(w, "title", pattern)),
+            "pid" | "cwd" | "cmdline" | "env" | "var" => tab
+                .windows
+                .iter()
+                .any(|w| window_field_matches(w, field, pattern)),
+            _ => false,
+        }
+    }
+
+    /// The tab focused `n` focus changes ago in the focused OS window, 0 being the active tab
+    fn recent_tab(&self, n: usize) -> Option<TabId> {
+        let os_window = self.os_windows.iter().find(|w| w.is_focused)?;
+        self.tab_history
+            .iter()
+            .rev()
+            .filter(|&&id| os_window.tabs.iter().any(|tab| tab.id == id))
+            .nth(n)
+            .copied()
+    }
+
+    /// The window of the active tab that was active `n` focus changes ago, 0 being the active
+    /// window
+    fn recent_window(&self, n: usize) -> Option<WindowId> {
+        let tab = self.tab(self.active_tab_id?)?;
+        if n == 0 {
+            return tab.windows.iter().find(|w| w.is_active).map(|w| w.id);
+        }
+        tab.active_window_history
+            .iter()
+            .rev()
+            .filter(|&&id| tab.windows.iter().any(|w| w.id == id))
+            .nth(n - 1)
+            .copied()
+    }
+
+    /// Move a window to a new OS window, a new tab or an existing tab, returning the ID of the
+    /// tab it ends up in
+    pub fn detach_window(&mut self, window_id: WindowId, target: &DetachTarget) -> Option<TabId> {
+        let source_tab = self.tab_id_for_window(window_id)?;
+        let target_tab = match target {
+            DetachTarget::Tab(expression) => Some(*self.match_tabs(expression).first()?),
+            DetachTarget::NewOsWindow | DetachTarget::NewTab => None,
+        };
+        if target_tab == Some(source_tab) {
+            return Some(source_tab);
+        }
+
+        let os_window_id = self.os_windows[self.os_window_index_for_tab(source_tab)?].id;
+        let mut window = self.take_window(window_id)?;
+        window.is_active = false;
+
+        match target_tab {
+            Some(tab_id) => {
+                let tab = self.tab_mut(tab_id)?;
+                window.num = Some(tab.windows.len() as u32);
+                tab.windows.push(window);
+                self.sync_focus();
+                Some(tab_id)
+            }
+            None => {
+                window.num = Some(0);
+                window.is_active = true;
+
+                let tab_id = TabId(self.next_tab_id);
+                self.next_tab_id += 1;
+                let tab = KittyTab {
+                    id: tab_id,
+                    index: Some(0),
+                    title: window.title.clone(),
+                    windows: vec![window],
+                    is_active: false,
+                    is_focused: false,
+                    state: Some("active".to_string()),
+                    recent: Some(0),
+                    layout: Some(DEFAULT_LAYOUT.to_string()),
+                    enabled_layouts: Vec::new(),
+                    active_window_history: Vec::new(),
+                };
+
+                // The source OS window is gone when the window was the last one there
+                let source_os_window = self
+                    .os_windows
+                    .iter_mut()
+                    .find(|os_window| os_window.id == os_window_id);
+                match source_os_window {
+                    Some(os_window) if *target == DetachTarget::NewTab => os_window.tabs.push(tab),
+                    _ => {
+                        self.push_os_window(vec![tab]);
+                    }
+                }
+                self.sync_focus();
+                Some(tab_id)
+            }
+        }
+    }
+
+    /// Move a tab into the OS window containing another tab
+    pub fn move_tab(&mut self, tab_id: TabId, target_tab_id: TabId) -> bool {
+        let (Some(source), Some(target)) = (
+            self.os_window_index_for_tab(tab_id),
+            self.os_window_index_for_tab(target_tab_id),
+        ) else {
+            return false;
+        };
+        if source == target {
+            return true;
+        }
+
+        let Some(position) = self.os_windows[source]
+            .tabs
+            .iter()
+            .position(|t| t.id == tab_id)
+        else {
+            return false;
+        };
+        let mut tab = self.os_windows[source].tabs.remove(position);
+        // The focused tab stays focused; any other tab joins in the background
+        if self.active_tab_id == Some(tab_id) {
+            for other in &mut self.os_windows[target].tabs {
+                other.is_active = false;
+            }
+        } else {
+            tab.is_active = false;
+        }
+        self.os_windows[target].tabs.push(tab);
+        self.close_empty_os_windows();
+        true
+    }
+
+    fn os_window_index_for_tab(&self, tab_id: TabId) -> Option<usize> {
+        self.os_windows
+            .iter()
+            .position(|os_window| os_window.tabs.iter().any(|t| t.id == tab_id))
+    }
+
+    /// Take a window out of its tab, removing the tab when it becomes empty and handing the
+    /// active flag to the most recently active remaining window otherwise
+    fn take_window(&mut self, window_id: WindowId) -> Option<KittyWindow> {
+        let tab_id = self.tab_id_for_window(window_id)?;
+        let tab = self.tab_mut(tab_id)?;
+        let position = tab.windows.iter().position(|w| w.id == window_id)?;
+        let window = tab.windows.remove(position);
+        tab.active_window_history.retain(|&id| id != window_id);
+
+        if tab.windows.is_empty() {
+            self.remove_tab(tab_id);
+        } else {
+            self.sync_focus();
+        }
+
+        Some(window)
+    }
+
+    /// Resolve a kitty match expression to window IDs
+    ///
+    /// Supports `id:`, `title:`, `pid:`, `cwd:`, `cmdline:`, `env:`, `var:`, `num:`, `recent:`
+    /// and `state:` terms combined with `and`, `or`, `not` and parentheses. Text patterns are
+    /// regular expressions found anywhere in the value and `num:` and `recent:` count within
+    /// the active tab, like kitty. With `tab_match` the expression selects tabs, as described
+    /// for [`MockLayout::match_tabs`], and every window of a matching tab is returned.
+    pub fn match_windows(&self, expression: &str, tab_match: bool) -> Vec<WindowId> {
+        let tabs = self.os_windows.iter().flat_map(|os_window| &os_window.tabs);
+
+        if tab_match {
+            let tab_ids = self.match_tabs(expression);
+            return tabs
+                .filter(|tab| tab_ids.contains(&tab.id))
+                .flat_map(|tab| tab.windows.iter().map(|window| window.id))
+                .collect();
+        }
+
+        let Ok(expression) = MatchExpression::parse(expression) else {
+            return Vec::new();
+        };
+        tabs.flat_map(|tab| tab.windows.iter().map(move |window| (tab, window)))
+            .filter(|(tab, window)| {
+                expression.evaluate(&mut |field, pattern| {
+                    self.window_matches_term(tab, window, field, pattern)
+                })
+            })
+            .map(|(_, window)| window.id)
+            .collect()
+    }
+
+    fn window_matches_term(
+        &self,
+        tab: &KittyTab,
+        window: &KittyWindow,
+        field: &str,
+        pattern: &str,
+    ) -> bool {
+        match field {
+            "num" => {
+                self.active_tab_id == Some(tab.id)
+                    && pattern
+                        .parse::<usize>()
+                        .is_ok_and(|num| tab.windows.get(num).is_some_and(|w| w.id == window.id))
+            }
+            "recent" => pattern
+                .parse::<usize>()
+                .is_ok_and(|n| self.recent_window(n) == Some(window.id)),
+            "state" => match pattern {
+                "active" => window.is_active,
+                "focused" => window.is_focused,
+                "parent_active" => tab.is_active,
+                "parent_focused" => tab.is_focused,
+                "self" => window.is_self,
+                // needs_attention and overlay_parent are not simulated
+                _ => false,
+            },
+            _ => window_field_matches(window, field, pattern),
+        }
+    }
+
+    /// The layout as `kitten @ ls --match` prints it, or `--match-tab` with `tab_match`
+    ///
+    /// Only OS windows and tabs holding a matched window are kept, each with just the matched
+    /// windows; a matched tab keeps all of its windows.
+    pub fn ls_matching(&self, expression: &str, tab_match: bool) -> KittyLsResponse {
+        let window_ids = self.match_windows(expression, tab_match);
+
+        self.os_windows
+            .iter()
+            .filter_map(|os_window| {
+                let tabs: Vec<KittyTab> = os_window
+                    .tabs
+                    .iter()
+                    .filter_map(|tab| {
+                        let windows: Vec<KittyWindow> = tab
+                            .windows
+                            .iter()
+                            .filter(|window| window_ids.contains(&window.id))
+                            .cloned()
+                            .collect();
+                        (!windows.is_empty()).then(|| KittyTab {
+                            windows,
+                            ..tab.clone()
+                        })
+                    })
+                    .collect();
+                (!tabs.is_empty()).then(|| KittyOsWindow {
+                    tabs,
+                    ..os_window.clone()
+                })
+            })
+            .collect()
+    }
+
     /// Set the title of the currently active tab
     pub fn set_active_tab_title(&mut self, title: &str) -> bool {
         if let Some(active_id) = self.active_tab_id {
@@ -370,12 +970,34 @@ impl MockLayout {
     }
 }
 
+/// Evaluate a match term that depends only on the window itself
+fn window_field_matches(window: &KittyWindow, field: &str, pattern: &str) -> bool {
+    match field {
+        "id" => pattern.parse::<u32>().is_ok_and(|id| window.id == id),
+        "pid" => pattern.parse::<u32>().is_ok_and(|pid| window.pid == pid),
+        "title" => regex_search(pattern, &window.title),
+        "cwd" => regex_search(pattern, &window.cwd),
+        "cmdline" => window.cmdline.iter().any(|arg| regex_search(pattern, arg)),
+        "env" => key_value_search(pattern, &window.env),
+        "var" => key_value_search(pattern, &window.user_vars),
+        _ => false,
+    }
+}
+
 impl Default for MockLayout {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// A command run through [`CommandExecutor::execute`], as it would reach kitten
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutedCommand {
+    pub name: String,
+    pub args: Vec<String>,
+    pub stdin: Option<String>,
+}
+
 #[derive(Debug)]
 pub struct MockExecutor {
     pub ls_calls: RefCell<Vec<KittenLsCommand>>,
@@ -384,13 +1006,40 @@ pub struct MockExecutor {
     pub launch_calls: RefCell<Vec<KittenLaunchCommand>>,
     pub navigate_tab_calls: RefCell<Vec<KittenNavigateTabCommand>>,
     pub set_tab_title_calls: RefCell<Vec<KittenSetTabTitleCommand>>,
-    pub ls_responses: RefCell<Vec<Result<KittyLsResponse>>>,
-    pub focus_tab_responses: RefCell<Vec<Result<KittyCommandResult<()>>>>,
-    pub close_tab_responses: RefCell<Vec<Result<KittyCommandResult<()>>>>,
-    pub launch_responses: RefCell<Vec<Result<KittyCommandResult<KittyLaunchResponse>>>>,
-    pub navigate_tab_responses: RefCell<Vec<Result<KittyCommandResult<()>>>>,
-    pub set_tab_title_responses: RefCell<Vec<Result<KittyCommandResult<()>>>>,
+    pub send_text_calls: RefCell<Vec<KittenSendTextCommand>>,
+    pub get_text_calls: RefCell<Vec<KittenGetTextCommand>>,
+    pub focus_window_calls: RefCell<Vec<KittenFocusWindowCommand>>,
+    pub close_window_calls: RefCell<Vec<KittenCloseWindowCommand>>,
+    pub set_window_title_calls: RefCell<Vec<KittenSetWindowTitleCommand>>,
+    pub resize_window_calls: RefCell<Vec<KittenResizeWindowCommand>>,
+    pub goto_layout_calls: RefCell<Vec<KittenGotoLayoutCommand>>,
+    pub last_used_layout_calls: RefCell<Vec<KittenLastUsedLayoutCommand>>,
+    pub detach_window_calls: RefCell<Vec<KittenDetachWindowCommand>>,
+    pub detach_tab_calls: RefCell<Vec<KittenDetachTabCommand>>,
+    pub set_user_vars_calls: RefCell<Vec<KittenSetUserVarsCommand>>,
+    pub execute_calls: RefCell<Vec<ExecutedCommand>>,
+    pub ls_responses: RefCell<Vec<KittyResult<KittyLsResponse>>>,
+    pub focus_tab_responses: RefCell<Vec<KittyResult<()>>>,
+    pub close_tab_responses: RefCell<Vec<KittyResult<()>>>,
+    pub launch_responses: RefCell<Vec<KittyResult<KittyLaunchResponse>>>,
+    pub navigate_tab_responses: RefCell<Vec<KittyResult<()>>>,
+    pub set_tab_title_responses: RefCell<Vec<KittyResult<()>>>,
+    pub send_text_responses: RefCell<Vec<KittyResult<()>>>,
+    pub get_text_responses: RefCell<Vec<KittyResult<String>>>,
+    pub focus_window_responses: RefCell<Vec<KittyResult<()>>>,
+    pub close_window_responses: RefCell<Vec<KittyResult<()>>>,
+    pub set_window_title_responses: RefCell<Vec<KittyResult<()>>>,
+    pub resize_window_responses: RefCell<Vec<KittyResult<bool>>>,
+    pub goto_layout_responses: RefCell<Vec<KittyResult<()>>>,
+    pub last_used_layout_responses: RefCell<Vec<KittyResult<()>>>,
+    pub detach_window_responses: RefCell<Vec<KittyResult<()>>>,
+    pub detach_tab_responses: RefCell<Vec<KittyResult<()>>>,
+    pub set_user_vars_responses: RefCell<Vec<KittyResult<()>>>,
+    /// kitten's stdout for commands run through `execute`
+    pub execute_responses: RefCell<Vec<KittyResult<String>>>,
     pub layout: RefCell<MockLayout>,
+    /// The kitty release to behave like, see [`MockExecutor::set_kitty_version`]
+    pub kitty_version: Cell<Option<KittyVersion>>,
 }
 
 impl MockExecutor {
@@ -402,13 +1051,38 @@ impl MockExecutor {
             launch_calls: RefCell::new(Vec::new()),
             navigate_tab_calls: RefCell::new(Vec::new()),
             set_tab_title_calls: RefCell::new(Vec::new()),
+            send_text_calls: RefCell::new(Vec::new()),
+            get_text_calls: RefCell::new(Vec::new()),
+            focus_window_calls: RefCell::new(Vec::new()),
+            close_window_calls: RefCell::new(Vec::new()),
+            set_window_title_calls: RefCell::new(Vec::new()),
+            resize_window_calls: RefCell::new(Vec::new()),
+            goto_layout_calls: RefCell::new(Vec::new()),
+            last_used_layout_calls: RefCell::new(Vec::new()),
+            detach_window_calls: RefCell::new(Vec::new()),
+            detach_tab_calls: RefCell::new(Vec::new()),
+            set_user_vars_calls: RefCell::new(Vec::new()),
+            execute_calls: RefCell::new(Vec::new()),
             ls_responses: RefCell::new(Vec::new()),
             focus_tab_responses: RefCell::new(Vec::new()),
             close_tab_responses: RefCell::new(Vec::new()),
             launch_responses: RefCell::new(Vec::new()),
             navigate_tab_responses: RefCell::new(Vec::new()),
             set_tab_title_responses: RefCell::new(Vec::new()),
+            send_text_responses: RefCell::new(Vec::new()),
+            get_text_responses: RefCell::new(Vec::new()),
+            focus_window_responses: RefCell::new(Vec::new()),
+            close_window_responses: RefCell::new(Vec::new()),
+            set_window_title_responses: RefCell::new(Vec::new()),
+            resize_window_responses: RefCell::new(Vec::new()),
+            goto_layout_responses: RefCell::new(Vec::new()),
+            last_used_layout_responses: RefCell::new(Vec::new()),
+            detach_window_responses: RefCell::new(Vec::new()),
+            detach_tab_responses: RefCell::new(Vec::new()),
+            set_user_vars_responses: RefCell::new(Vec::new()),
+            execute_responses: RefCell::new(Vec::new()),
             layout: RefCell::new(MockLayout::new()),
+            kitty_version: Cell::new(None),
         }
     }
 
@@ -416,33 +1090,89 @@ impl MockExecutor {
         Self::new()
     }
 
-    pub fn expect_ls_response(&self, response: Result<KittyLsResponse>) {
+    /// Behave like kitty `version`: commands needing features it lacks fail with
+    /// [`KittyError::Unsupported`], and `capabilities` reports it
+    pub fn set_kitty_version(&self, version: KittyVersion) {
+        self.kitty_version.set(Some(version));
+    }
+
+    /// Fail like an old kitty would when `command` needs what the set version lacks
+    fn check_version<C: KittenCommand>(&self, command: &C) -> KittyResult<()> {
+        match self.kitty_version.get() {
+            Some(version) => KittyCapabilities::of(version)
+                .require_for(command.name(), &command.args().unwrap_or_default()),
+            None => Ok(()),
+        }
+    }
+
+    pub fn expect_ls_response(&self, response: KittyResult<KittyLsResponse>) {
         self.ls_responses.borrow_mut().push(response);
     }
 
-    pub fn expect_focus_tab_response(&self, response: Result<KittyCommandResult<()>>) {
+    pub fn expect_focus_tab_response(&self, response: KittyResult<()>) {
         self.focus_tab_responses.borrow_mut().push(response);
     }
 
-    pub fn expect_close_tab_response(&self, response: Result<KittyCommandResult<()>>) {
+    pub fn expect_close_tab_response(&self, response: KittyResult<()>) {
         self.close_tab_responses.borrow_mut().push(response);
     }
 
-    pub fn expect_launch_response(
-        &self,
-        response: Result<KittyCommandResult<KittyLaunchResponse>>,
-    ) {
+    pub fn expect_launch_response(&self, response: KittyResult<KittyLaunchResponse>) {
         self.launch_responses.borrow_mut().push(response);
     }
 
-    pub fn expect_navigate_tab_response(&self, response: Result<KittyCommandResult<()>>) {
+    pub fn expect_navigate_tab_response(&self, response: KittyResult<()>) {
         self.navigate_tab_responses.borrow_mut().push(response);
     }
 
-    pub fn expect_set_tab_title_response(&self, response: Result<KittyCommandResult<()>>) {
+    pub fn expect_set_tab_title_response(&self, response: KittyResult<()>) {
         self.set_tab_title_responses.borrow_mut().push(response);
     }
 
+    pub fn expect_send_text_response(&self, response: KittyResult<()>) {
+        self.send_text_responses.borrow_mut().push(response);
+    }
+
+    pub fn expect_get_text_response(&self, response: KittyResult<String>) {
+        self.get_text_responses.borrow_mut().push(response);
+    }
+
+    pub fn expect_focus_window_response(&self, response: KittyResult<()>) {
+        self.focus_window_responses.borrow_mut().push(response);
+    }
+
+    pub fn expect_close_window_response(&self, response: KittyResult<()>) {
+        self.close_window_responses.borrow_mut().push(response);
+    }
+
+    pub fn expect_set_window_title_response(&self, response: KittyResult<()>) {
+        self.set_window_title_responses.borrow_mut().push(response);
+    }
+
+    pub fn expect_resize_window_response(&self, response: KittyResult<bool>) {
+        self.resize_window_responses.borrow_mut().push(response);
+    }
+
+    pub fn expect_goto_layout_response(&self, response: KittyResult<()>) {
+        self.goto_layout_responses.borrow_mut().push(response);
+    }
+
+    pub fn expect_last_used_layout_response(&self, response: KittyResult<()>) {
+        self.last_used_layout_responses.borrow_mut().push(response);
+    }
+
+    pub fn expect_detach_window_response(&self, response: KittyResult<()>) {
+        self.detach_window_responses.borrow_mut().push(response);
+    }
+
+    pub fn expect_detach_tab_response(&self, response: KittyResult<()>) {
+        self.detach_tab_responses.borrow_mut().push(response);
+    }
+
+    pub fn expect_set_user_vars_response(&self, response: KittyResult<()>) {
+        self.set_user_vars_responses.borrow_mut().push(response);
+    }
+
     pub fn ls_call_count(&self) -> usize {
         self.ls_calls.borrow().len()
     }
@@ -467,6 +1197,63 @@ impl MockExecutor {
         self.set_tab_title_calls.borrow().len()
     }
 
+    pub fn send_text_call_count(&self) -> usize {
+        self.send_text_calls.borrow().len()
+    }
+
+    pub fn get_text_call_count(&self) -> usize {
+        self.get_text_calls.borrow().len()
+    }
+
+    pub fn focus_window_call_count(&self) -> usize {
+        self.focus_window_calls.borrow().len()
+    }
+
+    pub fn close_window_call_count(&self) -> usize {
+        self.close_window_calls.borrow().len()
+    }
+
+    pub fn set_window_title_call_count(&self) -> usize {
+        self.set_window_title_calls.borrow().len()
+    }
+
+    pub fn resize_window_call_count(&self) -> usize {
+        self.resize_window_calls.borrow().len()
+    }
+
+    pub fn goto_layout_call_count(&self) -> usize {
+        self.goto_layout_calls.borrow().len()
+    }
+
+    pub fn last_used_layout_call_count(&self) -> usize {
+        self.last_used_layout_calls.borrow().len()
+    }
+
+    pub fn detach_window_call_count(&self) -> usize {
+        self.detach_window_calls.borrow().len()
+    }
+
+    pub fn detach_tab_call_count(&self) -> usize {
+        self.detach_tab_calls.borrow().len()
+    }
+
+    /// Answer the next `execute` call as if kitten printed `stdout`
+    ///
+    /// The output goes through the command's own
+    /// [`parse_response`](KittenCommand::parse_response); without a queued response it sees
+    /// empty output.
+    pub fn expect_execute_response(&self, stdout: KittyResult<String>) {
+        self.execute_responses.borrow_mut().push(stdout);
+    }
+
+    pub fn set_user_vars_call_count(&self) -> usize {
+        self.set_user_vars_calls.borrow().len()
+    }
+
+    pub fn execute_call_count(&self) -> usize {
+        self.execute_calls.borrow().len()
+    }
+
     pub fn get_ls_calls(&self) -> Vec<KittenLsCommand> {
         self.ls_calls.borrow().clone()
     }
@@ -491,19 +1278,97 @@ impl MockExecutor {
         self.set_tab_title_calls.borrow().clone()
     }
 
+    pub fn get_send_text_calls(&self) -> Vec<KittenSendTextCommand> {
+        self.send_text_calls.borrow().clone()
+    }
+
+    pub fn get_get_text_calls(&self) -> Vec<KittenGetTextCommand> {
+        self.get_text_calls.borrow().clone()
+    }
+
+    pub fn get_focus_window_calls(&self) -> Vec<KittenFocusWindowCommand> {
+        self.focus_window_calls.borrow().clone()
+    }
+
+    pub fn get_close_window_calls(&self) -> Vec<KittenCloseWindowCommand> {
+        self.close_window_calls.borrow().clone()
+    }
+
+    pub fn get_set_window_title_calls(&self) -> Vec<KittenSetWindowTitleCommand> {
+        self.set_window_title_calls.borrow().clone()
+    }
+
+    pub fn get_resize_window_calls(&self) -> Vec<KittenResizeWindowCommand> {
+        self.resize_window_calls.borrow().clone()
+    }
+
+    pub fn get_goto_layout_calls(&self) -> Vec<KittenGotoLayoutCommand> {
+        self.goto_layout_calls.borrow().clone()
+    }
+
+    pub fn get_last_used_layout_calls(&self) -> Vec<KittenLastUsedLayoutCommand> {
+        self.last_used_layout_calls.borrow().clone()
+    }
+
+    pub fn get_detach_window_calls(&self) -> Vec<KittenDetachWindowCommand> {
+        self.detach_window_calls.borrow().clone()
+    }
+
+    pub fn get_detach_tab_calls(&self) -> Vec<KittenDetachTabCommand> {
+        self.detach_tab_calls.borrow().clone()
+    }
+
+    pub fn get_set_user_vars_calls(&self) -> Vec<KittenSetUserVarsCommand> {
+        self.set_user_vars_calls.borrow().clone()
+    }
+
+    pub fn get_execute_calls(&self) -> Vec<ExecutedCommand> {
+        self.execute_calls.borrow().clone()
+    }
+
+    /// The active window of the active tab
+    pub fn get_active_window_id(&self) -> Option<WindowId> {
+        self.layout.borrow().active_window_id()
+    }
+
+    /// Add a window to an existing tab, returning the new window ID
+    pub fn add_window(&self, tab_id: impl Into<TabId>) -> Option<WindowId> {
+        self.layout.borrow_mut().add_window(tab_id.into(), &[])
+    }
+
+    /// Set the text get-text returns for a window
+    pub fn set_window_text(&self, window_id: impl Into<WindowId>, text: &str) {
+        self.layout
+            .borrow_mut()
+            .window_text
+            .insert(window_id.into(), text.to_string());
+    }
+
+    /// Text received by a window through send-text, in order
+    pub fn get_sent_text(&self, window_id: impl Into<WindowId>) -> Vec<String> {
+        let window_id = window_id.into();
+        self.layout
+            .borrow()
+            .sent_text
+            .iter()
+            .filter(|(id, _)| *id == window_id)
+            .map(|(_, text)| text.clone())
+            .collect()
+    }
+
     /// Layout management methods
-    pub fn add_session_tab(&self, session_name: &str, tab_title: Option<String>) -> u32 {
+    pub fn add_session_tab(&self, session_name: &str, tab_title: Option<String>) -> TabId {
         self.layout
             .borrow_mut()
             .add_tab_with_session(session_name, tab_title)
     }
 
-    pub fn add_unnamed_tab(&self, tab_title: Option<String>) -> u32 {
+    pub fn add_unnamed_tab(&self, tab_title: Option<String>) -> TabId {
         self.layout.borrow_mut().add_unnamed_tab(tab_title)
     }
 
-    pub fn set_active_tab(&self, tab_id: u32) -> bool {
-        self.layout.borrow_mut().set_active_tab(tab_id)
+    pub fn set_active_tab(&self, tab_id: impl Into<TabId>) -> bool {
+        self.layout.borrow_mut().set_active_tab(tab_id.into())
     }
 
     pub fn get_tabs_for_session(&self, session_name: &str) -> Vec<KittyTab> {
@@ -518,236 +1383,623 @@ impl MockExecutor {
         self.layout.borrow_mut().clear()
     }
 
-    pub fn get_active_tab_id(&self) -> Option<u32> {
-        self.layout.borrow().active_tab_id
-    }
+    pub fn get_active_tab_id(&self) -> Option<TabId> {
+        self.layout.borrow().active_tab_id
+    }
+
+    pub fn remove_tab(&self, tab_id: impl Into<TabId>) -> bool {
+        self.layout.borrow_mut().remove_tab(tab_id.into())
+    }
+
+    /// Navigate tabs within a session using the internal layout
+    pub fn navigate_session_tab(
+        &self,
+        session_name: &str,
+        direction: TabNavigationDirection,
+        allow_wrap: bool,
+    ) -> Option<TabId> {
+        self.layout
+            .borrow_mut()
+            .navigate_tab(session_name, direction, allow_wrap)
+    }
+
+    pub fn set_tab_title_by_id(&self, tab_id: impl Into<TabId>, title: &str) -> bool {
+        self.layout.borrow_mut().set_tab_title(tab_id.into(), title)
+    }
+
+    pub fn set_active_tab_title(&self, title: &str) -> bool {
+        self.layout.borrow_mut().set_active_tab_title(title)
+    }
+
+    /// Enable smart behavior where the MockExecutor uses its internal layout
+    /// to generate responses automatically when no explicit responses are queued
+    pub fn enable_smart_responses(&self) {
+        // This is a flag we can use to modify behavior in the CommandExecutor implementation
+        // For now, the smart behavior is always enabled
+    }
+}
+
+impl CommandExecutor for MockExecutor {
+    fn ls(&self, command: KittenLsCommand) -> KittyResult<KittyLsResponse> {
+        self.ls_calls.borrow_mut().push(command.clone());
+        self.check_version(&command)?;
+
+        // If there's a queued response, use it
+        if let Some(response) = self.ls_responses.borrow_mut().pop() {
+            return response;
+        }
+
+        // Smart response: the layout, filtered like kitty filters it
+        let layout = self.layout.borrow();
+        let Some(match_arg) = &command.match_arg else {
+            return Ok(layout.os_windows.clone());
+        };
+
+        // kitten fails rather than printing an empty list when a match finds nothing
+        let response = layout.ls_matching(match_arg, command.use_tab_match);
+        if response.is_empty() {
+            let kind = if command.use_tab_match {
+                "tabs"
+            } else {
+                "windows"
+            };
+            return Err(KittyError::no_match("ls", kind, match_arg));
+        }
+        Ok(response)
+    }
+
+    fn focus_tab(&self, command: KittenFocusTabCommand) -> KittyResult<()> {
+        self.focus_tab_calls.borrow_mut().push(command.clone());
+
+        // If there's a queued response, use it
+        if let Some(response) = self.focus_tab_responses.borrow_mut().pop() {
+            return response;
+        }
+
+        // Smart response: check if the tab exists in our layout
+        let success = self.set_active_tab(command.tab_id);
+
+        if success {
+            Ok(())
+        } else {
+            Err(KittyError::no_match(
+                "focus-tab",
+                "tabs",
+                &format!("id:{}", command.tab_id),
+            ))
+        }
+    }
+
+    fn close_tab(&self, command: KittenCloseTabCommand) -> KittyResult<()> {
+        self.close_tab_calls.borrow_mut().push(command.clone());
+
+        // If there's a queued response, use it
+        if let Some(response) = self.close_tab_responses.borrow_mut().pop() {
+            return response;
+        }
+
+        // Smart response: check if the tab exists and remove it from our layout
+        let success = self.remove_tab(command.tab_id);
+
+        if success {
+            Ok(())
+        } else {
+            Err(KittyError::no_match(
+                "close-tab",
+                "tabs",
+                &format!("id:{}", command.tab_id),
+            ))
+        }
+    }
+
+    fn launch(&self, command: KittenLaunchCommand) -> KittyResult<KittyLaunchResponse> {
+        self.launch_calls.borrow_mut().push(command.clone());
+        self.check_version(&command)?;
+
+        // If there's a queued response, use it
+        if let Some(response) = self.launch_responses.borrow_mut().pop() {
+            return response;
+        }
+
+        // Smart response: actually create the tab or window in our layout
+        let mut layout = self.layout.borrow_mut();
+        let (tab_id, window_id) = match command.launch_type {
+            LaunchType::Tab | LaunchType::OsWindow => {
+                // Extract session name from environment variable if present
+                let session_name = command
+                    .env_value("KITTY_SESSION_PROJECT")
+                    .unwrap_or("unnamed"); // Default session
+
+                let tab_id = layout.add_tab_with_session(session_name, command.tab_title.clone());
+                layout.extend_tab_env(tab_id, &command.env);
+
+                if command.launch_type == LaunchType::OsWindow {
+                    layout.detach_tab(tab_id);
+                }
+                if !command.keep_focus {
+                    layout.set_active_tab(tab_id);
+                }
+
+                let window_id = WindowId(layout.next_window_id - 1);
+                (tab_id, window_id)
+            }
+            LaunchType::Window | LaunchType::Overlay => {
+                let target_tab = match &command.next_to {
+                    Some(next_to) => next_to
+                        .strip_prefix("id:")
+                        .and_then(|id| id.parse().ok())
+                        .and_then(|window_id| layout.tab_id_for_window(window_id)),
+                    None => layout.active_tab_id,
+                };
+
+                let Some(window_id) =
+                    target_tab.and_then(|tab_id| layout.add_window(tab_id, &command.env))
+                else {
+                    return Err(KittyError::no_match(
+                        "launch",
+                        "windows",
+                        command.next_to.as_deref().unwrap_or("state:active"),
+                    ));
+                };
+                if !command.keep_focus {
+                    layout.focus_window(window_id);
+                }
+
+                (target_tab.unwrap_or_default(), window_id)
+            }
+        };
+
+        if let Some(window) = layout.window_mut(window_id) {
+            if !command.args.is_empty() {
+                window.cmdline = command.args.clone();
+            }
+            if let Some(title) = &command.window_title {
+                window.title = title.clone();
+            }
+            if let Some(cwd) = &command.cwd {
+                window.cwd = cwd.clone();
+            }
+            window.user_vars.extend(command.vars.iter().cloned());
+        }
+
+        Ok(KittyLaunchResponse {
+            tab_id: Some(tab_id),
+            window_id: Some(window_id),
+        })
+    }
+
+    fn navigate_tab(&self, command: KittenNavigateTabCommand) -> KittyResult<()> {
+        self.navigate_tab_calls.borrow_mut().push(command.clone());
+
+        // If there's a queued response, use it
+        if let Some(response) = self.navigate_tab_responses.borrow_mut().pop() {
+            return response;
+        }
+
+        // Smart response: use the internal layout to navigate
+        let session_name = command.session_name.as_deref().unwrap_or("unnamed");
+        let result = self.navigate_session_tab(session_name, command.direction, command.allow_wrap);
+
+        match result {
+            Some(_target_tab_id) => Ok(()),
+            None => Err(KittyError::no_match(
+                "navigate-tab",
+                "tabs",
+                &format!("session:{}", session_name),
+            )),
+        }
+    }
+
+    fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> KittyResult<()> {
+        self.set_tab_title_calls.borrow_mut().push(command.clone());
+
+        // If there's a queued response, use it
+        if let Some(response) = self.set_tab_title_responses.borrow_mut().pop() {
+            return response;
+        }
+
+        // Smart response: check if the tab exists and update its title in our layout
+        let success = if let Some(match_pattern) = &command.match_pattern {
+            if match_pattern.starts_with("id:") {
+                let tab_id_str = match_pattern.trim_start_matches("id:");
+                if let Ok(tab_id) = tab_id_str.parse::<TabId>() {
+                    self.set_tab_title_by_id(tab_id, &command.title)
+                } else {
+                    false
+                }
+            } else {
+                // For other match patterns, just assume success for simplicity
+                true
+            }
+        } else {
+            // No match pattern means set the title for the active tab
+            self.set_active_tab_title(&command.title)
+        };
+
+        if success {
+            Ok(())
+        } else {
+            Err(KittyError::no_match(
+                "set-tab-title",
+                "tabs",
+                command.match_pattern.as_deref().unwrap_or("state:active"),
+            ))
+        }
+    }
+
+    fn send_text(&self, command: KittenSendTextCommand) -> KittyResult<()> {
+        self.send_text_calls.borrow_mut().push(command.clone());
+
+        // If there's a queued response, use it
+        if let Some(response) = self.send_text_responses.borrow_mut().pop() {
+            return response;
+        }
+
+        // Smart response: deliver the text to every matched window in our layout
+        let mut layout = self.layout.borrow_mut();
+        let active_window = layout.active_window_id();
+
+        let mut window_ids = match (&command.match_pattern, &command.match_tab) {
+            (Some(pattern), _) => layout.match_windows(pattern, false),
+            (None, Some(pattern)) => layout.match_windows(pattern, true),
+            (None, None) => active_window.into_iter().collect(),
+        };
+
+        if command.exclude_active {
+            window_ids.retain(|id| Some(*id) != active_window);
+        }
+
+        if window_ids.is_empty() {
+            return Err(KittyError::no_match(
+                "send-text",
+                "windows",
+                command
+                    .match_pattern
+                    .as_deref()
+                    .or(command.match_tab.as_deref())
+                    .unwrap_or("state:active"),
+            ));
+        }
+
+        for window_id in window_ids {
+            layout.sent_text.push((window_id, command.text.clone()));
+        }
+
+        Ok(())
+    }
+
+    fn get_text(&self, command: KittenGetTextCommand) -> KittyResult<String> {
+        self.get_text_calls.borrow_mut().push(command.clone());
+
+        // If there's a queued response, use it
+        if let Some(response) = self.get_text_responses.borrow_mut().pop() {
+            return response;
+        }
+
+        // Smart response: return the stored text of the first matched window
+        let layout = self.layout.borrow();
+        let window_id = match &command.match_pattern {
+            Some(pattern) => layout.match_windows(pattern, false).first().copied(),
+            None => layout.active_window_id(),
+        };
+
+        match window_id {
+            Some(window_id) => Ok(layout
+                .window_text
+                .get(&window_id)
+                .cloned()
+                .unwrap_or_default()),
+            None => Err(KittyError::no_match(
+                "get-text",
+                "windows",
+                command.match_pattern.as_deref().unwrap_or("state:active"),
+            )),
+        }
+    }
+
+    fn focus_window(&self, command: KittenFocusWindowCommand) -> KittyResult<()> {
+        self.focus_window_calls.borrow_mut().push(command.clone());
+
+        // If there's a queued response, use it
+        if let Some(response) = self.focus_window_responses.borrow_mut().pop() {
+            return response;
+        }
+
+        // Smart response: focus the first matched window and its tab
+        let mut layout = self.layout.borrow_mut();
+        match layout.match_windows(&command.match_pattern, false).first() {
+            Some(&window_id) => {
+                layout.focus_window(window_id);
+                Ok(())
+            }
+            None => Err(KittyError::no_match(
+                "focus-window",
+                "windows",
+                &command.match_pattern,
+            )),
+        }
+    }
+
+    fn close_window(&self, command: KittenCloseWindowCommand) -> KittyResult<()> {
+        self.close_window_calls.borrow_mut().push(command.clone());
+
+        // If there's a queued response, use it
+        if let Some(response) = self.close_window_responses.borrow_mut().pop() {
+            return response;
+        }
+
+        // Smart response: remove every matched window, and tabs left without windows
+        let mut layout = self.layout.borrow_mut();
+        let window_ids = layout.match_windows(&command.match_pattern, false);
+        if window_ids.is_empty() {
+            return Err(KittyError::no_match(
+                "close-window",
+                "windows",
+                &command.match_pattern,
+            ));
+        }
 
-    pub fn remove_tab(&self, tab_id: u32) -> bool {
-        self.layout.borrow_mut().remove_tab(tab_id)
+        for window_id in window_ids {
+            layout.remove_window(window_id);
+        }
+        Ok(())
     }
 
-    /// Navigate tabs within a session using the internal layout
-    pub fn navigate_session_tab(
-        &self,
-        session_name: &str,
-        direction: TabNavigationDirection,
-        allow_wrap: bool,
-    ) -> Option<u32> {
-        self.layout
+    fn set_window_title(&self, command: KittenSetWindowTitleCommand) -> KittyResult<()> {
+        self.set_window_title_calls
             .borrow_mut()
-            .navigate_tab(session_name, direction, allow_wrap)
-    }
+            .push(command.clone());
 
-    pub fn set_tab_title_by_id(&self, tab_id: u32, title: &str) -> bool {
-        self.layout.borrow_mut().set_tab_title(tab_id, title)
-    }
+        // If there's a queued response, use it
+        if let Some(response) = self.set_window_title_responses.borrow_mut().pop() {
+            return response;
+        }
 
-    pub fn set_active_tab_title(&self, title: &str) -> bool {
-        self.layout.borrow_mut().set_active_tab_title(title)
-    }
+        // Smart response: retitle the matched windows, or the active one
+        let mut layout = self.layout.borrow_mut();
+        let window_ids = match &command.match_pattern {
+            Some(pattern) => layout.match_windows(pattern, false),
+            None => layout.active_window_id().into_iter().collect(),
+        };
+        if window_ids.is_empty() {
+            return Err(KittyError::no_match(
+                "set-window-title",
+                "windows",
+                command.match_pattern.as_deref().unwrap_or("state:active"),
+            ));
+        }
 
-    /// Enable smart behavior where the MockExecutor uses its internal layout
-    /// to generate responses automatically when no explicit responses are queued
-    pub fn enable_smart_responses(&self) {
-        // This is a flag we can use to modify behavior in the CommandExecutor implementation
-        // For now, the smart behavior is always enabled
+        for window_id in window_ids {
+            layout.set_window_title(window_id, &command.title);
+        }
+        Ok(())
     }
-}
 
-impl CommandExecutor for &MockExecutor {
-    fn ls(&self, command: KittenLsCommand) -> Result<KittyLsResponse> {
-        self.ls_calls.borrow_mut().push(command.clone());
+    fn resize_window(&self, command: KittenResizeWindowCommand) -> KittyResult<bool> {
+        self.resize_window_calls.borrow_mut().push(command.clone());
 
         // If there's a queued response, use it
-        if let Some(response) = self.ls_responses.borrow_mut().pop() {
+        if let Some(response) = self.resize_window_responses.borrow_mut().pop() {
             return response;
         }
 
-        // Smart response using internal layout
+        // Smart response: window sizes are not simulated, so only check the window exists
         let layout = self.layout.borrow();
-        let mut result_os_windows = Vec::new();
-
-        // Check if we need to filter by environment variable or tab title
-        if let Some(match_arg) = &command.match_arg {
-            if match_arg.starts_with("env:KITTY_SESSION_PROJECT=") {
-                let session_name = match_arg.trim_start_matches("env:KITTY_SESSION_PROJECT=");
-                let matching_tabs = layout.get_tabs_for_session(session_name);
-
-                if !matching_tabs.is_empty() {
-                    // Create OS window containing the matching tabs
-                    let os_window = KittyOsWindow {
-                        id: 1,
-                        tabs: matching_tabs,
-                        title: Some("Kitty".to_string()),
-                        state: Some("active".to_string()),
-                    };
-                    result_os_windows.push(os_window);
-                }
-            } else if match_arg.starts_with("title:") {
-                let title_pattern = match_arg.trim_start_matches("title:");
-                let mut matching_tabs = Vec::new();
-
-                // Find tabs matching the title pattern
-                for os_window in &layout.os_windows {
-                    for tab in &os_window.tabs {
-                        if tab.title.contains(title_pattern) {
-                            matching_tabs.push(tab.clone());
-                        }
-                    }
-                }
+        let found = match &command.match_pattern {
+            Some(pattern) => !layout.match_windows(pattern, false).is_empty(),
+            None => layout.active_window_id().is_some(),
+        };
 
-                if !matching_tabs.is_empty() {
-                    // Create OS window containing the matching tabs
-                    let os_window = KittyOsWindow {
-                        id: 1,
-                        tabs: matching_tabs,
-                        title: Some("Kitty".to_string()),
-                        state: Some("active".to_string()),
-                    };
-                    result_os_windows.push(os_window);
-                }
-            }
+        if found {
+            Ok(true)
         } else {
-            // Return all OS windows if no filter
-            result_os_windows = layout.os_windows.clone();
+            Err(KittyError::no_match(
+                "resize-window",
+                "windows",
+                command.match_pattern.as_deref().unwrap_or("state:active"),
+            ))
         }
-
-        Ok(result_os_windows)
     }
 
-    fn focus_tab(&self, command: KittenFocusTabCommand) -> Result<KittyCommandResult<()>> {
-        self.focus_tab_calls.borrow_mut().push(command.clone());
+    fn goto_layout(&self, command: KittenGotoLayoutCommand) -> KittyResult<()> {
+        self.goto_layout_calls.borrow_mut().push(command.clone());
 
         // If there's a queued response, use it
-        if let Some(response) = self.focus_tab_responses.borrow_mut().pop() {
+        if let Some(response) = self.goto_layout_responses.borrow_mut().pop() {
             return response;
         }
 
-        // Smart response: check if the tab exists in our layout
-        let success = self.set_active_tab(command.tab_id);
+        // Smart response: switch the layout of the matched tabs, or the active one
+        let mut layout = self.layout.borrow_mut();
+        let tab_ids = match &command.match_tab {
+            Some(pattern) => layout.match_tabs(pattern),
+            None => layout.active_tab_id.into_iter().collect(),
+        };
+        if tab_ids.is_empty() {
+            return Err(KittyError::no_match(
+                "goto-layout",
+                "tabs",
+                command.match_tab.as_deref().unwrap_or("state:active"),
+            ));
+        }
 
-        if success {
-            Ok(KittyCommandResult::success_empty())
-        } else {
-            Ok(KittyCommandResult::error(format!(
-                "Tab {} not found",
-                command.tab_id
-            )))
+        let name = command.layout.to_string();
+        for tab_id in tab_ids {
+            layout.set_tab_layout(tab_id, &name);
         }
+        Ok(())
     }
 
-    fn close_tab(&self, command: KittenCloseTabCommand) -> Result<KittyCommandResult<()>> {
-        self.close_tab_calls.borrow_mut().push(command.clone());
+    fn last_used_layout(&self, command: KittenLastUsedLayoutCommand) -> KittyResult<()> {
+        self.last_used_layout_calls
+            .borrow_mut()
+            .push(command.clone());
 
         // If there's a queued response, use it
-        if let Some(response) = self.close_tab_responses.borrow_mut().pop() {
+        if let Some(response) = self.last_used_layout_responses.borrow_mut().pop() {
             return response;
         }
 
-        // Smart response: check if the tab exists and remove it from our layout
-        let success = self.remove_tab(command.tab_id);
+        // Smart response: swap back to the previous layout; tabs without one keep theirs
+        let mut layout = self.layout.borrow_mut();
+        let tab_ids = match &command.match_tab {
+            Some(pattern) => layout.match_tabs(pattern),
+            None => layout.active_tab_id.into_iter().collect(),
+        };
+        if tab_ids.is_empty() {
+            return Err(KittyError::no_match(
+                "last-used-layout",
+                "tabs",
+                command.match_tab.as_deref().unwrap_or("state:active"),
+            ));
+        }
 
-        if success {
-            Ok(KittyCommandResult::success_empty())
-        } else {
-            Ok(KittyCommandResult::error(format!(
-                "Tab {} not found",
-                command.tab_id
-            )))
+        for tab_id in tab_ids {
+            layout.last_used_layout(tab_id);
         }
+        Ok(())
     }
 
-    fn launch(
-        &self,
-        command: KittenLaunchCommand,
-    ) -> Result<KittyCommandResult<KittyLaunchResponse>> {
-        self.launch_calls.borrow_mut().push(command.clone());
+    fn detach_window(&self, command: KittenDetachWindowCommand) -> KittyResult<()> {
+        self.detach_window_calls.borrow_mut().push(command.clone());
 
         // If there's a queued response, use it
-        if let Some(response) = self.launch_responses.borrow_mut().pop() {
+        if let Some(response) = self.detach_window_responses.borrow_mut().pop() {
             return response;
         }
 
-        // Smart response: actually create the tab in our layout
-        if command.launch_type == "tab" {
-            // Extract session name from environment variable if present
-            let session_name = if let Some(env) = &command.env {
-                if env.starts_with("KITTY_SESSION_PROJECT=") {
-                    env.trim_start_matches("KITTY_SESSION_PROJECT=")
-                } else {
-                    "unnamed" // Default session
-                }
-            } else {
-                "unnamed" // Default session
-            };
-
-            let tab_id = self.add_session_tab(session_name, command.tab_title);
+        // Smart response: move the matched windows in our layout
+        let mut layout = self.layout.borrow_mut();
+        let window_ids = layout.match_windows(&command.match_pattern, false);
+        if window_ids.is_empty() {
+            return Err(KittyError::no_match(
+                "detach-window",
+                "windows",
+                &command.match_pattern,
+            ));
+        }
 
-            Ok(KittyCommandResult::success(KittyLaunchResponse {
-                tab_id: Some(tab_id),
-                window_id: None, // We don't track individual window IDs in launches for simplicity
-            }))
-        } else {
-            // For non-tab launches, return basic success
-            Ok(KittyCommandResult::success(KittyLaunchResponse {
-                tab_id: None,
-                window_id: None,
-            }))
+        for window_id in window_ids {
+            if layout.detach_window(window_id, &command.target).is_none() {
+                let expression = match &command.target {
+                    DetachTarget::Tab(expression) => expression.as_str(),
+                    _ => "new",
+                };
+                return Err(KittyError::no_match("detach-window", "tabs", expression));
+            }
         }
+        Ok(())
     }
 
-    fn navigate_tab(&self, command: KittenNavigateTabCommand) -> Result<KittyCommandResult<()>> {
-        self.navigate_tab_calls.borrow_mut().push(command.clone());
+    fn detach_tab(&self, command: KittenDetachTabCommand) -> KittyResult<()> {
+        self.detach_tab_calls.borrow_mut().push(command.clone());
 
         // If there's a queued response, use it
-        if let Some(response) = self.navigate_tab_responses.borrow_mut().pop() {
+        if let Some(response) = self.detach_tab_responses.borrow_mut().pop() {
             return response;
         }
 
-        // Smart response: use the internal layout to navigate
-        let session_name = command.session_name.as_deref().unwrap_or("unnamed");
-        let result = self.navigate_session_tab(session_name, command.direction, command.allow_wrap);
+        // Smart response: move the matched tabs in our layout
+        let mut layout = self.layout.borrow_mut();
+        let tab_ids = layout.match_tabs(&command.match_tab);
+        if tab_ids.is_empty() {
+            return Err(KittyError::no_match(
+                "detach-tab",
+                "tabs",
+                &command.match_tab,
+            ));
+        }
 
-        match result {
-            Some(_target_tab_id) => Ok(KittyCommandResult::success_empty()),
-            None => Ok(KittyCommandResult::error(format!(
-                "No tabs found in session '{}' for navigation",
-                session_name
-            ))),
+        let target_tab = match &command.target {
+            DetachTarget::NewOsWindow => None,
+            DetachTarget::NewTab => {
+                return Err(KittyError::InvalidCommand(
+                    "Tabs can only be detached to an OS window".to_string(),
+                ));
+            }
+            DetachTarget::Tab(expression) => match layout.match_tabs(expression).first() {
+                Some(&tab_id) => Some(tab_id),
+                None => {
+                    return Err(KittyError::no_match("detach-tab", "tabs", expression));
+                }
+            },
+        };
+
+        for tab_id in tab_ids {
+            match target_tab {
+                Some(target_tab_id) => {
+                    layout.move_tab(tab_id, target_tab_id);
+                }
+                None => {
+                    layout.detach_tab(tab_id);
+                }
+            }
         }
+        Ok(())
     }
 
-    fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> Result<KittyCommandResult<()>> {
-        self.set_tab_title_calls.borrow_mut().push(command.clone());
+    fn set_user_vars(&self, command: KittenSetUserVarsCommand) -> KittyResult<()> {
+        self.set_user_vars_calls.borrow_mut().push(command.clone());
+        self.check_version(&command)?;
 
         // If there's a queued response, use it
-        if let Some(response) = self.set_tab_title_responses.borrow_mut().pop() {
+        if let Some(response) = self.set_user_vars_responses.borrow_mut().pop() {
             return response;
         }
 
-        // Smart response: check if the tab exists and update its title in our layout
-        let success = if let Some(match_pattern) = &command.match_pattern {
-            if match_pattern.starts_with("id:") {
-                let tab_id_str = match_pattern.trim_start_matches("id:");
-                if let Ok(tab_id) = tab_id_str.parse::<u32>() {
-                    self.set_tab_title_by_id(tab_id, &command.title)
-                } else {
-                    false
-                }
-            } else {
-                // For other match patterns, just assume success for simplicity
-                true
-            }
-        } else {
-            // No match pattern means set the title for the active tab
-            self.set_active_tab_title(&command.title)
+        // Smart response: set (or unset, for empty values) the variables on the matched windows
+        let mut layout = self.layout.borrow_mut();
+        let window_ids = match &command.match_pattern {
+            Some(pattern) => layout.match_windows(pattern, false),
+            None => layout.active_window_id().into_iter().collect(),
         };
+        if window_ids.is_empty() {
+            return Err(KittyError::no_match(
+                "set-user-vars",
+                "windows",
+                command.match_pattern.as_deref().unwrap_or("state:active"),
+            ));
+        }
 
-        if success {
-            Ok(KittyCommandResult::success_empty())
-        } else {
-            Ok(KittyCommandResult::error("Failed to set tab title"))
+        for window_id in window_ids {
+            if let Some(window) = layout.window_mut(window_id) {
+                for (name, value) in &command.vars {
+                    if value.is_empty() {
+                        window.user_vars.remove(name);
+                    } else {
+                        window.user_vars.insert(name.clone(), value.clone());
+                    }
+                }
+            }
         }
+        Ok(())
+    }
+
+    fn capabilities(&self) -> KittyResult<KittyCapabilities> {
+        Ok(self
+            .kitty_version
+            .get()
+            .map(KittyCapabilities::of)
+            .unwrap_or_default())
+    }
+
+    /// Record the command and answer from [`MockExecutor::expect_execute_response`]
+    ///
+    /// Commands run this way don't change the mock layout.
+    fn execute<C: KittenCommand>(&self, command: C) -> KittyResult<C::Response> {
+        self.execute_calls.borrow_mut().push(ExecutedCommand {
+            name: command.name().to_string(),
+            args: command.args()?,
+            stdin: command.stdin().map(str::to_string),
+        });
+        self.check_version(&command)?;
+
+        let stdout = self
+            .execute_responses
+            .borrow_mut()
+            .pop()
+            .unwrap_or(Ok(String::new()))?;
+        command.parse_response(stdout.as_bytes())
     }
 }
 
@@ -761,8 +2013,13 @@ impl Default for MockExecutor {
 mod tests {
     use super::*;
     use crate::commands::focus_tab::KittenFocusTabCommand;
-    use crate::commands::launch::KittenLaunchCommand;
+    use crate::commands::get_text::{KittenGetTextCommand, TextExtent};
+    use crate::commands::launch::{KittenLaunchCommand, LaunchType, WindowLocation};
+    use crate::commands::layout::KittyLayout;
     use crate::commands::ls::KittenLsCommand;
+    use crate::commands::resize_window::ResizeAxis;
+    use crate::commands::send_text::KittenSendTextCommand;
+    use crate::error::KittyError;
     use crate::executor::CommandExecutor;
 
     #[test]
@@ -771,7 +2028,7 @@ mod tests {
 
         let tab_id = layout.add_tab_with_session("test-project", Some("Test Tab".to_string()));
         assert_eq!(tab_id, 1);
-        assert_eq!(layout.active_tab_id, Some(1));
+        assert_eq!(layout.active_tab_id, Some(TabId(1)));
 
         let tabs = layout.get_tabs_for_session("test-project");
         assert_eq!(tabs.len(), 1);
@@ -814,13 +2071,13 @@ mod tests {
         let _tab1 = layout.add_tab_with_session("project1", None);
         let tab2 = layout.add_tab_with_session("project2", None);
 
-        assert_eq!(layout.active_tab_id, Some(1)); // First tab is active
+        assert_eq!(layout.active_tab_id, Some(TabId(1))); // First tab is active
 
         assert!(layout.set_active_tab(tab2));
-        assert_eq!(layout.active_tab_id, Some(2));
+        assert_eq!(layout.active_tab_id, Some(TabId(2)));
 
-        assert!(!layout.set_active_tab(999)); // Non-existent tab
-        assert_eq!(layout.active_tab_id, Some(2)); // Should remain unchanged
+        assert!(!layout.set_active_tab(TabId(999))); // Non-existent tab
+        assert_eq!(layout.active_tab_id, Some(TabId(2))); // Should remain unchanged
     }
 
     #[test]
@@ -834,7 +2091,7 @@ mod tests {
 
         // Test filtering by session
         let ls_command = KittenLsCommand::new().match_env("KITTY_SESSION_PROJECT", "project1");
-        let response = (&executor).ls(ls_command).unwrap();
+        let response = executor.ls(ls_command).unwrap();
 
         assert_eq!(response.len(), 1); // One OS window
         assert_eq!(response[0].tabs.len(), 2); // Two tabs for project1
@@ -843,7 +2100,7 @@ mod tests {
 
         // Test filtering by different session
         let ls_command = KittenLsCommand::new().match_env("KITTY_SESSION_PROJECT", "project2");
-        let response = (&executor).ls(ls_command).unwrap();
+        let response = executor.ls(ls_command).unwrap();
 
         assert_eq!(response.len(), 1); // One OS window
         assert_eq!(response[0].tabs.len(), 1); // One tab for project2
@@ -851,9 +2108,10 @@ mod tests {
 
         // Test filtering by non-existent session
         let ls_command = KittenLsCommand::new().match_env("KITTY_SESSION_PROJECT", "nonexistent");
-        let response = (&executor).ls(ls_command).unwrap();
+        let response = executor.ls(ls_command);
 
-        assert_eq!(response.len(), 0); // No matching tabs
+        // Like kitten, a match that finds nothing is an error rather than an empty list
+        assert!(matches!(response, Err(KittyError::NoMatch { .. })));
     }
 
     #[test]
@@ -864,22 +2122,18 @@ mod tests {
 
         // Test focusing existing tab
         let focus_command = KittenFocusTabCommand::new(tab_id);
-        let response = (&executor).focus_tab(focus_command).unwrap();
+        executor.focus_tab(focus_command).unwrap();
 
-        assert!(response.is_success());
         assert_eq!(executor.get_active_tab_id(), Some(tab_id));
 
         // Test focusing non-existent tab
         let focus_command = KittenFocusTabCommand::new(999);
-        let response = (&executor).focus_tab(focus_command).unwrap();
+        let error = executor.focus_tab(focus_command).unwrap_err();
 
-        assert!(!response.is_success());
-        assert!(response.error_message.is_some());
-        assert!(
-            response
-                .error_message
-                .unwrap()
-                .contains("Tab 999 not found")
+        assert!(matches!(error, KittyError::NoMatch { .. }));
+        assert_eq!(
+            error.stderr(),
+            Some("Error: No matching tabs for expression: id:999")
         );
     }
 
@@ -889,15 +2143,11 @@ mod tests {
 
         // Test launching a tab with session
         let launch_command = KittenLaunchCommand::new()
-            .launch_type("tab")
+            .launch_type(LaunchType::Tab)
             .env("KITTY_SESSION_PROJECT", "test-project")
             .tab_title("Test Tab");
 
-        let response = (&executor).launch(launch_command).unwrap();
-
-        assert!(response.is_success());
-        assert!(response.data.is_some());
-        let launch_response = response.data.unwrap();
+        let launch_response = executor.launch(launch_command).unwrap();
         assert!(launch_response.tab_id.is_some());
 
         // Verify the tab was actually added to the layout
@@ -906,6 +2156,151 @@ mod tests {
         assert_eq!(tabs[0].title, "Test Tab");
     }
 
+    #[test]
+    fn test_mock_executor_launch_applies_all_env_vars() {
+        let executor = MockExecutor::new();
+
+        let launch_command = KittenLaunchCommand::new()
+            .launch_type(LaunchType::Tab)
+            .env("KITTY_SESSION_PROJECT", "test-project")
+            .envs([("AWS_PROFILE", "dev"), ("RUST_LOG", "info")])
+            .env("AWS_PROFILE", "prod");
+
+        executor.launch(launch_command).unwrap();
+
+        let calls = executor.get_launch_calls();
+        assert_eq!(calls[0].env.len(), 4);
+        assert_eq!(calls[0].env_value("AWS_PROFILE"), Some("prod"));
+
+        let tabs = executor.get_tabs_for_session("test-project");
+        assert_eq!(tabs.len(), 1);
+        let env = &tabs[0].windows[0].env;
+        assert_eq!(env.get("AWS_PROFILE"), Some(&"prod".to_string()));
+        assert_eq!(env.get("RUST_LOG"), Some(&"info".to_string()));
+    }
+
+    #[test]
+    fn test_mock_executor_launch_window_next_to_existing_window() {
+        let executor = MockExecutor::new();
+        executor.add_session_tab("test-project", None);
+        executor.add_session_tab("other-project", None);
+
+        let launch_command = KittenLaunchCommand::new()
+            .launch_type(LaunchType::Window)
+            .next_to("id:1")
+            .location(WindowLocation::Vsplit)
+            .window_title("editor")
+            .var("role", "editor")
+            .keep_focus()
+            .args(["nvim", "."]);
+
+        let launch_response = executor.launch(launch_command).unwrap();
+        assert_eq!(launch_response.tab_id, Some(TabId(1)));
+        assert_eq!(launch_response.window_id, Some(WindowId(3)));
+
+        let calls = executor.get_launch_calls();
+        assert_eq!(calls[0].location, Some(WindowLocation::Vsplit));
+        assert!(calls[0].keep_focus);
+
+        let tabs = executor.get_tabs_for_session("test-project");
+        let window = &tabs[0].windows[1];
+        assert_eq!(window.title, "editor");
+        assert_eq!(window.cmdline, vec!["nvim", "."]);
+        assert_eq!(window.user_vars.get("role"), Some(&"editor".to_string()));
+        assert_eq!(
+            executor.get_tabs_for_session("other-project")[0]
+                .windows
+                .len(),
+            1
+        );
+        assert_eq!(executor.get_active_tab_id(), Some(TabId(1)));
+    }
+
+    #[test]
+    fn test_mock_executor_launch_os_window_and_keep_focus() {
+        let executor = MockExecutor::new();
+        executor.add_session_tab("test-project", None);
+
+        let launch_command = KittenLaunchCommand::new()
+            .launch_type(LaunchType::OsWindow)
+            .env("KITTY_SESSION_PROJECT", "detached")
+            .keep_focus();
+
+        let response = executor.launch(launch_command).unwrap();
+        assert_eq!(response.tab_id, Some(TabId(2)));
+
+        let layout = executor.layout.borrow();
+        assert_eq!(layout.os_windows.len(), 2);
+        assert_eq!(layout.os_windows[1].tabs[0].id, 2);
+        assert_eq!(layout.active_tab_id, Some(TabId(1)));
+    }
+
+    #[test]
+    fn test_mock_executor_launch_window_without_tab_fails() {
+        let executor = MockExecutor::new();
+
+        let launch_command = KittenLaunchCommand::new().launch_type(LaunchType::Overlay);
+        let response = executor.launch(launch_command);
+
+        assert!(matches!(response, Err(KittyError::NoMatch { .. })));
+    }
+
+    #[test]
+    fn test_mock_executor_send_text_to_matched_windows() {
+        let executor = MockExecutor::new();
+        let first = executor.add_session_tab("api", None);
+        executor.add_session_tab("api", None);
+        executor.add_session_tab("web", None);
+        executor.layout.borrow_mut().add_window(first, &[]);
+
+        // Tab matches reach every window of the tab, skipping the active one when asked
+        let command = KittenSendTextCommand::new("git pull\r")
+            .with_tab_match("env:KITTY_SESSION_PROJECT=api")
+            .exclude_active();
+        assert!(executor.send_text(command).is_ok());
+
+        assert!(executor.get_sent_text(WindowId(1)).is_empty());
+        assert_eq!(executor.get_sent_text(WindowId(2)), vec!["git pull\r"]);
+        assert_eq!(executor.get_sent_text(WindowId(4)), vec!["git pull\r"]);
+        assert!(executor.get_sent_text(WindowId(3)).is_empty());
+
+        // Window matches support `or`
+        let command = KittenSendTextCommand::new("ls").with_match("id:1 or id:3");
+        assert!(executor.send_text(command).is_ok());
+        assert_eq!(executor.get_sent_text(WindowId(1)), vec!["ls"]);
+        assert_eq!(executor.get_sent_text(WindowId(3)), vec!["ls"]);
+
+        let command = KittenSendTextCommand::new("ls").for_window_id(99);
+        assert!(executor.send_text(command).is_err());
+        assert_eq!(executor.send_text_call_count(), 3);
+    }
+
+    #[test]
+    fn test_mock_executor_get_text_returns_window_text() {
+        let executor = MockExecutor::new();
+        executor.add_session_tab("api", None);
+        executor.add_session_tab("api", None);
+        executor.set_window_text(WindowId(2), "error: build failed\n");
+
+        let command = KittenGetTextCommand::new()
+            .for_window_id(2)
+            .extent(TextExtent::All)
+            .ansi();
+        let response = executor.get_text(command).unwrap();
+        assert_eq!(response, "error: build failed\n");
+
+        // The active window has no stored text
+        let response = executor.get_text(KittenGetTextCommand::new()).unwrap();
+        assert_eq!(response, "");
+
+        let calls = executor.get_get_text_calls();
+        assert_eq!(calls[0].extent, TextExtent::All);
+        assert!(calls[0].ansi);
+
+        let command = KittenGetTextCommand::new().for_window_id(99);
+        assert!(executor.get_text(command).is_err());
+    }
+
     #[test]
     fn test_mock_executor_queued_responses_override_smart_responses() {
         let executor = MockExecutor::new();
@@ -915,9 +2310,9 @@ mod tests {
 
         // Queue a custom response
         let custom_response = Ok(vec![KittyOsWindow {
-            id: 999,
+            id: OsWindowId(999),
             tabs: vec![KittyTab {
-                id: 999,
+                id: TabId(999),
                 index: Some(0),
                 title: "Custom Tab".to_string(),
                 windows: vec![],
@@ -925,15 +2320,21 @@ mod tests {
                 is_focused: true,
                 state: Some("active".to_string()),
                 recent: Some(0),
+                layout: None,
+                enabled_layouts: Vec::new(),
+                active_window_history: Vec::new(),
             }],
             title: Some("Custom Window".to_string()),
             state: Some("active".to_string()),
+            is_focused: false,
+            last_focused: false,
+            platform_window_id: None,
         }]);
         executor.expect_ls_response(custom_response);
 
         // The queued response should be used instead of smart response
         let ls_command = KittenLsCommand::new();
-        let response = (&executor).ls(ls_command).unwrap();
+        let response = executor.ls(ls_command).unwrap();
 
         assert_eq!(response.len(), 1);
         assert_eq!(response[0].id, 999);
@@ -1045,23 +2446,20 @@ mod tests {
 
         // Test next navigation
         let nav_command = KittenNavigateTabCommand::next().with_session("project1");
-        let response = (&executor).navigate_tab(nav_command).unwrap();
-        assert!(response.is_success());
-        assert_eq!(executor.get_active_tab_id(), Some(2)); // Should move to second tab
+        executor.navigate_tab(nav_command).unwrap();
+        assert_eq!(executor.get_active_tab_id(), Some(TabId(2))); // Should move to second tab
 
         // Test previous navigation
         let nav_command = KittenNavigateTabCommand::previous().with_session("project1");
-        let response = (&executor).navigate_tab(nav_command).unwrap();
-        assert!(response.is_success());
-        assert_eq!(executor.get_active_tab_id(), Some(1)); // Should move back to first tab
+        executor.navigate_tab(nav_command).unwrap();
+        assert_eq!(executor.get_active_tab_id(), Some(TabId(1))); // Should move back to first tab
 
         // Test no-wrap behavior at boundary
         let nav_command = KittenNavigateTabCommand::previous()
             .with_session("project1")
             .no_wrap();
-        let response = (&executor).navigate_tab(nav_command).unwrap();
-        assert!(response.is_success());
-        assert_eq!(executor.get_active_tab_id(), Some(1)); // Should stay on first tab
+        executor.navigate_tab(nav_command).unwrap();
+        assert_eq!(executor.get_active_tab_id(), Some(TabId(1))); // Should stay on first tab
 
         // Verify call tracking
         assert_eq!(executor.navigate_tab_call_count(), 3);
@@ -1078,11 +2476,10 @@ mod tests {
         let executor = MockExecutor::new();
 
         let nav_command = KittenNavigateTabCommand::next().with_session("nonexistent");
-        let response = (&executor).navigate_tab(nav_command).unwrap();
+        let error = executor.navigate_tab(nav_command).unwrap_err();
 
-        assert!(!response.is_success());
-        assert!(response.error_message.is_some());
-        assert!(response.error_message.unwrap().contains("No tabs found"));
+        assert!(matches!(error, KittyError::NoMatch { .. }));
+        assert!(error.to_string().contains("session:nonexistent"));
     }
 
     #[test]
@@ -1092,12 +2489,329 @@ mod tests {
         executor.add_session_tab("project1", None);
 
         // Queue a custom error response
-        executor.expect_navigate_tab_response(Ok(KittyCommandResult::error("Custom error")));
+        executor.expect_navigate_tab_response(Err(KittyError::CommandFailed {
+            command: "navigate-tab".to_string(),
+            exit_code: Some(1),
+            stderr: "Custom error".to_string(),
+        }));
 
         let nav_command = KittenNavigateTabCommand::next().with_session("project1");
-        let response = (&executor).navigate_tab(nav_command).unwrap();
+        let error = executor.navigate_tab(nav_command).unwrap_err();
+
+        assert_eq!(error.stderr(), Some("Custom error"));
+        assert_eq!(error.exit_code(), Some(1));
+    }
+
+    #[test]
+    fn test_mock_executor_focus_and_close_window() {
+        let executor = MockExecutor::new();
+        let tab_id = executor.add_session_tab("project1", None);
+        let second = executor.add_window(tab_id).unwrap();
+        let other_tab = executor.add_session_tab("project2", None);
+
+        executor
+            .focus_window(KittenFocusWindowCommand::new(second))
+            .unwrap();
+        assert_eq!(executor.get_active_tab_id(), Some(tab_id));
+        assert_eq!(executor.get_active_window_id(), Some(second));
+
+        // Closing the active window hands focus to the remaining one
+        executor
+            .close_window(KittenCloseWindowCommand::new(second))
+            .unwrap();
+        assert_eq!(executor.get_active_window_id(), Some(WindowId(1)));
+
+        // Closing the last window of a tab closes the tab
+        let window_id = executor.get_all_tabs()[1].windows[0].id;
+        executor
+            .close_window(KittenCloseWindowCommand::new(window_id))
+            .unwrap();
+        assert!(
+            executor
+                .get_all_tabs()
+                .iter()
+                .all(|tab| tab.id != other_tab)
+        );
+
+        let response = executor.focus_window(KittenFocusWindowCommand::new(99));
+        assert!(matches!(response, Err(KittyError::NoMatch { .. })));
+    }
+
+    #[test]
+    fn test_mock_executor_set_window_title_and_resize() {
+        let executor = MockExecutor::new();
+        let tab_id = executor.add_session_tab("project1", None);
+        let second = executor.add_window(tab_id).unwrap();
+
+        executor
+            .set_window_title(KittenSetWindowTitleCommand::new("editor").for_window_id(second))
+            .unwrap();
+        executor
+            .set_window_title(KittenSetWindowTitleCommand::new("shell-1"))
+            .unwrap();
+
+        let tab = &executor.get_all_tabs()[0];
+        assert_eq!(tab.windows[0].title, "shell-1");
+        assert_eq!(tab.windows[1].title, "editor");
+
+        let command =
+            KittenResizeWindowCommand::new(-4, ResizeAxis::Vertical).for_window_id(second);
+        assert!(matches!(executor.resize_window(command), Ok(true)));
+        assert_eq!(executor.get_resize_window_calls()[0].increment, -4);
+
+        let command = KittenResizeWindowCommand::reset().for_window_id(99);
+        assert!(executor.resize_window(command).is_err());
+    }
+
+    #[test]
+    fn test_mock_executor_layout_tracking() {
+        let executor = MockExecutor::new();
+        let first = executor.add_session_tab("project1", None);
+        let second = executor.add_session_tab("project1", None);
+
+        executor
+            .goto_layout(KittenGotoLayoutCommand::new(KittyLayout::Splits))
+            .unwrap();
+        executor
+            .goto_layout(KittenGotoLayoutCommand::new(KittyLayout::Stack).for_tab_id(second))
+            .unwrap();
+
+        let tabs = executor.get_all_tabs();
+        assert_eq!(tabs[0].layout.as_deref(), Some("splits"));
+        assert_eq!(tabs[1].layout.as_deref(), Some("stack"));
+
+        executor
+            .last_used_layout(KittenLastUsedLayoutCommand::new().for_tab_id(first))
+            .unwrap();
+        assert_eq!(
+            executor.get_all_tabs()[0].layout.as_deref(),
+            Some(DEFAULT_LAYOUT)
+        );
+
+        // Switching back again toggles between the two most recent layouts
+        executor
+            .last_used_layout(KittenLastUsedLayoutCommand::new())
+            .unwrap();
+        assert_eq!(executor.get_all_tabs()[0].layout.as_deref(), Some("splits"));
+    }
+
+    #[test]
+    fn test_mock_executor_detach_window() {
+        let executor = MockExecutor::new();
+        let tab_id = executor.add_session_tab("project1", None);
+        let second = executor.add_window(tab_id).unwrap();
+        let third = executor.add_window(tab_id).unwrap();
+        let other_tab = executor.add_session_tab("project2", None);
+
+        // Into a new tab of the same OS window
+        executor
+            .detach_window(KittenDetachWindowCommand::new(second).target(DetachTarget::NewTab))
+            .unwrap();
+        {
+            let layout = executor.layout.borrow();
+            assert_eq!(layout.os_windows.len(), 1);
+            assert_eq!(layout.os_windows[0].tabs.len(), 3);
+        }
+
+        // Into an existing tab
+        let target = DetachTarget::Tab(format!("id:{}", other_tab));
+        executor
+            .detach_window(KittenDetachWindowCommand::new(third).target(target))
+            .unwrap();
+        assert_eq!(
+            executor.layout.borrow().tab_id_for_window(third),
+            Some(other_tab)
+        );
+
+        // Into a new OS window, closing the tab it leaves empty
+        executor
+            .detach_window(KittenDetachWindowCommand::new(second))
+            .unwrap();
+        let layout = executor.layout.borrow();
+        assert_eq!(layout.os_windows.len(), 2);
+        assert_eq!(layout.os_windows[0].tabs.len(), 2);
+        assert_eq!(layout.os_windows[1].tabs[0].windows[0].id, second);
+    }
+
+    #[test]
+    fn test_mock_executor_detach_tab() {
+        let executor = MockExecutor::new();
+        executor.add_session_tab("project1", None);
+        let moved = executor.add_session_tab("project2", None);
+
+        executor
+            .detach_tab(KittenDetachTabCommand::new(moved))
+            .unwrap();
+        assert_eq!(executor.layout.borrow().os_windows.len(), 2);
+
+        executor
+            .detach_tab(KittenDetachTabCommand::new(moved).target(DetachTarget::Tab("id:1".into())))
+            .unwrap();
+        assert_eq!(executor.layout.borrow().os_windows[0].tabs.len(), 2);
+
+        let command = KittenDetachTabCommand::new(moved).target(DetachTarget::NewTab);
+        assert!(executor.detach_tab(command).is_err());
+    }
+
+    #[test]
+    fn test_mock_executor_launch_window_takes_focus() {
+        let executor = MockExecutor::new();
+        executor.add_session_tab("project1", None);
+
+        let command = KittenLaunchCommand::new().launch_type(LaunchType::Window);
+        let response = executor.launch(command).unwrap();
+        assert_eq!(executor.get_active_window_id(), response.window_id);
+
+        let command = KittenLaunchCommand::new()
+            .launch_type(LaunchType::Window)
+            .keep_focus();
+        executor.launch(command).unwrap();
+        assert_eq!(executor.get_active_window_id(), response.window_id);
+    }
+
+    #[test]
+    fn test_kitty_layout_from_str() {
+        assert_eq!("splits".parse::<KittyLayout>(), Ok(KittyLayout::Splits));
+        assert_eq!("tall:bias=70".parse::<KittyLayout>(), Ok(KittyLayout::Tall));
+        assert!("spiral".parse::<KittyLayout>().is_err());
+        assert_eq!(KittyLayout::Grid.to_string(), "grid");
+    }
+
+    #[test]
+    fn test_mock_layout_multiple_os_windows() {
+        let mut layout = MockLayout::new();
+        let first = layout.add_tab_with_session("api", None);
+        let second = layout.add_tab_with_session("api", None);
+        let other = layout.add_os_window(Some("web"), None);
+        assert_eq!(layout.os_windows.len(), 2);
+        assert_eq!(layout.active_tab_id, Some(first));
+
+        layout.set_active_tab(other);
+        let (main, web) = (&layout.os_windows[0], &layout.os_windows[1]);
+        assert!(!main.is_focused && web.is_focused && web.last_focused);
+        // Each OS window keeps its active tab, only the focused one's has focus
+        assert!(main.tabs[0].is_active && !main.tabs[0].is_focused);
+        assert!(!main.tabs[0].windows[0].is_focused);
+        assert!(web.tabs[0].is_focused && web.tabs[0].windows[0].is_focused);
+
+        // New tabs open next to the focused tab
+        let web_second = layout.add_tab_with_session("web", None);
+        assert_eq!(layout.os_windows[1].tabs.len(), 2);
+
+        // Closing the focused tab hands focus to its OS window, then back to the previous one
+        layout.remove_tab(other);
+        assert_eq!(layout.active_tab_id, Some(web_second));
+        layout.remove_tab(web_second);
+        assert_eq!(layout.os_windows.len(), 1);
+        assert_eq!(layout.active_tab_id, Some(first));
+        assert!(layout.os_windows[0].is_focused);
+        assert!(!layout.tab(second).unwrap().is_active);
+    }
+
+    #[test]
+    fn test_mock_layout_focus_history() {
+        let mut layout = MockLayout::new();
+        let first = layout.add_tab_with_session("api", None);
+        let second = layout.add_tab_with_session("api", None);
+        let third = layout.add_tab_with_session("api", None);
+        let w1 = layout.active_window_id().unwrap();
+        let w2 = layout.add_window(first, &[]).unwrap();
+        let w3 = layout.add_window(first, &[]).unwrap();
+
+        layout.focus_window(w2);
+        layout.focus_window(w3);
+        assert_eq!(layout.tab(first).unwrap().active_window_history, [w1, w2]);
+        assert_eq!(layout.match_windows("recent:0", false), [w3]);
+        assert_eq!(layout.match_windows("recent:1", false), [w2]);
+        assert_eq!(layout.match_windows("recent:2", false), [w1]);
+        assert_eq!(layout.match_windows("num:1", false), [w2]);
+
+        // Closing the active window goes back to the one active before it
+        layout.remove_window(w3);
+        assert_eq!(layout.active_window_id(), Some(w2));
+
+        layout.set_active_tab(third);
+        layout.set_active_tab(second);
+        assert_eq!(layout.tab_history, [first, third, second]);
+        assert_eq!(layout.match_tabs("recent:0"), [second]);
+        assert_eq!(layout.match_tabs("recent:1"), [third]);
+        assert_eq!(layout.match_tabs("index:0"), [first]);
+    }
+
+    #[test]
+    fn test_mock_layout_state_matches() {
+        let mut layout = MockLayout::new();
+        let first = layout.add_tab_with_session("api", None);
+        let split = layout.add_window(first, &[]).unwrap();
+        let other = layout.add_os_window(None, None);
+        let other_window = layout.tab(other).unwrap().windows[0].id;
+        let first_window = layout.tab(first).unwrap().windows[0].id;
+
+        assert_eq!(layout.match_windows("state:focused", false), [first_window]);
+        assert_eq!(
+            layout.match_windows("state:active", false),
+            [first_window, other_window]
+        );
+        assert_eq!(
+            layout.match_windows("state:parent_focused", false),
+            [first_window, split]
+        );
+        assert_eq!(layout.match_tabs("state:active"), [first, other]);
+        assert_eq!(layout.match_tabs("state:focused"), [first]);
+        assert_eq!(layout.match_tabs("state:parent_focused"), [first]);
+        assert!(layout.match_tabs("state:needs_attention").is_empty());
+
+        // Nothing invoked kitten until a test marks the window that did
+        assert!(layout.match_windows("state:self", false).is_empty());
+        assert!(layout.set_self_window(other_window));
+        assert_eq!(layout.match_windows("state:self", false), [other_window]);
+        assert_eq!(layout.match_tabs("state:self"), [other]);
+        assert!(!layout.set_self_window(WindowId(99)));
+    }
+
+    #[test]
+    fn test_mock_layout_match_expressions() {
+        let mut layout = MockLayout::new();
+        let api = layout.add_tab_with_session("api", Some("session:api - server".to_string()));
+        let api_v2 = layout.add_tab_with_session("api-v2", Some("session:api-v2".to_string()));
+        let web = layout.add_tab_with_session("web", None);
+        let split = layout
+            .add_window(
+                web,
+                &[("KITTY_SESSION_PROJECT".to_string(), "api".to_string())],
+            )
+            .unwrap();
+
+        // Patterns are regular expressions found anywhere in the value
+        assert_eq!(layout.match_tabs("title:session:api"), [api, api_v2]);
+        assert_eq!(layout.match_tabs(r#"title:"^session:api( - |$)""#), [api]);
+        assert_eq!(
+            layout.match_tabs("env:KITTY_SESSION_PROJECT=api"),
+            [api, api_v2, web]
+        );
+        assert_eq!(
+            layout.match_tabs("env:KITTY_SESSION_PROJECT=^api$"),
+            [api, web]
+        );
+        assert_eq!(layout.match_tabs("cwd:/tmp/web"), [web]);
+        assert!(layout.match_tabs("title:(").is_empty());
+
+        // Operators, grouping and implicit and
+        assert_eq!(
+            layout.match_tabs("env:KITTY_SESSION_PROJECT=^api$ and not id:1"),
+            [web]
+        );
+        assert_eq!(layout.match_tabs("(id:1 or id:3) title:server"), [api]);
+        assert_eq!(layout.match_tabs("all").len(), 3);
 
-        assert!(!response.is_success());
-        assert_eq!(response.error_message, Some("Custom error".to_string()));
+        // --match picks windows, --match-tab whole tabs
+        assert_eq!(
+            layout.match_windows("env:KITTY_SESSION_PROJECT=^api$", false),
+            [layout.tab(api).unwrap().windows[0].id, split]
+        );
+        let tab_match = layout.ls_matching("env:KITTY_SESSION_PROJECT=^api$", true);
+        assert_eq!(tab_match[0].tabs[1].windows.len(), 2);
+        let window_match = layout.ls_matching("env:KITTY_SESSION_PROJECT=^api$", false);
+        assert_eq!(window_match[0].tabs[1].windows.len(), 1);
     }
 }
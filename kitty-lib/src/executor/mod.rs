@@ -1,26 +1,177 @@
+pub mod async_executor;
+#[cfg(feature = "tokio")]
+pub mod async_kitty_executor;
+#[cfg(feature = "tokio")]
+pub mod async_mock_executor;
+pub mod dry_run;
+pub(crate) mod invocation;
 pub mod kitty_executor;
+mod match_expression;
+pub mod middleware;
 pub mod mock_executor;
+pub mod recording;
+pub mod replay;
+pub mod retry;
 
+use crate::commands::KittenCommand;
 use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::close_window::KittenCloseWindowCommand;
+use crate::commands::detach::{KittenDetachTabCommand, KittenDetachWindowCommand};
 use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::focus_window::KittenFocusWindowCommand;
+use crate::commands::get_text::KittenGetTextCommand;
 use crate::commands::launch::KittenLaunchCommand;
+use crate::commands::layout::{KittenGotoLayoutCommand, KittenLastUsedLayoutCommand};
 use crate::commands::ls::KittenLsCommand;
 use crate::commands::navigate_tab::KittenNavigateTabCommand;
+use crate::commands::resize_window::KittenResizeWindowCommand;
+use crate::commands::send_text::KittenSendTextCommand;
 use crate::commands::set_tab_title::KittenSetTabTitleCommand;
-use crate::types::{KittyCommandResult, KittyLaunchResponse, KittyLsResponse};
-use anyhow::Result;
+use crate::commands::set_user_vars::KittenSetUserVarsCommand;
+use crate::commands::set_window_title::KittenSetWindowTitleCommand;
+use crate::error::KittyResult;
+use crate::types::{KittyLaunchResponse, KittyLsResponse};
+use crate::version::KittyCapabilities;
 
+/// Runs kitty remote control commands
+///
+/// Every method returns the command's data on success and a [`KittyError`](crate::KittyError)
+/// describing why kitty rejected or could not run it otherwise.
 pub trait CommandExecutor {
-    fn ls(&self, command: KittenLsCommand) -> Result<KittyLsResponse>;
-    fn focus_tab(&self, command: KittenFocusTabCommand) -> Result<KittyCommandResult<()>>;
-    fn close_tab(&self, command: KittenCloseTabCommand) -> Result<KittyCommandResult<()>>;
-    fn launch(
-        &self,
-        command: KittenLaunchCommand,
-    ) -> Result<KittyCommandResult<KittyLaunchResponse>>;
-    fn navigate_tab(&self, command: KittenNavigateTabCommand) -> Result<KittyCommandResult<()>>;
-    fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> Result<KittyCommandResult<()>>;
+    fn ls(&self, command: KittenLsCommand) -> KittyResult<KittyLsResponse>;
+    fn focus_tab(&self, command: KittenFocusTabCommand) -> KittyResult<()>;
+    fn close_tab(&self, command: KittenCloseTabCommand) -> KittyResult<()>;
+    fn launch(&self, command: KittenLaunchCommand) -> KittyResult<KittyLaunchResponse>;
+    fn navigate_tab(&self, command: KittenNavigateTabCommand) -> KittyResult<()>;
+    fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> KittyResult<()>;
+    fn send_text(&self, command: KittenSendTextCommand) -> KittyResult<()>;
+    fn get_text(&self, command: KittenGetTextCommand) -> KittyResult<String>;
+    fn focus_window(&self, command: KittenFocusWindowCommand) -> KittyResult<()>;
+    fn close_window(&self, command: KittenCloseWindowCommand) -> KittyResult<()>;
+    fn set_window_title(&self, command: KittenSetWindowTitleCommand) -> KittyResult<()>;
+    fn resize_window(&self, command: KittenResizeWindowCommand) -> KittyResult<bool>;
+    fn goto_layout(&self, command: KittenGotoLayoutCommand) -> KittyResult<()>;
+    fn last_used_layout(&self, command: KittenLastUsedLayoutCommand) -> KittyResult<()>;
+    fn detach_window(&self, command: KittenDetachWindowCommand) -> KittyResult<()>;
+    fn detach_tab(&self, command: KittenDetachTabCommand) -> KittyResult<()>;
+    fn set_user_vars(&self, command: KittenSetUserVarsCommand) -> KittyResult<()>;
+
+    /// What the kitty behind this executor supports, going by its version
+    ///
+    /// Executors that can't tell answer [`KittyCapabilities::unknown`], which supports
+    /// everything.
+    fn capabilities(&self) -> KittyResult<KittyCapabilities> {
+        Ok(KittyCapabilities::unknown())
+    }
+
+    /// How many requests this executor has sent to kitty so far, counting every retry and the
+    /// `--version` run of a version probe
+    ///
+    /// `None` for executors that don't talk to kitty themselves.
+    fn round_trips(&self) -> Option<usize> {
+        None
+    }
+
+    /// Run any [`KittenCommand`], including ones defined outside kitty-lib
+    fn execute<C: KittenCommand>(&self, command: C) -> KittyResult<C::Response>;
+}
+
+/// Lets callers lend an executor, e.g. a mock they inspect after the code under test ran
+impl<E: CommandExecutor> CommandExecutor for &E {
+    fn ls(&self, command: KittenLsCommand) -> KittyResult<KittyLsResponse> {
+        (**self).ls(command)
+    }
+
+    fn focus_tab(&self, command: KittenFocusTabCommand) -> KittyResult<()> {
+        (**self).focus_tab(command)
+    }
+
+    fn close_tab(&self, command: KittenCloseTabCommand) -> KittyResult<()> {
+        (**self).close_tab(command)
+    }
+
+    fn launch(&self, command: KittenLaunchCommand) -> KittyResult<KittyLaunchResponse> {
+        (**self).launch(command)
+    }
+
+    fn navigate_tab(&self, command: KittenNavigateTabCommand) -> KittyResult<()> {
+        (**self).navigate_tab(command)
+    }
+
+    fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> KittyResult<()> {
+        (**self).set_tab_title(command)
+    }
+
+    fn send_text(&self, command: KittenSendTextCommand) -> KittyResult<()> {
+        (**self).send_text(command)
+    }
+
+    fn get_text(&self, command: KittenGetTextCommand) -> KittyResult<String> {
+        (**self).get_text(command)
+    }
+
+    fn focus_window(&self, command: KittenFocusWindowCommand) -> KittyResult<()> {
+        (**self).focus_window(command)
+    }
+
+    fn close_window(&self, command: KittenCloseWindowCommand) -> KittyResult<()> {
+        (**self).close_window(command)
+    }
+
+    fn set_window_title(&self, command: KittenSetWindowTitleCommand) -> KittyResult<()> {
+        (**self).set_window_title(command)
+    }
+
+    fn resize_window(&self, command: KittenResizeWindowCommand) -> KittyResult<bool> {
+        (**self).resize_window(command)
+    }
+
+    fn goto_layout(&self, command: KittenGotoLayoutCommand) -> KittyResult<()> {
+        (**self).goto_layout(command)
+    }
+
+    fn last_used_layout(&self, command: KittenLastUsedLayoutCommand) -> KittyResult<()> {
+        (**self).last_used_layout(command)
+    }
+
+    fn detach_window(&self, command: KittenDetachWindowCommand) -> KittyResult<()> {
+        (**self).detach_window(command)
+    }
+
+    fn detach_tab(&self, command: KittenDetachTabCommand) -> KittyResult<()> {
+        (**self).detach_tab(command)
+    }
+
+    fn set_user_vars(&self, command: KittenSetUserVarsCommand) -> KittyResult<()> {
+        (**self).set_user_vars(command)
+    }
+
+    fn capabilities(&self) -> KittyResult<KittyCapabilities> {
+        (**self).capabilities()
+    }
+
+    fn round_trips(&self) -> Option<usize> {
+        (**self).round_trips()
+    }
+
+    fn execute<C: KittenCommand>(&self, command: C) -> KittyResult<C::Response> {
+        (**self).execute(command)
+    }
 }
 
+pub use async_executor::AsyncCommandExecutor;
+#[cfg(feature = "tokio")]
+pub use async_kitty_executor::AsyncKittyExecutor;
+#[cfg(feature = "tokio")]
+pub use async_mock_executor::AsyncMockExecutor;
+pub use dry_run::DryRunExecutor;
 pub use kitty_executor::KittyExecutor;
+#[cfg(feature = "tracing")]
+pub use middleware::CallTracer;
+pub use middleware::{
+    Call, CallCounter, CallLogger, CallTimer, CallTiming, LayeredExecutor, Middleware,
+};
 pub use mock_executor::MockExecutor;
+pub use recording::{RecordedCall, RecordedOutcome, Recording, RecordingExecutor};
+pub use replay::ReplayExecutor;
+pub use retry::{DEFAULT_TIMEOUT, RetryPolicy};
@@ -0,0 +1,383 @@
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Output;
+use std::time::Duration;
+
+use crate::commands::KittenCommand;
+use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::close_window::KittenCloseWindowCommand;
+use crate::commands::detach::{KittenDetachTabCommand, KittenDetachWindowCommand};
+use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::focus_window::KittenFocusWindowCommand;
+use crate::commands::get_text::KittenGetTextCommand;
+use crate::commands::launch::KittenLaunchCommand;
+use crate::commands::layout::{KittenGotoLayoutCommand, KittenLastUsedLayoutCommand};
+use crate::commands::ls::KittenLsCommand;
+use crate::commands::navigate_tab::KittenNavigateTabCommand;
+use crate::commands::resize_window::KittenResizeWindowCommand;
+use crate::commands::send_text::KittenSendTextCommand;
+use crate::commands::set_tab_title::KittenSetTabTitleCommand;
+use crate::commands::set_user_vars::KittenSetUserVarsCommand;
+use crate::commands::set_window_title::KittenSetWindowTitleCommand;
+use crate::error::{KittyError, KittyResult};
+use crate::executor::invocation::{self, Invocation};
+use crate::executor::{CommandExecutor, KittyExecutor};
+use crate::types::{KittyLaunchResponse, KittyLsResponse};
+use crate::version::{KittyCapabilities, KittyVersion};
+
+/// The kitten calls of a session with kitty, as stored in a fixture file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    /// The socket the calls went to, quoted again in replayed errors
+    pub socket: String,
+    /// The kitty version probed while recording, so replay makes the same capability choices
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kitty_version: Option<KittyVersion>,
+    pub calls: Vec<RecordedCall>,
+}
+
+impl Recording {
+    /// Read a fixture written by [`RecordingExecutor`]
+    pub fn load(path: impl AsRef<Path>) -> KittyResult<Self> {
+        let json = fs::read_to_string(path)?;
+        serde_json::from_str(&json)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e).into())
+    }
+
+    /// Write the recording to `path` as pretty-printed JSON
+    pub fn save(&self, path: impl AsRef<Path>) -> KittyResult<()> {
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other)?;
+        fs::write(path, json + "\n")?;
+        Ok(())
+    }
+}
+
+/// One `kitten @` call and how it ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedCall {
+    pub command: String,
+    pub args: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stdin: Option<String>,
+    pub outcome: RecordedOutcome,
+}
+
+impl RecordedCall {
+    /// The call as a kitten command line, for error messages
+    pub(crate) fn describe(command: &str, args: &[String]) -> String {
+        let mut line = command.to_string();
+        for arg in args {
+            line.push(' ');
+            line.push_str(arg);
+        }
+        line
+    }
+}
+
+/// How a recorded kitten call ended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "result")]
+pub enum RecordedOutcome {
+    /// kitten succeeded and printed `stdout`
+    Success {
+        stdout: String,
+    },
+    /// kitten exited with an error, classified again from `stderr` on replay
+    Failure {
+        exit_code: Option<i32>,
+        stderr: String,
+    },
+    /// kitten was killed after running for `timeout_ms`
+    Timeout {
+        timeout_ms: u64,
+    },
+    KittenNotFound,
+    NoSocket {
+        tried: Vec<String>,
+    },
+    /// kitten could not be run
+    Io {
+        message: String,
+    },
+}
+
+impl RecordedOutcome {
+    fn from_result(result: &KittyResult<Output>) -> Self {
+        match result {
+            Ok(output) => Self::Success {
+                stdout: String::from_utf8_lossy(&output.stdout).into_owned(),
+            },
+            Err(KittyError::Timeout { timeout, .. }) => Self::Timeout {
+                timeout_ms: timeout.as_millis() as u64,
+            },
+            Err(KittyError::KittenNotFound) => Self::KittenNotFound,
+            Err(KittyError::NoSocket { tried }) => Self::NoSocket {
+                tried: tried.clone(),
+            },
+            Err(KittyError::Io(error)) => Self::Io {
+                message: error.to_string(),
+            },
+            // Everything else came from kitten's exit code and stderr
+            Err(error) => Self::Failure {
+                exit_code: error.exit_code(),
+                stderr: error.stderr().unwrap_or_default().to_string(),
+            },
+        }
+    }
+
+    /// What kitten printed, or the error the call failed with
+    pub(crate) fn replay(&self, command: &str, socket: &str) -> KittyResult<String> {
+        match self {
+            Self::Success { stdout } => Ok(stdout.clone()),
+            Self::Failure { exit_code, stderr } => {
+                Err(KittyError::from_stderr(command, socket, *exit_code, stderr))
+            }
+            Self::Timeout { timeout_ms } => Err(KittyError::Timeout {
+                command: command.to_string(),
+                timeout: Duration::from_millis(*timeout_ms),
+            }),
+            Self::KittenNotFound => Err(KittyError::KittenNotFound),
+            Self::NoSocket { tried } => Err(KittyError::NoSocket {
+                tried: tried.clone(),
+            }),
+            Self::Io { message } => Err(io::Error::other(message.clone()).into()),
+        }
+    }
+}
+
+/// [`KittyExecutor`] that writes every kitten call and its outcome to a fixture file
+///
+/// Record a workflow once against a real kitty, then run it anywhere with
+/// [`ReplayExecutor`](crate::ReplayExecutor). The fixture is rewritten after every call, so
+/// it is complete even when the workflow fails halfway.
+#[derive(Debug)]
+pub struct RecordingExecutor {
+    inner: KittyExecutor,
+    path: PathBuf,
+    recording: RefCell<Recording>,
+}
+
+impl RecordingExecutor {
+    /// Record the calls made through `inner` to `path`, replacing any existing fixture
+    pub fn new(inner: KittyExecutor, path: impl Into<PathBuf>) -> Self {
+        let recording = Recording {
+            socket: inner.socket().unwrap_or_default().to_string(),
+            kitty_version: None,
+            calls: Vec::new(),
+        };
+        Self {
+            inner,
+            path: path.into(),
+            recording: RefCell::new(recording),
+        }
+    }
+
+    /// The fixture file being written
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The calls recorded so far
+    pub fn recording(&self) -> Recording {
+        self.recording.borrow().clone()
+    }
+}
+
+impl CommandExecutor for RecordingExecutor {
+    fn ls(&self, command: KittenLsCommand) -> KittyResult<KittyLsResponse> {
+        self.execute(command)
+    }
+
+    fn focus_tab(&self, command: KittenFocusTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn close_tab(&self, command: KittenCloseTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn launch(&self, command: KittenLaunchCommand) -> KittyResult<KittyLaunchResponse> {
+        self.execute(command)
+    }
+
+    fn navigate_tab(&self, command: KittenNavigateTabCommand) -> KittyResult<()> {
+        invocation::navigate_tab(self, command)
+    }
+
+    fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn send_text(&self, command: KittenSendTextCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn get_text(&self, command: KittenGetTextCommand) -> KittyResult<String> {
+        self.execute(command)
+    }
+
+    fn focus_window(&self, command: KittenFocusWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn close_window(&self, command: KittenCloseWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn set_window_title(&self, command: KittenSetWindowTitleCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn resize_window(&self, command: KittenResizeWindowCommand) -> KittyResult<bool> {
+        self.execute(command)
+    }
+
+    fn goto_layout(&self, command: KittenGotoLayoutCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn last_used_layout(&self, command: KittenLastUsedLayoutCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn detach_window(&self, command: KittenDetachWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn detach_tab(&self, command: KittenDetachTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn set_user_vars(&self, command: KittenSetUserVarsCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn capabilities(&self) -> KittyResult<KittyCapabilities> {
+        let capabilities = self.inner.capabilities()?;
+        let mut recording = self.recording.borrow_mut();
+        if capabilities.version().is_some() && recording.kitty_version != capabilities.version() {
+            recording.kitty_version = capabilities.version();
+            recording.save(&self.path)?;
+        }
+        Ok(capabilities)
+    }
+
+    fn round_trips(&self) -> Option<usize> {
+        self.inner.round_trips()
+    }
+
+    fn execute<C: KittenCommand>(&self, command: C) -> KittyResult<C::Response> {
+        let invocation = Invocation::of(&command)?;
+        let result = self.inner.run(&invocation);
+
+        {
+            let mut recording = self.recording.borrow_mut();
+            recording.calls.push(RecordedCall {
+                command: invocation.command.to_string(),
+                args: invocation.args.clone(),
+                stdin: invocation.stdin.clone(),
+                outcome: RecordedOutcome::from_result(&result),
+            });
+            recording.save(&self.path)?;
+        }
+
+        let output = result.map_err(|error| self.inner.explain(&invocation, error))?;
+        command.parse_response(&output.stdout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::process::ExitStatusExt;
+    use std::process::ExitStatus;
+
+    #[test]
+    fn test_outcomes_replay_as_the_original_result() {
+        let socket = "unix:/tmp/kitty";
+        let failures = [
+            KittyError::no_match("focus-tab", "tabs", "id:9"),
+            KittyError::RemoteControlDisabled {
+                exit_code: Some(1),
+                stderr: "Remote control is disabled".to_string(),
+            },
+            KittyError::Timeout {
+                command: "ls".to_string(),
+                timeout: Duration::from_millis(1500),
+            },
+            KittyError::KittenNotFound,
+        ];
+
+        for error in failures {
+            let outcome = RecordedOutcome::from_result(&Err(error));
+            let json = serde_json::to_string(&outcome).unwrap();
+            let outcome: RecordedOutcome = serde_json::from_str(&json).unwrap();
+            let replayed = outcome.replay("focus-tab", socket).unwrap_err();
+
+            match replayed {
+                KittyError::NoMatch { stderr, .. } => assert!(stderr.contains("id:9")),
+                KittyError::RemoteControlDisabled { .. } | KittyError::KittenNotFound => {}
+                KittyError::Timeout { timeout, .. } => {
+                    assert_eq!(timeout, Duration::from_millis(1500))
+                }
+                other => panic!("unexpected replay: {other:?}"),
+            }
+        }
+
+        let output = Output {
+            status: ExitStatus::from_raw(0),
+            stdout: b"[]".to_vec(),
+            stderr: Vec::new(),
+        };
+        let outcome = RecordedOutcome::from_result(&Ok(output));
+        assert_eq!(outcome.replay("ls", socket).unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_recording_round_trips_through_a_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.json");
+        let recording = Recording {
+            socket: "unix:/tmp/kitty".to_string(),
+            kitty_version: Some(KittyVersion::new(0, 26, 5)),
+            calls: vec![RecordedCall {
+                command: "send-text".to_string(),
+                args: vec!["--match=id:1".to_string(), "--stdin".to_string()],
+                stdin: Some("make\n".to_string()),
+                outcome: RecordedOutcome::Success {
+                    stdout: String::new(),
+                },
+            }],
+        };
+
+        recording.save(&path).unwrap();
+        assert_eq!(Recording::load(&path).unwrap(), recording);
+
+        fs::write(&path, "not json").unwrap();
+        assert!(matches!(Recording::load(&path), Err(KittyError::Io(_))));
+    }
+
+    #[test]
+    fn test_recording_executor_records_failed_calls() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("unreachable.json");
+        let executor = RecordingExecutor::new(
+            KittyExecutor::discover(&crate::SocketDiscovery::new().temp_dir(dir.path())),
+            &path,
+        );
+
+        let error = executor.ls(KittenLsCommand::new()).unwrap_err();
+        assert!(matches!(error, KittyError::NoSocket { .. }));
+
+        let recording = Recording::load(&path).unwrap();
+        assert_eq!(recording, executor.recording());
+        assert_eq!(recording.calls[0].command, "ls");
+        assert!(matches!(
+            recording.calls[0].outcome,
+            RecordedOutcome::NoSocket { .. }
+        ));
+    }
+}
@@ -0,0 +1,259 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::path::Path;
+
+use crate::commands::KittenCommand;
+use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::close_window::KittenCloseWindowCommand;
+use crate::commands::detach::{KittenDetachTabCommand, KittenDetachWindowCommand};
+use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::focus_window::KittenFocusWindowCommand;
+use crate::commands::get_text::KittenGetTextCommand;
+use crate::commands::launch::KittenLaunchCommand;
+use crate::commands::layout::{KittenGotoLayoutCommand, KittenLastUsedLayoutCommand};
+use crate::commands::ls::KittenLsCommand;
+use crate::commands::navigate_tab::KittenNavigateTabCommand;
+use crate::commands::resize_window::KittenResizeWindowCommand;
+use crate::commands::send_text::KittenSendTextCommand;
+use crate::commands::set_tab_title::KittenSetTabTitleCommand;
+use crate::commands::set_user_vars::KittenSetUserVarsCommand;
+use crate::commands::set_window_title::KittenSetWindowTitleCommand;
+use crate::error::{KittyError, KittyResult};
+use crate::executor::CommandExecutor;
+use crate::executor::invocation::{self, Invocation};
+use crate::executor::recording::{RecordedCall, Recording};
+use crate::types::{KittyLaunchResponse, KittyLsResponse};
+use crate::version::KittyCapabilities;
+
+/// Answers kitten calls from a [`Recording`], without kitty
+///
+/// Calls must arrive in the recorded order with the recorded arguments; anything else fails
+/// with [`KittyError::UnexpectedCall`]. Call [`ReplayExecutor::assert_finished`] at the end
+/// of a test to check the whole recording was used.
+#[derive(Debug)]
+pub struct ReplayExecutor {
+    socket: String,
+    capabilities: KittyCapabilities,
+    calls: RefCell<VecDeque<RecordedCall>>,
+}
+
+impl ReplayExecutor {
+    pub fn new(recording: Recording) -> Self {
+        Self {
+            socket: recording.socket,
+            capabilities: recording
+                .kitty_version
+                .map_or_else(KittyCapabilities::unknown, KittyCapabilities::of),
+            calls: RefCell::new(recording.calls.into()),
+        }
+    }
+
+    /// Replay the fixture file at `path`
+    pub fn load(path: impl AsRef<Path>) -> KittyResult<Self> {
+        Ok(Self::new(Recording::load(path)?))
+    }
+
+    /// How many recorded calls haven't been made yet
+    pub fn remaining(&self) -> usize {
+        self.calls.borrow().len()
+    }
+
+    /// Panic listing the recorded calls that were never made
+    pub fn assert_finished(&self) {
+        let calls = self.calls.borrow();
+        assert!(
+            calls.is_empty(),
+            "{} recorded kitten calls were not made: {}",
+            calls.len(),
+            calls
+                .iter()
+                .map(|call| RecordedCall::describe(&call.command, &call.args))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+    }
+
+    /// The recorded stdout for `invocation`, if it is the next call
+    fn replay(&self, invocation: &Invocation) -> KittyResult<String> {
+        let mut calls = self.calls.borrow_mut();
+        let expected = calls.front();
+
+        let matches = expected.is_some_and(|call| {
+            call.command == invocation.command
+                && call.args == invocation.args
+                && call.stdin == invocation.stdin
+        });
+        if !matches {
+            return Err(KittyError::UnexpectedCall {
+                call: RecordedCall::describe(invocation.command, &invocation.args),
+                expected: expected.map(|call| RecordedCall::describe(&call.command, &call.args)),
+            });
+        }
+
+        let call = calls.pop_front().expect("matched call");
+        call.outcome.replay(invocation.command, &self.socket)
+    }
+}
+
+impl CommandExecutor for ReplayExecutor {
+    fn ls(&self, command: KittenLsCommand) -> KittyResult<KittyLsResponse> {
+        self.execute(command)
+    }
+
+    fn focus_tab(&self, command: KittenFocusTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn close_tab(&self, command: KittenCloseTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn launch(&self, command: KittenLaunchCommand) -> KittyResult<KittyLaunchResponse> {
+        self.execute(command)
+    }
+
+    fn navigate_tab(&self, command: KittenNavigateTabCommand) -> KittyResult<()> {
+        invocation::navigate_tab(self, command)
+    }
+
+    fn set_tab_title(&self, command: KittenSetTabTitleCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn send_text(&self, command: KittenSendTextCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn get_text(&self, command: KittenGetTextCommand) -> KittyResult<String> {
+        self.execute(command)
+    }
+
+    fn focus_window(&self, command: KittenFocusWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn close_window(&self, command: KittenCloseWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn set_window_title(&self, command: KittenSetWindowTitleCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn resize_window(&self, command: KittenResizeWindowCommand) -> KittyResult<bool> {
+        self.execute(command)
+    }
+
+    fn goto_layout(&self, command: KittenGotoLayoutCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn last_used_layout(&self, command: KittenLastUsedLayoutCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn detach_window(&self, command: KittenDetachWindowCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn detach_tab(&self, command: KittenDetachTabCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    fn set_user_vars(&self, command: KittenSetUserVarsCommand) -> KittyResult<()> {
+        self.execute(command)
+    }
+
+    /// The capabilities of the kitty the recording was made against
+    fn capabilities(&self) -> KittyResult<KittyCapabilities> {
+        Ok(self.capabilities)
+    }
+
+    fn execute<C: KittenCommand>(&self, command: C) -> KittyResult<C::Response> {
+        let stdout = self.replay(&Invocation::of(&command)?)?;
+        command.parse_response(stdout.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::recording::RecordedOutcome;
+    use crate::types::TabId;
+
+    fn replay(calls: Vec<(&str, &[&str], RecordedOutcome)>) -> ReplayExecutor {
+        ReplayExecutor::new(Recording {
+            socket: "unix:/tmp/kitty".to_string(),
+            kitty_version: None,
+            calls: calls
+                .into_iter()
+                .map(|(command, args, outcome)| RecordedCall {
+                    command: command.to_string(),
+                    args: args.iter().map(|arg| arg.to_string()).collect(),
+                    stdin: None,
+                    outcome,
+                })
+                .collect(),
+        })
+    }
+
+    fn success(stdout: &str) -> RecordedOutcome {
+        RecordedOutcome::Success {
+            stdout: stdout.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_replay_answers_recorded_calls_in_order() {
+        let executor = replay(vec![
+            ("ls", &[], success("[]")),
+            (
+                "focus-tab",
+                &["--match=id:7"],
+                RecordedOutcome::Failure {
+                    exit_code: Some(1),
+                    stderr: "Error: No matching tabs for expression: id:7".to_string(),
+                },
+            ),
+        ]);
+
+        assert!(executor.ls(KittenLsCommand::new()).unwrap().is_empty());
+        let error = executor
+            .focus_tab(KittenFocusTabCommand::new(TabId(7)))
+            .unwrap_err();
+        assert!(matches!(error, KittyError::NoMatch { .. }));
+        executor.assert_finished();
+    }
+
+    #[test]
+    fn test_replay_rejects_unexpected_calls() {
+        let executor = replay(vec![("focus-tab", &["--match=id:2"], success(""))]);
+
+        let error = executor
+            .focus_tab(KittenFocusTabCommand::new(TabId(3)))
+            .unwrap_err();
+        match error {
+            KittyError::UnexpectedCall { call, expected } => {
+                assert_eq!(call, "focus-tab --match=id:3");
+                assert_eq!(expected.as_deref(), Some("focus-tab --match=id:2"));
+            }
+            other => panic!("unexpected error: {other:?}"),
+        }
+        assert_eq!(executor.remaining(), 1);
+
+        executor
+            .focus_tab(KittenFocusTabCommand::new(TabId(2)))
+            .unwrap();
+        let error = executor.ls(KittenLsCommand::new()).unwrap_err();
+        assert!(matches!(
+            error,
+            KittyError::UnexpectedCall { expected: None, .. }
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "1 recorded kitten calls were not made: ls")]
+    fn test_assert_finished_reports_unused_calls() {
+        replay(vec![("ls", &[], success("[]"))]).assert_finished();
+    }
+}
@@ -0,0 +1,78 @@
+use std::time::Duration;
+
+use crate::error::KittyError;
+
+/// How long a single `kitten` call may run before it is killed
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How idempotent commands (`ls`, focusing, `get-text`) are retried after transient failures
+///
+/// Commands that change kitty's state are never retried, since a timed out `launch` or
+/// `close-tab` may still have happened.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Attempts after the first one
+    pub retries: u32,
+    /// Delay before the first retry, doubled for each following one
+    pub backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Never retry
+    pub fn none() -> Self {
+        Self {
+            retries: 0,
+            backoff: Duration::ZERO,
+        }
+    }
+
+    pub fn new(retries: u32, backoff: Duration) -> Self {
+        Self { retries, backoff }
+    }
+
+    /// The delay before retry number `attempt`, counting from zero
+    pub fn delay(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(2u32.saturating_pow(attempt))
+    }
+
+    /// Whether to retry after `error` on attempt `attempt`, counting from zero
+    pub(crate) fn should_retry(&self, attempt: u32, error: &KittyError) -> bool {
+        attempt < self.retries && error.is_transient()
+    }
+}
+
+impl Default for RetryPolicy {
+    /// Two retries, 50ms then 100ms apart
+    fn default() -> Self {
+        Self::new(2, Duration::from_millis(50))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_policy_backs_off_and_stops() {
+        let policy = RetryPolicy::new(3, Duration::from_millis(10));
+        assert_eq!(policy.delay(0), Duration::from_millis(10));
+        assert_eq!(policy.delay(2), Duration::from_millis(40));
+
+        let unreachable = KittyError::SocketUnreachable {
+            socket: "unix:/tmp/kitty".to_string(),
+            exit_code: Some(1),
+            stderr: "Error: Connection refused".to_string(),
+        };
+        assert!(policy.should_retry(2, &unreachable));
+        assert!(!policy.should_retry(3, &unreachable));
+        assert!(!policy.should_retry(0, &KittyError::no_match("ls", "tabs", "id:1")));
+        assert!(!RetryPolicy::none().should_retry(0, &unreachable));
+
+        // A hung kitty fails after one timeout instead of one per attempt
+        let timeout = KittyError::Timeout {
+            command: "ls".to_string(),
+            timeout: DEFAULT_TIMEOUT,
+        };
+        assert!(!policy.should_retry(0, &timeout));
+    }
+}
@@ -1,15 +1,49 @@
 pub mod commands;
+pub mod error;
 pub mod executor;
+pub mod password;
+pub mod snapshot;
+pub mod socket;
+#[cfg(feature = "test-support")]
+pub mod testing;
 pub mod types;
 pub mod utils;
+pub mod version;
 
 // Re-export commonly used types
+pub use commands::KittenCommand;
 pub use commands::close_tab::KittenCloseTabCommand;
+pub use commands::close_window::KittenCloseWindowCommand;
+pub use commands::detach::{DetachTarget, KittenDetachTabCommand, KittenDetachWindowCommand};
 pub use commands::focus_tab::KittenFocusTabCommand;
-pub use commands::launch::KittenLaunchCommand;
+pub use commands::focus_window::KittenFocusWindowCommand;
+pub use commands::get_text::{KittenGetTextCommand, TextExtent};
+pub use commands::launch::{KittenLaunchCommand, LaunchType, WindowLocation};
+pub use commands::layout::{KittenGotoLayoutCommand, KittenLastUsedLayoutCommand, KittyLayout};
 pub use commands::ls::KittenLsCommand;
 pub use commands::navigate_tab::{KittenNavigateTabCommand, TabNavigationDirection};
-pub use executor::{CommandExecutor, KittyExecutor, MockExecutor};
+pub use commands::resize_window::{KittenResizeWindowCommand, ResizeAxis};
+pub use commands::send_text::KittenSendTextCommand;
+pub use commands::set_tab_title::KittenSetTabTitleCommand;
+pub use commands::set_user_vars::KittenSetUserVarsCommand;
+pub use commands::set_window_title::KittenSetWindowTitleCommand;
+pub use error::{KittyError, KittyResult};
+#[cfg(feature = "tracing")]
+pub use executor::CallTracer;
+pub use executor::mock_executor::ExecutedCommand;
+pub use executor::{
+    AsyncCommandExecutor, Call, CallCounter, CallLogger, CallTimer, CallTiming, CommandExecutor,
+    DEFAULT_TIMEOUT, DryRunExecutor, KittyExecutor, LayeredExecutor, Middleware, MockExecutor,
+    RecordedCall, RecordedOutcome, Recording, RecordingExecutor, ReplayExecutor, RetryPolicy,
+};
+#[cfg(feature = "tokio")]
+pub use executor::{AsyncKittyExecutor, AsyncMockExecutor};
+pub use password::RemoteControlPassword;
+pub use snapshot::{KittySnapshot, SessionMarker, classify_tab, parse_session_title};
+pub use socket::SocketDiscovery;
 pub use types::{
-    KittyCommandResult, KittyLaunchResponse, KittyLsResponse, KittyOsWindow, KittyTab, KittyWindow,
+    KittyLaunchResponse, KittyLsResponse, KittyOsWindow, KittyProcess, KittyTab, KittyWindow,
+    OsWindowId, TabId, WindowId,
 };
+pub use utils::{SESSION_PROJECT_ENV, SESSION_TITLE_PREFIX, SESSION_USER_VAR};
+pub use version::{Capability, KittyCapabilities, KittyVersion};
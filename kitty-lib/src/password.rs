@@ -0,0 +1,131 @@
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable kitten reads the remote control password from by default
+pub const KITTY_RC_PASSWORD_ENV: &str = "KITTY_RC_PASSWORD";
+
+/// Where kitten gets the password for kitty's `remote_control_password`
+///
+/// The password never appears on kitten's command line: literal passwords are handed over
+/// through its environment.
+#[derive(Clone, PartialEq, Eq)]
+pub enum RemoteControlPassword {
+    /// The password itself
+    Literal(String),
+    /// A file holding the password
+    File(PathBuf),
+    /// An environment variable holding the password
+    Env(String),
+}
+
+impl RemoteControlPassword {
+    /// The password kitten should use when none is given explicitly
+    ///
+    /// kitten already reads `KITTY_RC_PASSWORD`, so this only falls back to kitty.conf's
+    /// `remote_control_password` when that variable is unset.
+    pub fn discover(kitty_conf: Option<&Path>) -> Option<Self> {
+        if env::var_os(KITTY_RC_PASSWORD_ENV).is_some() {
+            return None;
+        }
+
+        let config = fs::read_to_string(kitty_conf?).ok()?;
+        parse_remote_control_password(&config).map(Self::Literal)
+    }
+
+    /// Arguments for `kitten @`, and an environment variable to set for it
+    pub(crate) fn kitten_args(&self) -> (String, Option<(&'static str, &str)>) {
+        match self {
+            Self::Literal(password) => (
+                format!("--password-env={}", KITTY_RC_PASSWORD_ENV),
+                Some((KITTY_RC_PASSWORD_ENV, password)),
+            ),
+            Self::File(path) => (format!("--password-file={}", path.display()), None),
+            Self::Env(name) => (format!("--password-env={}", name), None),
+        }
+    }
+}
+
+impl fmt::Debug for RemoteControlPassword {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Literal(_) => write!(f, "Literal(<redacted>)"),
+            Self::File(path) => f.debug_tuple("File").field(path).finish(),
+            Self::Env(name) => f.debug_tuple("Env").field(name).finish(),
+        }
+    }
+}
+
+/// The `remote_control_password` from kitty.conf text to authenticate with
+///
+/// A password without an action list grants every command, so it wins over restricted ones;
+/// otherwise the first password is used.
+pub fn parse_remote_control_password(config: &str) -> Option<String> {
+    let passwords: Vec<(String, bool)> = config
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (key, value) = line.split_once(char::is_whitespace)?;
+            (key == "remote_control_password").then(|| split_password(value.trim()))
+        })
+        .flatten()
+        .collect();
+
+    passwords
+        .iter()
+        .find(|(_, restricted)| !restricted)
+        .or_else(|| passwords.first())
+        .map(|(password, _)| password.clone())
+}
+
+/// Split a `remote_control_password` value into the password and whether actions follow it
+fn split_password(value: &str) -> Option<(String, bool)> {
+    let (password, rest) = match value.strip_prefix('"') {
+        Some(quoted) => quoted.split_once('"')?,
+        None => value.split_once(char::is_whitespace).unwrap_or((value, "")),
+    };
+
+    (!password.is_empty()).then(|| (password.to_string(), !rest.trim().is_empty()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_remote_control_password_prefers_unrestricted() {
+        let config = r#"
+# remote_control_password "commented"
+remote_control_password "read only" get-* ls
+remote_control_password "full access"
+"#;
+        assert_eq!(
+            parse_remote_control_password(config).as_deref(),
+            Some("full access")
+        );
+
+        let config = "remote_control_password first ls\nremote_control_password second focus-*";
+        assert_eq!(
+            parse_remote_control_password(config).as_deref(),
+            Some("first")
+        );
+        assert_eq!(parse_remote_control_password("listen_on unix:/tmp/k"), None);
+    }
+
+    #[test]
+    fn test_kitten_args_keep_literal_password_off_the_command_line() {
+        let literal = RemoteControlPassword::Literal("secret".to_string());
+        let (arg, env) = literal.kitten_args();
+        assert_eq!(arg, "--password-env=KITTY_RC_PASSWORD");
+        assert_eq!(env, Some(("KITTY_RC_PASSWORD", "secret")));
+
+        let file = RemoteControlPassword::File(PathBuf::from("/run/pw"));
+        let (arg, env) = file.kitten_args();
+        assert_eq!(arg, "--password-file=/run/pw");
+        assert_eq!(env, None);
+
+        assert_eq!(format!("{:?}", literal), "Literal(<redacted>)");
+    }
+}
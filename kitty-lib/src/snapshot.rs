@@ -0,0 +1,368 @@
+use crate::commands::ls::KittenLsCommand;
+use crate::error::KittyResult;
+use crate::executor::CommandExecutor;
+use crate::types::{KittyOsWindow, KittyTab, KittyWindow, OsWindowId, TabId, WindowId};
+use crate::utils::{SESSION_PROJECT_ENV, SESSION_TITLE_PREFIX, SESSION_USER_VAR};
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Bound;
+use std::path::Path;
+
+/// How a tab was recognised as part of a session, strongest marker first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SessionMarker {
+    /// A window in the tab carries the `ksm_session` user variable
+    UserVar,
+    /// The tab title starts with `session:<name>`
+    TabTitle,
+    /// A window in the tab has `KITTY_SESSION_PROJECT` in its environment
+    Environment,
+}
+
+/// Parse the session name from a tab title of the form `session:<name>[ - description]`
+pub fn parse_session_title(title: &str) -> Option<&str> {
+    let after_prefix = title.strip_prefix(SESSION_TITLE_PREFIX)?;
+    after_prefix
+        .split(" - ")
+        .next()
+        .filter(|name| !name.is_empty())
+}
+
+/// Identify the session a tab belongs to: its windows' session user variable first, then a
+/// `session:` title prefix, then the `KITTY_SESSION_PROJECT` environment of its windows
+pub fn classify_tab(tab: &KittyTab) -> Option<(String, SessionMarker)> {
+    let non_empty = |name: &&String| !name.is_empty();
+
+    if let Some(name) = tab
+        .windows
+        .iter()
+        .filter_map(|window| window.user_vars.get(SESSION_USER_VAR))
+        .find(non_empty)
+    {
+        return Some((name.clone(), SessionMarker::UserVar));
+    }
+
+    if let Some(name) = parse_session_title(&tab.title) {
+        return Some((name.to_string(), SessionMarker::TabTitle));
+    }
+
+    tab.windows
+        .iter()
+        .filter_map(|window| window.env.get(SESSION_PROJECT_ENV))
+        .find(non_empty)
+        .map(|name| (name.clone(), SessionMarker::Environment))
+}
+
+/// One `kitten @ ls` result with indexes for the lookups ksm needs
+///
+/// Fetch a snapshot once per operation and answer every question from it instead of issuing an
+/// `ls` per question. Tabs are classified into sessions with [`classify_tab`] when the snapshot
+/// is built.
+#[derive(Debug, Clone, Default)]
+pub struct KittySnapshot {
+    os_windows: Vec<KittyOsWindow>,
+    /// Tab id -> (OS window index, tab index)
+    tab_positions: HashMap<TabId, (usize, usize)>,
+    /// Window id -> (OS window index, tab index, window index)
+    window_positions: HashMap<WindowId, (usize, usize, usize)>,
+    /// Tab id -> the session it belongs to
+    tab_sessions: HashMap<TabId, (String, SessionMarker)>,
+    /// Session name -> ids of its tabs, in id order
+    session_tabs: BTreeMap<String, Vec<TabId>>,
+    /// Ids of tabs outside every session, in layout order
+    unnamed_tabs: Vec<TabId>,
+    /// Window cwd -> ids of the tabs holding a window there
+    cwd_tabs: BTreeMap<String, Vec<TabId>>,
+}
+
+impl KittySnapshot {
+    /// Index an `ls` result
+    pub fn new(os_windows: Vec<KittyOsWindow>) -> Self {
+        let mut snapshot = Self::default();
+
+        for (os_index, os_window) in os_windows.iter().enumerate() {
+            for (tab_index, tab) in os_window.tabs.iter().enumerate() {
+                snapshot.tab_positions.insert(tab.id, (os_index, tab_index));
+
+                for (window_index, window) in tab.windows.iter().enumerate() {
+                    snapshot
+                        .window_positions
+                        .insert(window.id, (os_index, tab_index, window_index));
+
+                    let tabs = snapshot.cwd_tabs.entry(window.cwd.clone()).or_default();
+                    if !tabs.contains(&tab.id) {
+                        tabs.push(tab.id);
+                    }
+                }
+
+                match classify_tab(tab) {
+                    Some((name, marker)) => {
+                        snapshot
+                            .session_tabs
+                            .entry(name.clone())
+                            .or_default()
+                            .push(tab.id);
+                        snapshot.tab_sessions.insert(tab.id, (name, marker));
+                    }
+                    None => snapshot.unnamed_tabs.push(tab.id),
+                }
+            }
+        }
+
+        for tab_ids in snapshot.session_tabs.values_mut() {
+            tab_ids.sort_unstable();
+        }
+
+        snapshot.os_windows = os_windows;
+        snapshot
+    }
+
+    /// Take a snapshot of every OS window with a single `ls`
+    pub fn fetch<E: CommandExecutor>(executor: &E) -> KittyResult<Self> {
+        Ok(Self::new(executor.ls(KittenLsCommand::new())?))
+    }
+
+    /// The OS windows as kitty reported them
+    pub fn os_windows(&self) -> &[KittyOsWindow] {
+        &self.os_windows
+    }
+
+    /// Every tab, in layout order
+    pub fn tabs(&self) -> impl Iterator<Item = &KittyTab> {
+        self.os_windows.iter().flat_map(|os_window| &os_window.tabs)
+    }
+
+    pub fn tab(&self, tab_id: TabId) -> Option<&KittyTab> {
+        let (os_index, tab_index) = *self.tab_positions.get(&tab_id)?;
+        Some(&self.os_windows[os_index].tabs[tab_index])
+    }
+
+    pub fn window(&self, window_id: WindowId) -> Option<&KittyWindow> {
+        let (os_index, tab_index, window_index) = *self.window_positions.get(&window_id)?;
+        Some(&self.os_windows[os_index].tabs[tab_index].windows[window_index])
+    }
+
+    /// The tab that owns a window
+    pub fn tab_for_window(&self, window_id: WindowId) -> Option<&KittyTab> {
+        let (os_index, tab_index, _) = *self.window_positions.get(&window_id)?;
+        Some(&self.os_windows[os_index].tabs[tab_index])
+    }
+
+    /// The OS window a tab lives in
+    pub fn os_window_for_tab(&self, tab_id: TabId) -> Option<&KittyOsWindow> {
+        let (os_index, _) = *self.tab_positions.get(&tab_id)?;
+        Some(&self.os_windows[os_index])
+    }
+
+    /// The active tab of an OS window
+    pub fn active_tab(&self, os_window_id: OsWindowId) -> Option<&KittyTab> {
+        self.os_windows
+            .iter()
+            .find(|os_window| os_window.id == os_window_id)?
+            .tabs
+            .iter()
+            .find(|tab| tab.is_active)
+    }
+
+    /// The OS window with keyboard focus, or the one focused last when kitty is in the background
+    pub fn focused_os_window(&self) -> Option<&KittyOsWindow> {
+        self.os_windows
+            .iter()
+            .find(|os_window| os_window.is_focused)
+            .or_else(|| {
+                self.os_windows
+                    .iter()
+                    .find(|os_window| os_window.last_focused)
+            })
+    }
+
+    /// The tab that currently has keyboard focus
+    pub fn focused_tab(&self) -> Option<&KittyTab> {
+        self.tabs().find(|tab| tab.is_focused)
+    }
+
+    /// The tab holding the window kitty marks as the caller (`is_self`)
+    pub fn self_tab(&self) -> Option<&KittyTab> {
+        self.tabs()
+            .find(|tab| tab.windows.iter().any(|window| window.is_self))
+    }
+
+    /// The session a tab belongs to and the marker that placed it there
+    pub fn session_of(&self, tab_id: TabId) -> Option<(&str, SessionMarker)> {
+        self.tab_sessions
+            .get(&tab_id)
+            .map(|(name, marker)| (name.as_str(), *marker))
+    }
+
+    /// The tabs of a session, in id order
+    pub fn session_tabs(&self, session_name: &str) -> Vec<&KittyTab> {
+        self.tabs_by_id(self.session_tabs.get(session_name).into_iter().flatten())
+    }
+
+    /// The tab to focus for a session: the lowest id among the tabs with its strongest marker
+    pub fn session_tab(&self, session_name: &str) -> Option<&KittyTab> {
+        self.session_tabs(session_name)
+            .into_iter()
+            .min_by_key(|tab| self.session_of(tab.id).map(|(_, marker)| marker))
+    }
+
+    /// The tabs outside every session, in layout order
+    pub fn unnamed_tabs(&self) -> Vec<&KittyTab> {
+        self.tabs_by_id(&self.unnamed_tabs)
+    }
+
+    /// Session names with their tab counts, sorted by name
+    pub fn sessions(&self) -> impl Iterator<Item = (&str, usize)> {
+        self.session_tabs
+            .iter()
+            .map(|(name, tab_ids)| (name.as_str(), tab_ids.len()))
+    }
+
+    /// Whether any tab belongs to a session
+    pub fn has_session_tabs(&self) -> bool {
+        !self.session_tabs.is_empty()
+    }
+
+    /// The tabs with a window at or below `cwd_prefix`, in id order
+    ///
+    /// The prefix matches whole path components, so `/srv/api` finds `/srv/api/src` but not
+    /// `/srv/api-old`. A trailing `/` is ignored, so `/srv/api/` finds `/srv/api` itself too.
+    pub fn tabs_under(&self, cwd_prefix: &str) -> Vec<&KittyTab> {
+        let prefix = Path::new(cwd_prefix);
+        // kitty reports cwds without a trailing `/`, so the string range must start before it
+        let start = cwd_prefix.trim_end_matches('/');
+        let mut tab_ids: Vec<TabId> = self
+            .cwd_tabs
+            .range::<str, _>((Bound::Included(start), Bound::Unbounded))
+            .take_while(|(cwd, _)| cwd.starts_with(start))
+            .filter(|(cwd, _)| Path::new(cwd.as_str()).starts_with(prefix))
+            .flat_map(|(_, tab_ids)| tab_ids.iter().copied())
+            .collect();
+        tab_ids.sort_unstable();
+        tab_ids.dedup();
+
+        self.tabs_by_id(&tab_ids)
+    }
+
+    fn tabs_by_id<'a>(&self, tab_ids: impl IntoIterator<Item = &'a TabId>) -> Vec<&KittyTab> {
+        tab_ids
+            .into_iter()
+            .filter_map(|tab_id| self.tab(*tab_id))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::MockExecutor;
+
+    fn layout() -> Vec<KittyOsWindow> {
+        serde_json::from_value(serde_json::json!([
+            {
+                "id": 1, "is_focused": false, "last_focused": false,
+                "tabs": [
+                    {"id": 3, "title": "vim", "is_active": true,
+                     "windows": [
+                        {"id": 30, "title": "vim", "pid": 1, "cwd": "/srv/api/src", "cmdline": ["vim"],
+                         "user_vars": {"ksm_session": "api"}},
+                        {"id": 31, "title": "zsh", "pid": 2, "cwd": "/srv/api-old", "cmdline": ["zsh"]}
+                     ]},
+                    {"id": 1, "title": "session:api - tests", "is_active": false,
+                     "windows": [{"id": 10, "title": "zsh", "pid": 3, "cwd": "/srv/api", "cmdline": ["zsh"]}]}
+                ]
+            },
+            {
+                "id": 2, "is_focused": true, "last_focused": true,
+                "tabs": [
+                    {"id": 2, "title": "zsh", "is_active": true, "is_focused": true,
+                     "windows": [{"id": 20, "title": "zsh", "pid": 4, "cwd": "/tmp", "cmdline": ["zsh"],
+                                  "env": {"KITTY_SESSION_PROJECT": "legacy"}, "is_self": true}]},
+                    {"id": 4, "title": "scratch", "is_active": false,
+                     "windows": [{"id": 40, "title": "zsh", "pid": 5, "cwd": "/home/me", "cmdline": ["zsh"]}]}
+                ]
+            }
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_classify_tab_marker_order() {
+        let snapshot = KittySnapshot::new(layout());
+
+        assert_eq!(
+            snapshot.session_of(TabId(3)),
+            Some(("api", SessionMarker::UserVar))
+        );
+        assert_eq!(
+            snapshot.session_of(TabId(1)),
+            Some(("api", SessionMarker::TabTitle))
+        );
+        assert_eq!(
+            snapshot.session_of(TabId(2)),
+            Some(("legacy", SessionMarker::Environment))
+        );
+        assert_eq!(snapshot.session_of(TabId(4)), None);
+
+        assert_eq!(parse_session_title("session:"), None);
+        assert_eq!(parse_session_title("session:web - docs"), Some("web"));
+    }
+
+    #[test]
+    fn test_snapshot_indexes() {
+        let snapshot = KittySnapshot::new(layout());
+
+        let ids = |tabs: Vec<&KittyTab>| tabs.iter().map(|tab| tab.id.get()).collect::<Vec<_>>();
+        assert_eq!(ids(snapshot.session_tabs("api")), vec![1, 3]);
+        assert_eq!(snapshot.session_tab("api").map(|tab| tab.id.get()), Some(3));
+        assert_eq!(ids(snapshot.unnamed_tabs()), vec![4]);
+        assert_eq!(
+            snapshot.sessions().collect::<Vec<_>>(),
+            vec![("api", 2), ("legacy", 1)]
+        );
+
+        assert_eq!(
+            snapshot
+                .tab_for_window(WindowId(31))
+                .map(|tab| tab.id.get()),
+            Some(3)
+        );
+        assert_eq!(
+            snapshot.window(WindowId(40)).map(|w| w.cwd.as_str()),
+            Some("/home/me")
+        );
+        assert_eq!(
+            snapshot.os_window_for_tab(TabId(4)).map(|w| w.id.get()),
+            Some(2)
+        );
+        assert_eq!(
+            snapshot.active_tab(OsWindowId(1)).map(|tab| tab.id.get()),
+            Some(3)
+        );
+        assert_eq!(snapshot.focused_os_window().map(|w| w.id.get()), Some(2));
+        assert_eq!(snapshot.focused_tab().map(|tab| tab.id.get()), Some(2));
+        assert_eq!(snapshot.self_tab().map(|tab| tab.id.get()), Some(2));
+
+        assert_eq!(ids(snapshot.tabs_under("/srv/api")), vec![1, 3]);
+        assert_eq!(ids(snapshot.tabs_under("/srv")), vec![1, 3]);
+        assert_eq!(ids(snapshot.tabs_under("/srv/api-old")), vec![3]);
+        assert_eq!(ids(snapshot.tabs_under("/srv/api/")), vec![1, 3]);
+        assert_eq!(ids(snapshot.tabs_under("/srv/")), vec![1, 3]);
+        assert_eq!(ids(snapshot.tabs_under("/")), vec![1, 2, 3, 4]);
+        assert!(snapshot.tabs_under("/nowhere").is_empty());
+    }
+
+    #[test]
+    fn test_fetch_issues_one_ls() -> KittyResult<()> {
+        let mock_executor = MockExecutor::new();
+        let tab_id = mock_executor.add_session_tab("web", None);
+
+        let snapshot = KittySnapshot::fetch(&&mock_executor)?;
+
+        assert_eq!(mock_executor.ls_call_count(), 1);
+        assert_eq!(
+            snapshot.session_of(tab_id).map(|(name, _)| name),
+            Some("web")
+        );
+        Ok(())
+    }
+}
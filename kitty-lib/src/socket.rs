@@ -0,0 +1,505 @@
+use log::debug;
+use std::env;
+use std::fs;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::error::{KittyError, KittyResult};
+
+/// Socket kitty sets in the environment of every window it starts
+pub const KITTY_LISTEN_ON_ENV: &str = "KITTY_LISTEN_ON";
+
+/// PID of the kitty instance a window belongs to
+pub const KITTY_PID_ENV: &str = "KITTY_PID";
+
+/// Socket path ksm assumed before reading kitty.conf, still searched for older setups
+const LEGACY_SOCKET: &str = "unix:/tmp/mykitty";
+
+/// Placeholder kitty replaces with its PID in `listen_on`
+const KITTY_PID_PLACEHOLDER: &str = "{kitty_pid}";
+
+/// How long to wait for a TCP socket to accept a connection when probing
+const TCP_PROBE_TIMEOUT: Duration = Duration::from_millis(250);
+
+/// Find the remote control socket of a running kitty
+///
+/// Candidates are tried in order: `KITTY_LISTEN_ON`, the `listen_on` address from kitty.conf
+/// for the instance in `KITTY_PID`, every other instance using that address, then the legacy
+/// `/tmp/mykitty*` sockets. The first one accepting a connection wins.
+#[derive(Debug, Clone, Default)]
+pub struct SocketDiscovery {
+    listen_on_env: Option<String>,
+    kitty_pid: Option<u32>,
+    config_path: Option<PathBuf>,
+    temp_dir: Option<PathBuf>,
+    proc_net_unix: Option<PathBuf>,
+    legacy: bool,
+}
+
+impl SocketDiscovery {
+    /// Discovery with nothing configured; see [`SocketDiscovery::from_env`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Discovery configured from the environment kitty and the user provide
+    pub fn from_env() -> Self {
+        Self {
+            listen_on_env: env::var(KITTY_LISTEN_ON_ENV)
+                .ok()
+                .filter(|value| !value.is_empty()),
+            kitty_pid: env::var(KITTY_PID_ENV)
+                .ok()
+                .and_then(|pid| pid.parse().ok()),
+            config_path: default_config_path(),
+            temp_dir: Some(env::temp_dir()),
+            proc_net_unix: Some(PathBuf::from("/proc/net/unix")),
+            legacy: true,
+        }
+    }
+
+    /// The socket kitty advertised through `KITTY_LISTEN_ON`
+    pub fn listen_on_env(mut self, address: impl Into<String>) -> Self {
+        self.listen_on_env = Some(address.into());
+        self
+    }
+
+    /// The kitty instance to prefer when several are running
+    pub fn kitty_pid(mut self, pid: u32) -> Self {
+        self.kitty_pid = Some(pid);
+        self
+    }
+
+    /// kitty.conf to read `listen_on` from
+    pub fn config_path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config_path = Some(path.into());
+        self
+    }
+
+    /// Directory relative `listen_on` paths are resolved against
+    pub fn temp_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.temp_dir = Some(path.into());
+        self
+    }
+
+    /// Also search the legacy `/tmp/mykitty*` sockets
+    pub fn with_legacy_sockets(mut self) -> Self {
+        self.legacy = true;
+        self
+    }
+
+    /// The kitty.conf discovery reads, which also holds `remote_control_password`
+    pub fn kitty_conf(&self) -> Option<&Path> {
+        self.config_path.as_deref()
+    }
+
+    /// Every address worth trying, most likely first
+    pub fn candidates(&self) -> Vec<String> {
+        let mut candidates = Vec::new();
+
+        if let Some(address) = &self.listen_on_env {
+            candidates.push(address.clone());
+        }
+
+        if let Some(listen_on) = self
+            .config_path
+            .as_deref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .and_then(|config| parse_listen_on(&config))
+        {
+            let address = self.resolve(&expand_env(&listen_on));
+            candidates.extend(self.instances_of(&address));
+        }
+
+        if self.legacy {
+            candidates.extend(self.instances_of(LEGACY_SOCKET));
+        }
+
+        let mut seen = Vec::new();
+        candidates.retain(|candidate| {
+            let new = !seen.contains(candidate);
+            if new {
+                seen.push(candidate.clone());
+            }
+            new
+        });
+        candidates
+    }
+
+    /// The first candidate with a kitty listening on it
+    pub fn discover(&self) -> KittyResult<String> {
+        let candidates = self.candidates();
+        for candidate in &candidates {
+            if is_alive(candidate) {
+                debug!("Using kitty socket {}", candidate);
+                return Ok(candidate.clone());
+            }
+            debug!("No kitty listening on {}", candidate);
+        }
+
+        Err(KittyError::NoSocket { tried: candidates })
+    }
+
+    /// Resolve a relative unix socket path against the temporary directory, as kitty does
+    fn resolve(&self, address: &str) -> String {
+        match address.strip_prefix("unix:") {
+            Some(path) if !path.starts_with('/') && !path.starts_with('@') => {
+                let temp_dir = self.temp_dir.clone().unwrap_or_else(env::temp_dir);
+                format!("unix:{}", temp_dir.join(path).display())
+            }
+            _ => address.to_string(),
+        }
+    }
+
+    /// The addresses kitty instances listen on for a `listen_on` value: the one for
+    /// `KITTY_PID` first, then the others found on disk, then the value itself
+    fn instances_of(&self, address: &str) -> Vec<String> {
+        let Some(unix_path) = address.strip_prefix("unix:") else {
+            return vec![address.to_string()];
+        };
+
+        let (prefix, suffix) = match unix_path.split_once(KITTY_PID_PLACEHOLDER) {
+            Some((prefix, suffix)) => (prefix.to_string(), suffix.to_string()),
+            None => (format!("{}-", unix_path), String::new()),
+        };
+
+        let mut instances = Vec::new();
+        if let Some(pid) = self.kitty_pid {
+            instances.push(format!("unix:{}{}{}", prefix, pid, suffix));
+        }
+
+        let mut found = match prefix.strip_prefix('@') {
+            Some(abstract_prefix) => self.abstract_sockets(abstract_prefix, &suffix),
+            None => socket_files(&prefix, &suffix),
+        };
+        found.sort();
+        instances.extend(found.into_iter().map(|path| format!("unix:{}", path)));
+
+        if !unix_path.contains(KITTY_PID_PLACEHOLDER) {
+            instances.push(address.to_string());
+        }
+        instances
+    }
+
+    /// Abstract sockets (`@name`) whose name matches, from `/proc/net/unix`
+    fn abstract_sockets(&self, prefix: &str, suffix: &str) -> Vec<String> {
+        let Some(table) = self
+            .proc_net_unix
+            .as_deref()
+            .and_then(|path| fs::read_to_string(path).ok())
+        else {
+            return Vec::new();
+        };
+
+        table
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.split_whitespace().nth(7))
+            .filter_map(|path| path.strip_prefix('@'))
+            .filter(|name| matches_instance(name, prefix, suffix))
+            .map(|name| format!("@{}", name))
+            .collect()
+    }
+}
+
+/// The last `listen_on` value in kitty.conf text, as kitty applies it
+pub fn parse_listen_on(config: &str) -> Option<String> {
+    config
+        .lines()
+        .rev()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .find_map(|line| {
+            let (key, value) = line.split_once(char::is_whitespace)?;
+            (key == "listen_on").then(|| value.trim().to_string())
+        })
+        .filter(|value| !value.is_empty() && value != "none")
+}
+
+/// Whether something accepts connections on a kitty socket address
+pub fn is_alive(address: &str) -> bool {
+    if let Some(path) = address.strip_prefix("unix:") {
+        return match path.strip_prefix('@') {
+            Some(name) => connect_abstract(name),
+            None => UnixStream::connect(path).is_ok(),
+        };
+    }
+
+    if let Some(host_port) = address
+        .strip_prefix("tcp:")
+        .or_else(|| address.strip_prefix("tcp6:"))
+    {
+        return host_port
+            .to_socket_addrs()
+            .map(|mut addrs| {
+                addrs.any(|addr| TcpStream::connect_timeout(&addr, TCP_PROBE_TIMEOUT).is_ok())
+            })
+            .unwrap_or(false);
+    }
+
+    // Other forms, like `fd:`, can't be probed; let kitten try them
+    true
+}
+
+#[cfg(target_os = "linux")]
+fn connect_abstract(name: &str) -> bool {
+    use std::os::linux::net::SocketAddrExt;
+    use std::os::unix::net::SocketAddr;
+
+    SocketAddr::from_abstract_name(name.as_bytes())
+        .and_then(|addr| UnixStream::connect_addr(&addr))
+        .is_ok()
+}
+
+#[cfg(not(target_os = "linux"))]
+fn connect_abstract(_name: &str) -> bool {
+    false
+}
+
+/// Socket files named `<prefix><pid><suffix>`, where `prefix` includes the directory
+fn socket_files(prefix: &str, suffix: &str) -> Vec<String> {
+    let prefix_path = Path::new(prefix);
+    let (dir, name_prefix) = if prefix.ends_with('/') {
+        (prefix_path, "")
+    } else {
+        (
+            prefix_path.parent().unwrap_or(Path::new("/")),
+            prefix_path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .unwrap_or(""),
+        )
+    };
+
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(Result::ok)
+        .filter(|entry| {
+            entry
+                .file_type()
+                .map(|file_type| file_type.is_socket())
+                .unwrap_or(false)
+        })
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            matches_instance(&name, name_prefix, suffix).then(|| entry.path().display().to_string())
+        })
+        .collect()
+}
+
+/// Whether `name` is `<prefix><pid><suffix>`
+fn matches_instance(name: &str, prefix: &str, suffix: &str) -> bool {
+    name.strip_prefix(prefix)
+        .and_then(|rest| rest.strip_suffix(suffix))
+        .is_some_and(|pid| !pid.is_empty() && pid.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// Expand `$VAR` and `${VAR}` the way kitty does for `listen_on`
+fn expand_env(value: &str) -> String {
+    let mut expanded = String::with_capacity(value.len());
+    let mut rest = value;
+
+    while let Some(start) = rest.find('$') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+
+        let (name, remainder) = if let Some(braced) = after.strip_prefix('{') {
+            match braced.split_once('}') {
+                Some((name, remainder)) => (name, remainder),
+                None => ("", after),
+            }
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            (&after[..end], &after[end..])
+        };
+
+        if name.is_empty() {
+            expanded.push('$');
+            rest = after;
+        } else {
+            expanded.push_str(&env::var(name).unwrap_or_default());
+            rest = remainder;
+        }
+    }
+
+    expanded.push_str(rest);
+    expanded
+}
+
+/// kitty.conf in `KITTY_CONFIG_DIRECTORY`, `XDG_CONFIG_HOME/kitty` or `~/.config/kitty`
+fn default_config_path() -> Option<PathBuf> {
+    let dir = env::var_os("KITTY_CONFIG_DIRECTORY")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("XDG_CONFIG_HOME").map(|dir| PathBuf::from(dir).join("kitty")))
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/kitty")))?;
+    Some(dir.join("kitty.conf"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixListener;
+    use tempfile::TempDir;
+
+    fn unix(path: &Path) -> String {
+        format!("unix:{}", path.display())
+    }
+
+    #[test]
+    fn test_parse_listen_on_uses_last_setting() {
+        let config = "\
+# listen_on unix:/tmp/commented
+font_size 12
+listen_on unix:/tmp/first
+listen_on   unix:${XDG_RUNTIME_DIR}/kitty-{kitty_pid}
+";
+        assert_eq!(
+            parse_listen_on(config).as_deref(),
+            Some("unix:${XDG_RUNTIME_DIR}/kitty-{kitty_pid}")
+        );
+        assert_eq!(parse_listen_on("listen_on none"), None);
+        assert_eq!(parse_listen_on("font_size 12"), None);
+    }
+
+    #[test]
+    fn test_expand_env() {
+        let home = env::var("HOME").unwrap_or_default();
+        assert_eq!(expand_env("unix:$HOME/k"), format!("unix:{}/k", home));
+        assert_eq!(expand_env("unix:${HOME}/k"), format!("unix:{}/k", home));
+        assert_eq!(expand_env("unix:/tmp/$"), "unix:/tmp/$");
+    }
+
+    #[test]
+    fn test_candidates_prefer_kitty_pid_and_find_other_instances() {
+        let dir = TempDir::new().unwrap();
+        let config = dir.path().join("kitty.conf");
+        fs::write(&config, "listen_on unix:kitty\n").unwrap();
+        let _first = UnixListener::bind(dir.path().join("kitty-100")).unwrap();
+        let _second = UnixListener::bind(dir.path().join("kitty-200")).unwrap();
+        fs::write(dir.path().join("kitty-300"), "not a socket").unwrap();
+
+        let discovery = SocketDiscovery::new()
+            .config_path(&config)
+            .temp_dir(dir.path())
+            .kitty_pid(200);
+
+        assert_eq!(
+            discovery.candidates(),
+            vec![
+                unix(&dir.path().join("kitty-200")),
+                unix(&dir.path().join("kitty-100")),
+                unix(&dir.path().join("kitty")),
+            ]
+        );
+        assert_eq!(
+            discovery.discover().unwrap(),
+            unix(&dir.path().join("kitty-200"))
+        );
+    }
+
+    #[test]
+    fn test_candidates_expand_kitty_pid_placeholder() {
+        let dir = TempDir::new().unwrap();
+        let config = dir.path().join("kitty.conf");
+        fs::write(
+            &config,
+            format!(
+                "listen_on unix:{}/rc-{{kitty_pid}}.sock\n",
+                dir.path().display()
+            ),
+        )
+        .unwrap();
+        let _listener = UnixListener::bind(dir.path().join("rc-42.sock")).unwrap();
+
+        let discovery = SocketDiscovery::new().config_path(&config);
+
+        assert_eq!(
+            discovery.candidates(),
+            vec![unix(&dir.path().join("rc-42.sock"))]
+        );
+    }
+
+    #[test]
+    fn test_listen_on_env_comes_first_and_dead_sockets_are_skipped() {
+        let dir = TempDir::new().unwrap();
+        let dead = dir.path().join("dead");
+        drop(UnixListener::bind(&dead).unwrap());
+        let live = dir.path().join("live");
+        let _listener = UnixListener::bind(&live).unwrap();
+        let config = dir.path().join("kitty.conf");
+        fs::write(&config, format!("listen_on {}\n", unix(&live))).unwrap();
+
+        let discovery = SocketDiscovery::new()
+            .listen_on_env(unix(&dead))
+            .config_path(&config);
+
+        assert_eq!(discovery.candidates()[0], unix(&dead));
+        assert_eq!(discovery.discover().unwrap(), unix(&live));
+    }
+
+    #[test]
+    fn test_tcp_listen_on_is_used_as_is() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("tcp:{}", listener.local_addr().unwrap());
+        let dir = TempDir::new().unwrap();
+        let config = dir.path().join("kitty.conf");
+        fs::write(&config, format!("listen_on {}\n", address)).unwrap();
+
+        let discovery = SocketDiscovery::new().config_path(&config).kitty_pid(7);
+
+        assert_eq!(discovery.candidates(), vec![address.clone()]);
+        assert_eq!(discovery.discover().unwrap(), address);
+
+        drop(listener);
+        assert!(!is_alive(&address));
+    }
+
+    #[test]
+    fn test_discover_reports_every_address_tried() {
+        let dir = TempDir::new().unwrap();
+        let missing = unix(&dir.path().join("missing"));
+
+        let error = SocketDiscovery::new()
+            .listen_on_env(&missing)
+            .discover()
+            .unwrap_err();
+
+        match error {
+            KittyError::NoSocket { tried } => assert_eq!(tried, vec![missing]),
+            other => panic!("unexpected error: {other}"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_abstract_sockets_from_proc_net_unix() {
+        let dir = TempDir::new().unwrap();
+        let table = dir.path().join("unix");
+        fs::write(
+            &table,
+            "Num       RefCount Protocol Flags    Type St Inode Path\n\
+             0000000000000000: 00000002 00000000 00010000 0001 01 1001 @mykitty-77\n\
+             0000000000000000: 00000002 00000000 00010000 0001 01 1002 @other-5\n\
+             0000000000000000: 00000002 00000000 00010000 0001 01 1003 /run/user/1000/bus\n",
+        )
+        .unwrap();
+
+        let discovery = SocketDiscovery {
+            proc_net_unix: Some(table),
+            ..SocketDiscovery::default()
+        };
+
+        assert_eq!(
+            discovery.instances_of("unix:@mykitty"),
+            vec!["unix:@mykitty-77".to_string(), "unix:@mykitty".to_string()]
+        );
+    }
+}
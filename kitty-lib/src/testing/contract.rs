@@ -0,0 +1,288 @@
+use std::fmt::Debug;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::launch::{KittenLaunchCommand, LaunchType};
+use crate::commands::ls::KittenLsCommand;
+use crate::error::{KittyError, KittyResult};
+use crate::executor::CommandExecutor;
+use crate::types::{KittyLsResponse, KittyTab, TabId, WindowId};
+
+/// Tells contract runs in one test process apart
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+/// The environment variable the contract launches tabs with
+const CONTRACT_ENV: &str = "KSM_CONTRACT";
+
+/// The user variable the contract launches tabs with
+const CONTRACT_VAR: &str = "ksm_contract";
+
+/// Behaviour every [`CommandExecutor`] has to share with kitty itself
+///
+/// Each check launches its own tabs, named after a tag unique to this run, asserts on what
+/// `ls` reports afterwards and closes the tabs again, so the suite can run against a kitty
+/// that already has other tabs open. A failed check panics like an `assert!`.
+///
+/// ```no_run
+/// use kitty_lib::MockExecutor;
+/// use kitty_lib::testing::ExecutorContract;
+///
+/// ExecutorContract::new(MockExecutor::new()).run_all();
+/// ```
+pub struct ExecutorContract<E> {
+    executor: E,
+    tag: String,
+}
+
+impl<E: CommandExecutor> ExecutorContract<E> {
+    pub fn new(executor: E) -> Self {
+        Self {
+            executor,
+            tag: format!(
+                "contract-{}-{}",
+                process::id(),
+                NEXT_RUN.fetch_add(1, Ordering::Relaxed)
+            ),
+        }
+    }
+
+    /// Run every check in turn
+    pub fn run_all(&self) {
+        self.launched_tabs_are_listed();
+        self.focus_moves_the_active_tab();
+        self.closed_tabs_are_gone();
+        self.title_matches_are_regular_expressions();
+        self.env_matches_select_launched_windows();
+    }
+
+    /// A launched tab shows up in `ls` with its title and user vars, and takes focus
+    pub fn launched_tabs_are_listed(&self) {
+        let mut tabs = self.tabs();
+        let title = self.name("listed");
+        let (tab_id, window_id) = tabs.launch(
+            KittenLaunchCommand::new()
+                .tab_title(&title)
+                .var(CONTRACT_VAR, &title),
+        );
+
+        let tab = self.tab(tab_id).expect("launched tab should be listed");
+        assert_eq!(tab.title, title, "launched tab should keep its title");
+        assert!(
+            tab.windows.iter().any(|window| window.id == window_id),
+            "launched tab should hold the launched window"
+        );
+        assert!(tab.is_active, "launched tab should become active");
+
+        let by_var = self.matching_tabs(KittenLsCommand::new().match_tab_var(CONTRACT_VAR, &title));
+        assert_eq!(by_var, [tab_id], "var: should match the launched tab");
+    }
+
+    /// `focus_tab` makes the tab active and the previously active one inactive
+    pub fn focus_moves_the_active_tab(&self) {
+        let mut tabs = self.tabs();
+        let (first, _) = tabs.launch(KittenLaunchCommand::new().tab_title(&self.name("focus-1")));
+        let (second, _) = tabs.launch(KittenLaunchCommand::new().tab_title(&self.name("focus-2")));
+        assert_active(&self.tab(second), &self.tab(first));
+
+        succeed(
+            "focus_tab",
+            self.executor.focus_tab(KittenFocusTabCommand::new(first)),
+        );
+        assert_active(&self.tab(first), &self.tab(second));
+
+        succeed(
+            "focus_tab",
+            self.executor.focus_tab(KittenFocusTabCommand::new(second)),
+        );
+        assert_active(&self.tab(second), &self.tab(first));
+    }
+
+    /// `close_tab` removes only that tab, and later commands for it find nothing
+    pub fn closed_tabs_are_gone(&self) {
+        let mut tabs = self.tabs();
+        let (closed, _) = tabs.launch(KittenLaunchCommand::new().tab_title(&self.name("close-1")));
+        let (kept, _) = tabs.launch(KittenLaunchCommand::new().tab_title(&self.name("close-2")));
+
+        succeed(
+            "close_tab",
+            self.executor.close_tab(KittenCloseTabCommand::new(closed)),
+        );
+        tabs.forget(closed);
+
+        assert!(
+            self.tab(closed).is_none(),
+            "closed tab should not be listed"
+        );
+        assert!(self.tab(kept).is_some(), "other tabs should stay open");
+        assert_no_match(
+            "focus_tab",
+            self.executor.focus_tab(KittenFocusTabCommand::new(closed)),
+        );
+    }
+
+    /// `title:` patterns are regular expressions found anywhere in the title
+    pub fn title_matches_are_regular_expressions(&self) {
+        let mut tabs = self.tabs();
+        let title = self.name("title-api");
+        let (api, _) = tabs.launch(KittenLaunchCommand::new().tab_title(&title));
+        let (api_v2, _) =
+            tabs.launch(KittenLaunchCommand::new().tab_title(&format!("{}-v2", title)));
+
+        let mut unanchored = self.matching_tabs(KittenLsCommand::new().match_tab_title(&title));
+        unanchored.sort();
+        assert_eq!(
+            unanchored,
+            [api, api_v2],
+            "title:{} should match both tabs",
+            title
+        );
+
+        let anchored =
+            self.matching_tabs(KittenLsCommand::new().match_tab_title(&format!("^{}$", title)));
+        assert_eq!(anchored, [api], "an anchored title should match one tab");
+
+        assert_no_match(
+            "ls",
+            self.executor
+                .ls(KittenLsCommand::new().match_tab_title(&self.name("title-missing"))),
+        );
+    }
+
+    /// `env:` matches the environment given at launch, for windows and for tabs
+    pub fn env_matches_select_launched_windows(&self) {
+        let mut tabs = self.tabs();
+        let one = self.name("env-one");
+        let two = self.name("env-two");
+        let (one_tab, one_window) = tabs.launch(KittenLaunchCommand::new().env(CONTRACT_ENV, &one));
+        let (_, two_window) = tabs.launch(KittenLaunchCommand::new().env(CONTRACT_ENV, &two));
+
+        let by_tab = self.matching_tabs(
+            KittenLsCommand::new().match_tab_env(CONTRACT_ENV, &format!("^{}$", one)),
+        );
+        assert_eq!(
+            by_tab,
+            [one_tab],
+            "env: should match the tab launched with it"
+        );
+
+        let mut by_window = window_ids(&succeed(
+            "ls",
+            self.executor
+                .ls(KittenLsCommand::new().match_env(CONTRACT_ENV, &self.name("env-"))),
+        ));
+        by_window.sort();
+        assert_eq!(
+            by_window,
+            [one_window, two_window],
+            "env: should match every window whose value contains the pattern"
+        );
+
+        assert_no_match(
+            "ls",
+            self.executor
+                .ls(KittenLsCommand::new().match_env(CONTRACT_ENV, &self.name("env-missing"))),
+        );
+    }
+
+    fn name(&self, check: &str) -> String {
+        format!("{}-{}", self.tag, check)
+    }
+
+    fn tabs(&self) -> LaunchedTabs<'_, E> {
+        LaunchedTabs {
+            executor: &self.executor,
+            tab_ids: Vec::new(),
+        }
+    }
+
+    fn tab(&self, tab_id: TabId) -> Option<KittyTab> {
+        succeed("ls", self.executor.ls(KittenLsCommand::new()))
+            .into_iter()
+            .flat_map(|os_window| os_window.tabs)
+            .find(|tab| tab.id == tab_id)
+    }
+
+    fn matching_tabs(&self, command: KittenLsCommand) -> Vec<TabId> {
+        succeed("ls", self.executor.ls(command))
+            .iter()
+            .flat_map(|os_window| &os_window.tabs)
+            .map(|tab| tab.id)
+            .collect()
+    }
+}
+
+/// Tabs a check launched, closed again when the check ends, even when it panics
+struct LaunchedTabs<'a, E: CommandExecutor> {
+    executor: &'a E,
+    tab_ids: Vec<TabId>,
+}
+
+impl<E: CommandExecutor> LaunchedTabs<'_, E> {
+    fn launch(&mut self, command: KittenLaunchCommand) -> (TabId, WindowId) {
+        let response = succeed(
+            "launch",
+            self.executor.launch(command.launch_type(LaunchType::Tab)),
+        );
+        let window_id = response
+            .window_id
+            .expect("launch should report the new window");
+
+        // kitty only reports the window, so find its tab
+        let tab_id = succeed("ls", self.executor.ls(KittenLsCommand::new()))
+            .into_iter()
+            .flat_map(|os_window| os_window.tabs)
+            .find(|tab| tab.windows.iter().any(|window| window.id == window_id))
+            .map(|tab| tab.id)
+            .expect("the launched window should be listed");
+        self.tab_ids.push(tab_id);
+        (tab_id, window_id)
+    }
+
+    fn forget(&mut self, tab_id: TabId) {
+        self.tab_ids.retain(|id| *id != tab_id);
+    }
+}
+
+impl<E: CommandExecutor> Drop for LaunchedTabs<'_, E> {
+    fn drop(&mut self) {
+        for tab_id in self.tab_ids.drain(..) {
+            let _ = self.executor.close_tab(KittenCloseTabCommand::new(tab_id));
+        }
+    }
+}
+
+fn succeed<T>(command: &str, result: KittyResult<T>) -> T {
+    result.unwrap_or_else(|error| panic!("{} should succeed: {}", command, error))
+}
+
+fn assert_no_match<T: Debug>(command: &str, result: KittyResult<T>) {
+    assert!(
+        matches!(result, Err(KittyError::NoMatch { .. })),
+        "{} should fail with NoMatch, got {:?}",
+        command,
+        result
+    );
+}
+
+fn assert_active(active: &Option<KittyTab>, inactive: &Option<KittyTab>) {
+    let (Some(active), Some(inactive)) = (active, inactive) else {
+        panic!("both tabs should be listed");
+    };
+    assert!(active.is_active, "tab {} should be active", active.id);
+    assert!(
+        !inactive.is_active,
+        "tab {} should not be active",
+        inactive.id
+    );
+}
+
+fn window_ids(response: &KittyLsResponse) -> Vec<WindowId> {
+    response
+        .iter()
+        .flat_map(|os_window| &os_window.tabs)
+        .flat_map(|tab| &tab.windows)
+        .map(|window| window.id)
+        .collect()
+}
@@ -0,0 +1,334 @@
+use serde_json::{Value, json};
+use std::env;
+use std::fs;
+use std::io;
+use std::os::unix::fs::symlink;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::thread::{self, JoinHandle};
+
+use super::protocol;
+use crate::commands::close_tab::KittenCloseTabCommand;
+use crate::commands::close_window::KittenCloseWindowCommand;
+use crate::commands::focus_tab::KittenFocusTabCommand;
+use crate::commands::focus_window::KittenFocusWindowCommand;
+use crate::commands::get_text::{KittenGetTextCommand, TextExtent};
+use crate::commands::launch::KittenLaunchCommand;
+use crate::commands::send_text::KittenSendTextCommand;
+use crate::commands::set_window_title::KittenSetWindowTitleCommand;
+use crate::error::{KittyError, KittyResult};
+use crate::executor::{CommandExecutor, KittyExecutor, MockExecutor};
+
+/// Tells fake servers started by one test process apart
+static NEXT_SERVER: AtomicUsize = AtomicUsize::new(0);
+
+/// A stand-in for kitty listening on a unix socket, backed by a [`MockExecutor`]'s layout
+///
+/// Point [`KittyExecutor`] at it with [`FakeKitty::executor`] to run the real executor, its
+/// argument building and its socket handling against the fake, or put [`FakeKitty::bin_dir`]
+/// first on a child process' `PATH` so it runs the fake `kitten` too.
+///
+/// Handles `ls`, `launch`, `focus-tab`, `close-tab`, `set-tab-title`, `focus-window`,
+/// `close-window`, `set-window-title`, `send-text` and `get-text`, resolving match expressions
+/// with [`MockLayout`](crate::executor::mock_executor::MockLayout). Other commands fail.
+pub struct FakeKitty {
+    dir: PathBuf,
+    socket: PathBuf,
+    mock: Arc<Mutex<MockExecutor>>,
+    stopping: Arc<AtomicBool>,
+    server: Option<JoinHandle<()>>,
+}
+
+impl FakeKitty {
+    /// Listen on a socket in a new temporary directory
+    ///
+    /// `kitten` is the fake kitten binary built from this crate's `fake-kitten` target, e.g.
+    /// `env!("CARGO_BIN_EXE_fake-kitten")` in an integration test.
+    pub fn start(kitten: impl AsRef<Path>) -> io::Result<Self> {
+        let dir = env::temp_dir().join(format!(
+            "fake-kitty-{}-{}",
+            process::id(),
+            NEXT_SERVER.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(dir.join("bin"))?;
+        symlink(kitten.as_ref(), dir.join("bin").join("kitten"))?;
+
+        let socket = dir.join("kitty.sock");
+        let listener = UnixListener::bind(&socket)?;
+        let mock = Arc::new(Mutex::new(MockExecutor::new()));
+        let stopping = Arc::new(AtomicBool::new(false));
+
+        let server = thread::spawn({
+            let mock = Arc::clone(&mock);
+            let stopping = Arc::clone(&stopping);
+            move || serve(listener, &mock, &stopping)
+        });
+
+        Ok(Self {
+            dir,
+            socket,
+            mock,
+            stopping,
+            server: Some(server),
+        })
+    }
+
+    /// The address to pass to `kitten @ --to`
+    pub fn address(&self) -> String {
+        format!("unix:{}", self.socket.display())
+    }
+
+    /// A directory holding the fake binary as `kitten`
+    pub fn bin_dir(&self) -> PathBuf {
+        self.dir.join("bin")
+    }
+
+    /// A [`KittyExecutor`] talking to this server through the fake `kitten`
+    pub fn executor(&self) -> KittyExecutor {
+        KittyExecutor::with_socket(self.address()).with_kitten(self.bin_dir().join("kitten"))
+    }
+
+    /// The mock whose layout the server works on, for setting up and checking state
+    pub fn mock(&self) -> MutexGuard<'_, MockExecutor> {
+        lock(&self.mock)
+    }
+}
+
+impl Drop for FakeKitty {
+    fn drop(&mut self) {
+        self.stopping.store(true, Ordering::SeqCst);
+        // Wake the server from accept so it sees the flag
+        let _ = UnixStream::connect(&self.socket);
+        if let Some(server) = self.server.take() {
+            let _ = server.join();
+        }
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+fn lock(mock: &Mutex<MockExecutor>) -> MutexGuard<'_, MockExecutor> {
+    mock.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Answer one request per connection, like kitty does for `kitten @`
+fn serve(listener: UnixListener, mock: &Mutex<MockExecutor>, stopping: &AtomicBool) {
+    for stream in listener.incoming() {
+        if stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+
+        let response = match protocol::read_message(&mut stream) {
+            Ok(request) => respond(&lock(mock), &request),
+            Err(error) => json!({"ok": false, "error": error.to_string()}),
+        };
+        let _ = protocol::write_message(&mut stream, &response);
+    }
+}
+
+fn respond(mock: &MockExecutor, request: &Value) -> Value {
+    let command = request["cmd"].as_str().unwrap_or_default();
+    match dispatch(mock, command, &Payload(&request["payload"])) {
+        Ok(Some(data)) => json!({"ok": true, "data": data}),
+        Ok(None) => json!({"ok": true}),
+        Err(error) => {
+            let message = match error.stderr() {
+                Some(stderr) => stderr.trim_start_matches("Error: ").to_string(),
+                None => error.to_string(),
+            };
+            json!({"ok": false, "error": message})
+        }
+    }
+}
+
+fn dispatch(mock: &MockExecutor, command: &str, payload: &Payload) -> KittyResult<Option<String>> {
+    match command {
+        "ls" => {
+            let layout = mock.layout.borrow();
+            let response = match (payload.string("match_tab"), payload.string("match")) {
+                (Some(expression), _) => non_empty(layout.ls_matching(&expression, true), || {
+                    KittyError::no_match("ls", "tabs", &expression)
+                })?,
+                (None, Some(expression)) => {
+                    non_empty(layout.ls_matching(&expression, false), || {
+                        KittyError::no_match("ls", "windows", &expression)
+                    })?
+                }
+                (None, None) => layout.os_windows.clone(),
+            };
+            let json = serde_json::to_string_pretty(&response).map_err(io::Error::other)?;
+            Ok(Some(json))
+        }
+        "launch" => {
+            let response = mock.launch(launch_command(payload)?)?;
+            Ok(response.window_id.map(|id| id.to_string()))
+        }
+        "focus-tab" => {
+            let tab_id = *matching_tabs(mock, command, payload)?
+                .first()
+                .expect("matching_tabs never returns an empty list");
+            mock.focus_tab(KittenFocusTabCommand::new(tab_id))?;
+            Ok(None)
+        }
+        "close-tab" => {
+            for tab_id in matching_tabs(mock, command, payload)? {
+                mock.close_tab(KittenCloseTabCommand::new(tab_id))?;
+            }
+            Ok(None)
+        }
+        "set-tab-title" => {
+            let title = payload.args().join(" ");
+            for tab_id in matching_tabs(mock, command, payload)? {
+                mock.layout.borrow_mut().set_tab_title(tab_id, &title);
+            }
+            Ok(None)
+        }
+        "focus-window" => {
+            let pattern = payload
+                .string("match")
+                .unwrap_or("state:focused".to_string());
+            mock.focus_window(KittenFocusWindowCommand::with_match(pattern))?;
+            Ok(None)
+        }
+        "close-window" => {
+            let pattern = payload
+                .string("match")
+                .unwrap_or("state:focused".to_string());
+            mock.close_window(KittenCloseWindowCommand::with_match(pattern))?;
+            Ok(None)
+        }
+        "set-window-title" => {
+            let mut title_command = KittenSetWindowTitleCommand::new(payload.args().join(" "));
+            title_command.match_pattern = payload.string("match");
+            mock.set_window_title(title_command)?;
+            Ok(None)
+        }
+        "send-text" => {
+            let text = payload
+                .string("data")
+                .unwrap_or_else(|| payload.args().join(" "));
+            let mut send_command = KittenSendTextCommand::new(text);
+            send_command.match_pattern = payload.string("match");
+            send_command.match_tab = payload.string("match_tab");
+            send_command.exclude_active = payload.flag("exclude_active");
+            mock.send_text(send_command)?;
+            Ok(None)
+        }
+        "get-text" => {
+            let mut text_command = KittenGetTextCommand::new();
+            text_command.match_pattern = payload.string("match");
+            if let Some(extent) = payload.string("extent") {
+                text_command.extent = extent
+                    .parse::<TextExtent>()
+                    .map_err(KittyError::InvalidCommand)?;
+            }
+            text_command.ansi = payload.flag("ansi");
+            Ok(Some(mock.get_text(text_command)?))
+        }
+        other => Err(KittyError::CommandFailed {
+            command: other.to_string(),
+            exit_code: Some(1),
+            stderr: format!("Error: Unknown remote control command: {}", other),
+        }),
+    }
+}
+
+/// The tabs `--match` selects, or the active tab without one
+fn matching_tabs(
+    mock: &MockExecutor,
+    command: &str,
+    payload: &Payload,
+) -> KittyResult<Vec<crate::types::TabId>> {
+    let layout = mock.layout.borrow();
+    let expression = payload.string("match");
+    let tab_ids = match &expression {
+        Some(expression) => layout.match_tabs(expression),
+        None => layout.active_tab_id.into_iter().collect(),
+    };
+    non_empty(tab_ids, || {
+        KittyError::no_match(
+            command,
+            "tabs",
+            expression.as_deref().unwrap_or("state:active"),
+        )
+    })
+}
+
+fn non_empty<T>(items: Vec<T>, error: impl FnOnce() -> KittyError) -> KittyResult<Vec<T>> {
+    if items.is_empty() {
+        Err(error())
+    } else {
+        Ok(items)
+    }
+}
+
+fn launch_command(payload: &Payload) -> KittyResult<KittenLaunchCommand> {
+    let mut command = KittenLaunchCommand::new();
+    if let Some(launch_type) = payload.string("type") {
+        command.launch_type = enum_value(&launch_type)?;
+    }
+    if let Some(location) = payload.string("location") {
+        command.location = Some(enum_value(&location)?);
+    }
+    command.cwd = payload.string("cwd");
+    command.env = payload.pairs("env");
+    command.vars = payload.pairs("var");
+    command.tab_title = payload.string("tab_title");
+    command.window_title = payload.string("title");
+    command.next_to = payload.string("next_to");
+    command.bias = payload.string("bias").and_then(|bias| bias.parse().ok());
+    command.hold = payload.flag("hold");
+    command.copy_env = payload.flag("copy_env");
+    command.keep_focus = payload.flag("keep_focus");
+    command.args = payload.args();
+    Ok(command)
+}
+
+/// A kebab-case option value such as `os-window` as one of kitty-lib's enums
+fn enum_value<T: serde::de::DeserializeOwned>(value: &str) -> KittyResult<T> {
+    serde_json::from_value(Value::from(value))
+        .map_err(|_| KittyError::InvalidCommand(format!("Unknown value {}", value)))
+}
+
+/// A request payload as the fake kitten shim builds it
+struct Payload<'a>(&'a Value);
+
+impl Payload<'_> {
+    /// The last value given for an option
+    fn string(&self, name: &str) -> Option<String> {
+        self.strings(name).pop()
+    }
+
+    fn strings(&self, name: &str) -> Vec<String> {
+        match &self.0[name] {
+            Value::String(value) => vec![value.clone()],
+            Value::Array(values) => values
+                .iter()
+                .filter_map(|value| value.as_str().map(str::to_string))
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// `NAME=VALUE` options such as `--env` as pairs
+    fn pairs(&self, name: &str) -> Vec<(String, String)> {
+        self.strings(name)
+            .iter()
+            .filter_map(|pair| pair.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.0[name].as_bool().unwrap_or(false)
+    }
+
+    fn args(&self) -> Vec<String> {
+        self.strings("args")
+    }
+}
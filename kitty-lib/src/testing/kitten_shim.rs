@@ -0,0 +1,126 @@
+//! A stand-in for `kitten @` that sends its command to a [`FakeKitty`](super::FakeKitty)
+
+use serde_json::{Map, Value, json};
+use std::io::{self, Read, Write};
+use std::os::unix::net::UnixStream;
+
+use super::protocol::{self, FAKE_KITTY_VERSION};
+
+/// Run `kitten <args>`, returning the process exit code
+///
+/// Understands `--version` and `@ [--to=unix:<path>] [--password...] <command> [options] [args]`. Options
+/// become payload fields named like kitten's (`--match-tab=x` is `match_tab`, repeated options
+/// collect into a list, bare flags are `true`) and the remaining words go into `args`. With
+/// `--stdin`, stdin is read into `data`. What kitty sends back is printed like kitten does.
+pub fn run_kitten_shim(args: &[String]) -> i32 {
+    match run(args) {
+        Ok(Some(data)) => {
+            print!("{}", data);
+            0
+        }
+        Ok(None) => 0,
+        Err(message) => {
+            eprintln!("Error: {}", message);
+            1
+        }
+    }
+}
+
+fn run(args: &[String]) -> Result<Option<String>, String> {
+    if args.first().map(String::as_str) == Some("--version") {
+        let [major, minor, patch] = FAKE_KITTY_VERSION;
+        return Ok(Some(format!(
+            "kitten {}.{}.{} created by Kovid Goyal\n",
+            major, minor, patch
+        )));
+    }
+
+    let mut args = args.iter().map(String::as_str);
+    if args.next() != Some("@") {
+        return Err("only `kitten @` is supported".to_string());
+    }
+
+    let mut to = None;
+    let command = loop {
+        match args.next() {
+            Some(arg) if arg.starts_with("--to=") => to = Some(arg["--to=".len()..].to_string()),
+            Some(arg) if arg.starts_with("--password") => {}
+            Some(arg) if arg.starts_with("--") => {
+                return Err(format!("unknown global option {}", arg));
+            }
+            Some(command) => break command.to_string(),
+            None => return Err("no command given".to_string()),
+        }
+    };
+
+    let mut payload = Map::new();
+    let mut positional = Vec::new();
+    let mut options_ended = false;
+    for arg in args {
+        if arg == "--" && !options_ended {
+            options_ended = true;
+            continue;
+        }
+        // Like kitten, options end at `--` or the first positional argument, e.g. launch's
+        // program
+        options_ended |= !positional.is_empty();
+        match arg.strip_prefix("--").filter(|_| !options_ended) {
+            Some(option) => {
+                let (name, value) = match option.split_once('=') {
+                    Some((name, value)) => (name, Value::from(value)),
+                    None => (option, Value::Bool(true)),
+                };
+                add_option(&mut payload, &name.replace('-', "_"), value);
+            }
+            None => positional.push(Value::from(arg)),
+        }
+    }
+    payload.insert("args".to_string(), Value::Array(positional));
+
+    if payload.get("stdin") == Some(&Value::Bool(true)) {
+        let mut data = String::new();
+        io::stdin()
+            .read_to_string(&mut data)
+            .map_err(|e| e.to_string())?;
+        payload.insert("data".to_string(), Value::from(data));
+    }
+
+    let to = to
+        .or_else(|| std::env::var("KITTY_LISTEN_ON").ok())
+        .ok_or("no socket given with --to and KITTY_LISTEN_ON is not set")?;
+    let path = to
+        .strip_prefix("unix:")
+        .ok_or_else(|| format!("only unix sockets are supported, not {}", to))?;
+    let mut stream =
+        UnixStream::connect(path).map_err(|e| format!("Failed to connect to {}: {}", to, e))?;
+
+    let request = json!({
+        "cmd": command,
+        "version": FAKE_KITTY_VERSION,
+        "no_response": false,
+        "payload": payload,
+    });
+    protocol::write_message(&mut stream, &request).map_err(|e| e.to_string())?;
+    stream.flush().map_err(|e| e.to_string())?;
+
+    let response = protocol::read_message(&mut stream).map_err(|e| e.to_string())?;
+    if response["ok"].as_bool() == Some(true) {
+        Ok(response["data"].as_str().map(str::to_string))
+    } else {
+        Err(response["error"]
+            .as_str()
+            .unwrap_or("kitty reported an error")
+            .to_string())
+    }
+}
+
+/// Add an option to the payload, turning repeated options into a list
+fn add_option(payload: &mut Map<String, Value>, name: &str, value: Value) {
+    match payload.get_mut(name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+        None => {
+            payload.insert(name.to_string(), value);
+        }
+    }
+}
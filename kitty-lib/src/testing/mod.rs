@@ -0,0 +1,16 @@
+//! A fake kitty for hermetic tests, enabled with the `test-support` feature
+//!
+//! [`FakeKitty`] speaks kitty's remote control protocol on a unix socket and the
+//! `fake-kitten` binary target ([`run_kitten_shim`]) stands in for `kitten @`, so tests can
+//! drive the real [`KittyExecutor`](crate::KittyExecutor) without a kitty install.
+//! [`ExecutorContract`] holds the checks every executor has to pass, to run against the mock,
+//! the fake and a real kitty alike.
+
+mod contract;
+mod fake_kitty;
+mod kitten_shim;
+pub mod protocol;
+
+pub use contract::ExecutorContract;
+pub use fake_kitty::FakeKitty;
+pub use kitten_shim::run_kitten_shim;
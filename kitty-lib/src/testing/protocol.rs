@@ -0,0 +1,58 @@
+//! Framing of kitty's remote control messages
+//!
+//! Requests and responses are JSON wrapped in a DCS escape: `ESC P @kitty-cmd <json> ESC \`.
+//! Passwords and encryption are not implemented.
+
+use serde_json::Value;
+use std::io::{self, BufRead, BufReader, Read, Write};
+
+/// kitty version the fake server and shim report
+pub const FAKE_KITTY_VERSION: [u32; 3] = [0, 39, 1];
+
+const PREFIX: &[u8] = b"\x1bP@kitty-cmd";
+const SUFFIX: &[u8] = b"\x1b\\";
+
+/// Write `message` as one framed remote control message
+pub fn write_message(mut writer: impl Write, message: &Value) -> io::Result<()> {
+    writer.write_all(PREFIX)?;
+    serde_json::to_writer(&mut writer, message)?;
+    writer.write_all(SUFFIX)?;
+    writer.flush()
+}
+
+/// Read one framed remote control message
+///
+/// Input is buffered, so anything after the message is lost; kitty sends one per connection.
+pub fn read_message(reader: impl Read) -> io::Result<Value> {
+    let mut reader = BufReader::new(reader);
+    let mut buffer = Vec::new();
+    while !buffer.ends_with(SUFFIX) && reader.read_until(b'\\', &mut buffer)? > 0 {}
+
+    let json = buffer
+        .strip_prefix(PREFIX)
+        .and_then(|rest| rest.strip_suffix(SUFFIX))
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "not a kitty remote control message",
+            )
+        })?;
+    Ok(serde_json::from_slice(json)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_messages_round_trip() {
+        let message = json!({"cmd": "ls", "payload": {"match": "title:\u{1b}odd"}});
+        let mut buffer = Vec::new();
+        write_message(&mut buffer, &message).unwrap();
+
+        assert!(buffer.starts_with(b"\x1bP@kitty-cmd{"));
+        assert_eq!(read_message(buffer.as_slice()).unwrap(), message);
+        assert!(read_message(&b"{\"cmd\": \"ls\"}"[..]).is_err());
+    }
+}
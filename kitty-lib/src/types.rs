@@ -1,10 +1,84 @@
-use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+macro_rules! kitty_id {
+    ($(#[$meta:meta])* $name:ident) => {
+        $(#[$meta])*
+        #[derive(
+            Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+        )]
+        #[serde(transparent)]
+        pub struct $name(pub u32);
+
+        impl $name {
+            pub fn get(self) -> u32 {
+                self.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                self.0.fmt(f)
+            }
+        }
+
+        impl From<u32> for $name {
+            fn from(id: u32) -> Self {
+                Self(id)
+            }
+        }
+
+        impl From<$name> for u32 {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl PartialEq<u32> for $name {
+            fn eq(&self, other: &u32) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = std::num::ParseIntError;
+
+            fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+                s.trim().parse().map(Self)
+            }
+        }
+    };
+}
+
+kitty_id!(
+    /// ID of a kitty window, as used in `id:<n>` matches and `KITTY_WINDOW_ID`
+    WindowId
+);
+kitty_id!(
+    /// ID of a kitty tab
+    TabId
+);
+kitty_id!(
+    /// ID of a kitty OS window
+    OsWindowId
+);
+
+/// A process running in the foreground of a window's terminal
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct KittyProcess {
+    pub pid: u32,
+    #[serde(default)]
+    pub cmdline: Vec<String>,
+    /// Missing when kitty cannot read the process' working directory
+    #[serde(default)]
+    pub cwd: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct KittyWindow {
-    pub id: u32,
+    pub id: WindowId,
     pub title: String,
     pub pid: u32,
     pub cwd: String,
@@ -19,11 +93,34 @@ pub struct KittyWindow {
     pub num: Option<u32>,
     #[serde(default)]
     pub recent: Option<u32>,
+    #[serde(default)]
+    pub user_vars: HashMap<String, String>,
+    /// Whether this is the active window of its tab
+    #[serde(default)]
+    pub is_active: bool,
+    /// Whether this window has keyboard focus
+    #[serde(default)]
+    pub is_focused: bool,
+    /// Processes in the foreground of the window's terminal, e.g. the editor started from the shell
+    #[serde(default)]
+    pub foreground_processes: Vec<KittyProcess>,
+    /// Whether the shell is showing its prompt; needs shell integration
+    #[serde(default)]
+    pub at_prompt: bool,
+    /// The command line the shell last reported running; needs shell integration
+    #[serde(default)]
+    pub last_reported_cmdline: Option<String>,
+    /// Width of the window in cells
+    #[serde(default)]
+    pub columns: u32,
+    /// Height of the window in cells
+    #[serde(default)]
+    pub lines: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct KittyTab {
-    pub id: u32,
+    pub id: TabId,
     #[serde(default)]
     pub index: Option<u32>,
     pub title: String,
@@ -36,73 +133,41 @@ pub struct KittyTab {
     pub state: Option<String>,
     #[serde(default)]
     pub recent: Option<u32>,
+    /// Name of the tab's current layout, e.g. `tall` or `splits`
+    #[serde(default)]
+    pub layout: Option<String>,
+    /// Layouts the tab cycles through
+    #[serde(default)]
+    pub enabled_layouts: Vec<String>,
+    /// Windows of the tab from least to most recently active
+    #[serde(default)]
+    pub active_window_history: Vec<WindowId>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct KittyOsWindow {
-    pub id: u32,
+    pub id: OsWindowId,
     pub tabs: Vec<KittyTab>,
     #[serde(default)]
     pub title: Option<String>,
     #[serde(default)]
     pub state: Option<String>,
+    /// Whether this OS window currently has keyboard focus
+    #[serde(default)]
+    pub is_focused: bool,
+    /// Whether this was the most recently focused OS window
+    #[serde(default)]
+    pub last_focused: bool,
+    /// The window system's id for the OS window (X11 window id, Cocoa window number); absent on
+    /// Wayland
+    #[serde(default)]
+    pub platform_window_id: Option<u64>,
 }
 
 pub type KittyLsResponse = Vec<KittyOsWindow>;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct KittyLaunchResponse {
-    pub tab_id: Option<u32>,
-    pub window_id: Option<u32>,
-}
-
-#[derive(Debug, Clone, PartialEq)]
-pub struct KittyCommandResult<T> {
-    pub success: bool,
-    pub data: Option<T>,
-    pub error_message: Option<String>,
-}
-
-impl<T> KittyCommandResult<T> {
-    pub fn success(data: T) -> Self {
-        Self {
-            success: true,
-            data: Some(data),
-            error_message: None,
-        }
-    }
-
-    pub fn success_empty() -> Self {
-        Self {
-            success: true,
-            data: None,
-            error_message: None,
-        }
-    }
-
-    pub fn error(message: impl Into<String>) -> Self {
-        Self {
-            success: false,
-            data: None,
-            error_message: Some(message.into()),
-        }
-    }
-
-    pub fn is_success(&self) -> bool {
-        self.success
-    }
-
-    pub fn into_result(self) -> Result<T> {
-        if self.success {
-            match self.data {
-                Some(data) => Ok(data),
-                None => Err(anyhow::anyhow!("Command succeeded but no data returned")),
-            }
-        } else {
-            let error_msg = self
-                .error_message
-                .unwrap_or_else(|| "Command failed".to_string());
-            Err(anyhow::anyhow!(error_msg))
-        }
-    }
+    pub tab_id: Option<TabId>,
+    pub window_id: Option<WindowId>,
 }
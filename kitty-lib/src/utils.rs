@@ -1,32 +1,10 @@
-use log::{debug, warn};
-use std::env;
-use std::process::Command;
+/// User variable marking the session a window belongs to; unlike the `session:` tab title
+/// prefix it survives programs and shell integration retitling the tab
+pub const SESSION_USER_VAR: &str = "ksm_session";
 
-pub fn get_kitty_socket() -> String {
-    if let Ok(socket) = env::var("KITTY_LISTEN_ON") {
-        debug!("Using KITTY_LISTEN_ON environment variable: {socket}");
-        return socket;
-    }
+/// Tab title prefix marking a session tab, as in `session:<name> - description`
+pub const SESSION_TITLE_PREFIX: &str = "session:";
 
-    debug!("KITTY_LISTEN_ON not set, searching for socket files");
-
-    // Find socket file
-    if let Ok(output) = Command::new("sh")
-        .arg("-c")
-        .arg("ls /tmp/mykitty* 2>/dev/null | head -1")
-        .output()
-    {
-        if let Ok(socket_file) = String::from_utf8(output.stdout) {
-            let socket_file = socket_file.trim();
-            if !socket_file.is_empty() {
-                let socket_path = format!("unix:{}", socket_file);
-                debug!("Found socket file: {}", socket_path);
-                return socket_path;
-            }
-        }
-    }
-
-    let default_socket = "unix:/tmp/mykitty".to_string();
-    warn!("No socket file found, using default: {}", default_socket);
-    default_socket
-}
+/// Environment variable ksm sets on session windows, kept for sessions created before user
+/// variables were used
+pub const SESSION_PROJECT_ENV: &str = "KITTY_SESSION_PROJECT";
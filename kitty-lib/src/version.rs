@@ -0,0 +1,281 @@
+//! kitty versions, and the features kitty-lib uses that older kitty releases lack
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use std::fmt;
+
+use crate::error::{KittyError, KittyResult};
+
+/// A kitty release, e.g. 0.35.2
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KittyVersion {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl KittyVersion {
+    pub const fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+        }
+    }
+
+    /// Find the version in `kitty --version` or `kitten --version` output, e.g.
+    /// `kitten 0.35.2 created by Kovid Goyal`, or in a bare `0.35.2`
+    pub fn parse(text: &str) -> Option<Self> {
+        text.split_whitespace().find_map(|word| {
+            let mut parts = word.split('.');
+            let major = parts.next()?.parse().ok()?;
+            let minor = parts.next()?.parse().ok()?;
+            let patch = match parts.next() {
+                Some(patch) => patch.parse().ok()?,
+                None => 0,
+            };
+            parts
+                .next()
+                .is_none()
+                .then_some(Self::new(major, minor, patch))
+        })
+    }
+}
+
+impl fmt::Display for KittyVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// Stored as its `0.35.2` text, e.g. in a [`Recording`](crate::Recording)
+impl Serialize for KittyVersion {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for KittyVersion {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        Self::parse(&text)
+            .ok_or_else(|| de::Error::custom(format!("invalid kitty version: {text:?}")))
+    }
+}
+
+/// A kitty feature that only newer releases have
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Capability {
+    /// `launch` printing the id of the window it opened
+    LaunchWindowId,
+    /// User variables: `var:` in match expressions, `launch --var` and `set-user-vars`
+    UserVars,
+    /// `ls --match` and `--match-tab`, listing only the matched windows or tabs
+    LsMatch,
+    /// The `tab_bar_filter` option, showing only some tabs in the tab bar
+    TabBarFilter,
+}
+
+impl Capability {
+    /// The first kitty release with the feature
+    pub const fn since(self) -> KittyVersion {
+        match self {
+            Self::LaunchWindowId => KittyVersion::new(0, 17, 0),
+            Self::UserVars => KittyVersion::new(0, 28, 0),
+            Self::LsMatch => KittyVersion::new(0, 30, 0),
+            Self::TabBarFilter => KittyVersion::new(0, 43, 0),
+        }
+    }
+
+    /// What the feature is called in error messages
+    pub const fn description(self) -> &'static str {
+        match self {
+            Self::LaunchWindowId => "launch reporting the new window id",
+            Self::UserVars => "user variables (var: matching, launch --var, set-user-vars)",
+            Self::LsMatch => "ls --match and --match-tab",
+            Self::TabBarFilter => "the tab_bar_filter option",
+        }
+    }
+
+    /// The features a `kitten @ <command> <args>` call relies on
+    pub fn required_by(command: &str, args: &[String]) -> Vec<Self> {
+        let mut required = Vec::new();
+        let mut require = |capability| {
+            if !required.contains(&capability) {
+                required.push(capability);
+            }
+        };
+
+        if command == "set-user-vars" {
+            require(Self::UserVars);
+        }
+        // Options end at `--`, after which come launch's program args
+        for arg in args.iter().take_while(|arg| *arg != "--") {
+            if arg.starts_with("--var=") {
+                require(Self::UserVars);
+            }
+
+            let Some(expression) = arg
+                .strip_prefix("--match=")
+                .or_else(|| arg.strip_prefix("--match-tab="))
+            else {
+                continue;
+            };
+            if command == "ls" {
+                require(Self::LsMatch);
+            }
+            if expression
+                .split(|c: char| c.is_whitespace() || c == '(')
+                .any(|term| term.starts_with("var:"))
+            {
+                require(Self::UserVars);
+            }
+        }
+        required
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+/// What a running kitty can do, going by its version
+///
+/// When the version is unknown every feature is assumed to be there, so callers try and let
+/// kitty have the final word.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KittyCapabilities {
+    version: Option<KittyVersion>,
+}
+
+impl KittyCapabilities {
+    pub fn of(version: KittyVersion) -> Self {
+        Self {
+            version: Some(version),
+        }
+    }
+
+    /// Capabilities of a kitty whose version could not be found out
+    pub fn unknown() -> Self {
+        Self::default()
+    }
+
+    pub fn version(&self) -> Option<KittyVersion> {
+        self.version
+    }
+
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.version
+            .is_none_or(|version| version >= capability.since())
+    }
+
+    /// Fail with [`KittyError::Unsupported`] when kitty is too old for `capability`
+    pub fn require(&self, capability: Capability) -> KittyResult<()> {
+        match self.version {
+            Some(found) if found < capability.since() => {
+                Err(KittyError::Unsupported { capability, found })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail for the first feature a `kitten @ <command> <args>` call needs that kitty lacks
+    pub fn require_for(&self, command: &str, args: &[String]) -> KittyResult<()> {
+        Capability::required_by(command, args)
+            .into_iter()
+            .try_for_each(|capability| self.require(capability))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_finds_the_version_in_version_output() {
+        assert_eq!(
+            KittyVersion::parse("kitten 0.35.2 created by Kovid Goyal\n"),
+            Some(KittyVersion::new(0, 35, 2))
+        );
+        assert_eq!(
+            KittyVersion::parse("kitty 0.26.5 created by Kovid Goyal"),
+            Some(KittyVersion::new(0, 26, 5))
+        );
+        assert_eq!(
+            KittyVersion::parse("0.40"),
+            Some(KittyVersion::new(0, 40, 0))
+        );
+        assert_eq!(KittyVersion::parse("kitten nightly"), None);
+        assert_eq!(KittyVersion::parse("1.2.3.4"), None);
+    }
+
+    #[test]
+    fn test_versions_order_numerically() {
+        assert!(KittyVersion::new(0, 9, 0) < KittyVersion::new(0, 10, 0));
+        assert!(KittyVersion::new(0, 28, 1) > KittyVersion::new(0, 28, 0));
+        assert_eq!(KittyVersion::new(0, 35, 2).to_string(), "0.35.2");
+    }
+
+    #[test]
+    fn test_required_by_reads_commands_and_match_expressions() {
+        assert_eq!(
+            Capability::required_by("ls", &args(&["--match-tab=var:ksm_session=api"])),
+            [Capability::LsMatch, Capability::UserVars]
+        );
+        assert_eq!(
+            Capability::required_by("ls", &args(&["--match-tab=title:envvar:x"])),
+            [Capability::LsMatch]
+        );
+        assert_eq!(
+            Capability::required_by("focus-tab", &args(&["--match=id:3 or (var:a=b)"])),
+            [Capability::UserVars]
+        );
+        assert_eq!(
+            Capability::required_by("launch", &args(&["--type=tab", "--var=ksm_session=api"])),
+            [Capability::UserVars]
+        );
+        assert!(
+            Capability::required_by("launch", &args(&["--type=tab", "--", "env", "--var=x"]))
+                .is_empty()
+        );
+        assert_eq!(
+            Capability::required_by("set-user-vars", &args(&["--match=id:1", "a=b"])),
+            [Capability::UserVars]
+        );
+        assert!(Capability::required_by("ls", &[]).is_empty());
+        assert!(Capability::required_by("focus-tab", &args(&["--match=id:3"])).is_empty());
+    }
+
+    #[test]
+    fn test_require_names_the_release_to_upgrade_to() {
+        let old = KittyCapabilities::of(KittyVersion::new(0, 26, 5));
+        assert!(!old.supports(Capability::UserVars));
+        assert!(old.supports(Capability::LaunchWindowId));
+
+        let error = old.require(Capability::UserVars).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "kitty 0.26.5 lacks user variables (var: matching, launch --var, set-user-vars); upgrade kitty to 0.28.0 or newer"
+        );
+        assert!(
+            old.require_for("ls", &args(&["--match-tab=var:ksm_session=api"]))
+                .is_err()
+        );
+
+        let current = KittyCapabilities::of(KittyVersion::new(0, 43, 1));
+        assert!(current.require(Capability::TabBarFilter).is_ok());
+    }
+
+    #[test]
+    fn test_unknown_versions_support_everything() {
+        let unknown = KittyCapabilities::unknown();
+        assert_eq!(unknown.version(), None);
+        assert!(unknown.supports(Capability::TabBarFilter));
+        assert!(unknown.require(Capability::UserVars).is_ok());
+    }
+}
@@ -850,10 +850,10 @@ pub fn compare_screenshots(
     log::debug!("Screenshot comparison score: {:.4}", similarity);
 
     // Generate diff image if similarity is low
-    if similarity < 0.95 {
-        if let Err(e) = generate_diff_image(&actual_rgba, &expected_rgba, actual_path) {
-            log::warn!("Failed to generate diff image: {}", e);
-        }
+    if similarity < 0.95
+        && let Err(e) = generate_diff_image(&actual_rgba, &expected_rgba, actual_path)
+    {
+        log::warn!("Failed to generate diff image: {}", e);
     }
 
     Ok(similarity)
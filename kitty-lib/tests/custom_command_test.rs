@@ -0,0 +1,109 @@
+use kitty_lib::{
+    CommandExecutor, ExecutedCommand, KittenCommand, KittenLsCommand, KittyError, KittyResult,
+    MockExecutor, WindowId,
+};
+
+/// `kitten @ scroll-window`, which kitty-lib doesn't wrap
+struct ScrollWindow {
+    window_id: WindowId,
+    amount: String,
+}
+
+impl KittenCommand for ScrollWindow {
+    type Response = ();
+
+    fn name(&self) -> &'static str {
+        "scroll-window"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        Ok(vec![
+            format!("--match=id:{}", self.window_id),
+            self.amount.clone(),
+        ])
+    }
+
+    fn parse_response(&self, _stdout: &[u8]) -> KittyResult<()> {
+        Ok(())
+    }
+}
+
+/// `kitten @ get-colors`, answering with the `name value` pairs kitten prints
+struct GetColors;
+
+impl KittenCommand for GetColors {
+    type Response = Vec<(String, String)>;
+
+    fn name(&self) -> &'static str {
+        "get-colors"
+    }
+
+    fn args(&self) -> KittyResult<Vec<String>> {
+        Ok(Vec::new())
+    }
+
+    fn is_idempotent(&self) -> bool {
+        true
+    }
+
+    fn parse_response(&self, stdout: &[u8]) -> KittyResult<Self::Response> {
+        String::from_utf8_lossy(stdout)
+            .lines()
+            .map(|line| {
+                line.split_once(char::is_whitespace)
+                    .map(|(name, value)| (name.to_string(), value.trim().to_string()))
+                    .ok_or_else(|| KittyError::InvalidResponse {
+                        command: "get-colors".to_string(),
+                        message: format!("unexpected line '{}'", line),
+                    })
+            })
+            .collect()
+    }
+}
+
+#[test]
+fn test_custom_command_through_mock() {
+    let mock = MockExecutor::new();
+    let executor = &mock;
+
+    executor
+        .execute(ScrollWindow {
+            window_id: WindowId(4),
+            amount: "2p-".to_string(),
+        })
+        .unwrap();
+
+    assert_eq!(
+        mock.get_execute_calls(),
+        vec![ExecutedCommand {
+            name: "scroll-window".to_string(),
+            args: vec!["--match=id:4".to_string(), "2p-".to_string()],
+            stdin: None,
+        }]
+    );
+}
+
+#[test]
+fn test_custom_command_parses_its_response() {
+    let mock = MockExecutor::new();
+    let executor = &mock;
+
+    mock.expect_execute_response(Ok("foreground #dddddd\nbackground #000000\n".to_string()));
+    let colors = executor.execute(GetColors).unwrap();
+    assert_eq!(colors[1], ("background".to_string(), "#000000".to_string()));
+
+    mock.expect_execute_response(Ok("garbage".to_string()));
+    let error = executor.execute(GetColors).unwrap_err();
+    assert!(matches!(error, KittyError::InvalidResponse { .. }));
+}
+
+#[test]
+fn test_builtin_commands_are_kitten_commands() {
+    let ls = KittenLsCommand::new().match_tab_title("session:api");
+    assert_eq!(ls.name(), "ls");
+    assert_eq!(ls.args().unwrap(), vec!["--match-tab=title:session:api"]);
+    assert!(ls.is_idempotent());
+
+    let response = ls.parse_response(b"[]").unwrap();
+    assert!(response.is_empty());
+}
@@ -0,0 +1,99 @@
+#![cfg(feature = "test-support")]
+
+mod common;
+
+use common::KittyTestHarness;
+use kitty_lib::testing::{ExecutorContract, FakeKitty};
+use kitty_lib::{KittyExecutor, MockExecutor};
+
+const FAKE_KITTEN: &str = env!("CARGO_BIN_EXE_fake-kitten");
+
+fn mock_contract() -> ExecutorContract<MockExecutor> {
+    ExecutorContract::new(MockExecutor::new())
+}
+
+#[test]
+fn test_mock_launched_tabs_are_listed() {
+    mock_contract().launched_tabs_are_listed();
+}
+
+#[test]
+fn test_mock_focus_moves_the_active_tab() {
+    mock_contract().focus_moves_the_active_tab();
+}
+
+#[test]
+fn test_mock_closed_tabs_are_gone() {
+    mock_contract().closed_tabs_are_gone();
+}
+
+#[test]
+fn test_mock_title_matches_are_regular_expressions() {
+    mock_contract().title_matches_are_regular_expressions();
+}
+
+#[test]
+fn test_mock_env_matches_select_launched_windows() {
+    mock_contract().env_matches_select_launched_windows();
+}
+
+#[test]
+fn test_mock_passes_the_whole_contract_on_one_layout() {
+    let mock = MockExecutor::new();
+    let contract = ExecutorContract::new(&mock);
+    contract.run_all();
+    contract.run_all();
+
+    assert!(
+        mock.layout.borrow().get_all_tabs().is_empty(),
+        "every check should close the tabs it launched"
+    );
+}
+
+#[test]
+#[should_panic(expected = "should be active")]
+fn test_contract_catches_an_executor_that_ignores_focus() {
+    let mock = MockExecutor::new();
+    mock.expect_focus_tab_response(Ok(()));
+    ExecutorContract::new(&mock).focus_moves_the_active_tab();
+}
+
+#[test]
+fn test_kitty_executor_against_fake_kitty() {
+    let kitty = FakeKitty::start(FAKE_KITTEN).expect("fake kitty should start");
+    ExecutorContract::new(kitty.executor()).run_all();
+}
+
+#[tokio::test]
+async fn test_kitty_executor_against_real_kitty() -> Result<(), Box<dyn std::error::Error>> {
+    let kitty_available = tokio::process::Command::new("kitty")
+        .arg("--version")
+        .output()
+        .await
+        .map(|output| output.status.success())
+        .unwrap_or(false);
+    if !kitty_available {
+        println!("Skipping test_kitty_executor_against_real_kitty: Kitty not available");
+        return Ok(());
+    }
+
+    let harness =
+        match KittyTestHarness::launch_with_test_name("test_kitty_executor_contract").await {
+            Ok(harness) => harness,
+            Err(e) if std::env::var("KSM_TEST_FAIL_ON_LAUNCH_ERROR").unwrap_or_default() == "1" => {
+                return Err(format!("Failed to launch Kitty: {}", e).into());
+            }
+            Err(e) => {
+                println!(
+                    "Skipping test_kitty_executor_against_real_kitty due to launch failure: {}",
+                    e
+                );
+                return Ok(());
+            }
+        };
+
+    let socket = format!("unix:{}", harness.socket_path().display());
+    ExecutorContract::new(KittyExecutor::with_socket(socket)).run_all();
+
+    harness.cleanup().await
+}
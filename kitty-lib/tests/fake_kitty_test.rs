@@ -0,0 +1,299 @@
+#![cfg(feature = "test-support")]
+
+use kitty_lib::testing::FakeKitty;
+use kitty_lib::{
+    Capability, CommandExecutor, DryRunExecutor, KittenCloseTabCommand, KittenCommand,
+    KittenFocusTabCommand, KittenGetTextCommand, KittenLaunchCommand, KittenLsCommand,
+    KittenSendTextCommand, KittenSetTabTitleCommand, KittyError, KittyExecutor, KittyVersion,
+};
+use std::process::{Command, Stdio};
+
+const FAKE_KITTEN: &str = env!("CARGO_BIN_EXE_fake-kitten");
+
+fn start() -> FakeKitty {
+    FakeKitty::start(FAKE_KITTEN).expect("fake kitty should start")
+}
+
+#[test]
+fn test_launch_and_match_tabs_through_the_real_executor() {
+    let kitty = start();
+    let executor = kitty.executor();
+
+    let launched = executor
+        .launch(
+            KittenLaunchCommand::new()
+                .tab_title("api")
+                .env("KITTY_SESSION_PROJECT", "api")
+                .var("ksm_session", "api")
+                .cwd("/work/api")
+                .args(["nvim", "--clean"]),
+        )
+        .unwrap();
+    let window_id = launched.window_id.expect("launch should report a window");
+
+    let by_var = executor
+        .ls(KittenLsCommand::new().match_tab_var("ksm_session", "api"))
+        .unwrap();
+    let tab = &by_var[0].tabs[0];
+    assert_eq!(tab.title, "api");
+    assert_eq!(tab.windows[0].id, window_id);
+    assert_eq!(tab.windows[0].cwd, "/work/api");
+    assert_eq!(tab.windows[0].cmdline, ["nvim", "--clean"]);
+
+    let by_title = executor
+        .ls(KittenLsCommand::new().match_tab_title("api"))
+        .unwrap();
+    assert_eq!(by_title[0].tabs[0].id, tab.id);
+
+    let missing = executor.ls(KittenLsCommand::new().match_tab_var("ksm_session", "web"));
+    assert!(matches!(missing, Err(KittyError::NoMatch { .. })));
+}
+
+#[test]
+fn test_launch_program_args_are_not_taken_for_options() {
+    let kitty = start();
+    let executor = kitty.executor();
+    let command = KittenLaunchCommand::new()
+        .tab_title("logs")
+        .args(["--hold", "tail", "-f"]);
+
+    let args = KittenCommand::args(&command).unwrap();
+    assert_eq!(args[args.len() - 4..], ["--", "--hold", "tail", "-f"]);
+    assert!(
+        !KittenCommand::args(&KittenLaunchCommand::new())
+            .unwrap()
+            .contains(&"--".to_string())
+    );
+
+    executor.launch(command).unwrap();
+    let tabs = kitty.mock().layout.borrow().get_all_tabs();
+    let window = &tabs.last().unwrap().windows[0];
+    assert_eq!(window.cmdline, ["--hold", "tail", "-f"]);
+}
+
+#[test]
+fn test_tab_commands_change_the_fake_layout() {
+    let kitty = start();
+    let (first, second) = {
+        let mock = kitty.mock();
+        let mut layout = mock.layout.borrow_mut();
+        (
+            layout.add_tab_with_session("api", Some("api".to_string())),
+            layout.add_tab_with_session("web", Some("web".to_string())),
+        )
+    };
+    let executor = kitty.executor();
+
+    executor
+        .focus_tab(KittenFocusTabCommand::new(first))
+        .unwrap();
+    assert_eq!(kitty.mock().layout.borrow().active_tab_id, Some(first));
+
+    executor
+        .set_tab_title(KittenSetTabTitleCommand::new("api (main)").for_tab_id(first))
+        .unwrap();
+    executor
+        .close_tab(KittenCloseTabCommand::new(second))
+        .unwrap();
+
+    let tabs = kitty.mock().layout.borrow().get_all_tabs();
+    assert_eq!(tabs.len(), 1);
+    assert_eq!(tabs[0].title, "api (main)");
+
+    let error = executor
+        .focus_tab(KittenFocusTabCommand::new(second))
+        .unwrap_err();
+    assert!(matches!(error, KittyError::NoMatch { .. }), "{:?}", error);
+}
+
+#[test]
+fn test_send_text_over_stdin_and_get_text() {
+    let kitty = start();
+    let window_id = {
+        let mock = kitty.mock();
+        let mut layout = mock.layout.borrow_mut();
+        layout.add_tab_with_session("api", None);
+        let window_id = layout.active_window_id().unwrap();
+        layout
+            .window_text
+            .insert(window_id, "$ cargo build\n   Finished".to_string());
+        window_id
+    };
+    let executor = kitty.executor();
+
+    executor
+        .send_text(
+            KittenSendTextCommand::new("cargo test --workspace\n")
+                .for_window_id(window_id)
+                .from_stdin(),
+        )
+        .unwrap();
+
+    let sent = kitty.mock().layout.borrow().sent_text.clone();
+    assert_eq!(sent, [(window_id, "cargo test --workspace\n".to_string())]);
+
+    let text = executor
+        .get_text(KittenGetTextCommand::new().for_window_id(window_id))
+        .unwrap();
+    assert_eq!(text, "$ cargo build\n   Finished");
+}
+
+#[test]
+fn test_send_text_starting_with_a_dash_is_not_an_option() {
+    let kitty = start();
+    let window_id = {
+        let mock = kitty.mock();
+        mock.add_session_tab("api", None);
+        mock.layout.borrow().active_window_id().unwrap()
+    };
+
+    kitty
+        .executor()
+        .send_text(KittenSendTextCommand::new("--help\n").for_window_id(window_id))
+        .unwrap();
+
+    let sent = kitty.mock().layout.borrow().sent_text.clone();
+    assert_eq!(sent, [(window_id, "--help\n".to_string())]);
+}
+
+#[test]
+fn test_unknown_commands_fail_like_kitten() {
+    let kitty = start();
+
+    let output = Command::new(kitty.bin_dir().join("kitten"))
+        .args(["@", &format!("--to={}", kitty.address()), "scroll-window"])
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        String::from_utf8_lossy(&output.stderr).starts_with("Error: Unknown remote control"),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
+
+#[test]
+fn test_kitty_version_is_probed_once_per_socket() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let kitty = start();
+    // Stands in for kitty's process: a fake kitten waiting on stdin, which reports the fake
+    // kitty's version when its binary is asked
+    let mut kitty_process = Command::new(FAKE_KITTEN)
+        .args(["@", "send-text", "--stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+    {
+        let mock = kitty.mock();
+        let tab_id = mock.add_session_tab("api", None);
+        mock.layout.borrow_mut().extend_tab_env(
+            tab_id,
+            &[("KITTY_PID".to_string(), kitty_process.id().to_string())],
+        );
+    }
+
+    // The local kitten belongs to an older install and must not be asked
+    let dir = tempfile::tempdir().unwrap();
+    let kitten = dir.path().join("kitten");
+    std::fs::write(
+        &kitten,
+        format!(
+            "#!/bin/sh\n[ \"$1\" = --version ] && {{ echo 'kitten 0.26.5'; exit; }}\nexec '{}' \"$@\"\n",
+            FAKE_KITTEN
+        ),
+    )
+    .unwrap();
+    std::fs::set_permissions(&kitten, std::fs::Permissions::from_mode(0o755)).unwrap();
+    let executor = KittyExecutor::with_socket(kitty.address()).with_kitten(&kitten);
+
+    assert_eq!(
+        executor.kitty_version().unwrap(),
+        KittyVersion::new(0, 39, 1)
+    );
+    let capabilities = executor.capabilities().unwrap();
+    assert!(capabilities.supports(Capability::LsMatch));
+    assert!(!capabilities.supports(Capability::TabBarFilter));
+
+    // kitty and its process are gone, so this answer can only come from the cache
+    kitty_process.kill().unwrap();
+    kitty_process.wait().unwrap();
+    drop(kitty);
+    assert_eq!(
+        executor.kitty_version().unwrap(),
+        KittyVersion::new(0, 39, 1)
+    );
+}
+
+#[test]
+fn test_stopped_server_is_unreachable() {
+    let kitty = start();
+    let address = kitty.address();
+    let kitten = kitty.bin_dir().join("kitten");
+    let executor = KittyExecutor::with_socket(&address).with_kitten(FAKE_KITTEN);
+    assert!(executor.ls(KittenLsCommand::new()).is_ok());
+    assert!(kitten.exists());
+
+    drop(kitty);
+
+    let error = executor.ls(KittenLsCommand::new()).unwrap_err();
+    assert!(
+        matches!(error, KittyError::SocketUnreachable { .. }),
+        "{:?}",
+        error
+    );
+    assert!(!kitten.exists());
+}
+
+#[test]
+fn test_dry_run_script_replays_against_kitty() {
+    let kitty = start();
+    let existing = kitty
+        .mock()
+        .layout
+        .borrow_mut()
+        .add_tab_with_session("api", None);
+    let dry_run = DryRunExecutor::new(kitty.executor());
+
+    let listed = dry_run.ls(KittenLsCommand::new()).unwrap();
+    assert_eq!(listed[0].tabs[0].id, existing);
+    let launched = dry_run
+        .launch(KittenLaunchCommand::new().tab_title("web's tab"))
+        .unwrap();
+    dry_run
+        .send_text(
+            KittenSendTextCommand::new("echo 'hi' \"there\"\n")
+                .for_window_id(launched.window_id.unwrap())
+                .from_stdin(),
+        )
+        .unwrap();
+    dry_run
+        .close_tab(KittenCloseTabCommand::new(existing))
+        .unwrap();
+
+    assert_eq!(kitty.mock().layout.borrow().get_all_tabs().len(), 1);
+    assert_eq!(kitty.mock().launch_call_count(), 0);
+
+    let output = Command::new("sh")
+        .arg("-c")
+        .arg(dry_run.script())
+        .output()
+        .unwrap();
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let layout = kitty.mock().layout.borrow().clone();
+    let tabs = layout.get_all_tabs();
+    assert_eq!(tabs.len(), 1);
+    assert_eq!(tabs[0].title, "web's tab");
+    assert_eq!(
+        layout.sent_text,
+        [(tabs[0].windows[0].id, "echo 'hi' \"there\"\n".to_string())]
+    );
+}
@@ -203,16 +203,13 @@ async fn test_session_aware_tab_navigation() -> Result<(), Box<dyn std::error::E
                             if let Some(windows) = tab.get("windows").and_then(|w| w.as_array()) {
                                 for window in windows {
                                     if let Some(env) = window.get("env").and_then(|e| e.as_object())
-                                    {
-                                        if let Some(session_value) = env
+                                        && let Some(session_value) = env
                                             .get("KITTY_SESSION_PROJECT")
                                             .and_then(|v| v.as_str())
-                                        {
-                                            if session_value == "test_session" {
-                                                session_tab_count += 1;
-                                                break; // Found matching window in this tab
-                                            }
-                                        }
+                                        && session_value == "test_session"
+                                    {
+                                        session_tab_count += 1;
+                                        break; // Found matching window in this tab
                                     }
                                 }
                             }
@@ -229,22 +226,19 @@ async fn test_session_aware_tab_navigation() -> Result<(), Box<dyn std::error::E
 
             // 8. Test tab focusing using session context
             // Focus on one of the session tabs
-            if let Some(os_windows) = session_json.as_array() {
-                if let Some(first_window) = os_windows.first() {
-                    if let Some(tabs) = first_window.get("tabs").and_then(|t| t.as_array()) {
-                        if let Some(first_tab) = tabs.first() {
-                            if let Some(tab_id) = first_tab.get("id").and_then(|id| id.as_u64()) {
-                                let focus_cmd = format!("focus-tab --match id:{}", tab_id);
-                                let focus_output = harness.execute_command(&focus_cmd).await?;
-                                println!("Focus tab output: {}", focus_output);
-                                // Focus command typically returns empty output on success
-
-                                // Wait a moment for the focus change to take effect
-                                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
-                            }
-                        }
-                    }
-                }
+            if let Some(os_windows) = session_json.as_array()
+                && let Some(first_window) = os_windows.first()
+                && let Some(tabs) = first_window.get("tabs").and_then(|t| t.as_array())
+                && let Some(first_tab) = tabs.first()
+                && let Some(tab_id) = first_tab.get("id").and_then(|id| id.as_u64())
+            {
+                let focus_cmd = format!("focus-tab --match id:{}", tab_id);
+                let focus_output = harness.execute_command(&focus_cmd).await?;
+                println!("Focus tab output: {}", focus_output);
+                // Focus command typically returns empty output on success
+
+                // Wait a moment for the focus change to take effect
+                tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
             }
 
             // Capture screenshot after navigation (baseline after navigation)
@@ -453,13 +447,11 @@ async fn test_navigation_wrap_around() -> Result<(), Box<dyn std::error::Error>>
                     if let Some(tabs) = os_window.get("tabs").and_then(|t| t.as_array()) {
                         for tab in tabs {
                             if let Some(is_active) = tab.get("is_active").and_then(|a| a.as_bool())
+                                && is_active
+                                && let Some(tab_id) = tab.get("id").and_then(|id| id.as_u64())
                             {
-                                if is_active {
-                                    if let Some(tab_id) = tab.get("id").and_then(|id| id.as_u64()) {
-                                        active_tab_id = Some(tab_id);
-                                        break;
-                                    }
-                                }
+                                active_tab_id = Some(tab_id);
+                                break;
                             }
                         }
                     }
@@ -490,13 +482,11 @@ async fn test_navigation_wrap_around() -> Result<(), Box<dyn std::error::Error>>
                     if let Some(tabs) = os_window.get("tabs").and_then(|t| t.as_array()) {
                         for tab in tabs {
                             if let Some(is_active) = tab.get("is_active").and_then(|a| a.as_bool())
+                                && is_active
+                                && let Some(tab_id) = tab.get("id").and_then(|id| id.as_u64())
                             {
-                                if is_active {
-                                    if let Some(tab_id) = tab.get("id").and_then(|id| id.as_u64()) {
-                                        final_active_tab_id = Some(tab_id);
-                                        break;
-                                    }
-                                }
+                                final_active_tab_id = Some(tab_id);
+                                break;
                             }
                         }
                     }
@@ -579,16 +569,13 @@ async fn test_session_context_detection() -> Result<(), Box<dyn std::error::Erro
                             if let Some(windows) = tab.get("windows").and_then(|w| w.as_array()) {
                                 for window in windows {
                                     if let Some(env) = window.get("env").and_then(|e| e.as_object())
-                                    {
-                                        if let Some(session_value) = env
+                                        && let Some(session_value) = env
                                             .get("KITTY_SESSION_PROJECT")
                                             .and_then(|v| v.as_str())
-                                        {
-                                            if session_value == "test_project" {
-                                                test_project_count += 1;
-                                                break; // Found matching window in this tab
-                                            }
-                                        }
+                                        && session_value == "test_project"
+                                    {
+                                        test_project_count += 1;
+                                        break; // Found matching window in this tab
                                     }
                                 }
                             }
@@ -619,16 +606,13 @@ async fn test_session_context_detection() -> Result<(), Box<dyn std::error::Erro
                             if let Some(windows) = tab.get("windows").and_then(|w| w.as_array()) {
                                 for window in windows {
                                     if let Some(env) = window.get("env").and_then(|e| e.as_object())
-                                    {
-                                        if let Some(session_value) = env
+                                        && let Some(session_value) = env
                                             .get("KITTY_SESSION_PROJECT")
                                             .and_then(|v| v.as_str())
-                                        {
-                                            if session_value == "other_project" {
-                                                other_project_count += 1;
-                                                break;
-                                            }
-                                        }
+                                        && session_value == "other_project"
+                                    {
+                                        other_project_count += 1;
+                                        break;
                                     }
                                 }
                             }
@@ -690,18 +674,16 @@ async fn test_session_context_detection() -> Result<(), Box<dyn std::error::Erro
                             if let Some(windows) = tab.get("windows").and_then(|w| w.as_array()) {
                                 for window in windows {
                                     if let Some(env) = window.get("env").and_then(|e| e.as_object())
-                                    {
-                                        if let Some(session_value) = env
+                                        && let Some(session_value) = env
                                             .get("KITTY_SESSION_PROJECT")
                                             .and_then(|v| v.as_str())
+                                    {
+                                        // Only count tabs with our specific session values
+                                        if session_value == "test_project"
+                                            || session_value == "other_project"
                                         {
-                                            // Only count tabs with our specific session values
-                                            if session_value == "test_project"
-                                                || session_value == "other_project"
-                                            {
-                                                has_session = true;
-                                                break;
-                                            }
+                                            has_session = true;
+                                            break;
                                         }
                                     }
                                 }
@@ -743,18 +725,14 @@ async fn test_session_context_detection() -> Result<(), Box<dyn std::error::Erro
             let session_tabs_json: serde_json::Value = serde_json::from_str(&session_tabs_output)?;
 
             // Focus on the first tab in the session
-            if let Some(os_windows) = session_tabs_json.as_array() {
-                if let Some(first_window) = os_windows.first() {
-                    if let Some(tabs) = first_window.get("tabs").and_then(|t| t.as_array()) {
-                        if let Some(first_tab) = tabs.first() {
-                            if let Some(tab_id) = first_tab.get("id").and_then(|id| id.as_u64()) {
-                                let focus_session_tab_cmd =
-                                    format!("focus-tab --match id:{}", tab_id);
-                                harness.execute_command(&focus_session_tab_cmd).await?;
-                            }
-                        }
-                    }
-                }
+            if let Some(os_windows) = session_tabs_json.as_array()
+                && let Some(first_window) = os_windows.first()
+                && let Some(tabs) = first_window.get("tabs").and_then(|t| t.as_array())
+                && let Some(first_tab) = tabs.first()
+                && let Some(tab_id) = first_tab.get("id").and_then(|id| id.as_u64())
+            {
+                let focus_session_tab_cmd = format!("focus-tab --match id:{}", tab_id);
+                harness.execute_command(&focus_session_tab_cmd).await?;
             }
             tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
@@ -768,26 +746,18 @@ async fn test_session_context_detection() -> Result<(), Box<dyn std::error::Erro
                     if let Some(tabs) = os_window.get("tabs").and_then(|t| t.as_array()) {
                         for tab in tabs {
                             if let Some(is_active) = tab.get("is_active").and_then(|a| a.as_bool())
+                                && is_active
+                                && let Some(windows) = tab.get("windows").and_then(|w| w.as_array())
                             {
-                                if is_active {
-                                    if let Some(windows) =
-                                        tab.get("windows").and_then(|w| w.as_array())
+                                for window in windows {
+                                    if let Some(env) = window.get("env").and_then(|e| e.as_object())
+                                        && let Some(session_value) = env
+                                            .get("KITTY_SESSION_PROJECT")
+                                            .and_then(|v| v.as_str())
+                                        && session_value == "test_project"
                                     {
-                                        for window in windows {
-                                            if let Some(env) =
-                                                window.get("env").and_then(|e| e.as_object())
-                                            {
-                                                if let Some(session_value) = env
-                                                    .get("KITTY_SESSION_PROJECT")
-                                                    .and_then(|v| v.as_str())
-                                                {
-                                                    if session_value == "test_project" {
-                                                        active_session_tab = true;
-                                                        break;
-                                                    }
-                                                }
-                                            }
-                                        }
+                                        active_session_tab = true;
+                                        break;
                                     }
                                 }
                             }
@@ -906,7 +876,7 @@ async fn test_kitty_executor_navigate_tab() -> Result<(), Box<dyn std::error::Er
             println!("Test harness launched successfully for KittyExecutor navigation test");
 
             // Create a KittyExecutor instance using the harness socket
-            use kitty_lib::commands::launch::KittenLaunchCommand;
+            use kitty_lib::commands::launch::{KittenLaunchCommand, LaunchType};
             use kitty_lib::commands::navigate_tab::KittenNavigateTabCommand;
             use kitty_lib::executor::{CommandExecutor, KittyExecutor};
 
@@ -926,12 +896,12 @@ async fn test_kitty_executor_navigate_tab() -> Result<(), Box<dyn std::error::Er
             // Create three tabs in the session
             for i in 1..=3 {
                 let launch_cmd = KittenLaunchCommand::new()
-                    .launch_type("tab")
+                    .launch_type(LaunchType::Tab)
                     .env("KITTY_SESSION_PROJECT", session_name)
                     .tab_title(&format!("Nav Test Tab {}", i));
 
-                let launch_result = executor.launch(launch_cmd)?;
-                assert!(launch_result.is_success(), "Failed to create tab {}", i);
+                let launch_result = executor.launch(launch_cmd);
+                assert!(launch_result.is_ok(), "Failed to create tab {}", i);
 
                 // Wait a bit between tab creation
                 tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
@@ -946,8 +916,8 @@ async fn test_kitty_executor_navigate_tab() -> Result<(), Box<dyn std::error::Er
                 .with_session(session_name.to_string())
                 .with_wrap(true);
 
-            let nav_result = executor.navigate_tab(nav_command)?;
-            assert!(nav_result.is_success(), "Forward navigation should succeed");
+            let nav_result = executor.navigate_tab(nav_command);
+            assert!(nav_result.is_ok(), "Forward navigation should succeed");
 
             // Test 2: Backward navigation with wrap
             println!("Test 2: Backward navigation with wrap");
@@ -955,49 +925,44 @@ async fn test_kitty_executor_navigate_tab() -> Result<(), Box<dyn std::error::Er
                 .with_session(session_name.to_string())
                 .with_wrap(true);
 
-            let nav_result = executor.navigate_tab(nav_command)?;
-            assert!(
-                nav_result.is_success(),
-                "Backward navigation should succeed"
-            );
+            let nav_result = executor.navigate_tab(nav_command);
+            assert!(nav_result.is_ok(), "Backward navigation should succeed");
 
             // Test 3: Navigation in non-existent session
             println!("Test 3: Navigation in non-existent session");
             let nav_command =
                 KittenNavigateTabCommand::next().with_session("nonexistent_session".to_string());
 
-            let nav_result = executor.navigate_tab(nav_command)?;
+            let nav_result = executor.navigate_tab(nav_command);
             assert!(
-                !nav_result.is_success(),
+                nav_result.is_err(),
                 "Navigation in non-existent session should fail"
             );
 
             // The error message should indicate no tabs found
-            if let Some(error_msg) = &nav_result.error_message {
-                assert!(
-                    error_msg.contains("No tabs found"),
-                    "Error should mention no tabs found"
-                );
-            }
+            assert!(
+                matches!(nav_result, Err(kitty_lib::KittyError::NoMatch { .. })),
+                "Error should mention no tabs found"
+            );
 
             // Test 4: Navigation with single tab
             println!("Test 4: Navigation with single tab in session");
             let single_session = "single_tab_session";
             let launch_cmd = KittenLaunchCommand::new()
-                .launch_type("tab")
+                .launch_type(LaunchType::Tab)
                 .env("KITTY_SESSION_PROJECT", single_session)
                 .tab_title("Single Tab");
 
-            let launch_result = executor.launch(launch_cmd)?;
-            assert!(launch_result.is_success(), "Failed to create single tab");
+            let launch_result = executor.launch(launch_cmd);
+            assert!(launch_result.is_ok(), "Failed to create single tab");
             tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
             let nav_command =
                 KittenNavigateTabCommand::next().with_session(single_session.to_string());
 
-            let nav_result = executor.navigate_tab(nav_command)?;
+            let nav_result = executor.navigate_tab(nav_command);
             assert!(
-                nav_result.is_success(),
+                nav_result.is_ok(),
                 "Navigation in single tab session should succeed (no-op)"
             );
 
@@ -1006,19 +971,19 @@ async fn test_kitty_executor_navigate_tab() -> Result<(), Box<dyn std::error::Er
 
             // Create a tab without session environment (unnamed session)
             let launch_cmd = KittenLaunchCommand::new()
-                .launch_type("tab")
+                .launch_type(LaunchType::Tab)
                 .tab_title("Unnamed Tab");
 
-            let launch_result = executor.launch(launch_cmd)?;
-            assert!(launch_result.is_success(), "Failed to create unnamed tab");
+            let launch_result = executor.launch(launch_cmd);
+            assert!(launch_result.is_ok(), "Failed to create unnamed tab");
             tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
             let nav_command = KittenNavigateTabCommand::next().with_session("unnamed".to_string());
 
-            let nav_result = executor.navigate_tab(nav_command)?;
+            let nav_result = executor.navigate_tab(nav_command);
             // This should succeed but may be a no-op if there's only one unnamed tab
             assert!(
-                nav_result.is_success(),
+                nav_result.is_ok(),
                 "Unnamed session navigation should succeed"
             );
 
@@ -1049,7 +1014,7 @@ async fn test_no_wrap_navigation() -> Result<(), Box<dyn std::error::Error>> {
         Ok(harness) => {
             println!("Test harness launched successfully for no-wrap navigation test");
 
-            use kitty_lib::commands::launch::KittenLaunchCommand;
+            use kitty_lib::commands::launch::{KittenLaunchCommand, LaunchType};
             use kitty_lib::commands::ls::KittenLsCommand;
             use kitty_lib::commands::navigate_tab::KittenNavigateTabCommand;
             use kitty_lib::executor::{CommandExecutor, KittyExecutor};
@@ -1067,12 +1032,12 @@ async fn test_no_wrap_navigation() -> Result<(), Box<dyn std::error::Error>> {
             // Create exactly 3 tabs for testing boundaries
             for i in 1..=3 {
                 let launch_cmd = KittenLaunchCommand::new()
-                    .launch_type("tab")
+                    .launch_type(LaunchType::Tab)
                     .env("KITTY_SESSION_PROJECT", session_name)
                     .tab_title(&format!("NoWrap Tab {}", i));
 
-                let launch_result = executor.launch(launch_cmd)?;
-                assert!(launch_result.is_success(), "Failed to create tab {}", i);
+                let launch_result = executor.launch(launch_cmd);
+                assert!(launch_result.is_ok(), "Failed to create tab {}", i);
                 tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
             }
 
@@ -1116,8 +1081,8 @@ async fn test_no_wrap_navigation() -> Result<(), Box<dyn std::error::Error>> {
             // Test 1: Navigate to first tab
             use kitty_lib::commands::focus_tab::KittenFocusTabCommand;
             let focus_cmd = KittenFocusTabCommand::new(tab_ids[0]);
-            let focus_result = executor.focus_tab(focus_cmd)?;
-            assert!(focus_result.is_success(), "Failed to focus first tab");
+            let focus_result = executor.focus_tab(focus_cmd);
+            assert!(focus_result.is_ok(), "Failed to focus first tab");
             tokio::time::sleep(tokio::time::Duration::from_millis(1000)).await; // Give more time for state to update
 
             let initial_active = get_active_tab_id().await;
@@ -1138,9 +1103,9 @@ async fn test_no_wrap_navigation() -> Result<(), Box<dyn std::error::Error>> {
                 .with_session(session_name.to_string())
                 .no_wrap(); // This disables wrapping
 
-            let nav_result = executor.navigate_tab(nav_command)?;
+            let nav_result = executor.navigate_tab(nav_command);
             assert!(
-                nav_result.is_success(),
+                nav_result.is_ok(),
                 "No-wrap navigation should succeed (even if no movement)"
             );
             tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
@@ -1162,8 +1127,8 @@ async fn test_no_wrap_navigation() -> Result<(), Box<dyn std::error::Error>> {
             // Test 3: Navigate to last tab
             println!("Test 3: Navigate to last tab");
             let focus_cmd = KittenFocusTabCommand::new(tab_ids[2]);
-            let focus_result = executor.focus_tab(focus_cmd)?;
-            assert!(focus_result.is_success(), "Failed to focus last tab");
+            let focus_result = executor.focus_tab(focus_cmd);
+            assert!(focus_result.is_ok(), "Failed to focus last tab");
             tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
             let last_active = get_active_tab_id().await;
@@ -1180,9 +1145,9 @@ async fn test_no_wrap_navigation() -> Result<(), Box<dyn std::error::Error>> {
                 .with_session(session_name.to_string())
                 .no_wrap(); // This disables wrapping
 
-            let nav_result = executor.navigate_tab(nav_command)?;
+            let nav_result = executor.navigate_tab(nav_command);
             assert!(
-                nav_result.is_success(),
+                nav_result.is_ok(),
                 "No-wrap navigation should succeed (even if no movement)"
             );
             tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
@@ -1201,8 +1166,8 @@ async fn test_no_wrap_navigation() -> Result<(), Box<dyn std::error::Error>> {
             // Test 5: Verify normal navigation still works in the middle
             println!("Test 5: Normal navigation in the middle");
             let focus_cmd = KittenFocusTabCommand::new(tab_ids[1]);
-            let focus_result = executor.focus_tab(focus_cmd)?;
-            assert!(focus_result.is_success(), "Failed to focus middle tab");
+            let focus_result = executor.focus_tab(focus_cmd);
+            assert!(focus_result.is_ok(), "Failed to focus middle tab");
             tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
             // Navigate next (should go to last tab)
@@ -1210,8 +1175,8 @@ async fn test_no_wrap_navigation() -> Result<(), Box<dyn std::error::Error>> {
                 .with_session(session_name.to_string())
                 .no_wrap();
 
-            let nav_result = executor.navigate_tab(nav_command)?;
-            assert!(nav_result.is_success(), "Next navigation should succeed");
+            let nav_result = executor.navigate_tab(nav_command);
+            assert!(nav_result.is_ok(), "Next navigation should succeed");
             tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
             let middle_next_active = get_active_tab_id().await;
@@ -1230,11 +1195,8 @@ async fn test_no_wrap_navigation() -> Result<(), Box<dyn std::error::Error>> {
                 .with_session(session_name.to_string())
                 .no_wrap();
 
-            let nav_result = executor.navigate_tab(nav_command)?;
-            assert!(
-                nav_result.is_success(),
-                "Previous navigation should succeed"
-            );
+            let nav_result = executor.navigate_tab(nav_command);
+            assert!(nav_result.is_ok(), "Previous navigation should succeed");
             tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
             let back_to_middle_active = get_active_tab_id().await;
@@ -1253,8 +1215,8 @@ async fn test_no_wrap_navigation() -> Result<(), Box<dyn std::error::Error>> {
 
             // Go to first tab
             let focus_cmd = KittenFocusTabCommand::new(tab_ids[0]);
-            let focus_result = executor.focus_tab(focus_cmd)?;
-            assert!(focus_result.is_success(), "Failed to focus first tab");
+            let focus_result = executor.focus_tab(focus_cmd);
+            assert!(focus_result.is_ok(), "Failed to focus first tab");
             tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
             // Navigate previous WITH wrap (should go to last tab)
@@ -1262,8 +1224,8 @@ async fn test_no_wrap_navigation() -> Result<(), Box<dyn std::error::Error>> {
                 .with_session(session_name.to_string())
                 .with_wrap(true); // Enable wrapping
 
-            let nav_result = executor.navigate_tab(nav_command)?;
-            assert!(nav_result.is_success(), "Wrap navigation should succeed");
+            let nav_result = executor.navigate_tab(nav_command);
+            assert!(nav_result.is_ok(), "Wrap navigation should succeed");
             tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
             let wrap_prev_active = get_active_tab_id().await;
@@ -1305,7 +1267,7 @@ async fn test_session_lifecycle_management() -> Result<(), Box<dyn std::error::E
             println!("Test harness launched successfully for session lifecycle test");
 
             use kitty_lib::commands::close_tab::KittenCloseTabCommand;
-            use kitty_lib::commands::launch::KittenLaunchCommand;
+            use kitty_lib::commands::launch::{KittenLaunchCommand, LaunchType};
             use kitty_lib::commands::ls::KittenLsCommand;
             use kitty_lib::executor::{CommandExecutor, KittyExecutor};
 
@@ -1353,7 +1315,10 @@ async fn test_session_lifecycle_management() -> Result<(), Box<dyn std::error::E
             }
 
             // Helper function to get all tab IDs in session
-            async fn get_session_tab_ids(executor: &KittyExecutor, session: &str) -> Vec<u32> {
+            async fn get_session_tab_ids(
+                executor: &KittyExecutor,
+                session: &str,
+            ) -> Vec<kitty_lib::TabId> {
                 let ls_cmd = if session != "unnamed" {
                     KittenLsCommand::new().match_tab_env("KITTY_SESSION_PROJECT", session)
                 } else {
@@ -1395,15 +1360,12 @@ async fn test_session_lifecycle_management() -> Result<(), Box<dyn std::error::E
 
             // Create first tab in session
             let launch_cmd = KittenLaunchCommand::new()
-                .launch_type("tab")
+                .launch_type(LaunchType::Tab)
                 .env("KITTY_SESSION_PROJECT", session_name)
                 .tab_title("Session Tab 1");
 
-            let launch_result = executor.launch(launch_cmd)?;
-            assert!(
-                launch_result.is_success(),
-                "Failed to create first session tab"
-            );
+            let launch_result = executor.launch(launch_cmd);
+            assert!(launch_result.is_ok(), "Failed to create first session tab");
             tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
 
             let session_count_1 = count_session_tabs(&executor, session_name).await;
@@ -1424,23 +1386,19 @@ async fn test_session_lifecycle_management() -> Result<(), Box<dyn std::error::E
 
             use kitty_lib::commands::focus_tab::KittenFocusTabCommand;
             let focus_cmd = KittenFocusTabCommand::new(session_tab_ids[0]);
-            let focus_result = executor.focus_tab(focus_cmd)?;
-            assert!(focus_result.is_success(), "Failed to focus session tab");
+            let focus_result = executor.focus_tab(focus_cmd);
+            assert!(focus_result.is_ok(), "Failed to focus session tab");
             tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
             // Create additional tabs with session inheritance enabled
             for i in 2..=4 {
                 let launch_cmd = KittenLaunchCommand::new()
-                    .launch_type("tab")
+                    .launch_type(LaunchType::Tab)
                     .env("KITTY_SESSION_PROJECT", session_name)
                     .tab_title(&format!("Session Tab {}", i));
 
-                let launch_result = executor.launch(launch_cmd)?;
-                assert!(
-                    launch_result.is_success(),
-                    "Failed to create session tab {}",
-                    i
-                );
+                let launch_result = executor.launch(launch_cmd);
+                assert!(launch_result.is_ok(), "Failed to create session tab {}", i);
                 tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
             }
 
@@ -1458,13 +1416,13 @@ async fn test_session_lifecycle_management() -> Result<(), Box<dyn std::error::E
             // Create tabs in a different session
             for i in 1..=2 {
                 let launch_cmd = KittenLaunchCommand::new()
-                    .launch_type("tab")
+                    .launch_type(LaunchType::Tab)
                     .env("KITTY_SESSION_PROJECT", other_session)
                     .tab_title(&format!("Other Session Tab {}", i));
 
-                let launch_result = executor.launch(launch_cmd)?;
+                let launch_result = executor.launch(launch_cmd);
                 assert!(
-                    launch_result.is_success(),
+                    launch_result.is_ok(),
                     "Failed to create other session tab {}",
                     i
                 );
@@ -1474,15 +1432,11 @@ async fn test_session_lifecycle_management() -> Result<(), Box<dyn std::error::E
             // Create some unnamed tabs (no session)
             for i in 1..=2 {
                 let launch_cmd = KittenLaunchCommand::new()
-                    .launch_type("tab")
+                    .launch_type(LaunchType::Tab)
                     .tab_title(&format!("Unnamed Tab {}", i));
 
-                let launch_result = executor.launch(launch_cmd)?;
-                assert!(
-                    launch_result.is_success(),
-                    "Failed to create unnamed tab {}",
-                    i
-                );
+                let launch_result = executor.launch(launch_cmd);
+                assert!(launch_result.is_ok(), "Failed to create unnamed tab {}", i);
                 tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
             }
 
@@ -1514,8 +1468,8 @@ async fn test_session_lifecycle_management() -> Result<(), Box<dyn std::error::E
             // Close the first two tabs from main session
             for &tab_id in &main_session_tab_ids[0..2] {
                 let close_cmd = KittenCloseTabCommand::new(tab_id);
-                let close_result = executor.close_tab(close_cmd)?;
-                assert!(close_result.is_success(), "Failed to close tab {}", tab_id);
+                let close_result = executor.close_tab(close_cmd);
+                assert!(close_result.is_ok(), "Failed to close tab {}", tab_id);
                 tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
             }
 
@@ -1546,9 +1500,9 @@ async fn test_session_lifecycle_management() -> Result<(), Box<dyn std::error::E
             // Close all remaining tabs in main session
             for &tab_id in &remaining_main_tab_ids {
                 let close_cmd = KittenCloseTabCommand::new(tab_id);
-                let close_result = executor.close_tab(close_cmd)?;
+                let close_result = executor.close_tab(close_cmd);
                 assert!(
-                    close_result.is_success(),
+                    close_result.is_ok(),
                     "Failed to close remaining tab {}",
                     tab_id
                 );
@@ -1575,9 +1529,9 @@ async fn test_session_lifecycle_management() -> Result<(), Box<dyn std::error::E
             let other_session_tab_ids = get_session_tab_ids(&executor, other_session).await;
             for &tab_id in &other_session_tab_ids {
                 let close_cmd = KittenCloseTabCommand::new(tab_id);
-                let close_result = executor.close_tab(close_cmd)?;
+                let close_result = executor.close_tab(close_cmd);
                 assert!(
-                    close_result.is_success(),
+                    close_result.is_ok(),
                     "Failed to close other session tab {}",
                     tab_id
                 );
@@ -1593,18 +1547,16 @@ async fn test_session_lifecycle_management() -> Result<(), Box<dyn std::error::E
             let nav_command =
                 KittenNavigateTabCommand::next().with_session(session_name.to_string());
 
-            let nav_result = executor.navigate_tab(nav_command)?;
+            let nav_result = executor.navigate_tab(nav_command);
             assert!(
-                !nav_result.is_success(),
+                nav_result.is_err(),
                 "Navigation in empty session should fail"
             );
 
-            if let Some(error_msg) = &nav_result.error_message {
-                assert!(
-                    error_msg.contains("No tabs found"),
-                    "Error should indicate no tabs found in session"
-                );
-            }
+            assert!(
+                matches!(nav_result, Err(kitty_lib::KittyError::NoMatch { .. })),
+                "Error should indicate no tabs found in session"
+            );
 
             // Clean up remaining unnamed tabs we created
             let unnamed_tab_ids = get_session_tab_ids(&executor, "unnamed").await;
@@ -1720,21 +1672,18 @@ async fn test_edge_cases() -> Result<(), Box<dyn std::error::Error>> {
                 serde_json::from_str(&single_session_output)?;
 
             // Focus on the single tab
-            if let Some(os_windows) = single_session_json.as_array() {
-                if let Some(first_window) = os_windows.first() {
-                    if let Some(tabs) = first_window.get("tabs").and_then(|t| t.as_array()) {
-                        if let Some(first_tab) = tabs.first() {
-                            if let Some(tab_id) = first_tab.get("id").and_then(|id| id.as_u64()) {
-                                let focus_single_cmd = format!("focus-tab --match id:{}", tab_id);
-                                harness.execute_command(&focus_single_cmd).await?;
-                                tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
-
-                                // Try focusing again (simulating next/previous on single tab)
-                                harness.execute_command(&focus_single_cmd).await?;
-                            }
-                        }
-                    }
-                }
+            if let Some(os_windows) = single_session_json.as_array()
+                && let Some(first_window) = os_windows.first()
+                && let Some(tabs) = first_window.get("tabs").and_then(|t| t.as_array())
+                && let Some(first_tab) = tabs.first()
+                && let Some(tab_id) = first_tab.get("id").and_then(|id| id.as_u64())
+            {
+                let focus_single_cmd = format!("focus-tab --match id:{}", tab_id);
+                harness.execute_command(&focus_single_cmd).await?;
+                tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
+
+                // Try focusing again (simulating next/previous on single tab)
+                harness.execute_command(&focus_single_cmd).await?;
             }
             tokio::time::sleep(tokio::time::Duration::from_millis(300)).await;
 
@@ -1751,11 +1700,10 @@ async fn test_edge_cases() -> Result<(), Box<dyn std::error::Error>> {
                     if let Some(tabs) = os_window.get("tabs").and_then(|t| t.as_array()) {
                         for tab in tabs {
                             if let Some(is_active) = tab.get("is_active").and_then(|a| a.as_bool())
+                                && is_active
                             {
-                                if is_active {
-                                    single_tab_active = true;
-                                    break;
-                                }
+                                single_tab_active = true;
+                                break;
                             }
                         }
                     }
@@ -1790,11 +1738,11 @@ async fn test_edge_cases() -> Result<(), Box<dyn std::error::Error>> {
 
                     if let Some(os_windows) = special_json.as_array() {
                         for os_window in os_windows {
-                            if let Some(tabs) = os_window.get("tabs").and_then(|t| t.as_array()) {
-                                if !tabs.is_empty() {
-                                    found_special = true;
-                                    break;
-                                }
+                            if let Some(tabs) = os_window.get("tabs").and_then(|t| t.as_array())
+                                && !tabs.is_empty()
+                            {
+                                found_special = true;
+                                break;
                             }
                         }
                     }
@@ -1860,11 +1808,11 @@ async fn test_edge_cases() -> Result<(), Box<dyn std::error::Error>> {
 
                     if let Some(os_windows) = long_json.as_array() {
                         for os_window in os_windows {
-                            if let Some(tabs) = os_window.get("tabs").and_then(|t| t.as_array()) {
-                                if !tabs.is_empty() {
-                                    found_long = true;
-                                    break;
-                                }
+                            if let Some(tabs) = os_window.get("tabs").and_then(|t| t.as_array())
+                                && !tabs.is_empty()
+                            {
+                                found_long = true;
+                                break;
                             }
                         }
                     }
@@ -0,0 +1,185 @@
+use kitty_lib::{
+    KittyLsResponse, KittySnapshot, KittyVersion, OsWindowId, SessionMarker, TabId, WindowId,
+};
+use std::fs;
+use std::path::Path;
+
+const MINIMAL: &str = include_str!("fixtures/ls/synthetic-minimal.json");
+const SHELL_INTEGRATION: &str = include_str!("fixtures/ls/synthetic-shell-integration.json");
+const WAYLAND_BACKGROUND: &str = include_str!("fixtures/ls/synthetic-wayland-background.json");
+
+fn parse(fixture: &str) -> KittyLsResponse {
+    serde_json::from_str(fixture).expect("fixture should deserialize into KittyLsResponse")
+}
+
+#[test]
+fn test_all_fixtures_deserialize() {
+    for (name, fixture) in [
+        ("minimal", MINIMAL),
+        ("shell-integration", SHELL_INTEGRATION),
+        ("wayland-background", WAYLAND_BACKGROUND),
+    ] {
+        let response: Result<KittyLsResponse, _> = serde_json::from_str(fixture);
+        assert!(response.is_ok(), "{name}: {:?}", response.err());
+        assert!(!response.unwrap().is_empty(), "{name} has no OS windows");
+    }
+}
+
+/// Real captures named `kitty-<version>.json`, so a kitty release that changes the output shape
+/// fails here; see the fixtures README for recording one
+#[test]
+fn test_real_captures_deserialize() {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ls");
+    for entry in fs::read_dir(&dir).unwrap() {
+        let path = entry.unwrap().path();
+        let Some(version) = path
+            .file_stem()
+            .and_then(|stem| stem.to_str())
+            .and_then(|stem| stem.strip_prefix("kitty-"))
+        else {
+            continue;
+        };
+        assert!(
+            KittyVersion::parse(version).is_some(),
+            "{}: the file name has no kitty version",
+            path.display()
+        );
+
+        let fixture = fs::read_to_string(&path).unwrap();
+        let response: KittyLsResponse = serde_json::from_str(&fixture)
+            .unwrap_or_else(|error| panic!("kitty {}: {}", version, error));
+        assert!(!response.is_empty(), "kitty {} has no OS windows", version);
+        assert!(
+            response
+                .iter()
+                .flat_map(|os_window| &os_window.tabs)
+                .all(|tab| !tab.windows.is_empty()),
+            "kitty {} has a tab without windows",
+            version
+        );
+    }
+}
+
+#[test]
+fn test_minimal_output_without_active_flags() {
+    let response = parse(MINIMAL);
+    let os_window = &response[0];
+    assert_eq!(os_window.id, OsWindowId(1));
+    assert_eq!(os_window.platform_window_id, Some(48234498));
+    assert!(os_window.is_focused);
+    assert!(!os_window.last_focused);
+
+    let tab = &os_window.tabs[0];
+    assert_eq!(tab.id, TabId(1));
+    assert!(!tab.is_active);
+    assert_eq!(tab.layout.as_deref(), Some("tall"));
+    assert_eq!(tab.enabled_layouts, vec!["tall", "stack"]);
+    assert_eq!(tab.active_window_history, vec![WindowId(2), WindowId(1)]);
+
+    let editor = &tab.windows[0];
+    assert!(editor.is_focused);
+    assert!(!editor.at_prompt);
+    assert_eq!(editor.last_reported_cmdline, None);
+    assert_eq!((editor.columns, editor.lines), (105, 52));
+    assert_eq!(editor.foreground_processes.len(), 1);
+    assert_eq!(
+        editor.foreground_processes[0].cmdline,
+        vec!["nvim", "src/main.rs"]
+    );
+    assert_eq!(
+        editor.foreground_processes[0].cwd.as_deref(),
+        Some("/home/dev/src/api")
+    );
+
+    let snapshot = KittySnapshot::new(response);
+    assert_eq!(snapshot.focused_tab().map(|tab| tab.id), Some(TabId(1)));
+    assert_eq!(snapshot.self_tab().map(|tab| tab.id), Some(TabId(2)));
+    assert_eq!(
+        snapshot.session_of(TabId(1)),
+        Some(("api", SessionMarker::TabTitle))
+    );
+    assert_eq!(snapshot.unnamed_tabs().len(), 1);
+}
+
+#[test]
+fn test_shell_integration_fields() {
+    let response = parse(SHELL_INTEGRATION);
+    assert_eq!(response.len(), 2);
+
+    let background = &response[0];
+    assert!(!background.is_focused);
+    let tab = &background.tabs[0];
+    assert!(tab.is_active);
+    assert_eq!(tab.layout.as_deref(), Some("splits"));
+
+    let nvim = &tab.windows[0];
+    assert!(nvim.is_active);
+    assert!(!nvim.at_prompt);
+    assert_eq!(nvim.last_reported_cmdline.as_deref(), Some("nvim ."));
+    assert_eq!(
+        nvim.user_vars.get("ksm_session").map(String::as_str),
+        Some("api")
+    );
+
+    let shell = &tab.windows[1];
+    assert!(shell.at_prompt);
+    assert_eq!(shell.cwd, "/home/dev/src/api/migrations");
+
+    let snapshot = KittySnapshot::new(response);
+    assert_eq!(
+        snapshot.focused_os_window().map(|os_window| os_window.id),
+        Some(OsWindowId(2))
+    );
+    assert_eq!(
+        snapshot.session_of(TabId(1)),
+        Some(("api", SessionMarker::UserVar))
+    );
+    assert_eq!(
+        snapshot.session_of(TabId(2)),
+        Some(("web", SessionMarker::TabTitle))
+    );
+    assert_eq!(
+        snapshot.tab_for_window(WindowId(2)).map(|tab| tab.id),
+        Some(TabId(1))
+    );
+    assert_eq!(snapshot.tabs_under("/home/dev/src/api").len(), 1);
+}
+
+#[test]
+fn test_wayland_kitty_in_background() {
+    let response = parse(WAYLAND_BACKGROUND);
+    let os_window = &response[0];
+    assert_eq!(os_window.id, OsWindowId(3));
+    assert_eq!(os_window.platform_window_id, None);
+    assert!(!os_window.is_focused);
+    assert!(os_window.last_focused);
+
+    let tab = &os_window.tabs[0];
+    assert_eq!(tab.id, TabId(7));
+    assert_eq!(tab.enabled_layouts, vec!["grid", "tall", "stack"]);
+    assert_eq!(tab.active_window_history, vec![WindowId(13), WindowId(12)]);
+
+    let tests = &tab.windows[0];
+    assert_eq!(tests.foreground_processes.len(), 2);
+    assert_eq!(
+        tests.last_reported_cmdline.as_deref(),
+        Some("cargo test --workspace")
+    );
+
+    let shell = &tab.windows[1];
+    assert!(shell.at_prompt);
+    assert_eq!(shell.foreground_processes[0].cwd, None);
+
+    let snapshot = KittySnapshot::new(response);
+    assert_eq!(snapshot.focused_tab(), None);
+    assert_eq!(
+        snapshot.focused_os_window().map(|os_window| os_window.id),
+        Some(OsWindowId(3))
+    );
+    assert_eq!(
+        snapshot.active_tab(OsWindowId(3)).map(|tab| tab.id),
+        Some(TabId(7))
+    );
+    assert_eq!(snapshot.session_tabs("ksm").len(), 1);
+    assert_eq!(snapshot.unnamed_tabs().len(), 1);
+}
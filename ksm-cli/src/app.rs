@@ -1,10 +1,15 @@
+use std::fmt::Display;
+
 use crate::config::Config;
+use crate::hooks::{self, HookContext, HookKind};
 use crate::kitty::Kitty;
 use kitty_lib::{CommandExecutor, KittyExecutor};
 
 pub struct App<E: CommandExecutor = KittyExecutor> {
     pub config: Config,
     pub kitty: Kitty<E>,
+    /// Whether kitty calls are only printed as a script, see `ksm --dry-run`
+    pub dry_run: bool,
 }
 
 impl App<KittyExecutor> {
@@ -12,6 +17,7 @@ impl App<KittyExecutor> {
         Self {
             config,
             kitty: Kitty::new(),
+            dry_run: false,
         }
     }
 }
@@ -19,6 +25,40 @@ impl App<KittyExecutor> {
 impl<E: CommandExecutor> App<E> {
     /// Create an App with a custom Kitty instance (for testing)
     pub fn with_kitty(config: Config, kitty: Kitty<E>) -> Self {
-        Self { config, kitty }
+        Self {
+            config,
+            kitty,
+            dry_run: false,
+        }
+    }
+
+    /// Print local side effects as script lines instead of running them; pair with a kitty
+    /// that prints its commands to stdout
+    pub fn with_dry_run(mut self) -> Self {
+        self.dry_run = true;
+        self
+    }
+
+    /// Tell the user what happened, on stderr in a dry run so stdout stays a runnable script
+    pub fn report(&self, message: impl Display) {
+        if self.dry_run {
+            eprintln!("{}", message);
+        } else {
+            println!("{}", message);
+        }
+    }
+
+    /// Run hooks locally, or print them as part of the script in a dry run
+    ///
+    /// Returns the number of hooks that failed, always 0 in a dry run.
+    pub fn run_hooks(&self, kind: HookKind, commands: &[String], context: &HookContext) -> usize {
+        if !self.dry_run {
+            return hooks::run_hooks(kind, commands, context);
+        }
+
+        for line in hooks::script_lines(kind, commands, context) {
+            println!("{}", line);
+        }
+        0
     }
 }
@@ -1,4 +1,5 @@
 use clap::{Parser, Subcommand};
+use kitty_lib::TextExtent;
 
 #[derive(Debug, Parser)]
 #[command(name = "ksm")]
@@ -14,6 +15,25 @@ pub struct Cli {
     /// List of profile(s) names to load, overriding the implicit profile
     #[arg(short, long)]
     pub profile: Option<Vec<String>>,
+
+    /// kitty remote control socket, e.g. unix:/tmp/mykitty-1234 (default: $KSM_KITTY_SOCKET,
+    /// then discovered from kitty.conf)
+    #[arg(long, global = true)]
+    pub socket: Option<String>,
+
+    /// File holding kitty's remote_control_password (default: $KSM_KITTY_PASSWORD_FILE, then
+    /// $KSM_KITTY_PASSWORD, $KITTY_RC_PASSWORD or kitty.conf)
+    #[arg(long, global = true)]
+    pub password_file: Option<String>,
+
+    /// Print the kitten commands that would change kitty as a shell script instead of running
+    /// them; hooks are printed too, and messages go to stderr
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+
+    /// Print how long each kitty call took and how many calls the command made, to stderr
+    #[arg(long, global = true)]
+    pub timings: bool,
 }
 
 #[derive(Debug, Subcommand)]
@@ -67,6 +87,66 @@ pub enum Commands {
         #[arg(long)]
         force: bool,
     },
+    /// Run a command inside a session without switching to it, creating the session if needed
+    Exec {
+        /// Session to run the command in
+        session: String,
+        /// Run in the session tab with this title, creating it if needed
+        #[arg(long)]
+        tab: Option<String>,
+        /// Run in a new window of the session instead of a new tab
+        #[arg(long)]
+        window: bool,
+        /// Keep focus on the currently active window
+        #[arg(long)]
+        keep_focus: bool,
+        /// Command and arguments to run
+        #[arg(last = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Run a named task of the current session's project in its own tab
+    Run {
+        /// Task to run (pick interactively when omitted)
+        task: Option<String>,
+    },
+    /// Send text to a session's active window, followed by Enter
+    Send {
+        /// Session to send the text to
+        session: String,
+        /// Text to send
+        text: String,
+        /// Do not press Enter after the text
+        #[arg(long)]
+        no_enter: bool,
+    },
+    /// Send text to every window of the current session (or specified session)
+    Broadcast {
+        /// Text to send
+        text: String,
+        /// Specific session name to send to (if not provided, uses current session)
+        #[arg(long)]
+        session: Option<String>,
+        /// Skip the active window
+        #[arg(long)]
+        exclude_active: bool,
+        /// Do not press Enter after the text
+        #[arg(long)]
+        no_enter: bool,
+    },
+    /// Save the text of every window in the current session (or specified session) to files
+    Capture {
+        /// Session to capture (if not provided, uses current session)
+        session: Option<String>,
+        /// Directory to write the files to (default: ./ksm-capture-<session>)
+        #[arg(long)]
+        out: Option<String>,
+        /// What to capture: screen, all (with scrollback), selection or last_cmd_output
+        #[arg(long, default_value = "all")]
+        extent: TextExtent,
+        /// Keep colors and formatting as ANSI escape codes
+        #[arg(long)]
+        ansi: bool,
+    },
     /// Rename the current tab while preserving session markers
     #[command(name = "rename-tab")]
     RenameTab {
@@ -0,0 +1,262 @@
+use anyhow::{Result, anyhow};
+use kitty_lib::{CommandExecutor, KittenGetTextCommand, TextExtent};
+use log::{info, warn};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::app::App;
+use crate::session::SessionContext;
+
+/// Write the text of every window in the current session (or specified session) to files
+pub fn cmd_capture<E: CommandExecutor>(
+    app: &App<E>,
+    session_name: Option<&str>,
+    out_dir: Option<&str>,
+    extent: TextExtent,
+    ansi: bool,
+) -> Result<Vec<PathBuf>> {
+    cmd_capture_with_context(app, session_name, out_dir, extent, ansi, || {
+        app.kitty.detect_session()
+    })
+}
+
+/// Capture session windows with injectable session detection
+///
+/// Files are named `<tab title>__<window title>.txt`, with the window id appended when two
+/// windows would share a name. Returns the paths written; a dry run writes nothing and returns
+/// the paths it would have written.
+pub fn cmd_capture_with_context<E: CommandExecutor, F>(
+    app: &App<E>,
+    session_name: Option<&str>,
+    out_dir: Option<&str>,
+    extent: TextExtent,
+    ansi: bool,
+    detect_session: F,
+) -> Result<Vec<PathBuf>>
+where
+    F: FnOnce() -> Result<SessionContext>,
+{
+    let session_context = match session_name {
+        Some(name) => SessionContext::new(name),
+        None => detect_session()?,
+    };
+    let session_name = session_context.name().to_string();
+
+    let session_tabs = app.kitty.get_session_tabs(&session_context)?;
+    if session_tabs.is_empty() {
+        return Err(anyhow!(
+            "Session '{}' not found or has no tabs",
+            session_name
+        ));
+    }
+
+    let out_dir = match out_dir {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(format!("ksm-capture-{}", sanitize_file_name(&session_name))),
+    };
+    if !app.dry_run {
+        fs::create_dir_all(&out_dir)
+            .map_err(|e| anyhow!("Failed to create {}: {}", out_dir.display(), e))?;
+    }
+
+    info!(
+        "Capturing {} of session '{}' into {}",
+        extent,
+        session_name,
+        out_dir.display()
+    );
+
+    let mut used_names = HashSet::new();
+    let mut written = Vec::new();
+
+    for tab in &session_tabs {
+        for window in &tab.windows {
+            let mut command = KittenGetTextCommand::new()
+                .for_window_id(window.id)
+                .extent(extent);
+            if ansi {
+                command = command.ansi();
+            }
+
+            let text = match app.kitty.get_text(command) {
+                Ok(text) => text,
+                Err(e) => {
+                    warn!("Skipping window {}: {}", window.id, e);
+                    continue;
+                }
+            };
+
+            let base_name = format!(
+                "{}__{}",
+                sanitize_file_name(&tab.title),
+                sanitize_file_name(&window.title)
+            );
+            let file_name = if used_names.insert(base_name.clone()) {
+                format!("{}.txt", base_name)
+            } else {
+                format!("{}-{}.txt", base_name, window.id)
+            };
+
+            let path = out_dir.join(file_name);
+            if app.dry_run {
+                app.report(format!("Would write {}", path.display()));
+            } else {
+                write_capture(&path, &text)?;
+                app.report(path.display());
+            }
+            written.push(path);
+        }
+    }
+
+    info!(
+        "Captured {} window(s) of session '{}'",
+        written.len(),
+        session_name
+    );
+    Ok(written)
+}
+
+fn write_capture(path: &Path, text: &str) -> Result<()> {
+    fs::write(path, text).map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+}
+
+/// Replace characters that are awkward in file names, keeping titles recognisable
+fn sanitize_file_name(name: &str) -> String {
+    let sanitized: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+
+    if sanitized.is_empty() {
+        "untitled".to_string()
+    } else {
+        sanitized
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::create_test_app_with_executor;
+    use assert_fs::TempDir;
+    use kitty_lib::MockExecutor;
+
+    #[test]
+    fn test_cmd_capture_writes_one_file_per_window() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        let (app, _config_dir) = create_test_app_with_executor(&mock_executor);
+        let out_dir = TempDir::new().unwrap();
+
+        let tab = mock_executor.add_session_tab(
+            "capture-project",
+            Some("session:capture-project - server".to_string()),
+        );
+        mock_executor.layout.borrow_mut().add_window(tab, &[]);
+        mock_executor.add_session_tab("other-project", None);
+        mock_executor.set_window_text(1, "listening on :8080\n");
+        mock_executor.set_window_text(2, "error: connection refused\n");
+
+        let written = cmd_capture(
+            &app,
+            Some("capture-project"),
+            Some(out_dir.path().to_str().unwrap()),
+            TextExtent::All,
+            false,
+        )?;
+
+        // Both windows are titled "shell", so the second one gets its id appended
+        assert_eq!(
+            written,
+            vec![
+                out_dir
+                    .path()
+                    .join("session_capture-project_-_server__shell.txt"),
+                out_dir
+                    .path()
+                    .join("session_capture-project_-_server__shell-2.txt"),
+            ]
+        );
+        assert_eq!(fs::read_to_string(&written[0])?, "listening on :8080\n");
+        assert_eq!(
+            fs::read_to_string(&written[1])?,
+            "error: connection refused\n"
+        );
+
+        let calls = mock_executor.get_get_text_calls();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].extent, TextExtent::All);
+        assert!(!calls[0].ansi);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmd_capture_uses_detected_session() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        let (app, _config_dir) = create_test_app_with_executor(&mock_executor);
+        let out_dir = TempDir::new().unwrap();
+        mock_executor.add_session_tab("detected-project", None);
+
+        let written = cmd_capture_with_context(
+            &app,
+            None,
+            Some(out_dir.path().to_str().unwrap()),
+            TextExtent::Screen,
+            true,
+            || Ok(SessionContext::new("detected-project")),
+        )?;
+
+        assert_eq!(written.len(), 1);
+        assert!(mock_executor.get_get_text_calls()[0].ansi);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmd_capture_dry_run_writes_nothing() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        let (app, _config_dir) = create_test_app_with_executor(&mock_executor);
+        let app = app.with_dry_run();
+        let out_dir = TempDir::new().unwrap();
+        let capture_dir = out_dir.path().join("captures");
+        mock_executor.add_session_tab("dry-project", Some("server".to_string()));
+
+        let written = cmd_capture(
+            &app,
+            Some("dry-project"),
+            Some(capture_dir.to_str().unwrap()),
+            TextExtent::Screen,
+            false,
+        )?;
+
+        assert_eq!(written, vec![capture_dir.join("server__shell.txt")]);
+        assert!(!capture_dir.exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmd_capture_unknown_session_fails() {
+        let mock_executor = MockExecutor::new();
+        let (app, _config_dir) = create_test_app_with_executor(&mock_executor);
+
+        let result = cmd_capture(&app, Some("missing"), None, TextExtent::Screen, false);
+        assert!(result.is_err());
+        assert_eq!(mock_executor.get_text_call_count(), 0);
+    }
+
+    #[test]
+    fn test_sanitize_file_name() {
+        assert_eq!(sanitize_file_name("session:api - db"), "session_api_-_db");
+        assert_eq!(sanitize_file_name("vim ~/src/main.rs"), "vim___src_main.rs");
+        assert_eq!(sanitize_file_name("  "), "untitled");
+    }
+}
@@ -1,10 +1,12 @@
 use anyhow::Result;
-use kitty_lib::{CommandExecutor, KittenLsCommand};
+use kitty_lib::CommandExecutor;
 use log::{debug, info, warn};
 use std::io::{self, Write};
 
 use crate::app::App;
+use crate::hooks::{HookContext, HookKind};
 use crate::session::SessionContext;
+use crate::utils::expand_tilde;
 
 /// Close all tabs in the current session (or specified session)
 pub fn cmd_close_all_session_tabs<E: CommandExecutor>(
@@ -12,7 +14,7 @@ pub fn cmd_close_all_session_tabs<E: CommandExecutor>(
     session_name: Option<&str>,
     force: bool,
 ) -> Result<()> {
-    cmd_close_all_session_tabs_with_context(app, session_name, force, SessionContext::detect)
+    cmd_close_all_session_tabs_with_context(app, session_name, force, || app.kitty.detect_session())
 }
 
 /// Close all tabs in the current session (or specified session) with injectable session detection
@@ -23,13 +25,13 @@ pub fn cmd_close_all_session_tabs_with_context<E: CommandExecutor, F>(
     detect_session: F,
 ) -> Result<()>
 where
-    F: FnOnce() -> SessionContext,
+    F: FnOnce() -> Result<SessionContext>,
 {
     // Determine the target session
     let target_session = match session_name {
         Some(name) => name.to_string(),
         None => {
-            let context = detect_session();
+            let context = detect_session()?;
             if !context.is_explicit {
                 warn!(
                     "No active session detected. Use --session <name> to specify a session, or run from within a session context."
@@ -42,33 +44,16 @@ where
 
     info!("Querying tabs for session '{}'", target_session);
 
-    // Query all tabs in the target session
-    let ls_command = KittenLsCommand::new().match_tab_env("KITTY_SESSION_PROJECT", &target_session);
-    let os_windows = app.kitty.ls(ls_command)?;
-
-    // Collect all tabs from the session
-    let mut session_tabs = Vec::new();
-    for os_window in os_windows {
-        for tab in os_window.tabs {
-            // Verify this tab actually belongs to our target session
-            let matches_session = tab.windows.iter().any(|w| {
-                w.env
-                    .get("KITTY_SESSION_PROJECT")
-                    .is_some_and(|v| v == &target_session)
-            });
-            if matches_session {
-                session_tabs.push(tab);
-            }
-        }
-    }
+    // Query all tabs in the target session, however they are marked
+    let session_tabs = app
+        .kitty
+        .get_session_tabs(&SessionContext::new(&target_session))?;
 
     if session_tabs.is_empty() {
         info!("No tabs found in session '{}'", target_session);
         return Ok(());
     }
 
-    // Sort tabs by ID for consistent ordering
-    session_tabs.sort_by_key(|t| t.id);
     let tab_count = session_tabs.len();
 
     // Handle the edge case of closing the last tab
@@ -79,8 +64,8 @@ where
         );
     }
 
-    // Confirmation prompt (unless --force is specified)
-    if !force {
+    // Confirmation prompt (unless --force is specified, or nothing is really closed)
+    if !force && !app.dry_run {
         print!(
             "Close {} tab{} in session '{}'? (y/N): ",
             tab_count,
@@ -99,6 +84,14 @@ where
         }
     }
 
+    // Give the project a chance to tear down before its tabs disappear
+    if let Some(project) = app.config.session_project(&target_session, None) {
+        let hook_context = HookContext::new(&target_session)
+            .with_path(expand_tilde(project.path()))
+            .with_tabs(session_tabs.iter().map(|tab| tab.id));
+        app.run_hooks(HookKind::Close, project.on_close(), &hook_context);
+    }
+
     // Close all tabs in the session
     let mut successfully_closed = 0;
     let mut failed_closes = Vec::new();
@@ -107,22 +100,13 @@ where
         debug!("Closing tab {} ('{}')", tab.id, tab.title);
 
         match app.kitty.close_tab(tab.id) {
-            Ok(result) => {
-                if result.is_success() {
-                    successfully_closed += 1;
-                    debug!("Successfully closed tab {} ('{}')", tab.id, tab.title);
-                } else {
-                    let error_msg = result.error_message.unwrap_or_default();
-                    warn!(
-                        "Failed to close tab {} ('{}'): {}",
-                        tab.id, tab.title, error_msg
-                    );
-                    failed_closes.push((tab.id, error_msg));
-                }
+            Ok(()) => {
+                successfully_closed += 1;
+                debug!("Successfully closed tab {} ('{}')", tab.id, tab.title);
             }
             Err(e) => {
-                failed_closes.push((tab.id, e.to_string()));
-                warn!("Error closing tab {} ('{}'): {}", tab.id, tab.title, e);
+                warn!("Failed to close tab {} ('{}'): {:#}", tab.id, tab.title, e);
+                failed_closes.push((tab.id, format!("{:#}", e)));
             }
         }
     }
@@ -170,7 +154,7 @@ mod tests {
     use anyhow::Result;
     use assert_fs::TempDir;
     use assert_fs::prelude::*;
-    use kitty_lib::MockExecutor;
+    use kitty_lib::{KittyError, MockExecutor, TabId};
 
     use crate::app::App;
     use crate::config::Config;
@@ -202,21 +186,14 @@ vsc = []
         // Test closing all tabs with --force (no confirmation)
         cmd_close_all_session_tabs(&app, Some("test-project"), true)?;
 
-        // Verify ls was called to query session tabs
+        // Verify one snapshot of the layout answered the session lookup
         assert_eq!(mock_executor.ls_call_count(), 1);
-        let ls_calls = mock_executor.get_ls_calls();
-        assert!(
-            ls_calls[0]
-                .match_arg
-                .as_ref()
-                .unwrap()
-                .contains("test-project")
-        );
+        assert!(mock_executor.get_ls_calls()[0].match_arg.is_none());
 
         // Verify all tabs were closed
         assert_eq!(mock_executor.close_tab_call_count(), 3);
         let close_calls = mock_executor.get_close_tab_calls();
-        let closed_tab_ids: Vec<u32> = close_calls.iter().map(|c| c.tab_id).collect();
+        let closed_tab_ids: Vec<TabId> = close_calls.iter().map(|c| c.tab_id).collect();
         assert!(closed_tab_ids.contains(&tab1));
         assert!(closed_tab_ids.contains(&tab2));
         assert!(closed_tab_ids.contains(&tab3));
@@ -279,26 +256,19 @@ vsc = []
         let app = App::with_kitty(config, kitty);
 
         // Mock session detection to simulate being in a session
-        let mock_detect = || SessionContext::new("current-session");
+        let mock_detect = || Ok(SessionContext::new("current-session"));
 
         // Test closing all tabs in current session (no explicit session name)
         cmd_close_all_session_tabs_with_context(&app, None, true, mock_detect)?;
 
-        // Verify correct session was queried
+        // Verify one snapshot answered the session lookup
         assert_eq!(mock_executor.ls_call_count(), 1);
-        let ls_calls = mock_executor.get_ls_calls();
-        assert!(
-            ls_calls[0]
-                .match_arg
-                .as_ref()
-                .unwrap()
-                .contains("current-session")
-        );
+        assert!(mock_executor.get_ls_calls()[0].match_arg.is_none());
 
         // Verify both tabs were closed
         assert_eq!(mock_executor.close_tab_call_count(), 2);
         let close_calls = mock_executor.get_close_tab_calls();
-        let closed_tab_ids: Vec<u32> = close_calls.iter().map(|c| c.tab_id).collect();
+        let closed_tab_ids: Vec<TabId> = close_calls.iter().map(|c| c.tab_id).collect();
         assert!(closed_tab_ids.contains(&tab1));
         assert!(closed_tab_ids.contains(&tab2));
 
@@ -324,7 +294,7 @@ vsc = []
         let app = App::with_kitty(config, kitty);
 
         // Mock session detection to simulate no session context
-        let mock_detect = || SessionContext::unnamed();
+        let mock_detect = || Ok(SessionContext::unnamed());
 
         // Test with no session context and no explicit session name
         let result = cmd_close_all_session_tabs_with_context(&app, None, true, mock_detect);
@@ -347,10 +317,13 @@ vsc = []
         let tab3 = mock_executor.add_session_tab("test-session", Some("Tab 3".to_string()));
 
         // Queue responses: first close succeeds, second fails, third succeeds
-        use kitty_lib::KittyCommandResult;
-        mock_executor.expect_close_tab_response(Ok(KittyCommandResult::success_empty()));
-        mock_executor.expect_close_tab_response(Ok(KittyCommandResult::error("Permission denied")));
-        mock_executor.expect_close_tab_response(Ok(KittyCommandResult::success_empty()));
+        mock_executor.expect_close_tab_response(Ok(()));
+        mock_executor.expect_close_tab_response(Err(KittyError::CommandFailed {
+            command: "close-tab".to_string(),
+            exit_code: Some(1),
+            stderr: "Permission denied".to_string(),
+        }));
+        mock_executor.expect_close_tab_response(Ok(()));
 
         let kitty = Kitty::with_executor(&mock_executor);
         let temp_dir = TempDir::new().unwrap();
@@ -379,10 +352,79 @@ vsc = []
         // Verify all tabs were attempted to be closed
         assert_eq!(mock_executor.close_tab_call_count(), 3);
         let close_calls = mock_executor.get_close_tab_calls();
-        let attempted_tab_ids: Vec<u32> = close_calls.iter().map(|c| c.tab_id).collect();
+        let attempted_tab_ids: Vec<TabId> = close_calls.iter().map(|c| c.tab_id).collect();
         assert!(attempted_tab_ids.contains(&tab1));
         assert!(attempted_tab_ids.contains(&tab3));
 
         Ok(())
     }
+
+    #[test]
+    fn test_cmd_close_all_session_tabs_runs_on_close_hooks_before_closing() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+
+        let tab1 = mock_executor.add_session_tab("hooked", Some("Tab 1".to_string()));
+        let tab2 = mock_executor.add_session_tab("hooked", Some("Tab 2".to_string()));
+
+        let kitty = Kitty::with_executor(&mock_executor);
+        let temp_dir = TempDir::new().unwrap();
+        let hook_output = temp_dir.child("on_close.out");
+        let config_content = format!(
+            r#"[global]
+version = "1.0"
+
+[projects.hooked]
+path = "{}"
+on_close = ["exit 1", "echo \"$KSM_SESSION_NAME $KSM_TAB_IDS\" > {}"]
+"#,
+            temp_dir.path().display(),
+            hook_output.path().display()
+        );
+        let config_file = temp_dir.child("test_config.toml");
+        config_file.write_str(&config_content).unwrap();
+        let config = Config::load_from_path(Some(config_file.path().to_path_buf()), None).unwrap();
+        let app = App::with_kitty(config, kitty);
+
+        // A failing hook is reported but does not stop the tabs from closing
+        cmd_close_all_session_tabs(&app, Some("hooked"), true)?;
+
+        let written = std::fs::read_to_string(hook_output.path()).unwrap();
+        assert_eq!(written.trim(), format!("hooked {} {}", tab1, tab2));
+        assert_eq!(mock_executor.close_tab_call_count(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmd_close_all_session_tabs_dry_run_leaves_hooks_to_the_script() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        mock_executor.add_session_tab("hooked", Some("Tab 1".to_string()));
+
+        let temp_dir = TempDir::new().unwrap();
+        let hook_output = temp_dir.child("on_close.out");
+        let config_file = temp_dir.child("test_config.toml");
+        config_file
+            .write_str(&format!(
+                r#"[projects.hooked]
+path = "{}"
+on_close = ["touch {}"]
+"#,
+                temp_dir.path().display(),
+                hook_output.path().display()
+            ))
+            .unwrap();
+        let config = Config::load_from_path(Some(config_file.path().to_path_buf()), None).unwrap();
+        let app = App::with_kitty(config, Kitty::with_executor(&mock_executor)).with_dry_run();
+
+        // Nothing is really closed, so there is no confirmation to wait for
+        cmd_close_all_session_tabs(&app, Some("hooked"), false)?;
+
+        assert!(
+            !hook_output.path().exists(),
+            "hooks must not run in a dry run"
+        );
+        assert_eq!(mock_executor.close_tab_call_count(), 1);
+
+        Ok(())
+    }
 }
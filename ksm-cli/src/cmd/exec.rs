@@ -0,0 +1,291 @@
+use anyhow::{Result, anyhow};
+use kitty_lib::{
+    CommandExecutor, KittenLaunchCommand, KittyTab, LaunchType, SESSION_USER_VAR, WindowId,
+};
+use log::{debug, info};
+use std::path::Path;
+
+use crate::app::App;
+use crate::session::{SessionContext, SessionUtils};
+use crate::utils::{expand_tilde, format_session_tab_title_with_description};
+
+/// Run a command inside a session without switching to it, creating the session if needed
+///
+/// With `tab_title` the command runs in the session tab of that name (as a new window when the
+/// tab already exists), with `new_window` it runs in a new window of the session's last active
+/// tab, and otherwise it gets a new session tab named after the command. Returns the id of the
+/// new window when kitty reports one.
+pub fn cmd_exec<E: CommandExecutor>(
+    app: &App<E>,
+    session_name: &str,
+    tab_title: Option<&str>,
+    new_window: bool,
+    keep_focus: bool,
+    command: &[String],
+) -> Result<Option<WindowId>> {
+    if command.is_empty() {
+        return Err(anyhow!(
+            "No command given to run in session '{}'",
+            session_name
+        ));
+    }
+
+    let project = app.config.project_for_session(session_name);
+    let project_path = project.as_ref().map(|p| expand_tilde(p.path()));
+    let session_context = SessionContext::new(session_name);
+
+    let snapshot = app.kitty.snapshot()?;
+    let mut session_tabs: Vec<KittyTab> = session_context
+        .tabs_in(&snapshot)
+        .into_iter()
+        .cloned()
+        .collect();
+    if session_tabs.is_empty() {
+        let (Some(project), Some(project_path)) = (&project, &project_path) else {
+            return Err(anyhow!(
+                "Session '{}' is not running and no project is configured for it",
+                session_name
+            ));
+        };
+
+        info!("Session '{}' not found, creating it", session_name);
+        let project_env = app.config.session_env(session_name, Some(project_path));
+        if keep_focus {
+            app.kitty.create_background_session_tab(
+                project_path,
+                session_name,
+                &project_env,
+                project.on_create(),
+                project.windows(),
+            )?;
+        } else {
+            app.kitty.create_session_tab_by_path(
+                project_path,
+                session_name,
+                &project_env,
+                project.on_create(),
+                project.windows(),
+            )?;
+        }
+
+        session_tabs = app.kitty.get_session_tabs(&session_context)?;
+    }
+
+    let cwd = project_path.or_else(|| {
+        session_tabs
+            .first()
+            .and_then(|tab| tab.windows.first())
+            .map(|window| window.cwd.clone())
+    });
+
+    let mut launch_command = KittenLaunchCommand::new()
+        .env("KITTY_SESSION_PROJECT", session_name)
+        .var(SESSION_USER_VAR, session_name)
+        .envs(app.config.session_env(session_name, cwd.as_deref()))
+        .args(command.iter().cloned());
+
+    if let Some(cwd) = &cwd {
+        launch_command = launch_command.cwd(cwd);
+    }
+    if keep_focus {
+        launch_command = launch_command.keep_focus();
+    }
+
+    // Without a name the tab is named after the command, so it is never mistaken for the
+    // session's own `session:<name>` tab
+    let full_tab_title = match tab_title {
+        Some(title) => format_session_tab_title_with_description(session_name, title),
+        None => format_session_tab_title_with_description(session_name, &program_name(command)),
+    };
+
+    let target_tab = match tab_title {
+        Some(_) => session_tabs.iter().find(|tab| tab.title == full_tab_title),
+        None if new_window => SessionUtils::last_active_tab(session_name, &session_tabs),
+        None => None,
+    };
+
+    launch_command = match target_tab.and_then(|tab| tab.windows.first()) {
+        Some(window) => {
+            debug!(
+                "Running {:?} in a new window next to window {} of session '{}'",
+                command, window.id, session_name
+            );
+            launch_command
+                .launch_type(LaunchType::Window)
+                .next_to(&format!("id:{}", window.id))
+        }
+        None => {
+            debug!(
+                "Running {:?} in new tab '{}' of session '{}'",
+                command, full_tab_title, session_name
+            );
+            launch_command
+                .launch_type(LaunchType::Tab)
+                .tab_title(&full_tab_title)
+        }
+    };
+
+    let response = app.kitty.launch(launch_command)?;
+
+    if let Some(window_id) = response.window_id {
+        app.report(window_id);
+    }
+
+    info!(
+        "Started {:?} in session '{}' (window: {:?})",
+        command, session_name, response.window_id
+    );
+    Ok(response.window_id)
+}
+
+/// The file name of the program `command` runs, e.g. `cargo` for `/usr/bin/cargo test`
+fn program_name(command: &[String]) -> String {
+    Path::new(&command[0])
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| command[0].clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::{ConfigBuilder, ProjectBuilder};
+    use crate::utils::test_utils::create_test_app_with_config;
+    use assert_fs::TempDir;
+    use kitty_lib::{MockExecutor, TabId};
+
+    fn create_app_with_project(mock_executor: &MockExecutor) -> (App<&MockExecutor>, TempDir) {
+        create_test_app_with_config(
+            mock_executor,
+            ConfigBuilder::new().project(
+                ProjectBuilder::new("exec", "/tmp/exec-project").env_var("RUST_LOG", "debug"),
+            ),
+        )
+    }
+
+    fn command(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_cmd_exec_opens_window_in_existing_session() {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_app_with_project(&mock_executor);
+        mock_executor.add_unnamed_tab(None);
+        let session_tab = mock_executor.add_session_tab("exec-project", None);
+
+        let window_id = cmd_exec(
+            &app,
+            "exec-project",
+            None,
+            true,
+            true,
+            &command(&["cargo", "test"]),
+        )
+        .unwrap();
+
+        assert_eq!(window_id, Some(WindowId(3)));
+        let launch_calls = mock_executor.get_launch_calls();
+        assert_eq!(launch_calls.len(), 1);
+        assert_eq!(launch_calls[0].launch_type, LaunchType::Window);
+        assert_eq!(launch_calls[0].next_to, Some("id:2".to_string()));
+        assert_eq!(launch_calls[0].args, vec!["cargo", "test"]);
+        assert_eq!(launch_calls[0].env_value("RUST_LOG"), Some("debug"));
+        assert!(launch_calls[0].keep_focus);
+
+        let tabs = mock_executor.get_tabs_for_session("exec-project");
+        assert_eq!(tabs.len(), 1);
+        assert_eq!(tabs[0].id, session_tab);
+        assert_eq!(tabs[0].windows.len(), 2);
+        assert_eq!(mock_executor.get_active_tab_id(), Some(TabId(1)));
+    }
+
+    #[test]
+    fn test_cmd_exec_creates_missing_session_and_named_tab() {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_app_with_project(&mock_executor);
+        let original_tab = mock_executor.add_unnamed_tab(None);
+
+        cmd_exec(
+            &app,
+            "exec-project",
+            Some("server"),
+            false,
+            true,
+            &command(&["npm", "run", "dev"]),
+        )
+        .unwrap();
+
+        let launch_calls = mock_executor.get_launch_calls();
+        assert_eq!(launch_calls.len(), 2);
+        assert_eq!(
+            launch_calls[0].tab_title,
+            Some("session:exec-project".to_string())
+        );
+        assert_eq!(launch_calls[1].launch_type, LaunchType::Tab);
+        assert_eq!(
+            launch_calls[1].tab_title,
+            Some("session:exec-project - server".to_string())
+        );
+        assert_eq!(launch_calls[1].args, vec!["npm", "run", "dev"]);
+        assert_eq!(launch_calls[1].cwd.as_deref(), Some("/tmp/exec-project"));
+
+        // Focus stays where it was, without focusing the new session on the way
+        assert!(launch_calls.iter().all(|call| call.keep_focus));
+        assert_eq!(mock_executor.focus_tab_call_count(), 0);
+        assert_eq!(mock_executor.get_active_tab_id(), Some(original_tab));
+
+        // A second run reuses the named tab
+        cmd_exec(
+            &app,
+            "exec-project",
+            Some("server"),
+            false,
+            false,
+            &command(&["npm", "test"]),
+        )
+        .unwrap();
+
+        let launch_calls = mock_executor.get_launch_calls();
+        assert_eq!(launch_calls.len(), 3);
+        assert_eq!(launch_calls[2].launch_type, LaunchType::Window);
+        assert_eq!(mock_executor.get_tabs_for_session("exec-project").len(), 2);
+    }
+
+    #[test]
+    fn test_cmd_exec_new_tab_is_named_after_the_command() {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_app_with_project(&mock_executor);
+        mock_executor.add_session_tab("exec-project", Some("session:exec-project".to_string()));
+
+        cmd_exec(
+            &app,
+            "exec-project",
+            None,
+            false,
+            false,
+            &command(&["/usr/bin/cargo", "test"]),
+        )
+        .unwrap();
+
+        let launch_calls = mock_executor.get_launch_calls();
+        assert_eq!(launch_calls.len(), 1);
+        assert_eq!(launch_calls[0].launch_type, LaunchType::Tab);
+        assert_eq!(
+            launch_calls[0].tab_title,
+            Some("session:exec-project - cargo".to_string())
+        );
+        assert_eq!(mock_executor.get_tabs_for_session("exec-project").len(), 2);
+    }
+
+    #[test]
+    fn test_cmd_exec_unknown_session_fails() {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_app_with_project(&mock_executor);
+
+        let result = cmd_exec(&app, "missing", None, false, false, &command(&["ls"]));
+
+        assert!(result.is_err());
+        assert_eq!(mock_executor.launch_call_count(), 0);
+    }
+}
@@ -11,23 +11,26 @@ pub fn cmd_list<E: CommandExecutor>(app: &App<E>) -> Result<()> {
 
     let projects = get_projects(app)?;
 
-    println!("Available sessions:");
+    // One snapshot answers every project instead of an ls per project
+    let snapshot = app.kitty.snapshot().unwrap_or_default();
+
+    app.report("Available sessions:");
     for project in projects {
-        let (status, tab_info) = match app.kitty.match_session_tab(&project) {
-            Ok(Some(tab)) => {
+        let (status, tab_info) = match snapshot.session_tab(&project) {
+            Some(tab) => {
                 debug!(
                     "Project '{}' has active session with tab id: {}",
                     project, tab.id
                 );
                 ("✓ (active)".to_string(), format!(" [tab:{}]", tab.id))
             }
-            _ => {
+            None => {
                 debug!("Project '{}' has no active session", project);
                 ("○ (available)".to_string(), String::new())
             }
         };
 
-        println!("  {} {}{}", status, project, tab_info);
+        app.report(format!("  {} {}{}", status, project, tab_info));
     }
 
     info!("Finished listing sessions");
@@ -1,18 +1,26 @@
+pub mod capture;
 pub mod close_all_session_tabs;
+pub mod exec;
 pub mod key;
 pub mod list;
 pub mod new_tab;
 pub mod next_tab;
 pub mod prev_tab;
 pub mod rename_tab;
+pub mod run;
 pub mod select;
+pub mod send;
 
 // Re-export the main command functions
+pub use capture::cmd_capture;
 pub use close_all_session_tabs::cmd_close_all_session_tabs;
+pub use exec::cmd_exec;
 pub use key::{cmd_key, cmd_keys};
 pub use list::cmd_list;
 pub use new_tab::cmd_new_tab;
 pub use next_tab::cmd_next_tab;
 pub use prev_tab::cmd_prev_tab;
 pub use rename_tab::cmd_rename_tab;
+pub use run::cmd_run;
 pub use select::cmd_select;
+pub use send::{cmd_broadcast, cmd_send};
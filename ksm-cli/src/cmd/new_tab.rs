@@ -3,7 +3,6 @@ use kitty_lib::CommandExecutor;
 use log::info;
 
 use crate::app::App;
-use crate::session::SessionContext;
 
 /// Create a new tab with automatic session context inheritance
 pub fn cmd_new_tab<E: CommandExecutor>(
@@ -11,7 +10,7 @@ pub fn cmd_new_tab<E: CommandExecutor>(
     cwd: Option<&str>,
     title: Option<&str>,
 ) -> Result<()> {
-    let session_context = SessionContext::detect();
+    let session_context = app.kitty.detect_session()?;
 
     if session_context.is_explicit {
         info!(
@@ -37,9 +36,17 @@ pub fn cmd_new_tab<E: CommandExecutor>(
         info!("Using default tab title");
     }
 
+    // Carry the project's environment into the new tab so every tab in the session matches
+    let session_env = if session_context.is_explicit {
+        app.config.session_env(session_context.name(), None)
+    } else {
+        Vec::new()
+    };
+
     // Use the existing create_tab_with_session_inheritance method which handles
     // all the session-aware logic including auto-inheritance and title generation
-    app.kitty.create_tab_with_session_inheritance(cwd, title)?;
+    app.kitty
+        .create_tab_with_session_inheritance(&session_context, cwd, title, &session_env)?;
 
     info!("Successfully created new tab");
     Ok(())
@@ -51,7 +58,7 @@ mod tests {
     use anyhow::Result;
     use assert_fs::TempDir;
     use assert_fs::prelude::*;
-    use kitty_lib::MockExecutor;
+    use kitty_lib::{KittyError, MockExecutor};
     use std::env;
 
     use crate::app::App;
@@ -152,16 +159,14 @@ vsc = []
         let actual_title = &launch_calls[0].tab_title;
 
         // Should have generated a session-based title (either my-project or the current env var)
+        // If no title was set, no session context was detected, which is also valid
+        // depending on the environment state
         if let Some(title) = actual_title {
             assert!(
                 title.starts_with("session:"),
                 "Expected title to start with session prefix, got: {}",
                 title
             );
-        } else {
-            // If no title was set, it means no session context was detected
-            // This is also valid behavior depending on the environment state
-            println!("No title generated - this may indicate no session context was detected");
         }
         assert!(launch_calls[0].inherit_session); // Should inherit session
 
@@ -218,9 +223,8 @@ vsc = []
         assert!(launch_calls[0].inherit_session); // Still uses inherit flag (but no session to inherit)
 
         // Restore original environment variable if it existed
-        match original_value {
-            Some(value) => unsafe { env::set_var("KITTY_SESSION_PROJECT", value) },
-            None => {} // Keep it unset
+        if let Some(value) = original_value {
+            unsafe { env::set_var("KITTY_SESSION_PROJECT", value) };
         }
 
         // Add delay after restoration to prevent race conditions
@@ -273,9 +277,8 @@ vsc = []
         assert!(launch_calls[0].inherit_session); // Still uses inherit flag
 
         // Restore original environment variable if it existed
-        match original_value {
-            Some(value) => unsafe { env::set_var("KITTY_SESSION_PROJECT", value) },
-            None => {} // Keep it unset
+        if let Some(value) = original_value {
+            unsafe { env::set_var("KITTY_SESSION_PROJECT", value) };
         }
 
         // Add delay after restoration to prevent race conditions
@@ -298,7 +301,11 @@ vsc = []
         let mock_executor = MockExecutor::new();
 
         // Setup mock to return an error
-        mock_executor.expect_launch_response(Err(anyhow::anyhow!("Launch failed")));
+        mock_executor.expect_launch_response(Err(KittyError::CommandFailed {
+            command: "launch".to_string(),
+            exit_code: Some(1),
+            stderr: "Launch failed".to_string(),
+        }));
 
         let kitty = Kitty::with_executor(&mock_executor);
         let temp_dir = TempDir::new().unwrap();
@@ -317,7 +324,9 @@ vsc = []
         // Test that error is properly propagated
         let result = cmd_new_tab(&app, None, None);
         assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("Launch failed"));
+        let error = result.unwrap_err();
+        assert!(format!("{:#}", error).contains("Launch failed"));
+        assert!(error.downcast_ref::<KittyError>().is_some());
 
         // Restore original environment variable if it existed
         match original_value {
@@ -19,7 +19,7 @@ mod tests {
     use anyhow::Result;
     use assert_fs::TempDir;
     use assert_fs::prelude::*;
-    use kitty_lib::MockExecutor;
+    use kitty_lib::{MockExecutor, TabId};
 
     #[test]
     fn test_cmd_prev_tab() -> Result<()> {
@@ -44,11 +44,11 @@ mod tests {
 
         // Test previous tab navigation with default (config default is true)
         cmd_prev_tab(&app, None)?; // use config default (wrap = true)
-        assert_eq!(mock_executor.get_active_tab_id(), Some(1));
+        assert_eq!(mock_executor.get_active_tab_id(), Some(TabId(1)));
 
         // Test previous tab with explicit no-wrap from first tab
         cmd_prev_tab(&app, Some(true))?; // explicit no wrap
-        assert_eq!(mock_executor.get_active_tab_id(), Some(1)); // Should stay on first tab
+        assert_eq!(mock_executor.get_active_tab_id(), Some(TabId(1))); // Should stay on first tab
 
         Ok(())
     }
@@ -1,16 +1,15 @@
 use anyhow::Result;
 use kitty_lib::CommandExecutor;
-use log::info;
+use log::{info, warn};
 
 use crate::app::App;
-use crate::session::SessionContext;
 
 /// Rename the current tab while preserving session markers
 pub fn cmd_rename_tab<E: CommandExecutor>(app: &App<E>, new_description: &str) -> Result<()> {
     info!("Renaming current tab to: '{}'", new_description);
 
     // Get the current session context to determine how to format the title
-    let session_context = SessionContext::detect();
+    let session_context = app.kitty.detect_session()?;
 
     let new_title = if session_context.is_explicit {
         // If we have an explicit session, preserve the session prefix
@@ -29,6 +28,14 @@ pub fn cmd_rename_tab<E: CommandExecutor>(app: &App<E>, new_description: &str) -
     // Execute the command
     app.kitty.set_tab_title(&new_title)?;
 
+    // Mark the tab's windows too, so the session survives later title changes
+    if session_context.is_explicit
+        && let Some(tab) = app.kitty.focused_tab()?
+        && let Err(e) = app.kitty.tag_session_tab(&tab, session_context.name())
+    {
+        warn!("Failed to mark tab {} with its session: {}", tab.id, e);
+    }
+
     info!("Successfully renamed tab");
     Ok(())
 }
@@ -39,7 +46,7 @@ mod tests {
     use anyhow::Result;
     use assert_fs::TempDir;
     use assert_fs::prelude::*;
-    use kitty_lib::MockExecutor;
+    use kitty_lib::{KittyError, MockExecutor};
     use std::env;
 
     use crate::app::App;
@@ -64,8 +71,7 @@ mod tests {
         let mock_executor = MockExecutor::new();
 
         // Setup mock to return success for set_tab_title
-        mock_executor
-            .expect_set_tab_title_response(Ok(kitty_lib::KittyCommandResult::success_empty()));
+        mock_executor.expect_set_tab_title_response(Ok(()));
 
         let kitty = Kitty::with_executor(&mock_executor);
         let temp_dir = TempDir::new().unwrap();
@@ -123,8 +129,7 @@ vsc = []
         let mock_executor = MockExecutor::new();
 
         // Setup mock to return success for set_tab_title
-        mock_executor
-            .expect_set_tab_title_response(Ok(kitty_lib::KittyCommandResult::success_empty()));
+        mock_executor.expect_set_tab_title_response(Ok(()));
 
         let kitty = Kitty::with_executor(&mock_executor);
         let temp_dir = TempDir::new().unwrap();
@@ -154,9 +159,8 @@ vsc = []
         assert_eq!(calls[0].match_pattern, None); // Should target current tab
 
         // Restore original environment variable if it existed
-        match original_value {
-            Some(value) => unsafe { env::set_var("KITTY_SESSION_PROJECT", value) },
-            None => {} // Keep it unset
+        if let Some(value) = original_value {
+            unsafe { env::set_var("KITTY_SESSION_PROJECT", value) };
         }
 
         // Add delay after restoration to prevent race conditions
@@ -183,7 +187,11 @@ vsc = []
         let mock_executor = MockExecutor::new();
 
         // Setup mock to return an error
-        mock_executor.expect_set_tab_title_response(Err(anyhow::anyhow!("Set tab title failed")));
+        mock_executor.expect_set_tab_title_response(Err(KittyError::CommandFailed {
+            command: "set-tab-title".to_string(),
+            exit_code: Some(1),
+            stderr: "Set tab title failed".to_string(),
+        }));
 
         let kitty = Kitty::with_executor(&mock_executor);
         let temp_dir = TempDir::new().unwrap();
@@ -202,12 +210,7 @@ vsc = []
         // Test that error is properly propagated
         let result = cmd_rename_tab(&app, "Test Title");
         assert!(result.is_err());
-        assert!(
-            result
-                .unwrap_err()
-                .to_string()
-                .contains("Set tab title failed")
-        );
+        assert!(format!("{:#}", result.unwrap_err()).contains("Set tab title failed"));
 
         // Restore original environment variable if it existed
         match original_value {
@@ -239,8 +242,7 @@ vsc = []
         let mock_executor = MockExecutor::new();
 
         // Setup mock to return success for set_tab_title
-        mock_executor
-            .expect_set_tab_title_response(Ok(kitty_lib::KittyCommandResult::success_empty()));
+        mock_executor.expect_set_tab_title_response(Ok(()));
 
         let kitty = Kitty::with_executor(&mock_executor);
         let temp_dir = TempDir::new().unwrap();
@@ -298,8 +300,7 @@ vsc = []
         let mock_executor = MockExecutor::new();
 
         // Setup mock to return success for set_tab_title
-        mock_executor
-            .expect_set_tab_title_response(Ok(kitty_lib::KittyCommandResult::success_empty()));
+        mock_executor.expect_set_tab_title_response(Ok(()));
 
         let kitty = Kitty::with_executor(&mock_executor);
         let temp_dir = TempDir::new().unwrap();
@@ -0,0 +1,338 @@
+use anyhow::{Result, anyhow};
+use kitty_lib::{
+    CommandExecutor, KittenLaunchCommand, KittyTab, LaunchType, SESSION_USER_VAR, WindowId,
+};
+use log::{debug, info};
+use skim::prelude::*;
+use std::io::Cursor;
+
+use crate::app::App;
+use crate::config::TaskDefinition;
+use crate::session::SessionContext;
+use crate::utils::{expand_tilde, format_session_tab_title_with_description};
+
+/// User variable marking the window a task runs in
+pub const KSM_TASK_VAR: &str = "ksm_task";
+
+/// Run a named task of the current session's project in its dedicated tab
+pub fn cmd_run<E: CommandExecutor>(app: &App<E>, task_name: Option<&str>) -> Result<()> {
+    cmd_run_with_context(app, task_name, || app.kitty.detect_session(), pick_task)
+}
+
+/// Run a task with injectable session detection and task picker
+pub fn cmd_run_with_context<E: CommandExecutor, F, P>(
+    app: &App<E>,
+    task_name: Option<&str>,
+    detect_session: F,
+    pick: P,
+) -> Result<()>
+where
+    F: FnOnce() -> Result<SessionContext>,
+    P: FnOnce(&[String]) -> Option<String>,
+{
+    let session_context = detect_session()?;
+    if !session_context.is_explicit {
+        return Err(anyhow!(
+            "No active session detected. Run tasks from within a project session."
+        ));
+    }
+    let session_name = session_context.name();
+
+    let project = app
+        .config
+        .session_project(session_name, None)
+        .ok_or_else(|| anyhow!("No project configured for session '{}'", session_name))?;
+
+    let tasks = project.tasks().cloned().unwrap_or_default();
+    if tasks.is_empty() {
+        app.report(format!(
+            "No tasks configured for session '{}'",
+            session_name
+        ));
+        return Ok(());
+    }
+
+    let task_name = match task_name {
+        Some(name) => name.to_string(),
+        None => {
+            let names: Vec<String> = tasks.keys().cloned().collect();
+            match pick(&names) {
+                Some(name) => name,
+                None => {
+                    info!("No task selected");
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    let task = tasks.get(&task_name).ok_or_else(|| {
+        anyhow!(
+            "No task '{}' in session '{}' (available: {})",
+            task_name,
+            session_name,
+            tasks.keys().cloned().collect::<Vec<_>>().join(", ")
+        )
+    })?;
+
+    let tab_name = task.tab().unwrap_or(&task_name);
+    let tab_title = format_session_tab_title_with_description(session_name, tab_name);
+    let project_path = expand_tilde(project.path());
+
+    let mut launch_command = KittenLaunchCommand::new()
+        .cwd(&project_path)
+        .env("KITTY_SESSION_PROJECT", session_name)
+        .var(SESSION_USER_VAR, session_name)
+        .envs(app.config.session_env(session_name, Some(&project_path)))
+        .var(KSM_TASK_VAR, &task_name)
+        .hold()
+        .args(task_command(task));
+
+    let session_tabs = app.kitty.get_session_tabs(&session_context)?;
+    let mut previous_run = None;
+    launch_command = match find_task_tab(&session_tabs, &task_name, &tab_title) {
+        Some(tab) => {
+            info!(
+                "Reusing tab {} for task '{}' in session '{}'",
+                tab.id, task_name, session_name
+            );
+            app.kitty.focus_tab(tab.id)?;
+
+            previous_run = task_window_id(tab, &task_name);
+            let next_to = previous_run.or(tab.windows.first().map(|w| w.id));
+            let launch_command = launch_command.launch_type(LaunchType::Window);
+            match next_to {
+                Some(window_id) => launch_command.next_to(&format!("id:{}", window_id)),
+                None => launch_command,
+            }
+        }
+        None => {
+            info!(
+                "Creating tab '{}' for task '{}' in session '{}'",
+                tab_title, task_name, session_name
+            );
+            launch_command
+                .launch_type(LaunchType::Tab)
+                .tab_title(&tab_title)
+        }
+    };
+
+    debug!("Running task '{}': {}", task_name, task.cmd());
+    app.kitty.launch(launch_command)?;
+
+    // The new run replaces the previous one. It is closed only after the launch, so a task
+    // window that was alone in its tab doesn't take the tab with it.
+    if let Some(window_id) = previous_run {
+        debug!(
+            "Closing window {} of the previous '{}' run",
+            window_id, task_name
+        );
+        app.kitty.close_window(window_id)?;
+    }
+
+    app.report(format!(
+        "Running task '{}' in session '{}'",
+        task_name, session_name
+    ));
+    Ok(())
+}
+
+/// The program line for a task, run through the shell so pipes and `&&` work
+fn task_command(task: &TaskDefinition) -> Vec<String> {
+    vec![
+        "/bin/sh".to_string(),
+        "-c".to_string(),
+        task.cmd().to_string(),
+    ]
+}
+
+/// Find the tab a task ran in before, by its user variable or by tab title
+fn find_task_tab<'a>(
+    session_tabs: &'a [KittyTab],
+    task_name: &str,
+    tab_title: &str,
+) -> Option<&'a KittyTab> {
+    session_tabs
+        .iter()
+        .find(|tab| task_window_id(tab, task_name).is_some())
+        .or_else(|| session_tabs.iter().find(|tab| tab.title == tab_title))
+}
+
+fn task_window_id(tab: &KittyTab, task_name: &str) -> Option<WindowId> {
+    tab.windows
+        .iter()
+        .find(|window| {
+            window
+                .user_vars
+                .get(KSM_TASK_VAR)
+                .is_some_and(|name| name == task_name)
+        })
+        .map(|window| window.id)
+}
+
+/// Let the user pick a task with skim, returning `None` when aborted
+fn pick_task(task_names: &[String]) -> Option<String> {
+    let options = SkimOptionsBuilder::default()
+        .height(Some("50%"))
+        .multi(false)
+        .prompt(Some("Select task> "))
+        .build()
+        .unwrap();
+
+    let item_reader = SkimItemReader::default();
+    let items = item_reader.of_bufread(Cursor::new(task_names.join("\n")));
+
+    let output = Skim::run_with(&options, Some(items))?;
+    if output.is_abort {
+        info!("User aborted task selection");
+        return None;
+    }
+
+    output
+        .selected_items
+        .first()
+        .map(|item| item.output().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::test_helpers::{ConfigBuilder, ProjectBuilder};
+    use crate::utils::test_utils::create_test_app_with_config;
+    use assert_fs::TempDir;
+    use kitty_lib::MockExecutor;
+
+    fn create_app_with_tasks(mock_executor: &MockExecutor) -> (App<&MockExecutor>, TempDir) {
+        create_test_app_with_config(
+            mock_executor,
+            ConfigBuilder::new().project(
+                ProjectBuilder::new("tasks", "/tmp/task-project")
+                    .task("test", r#""cargo test""#)
+                    .task("serve", r#"{ cmd = "npm run dev", tab = "server" }"#),
+            ),
+        )
+    }
+
+    fn session() -> Result<SessionContext> {
+        Ok(SessionContext::new("task-project"))
+    }
+
+    fn no_pick(_: &[String]) -> Option<String> {
+        panic!("picker should not be shown when a task is given")
+    }
+
+    #[test]
+    fn test_cmd_run_creates_task_tab() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_app_with_tasks(&mock_executor);
+        mock_executor.add_session_tab("task-project", None);
+
+        cmd_run_with_context(&app, Some("serve"), session, no_pick)?;
+
+        let launch_calls = mock_executor.get_launch_calls();
+        assert_eq!(launch_calls.len(), 1);
+        assert_eq!(launch_calls[0].launch_type, LaunchType::Tab);
+        assert_eq!(
+            launch_calls[0].tab_title,
+            Some("session:task-project - server".to_string())
+        );
+        assert_eq!(launch_calls[0].args, vec!["/bin/sh", "-c", "npm run dev"]);
+        assert_eq!(
+            launch_calls[0].vars,
+            vec![
+                ("ksm_session".to_string(), "task-project".to_string()),
+                ("ksm_task".to_string(), "serve".to_string())
+            ]
+        );
+        assert!(launch_calls[0].hold);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmd_run_reuses_task_tab() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_app_with_tasks(&mock_executor);
+        mock_executor.add_session_tab("task-project", None);
+
+        cmd_run_with_context(&app, Some("test"), session, no_pick)?;
+        let task_tab = mock_executor.get_tabs_for_session("task-project")[1].id;
+        mock_executor.set_active_tab(1);
+
+        cmd_run_with_context(&app, Some("test"), session, no_pick)?;
+
+        let launch_calls = mock_executor.get_launch_calls();
+        assert_eq!(launch_calls.len(), 2);
+        assert_eq!(launch_calls[1].launch_type, LaunchType::Window);
+        assert_eq!(launch_calls[1].next_to, Some("id:2".to_string()));
+        assert_eq!(mock_executor.get_active_tab_id(), Some(task_tab));
+
+        // The previous run's window is replaced, not kept beside the new one
+        let tabs = mock_executor.get_tabs_for_session("task-project");
+        assert_eq!(tabs.len(), 2);
+        assert_eq!(tabs[1].id, task_tab);
+        assert_eq!(tabs[1].windows.len(), 1);
+        assert_ne!(tabs[1].windows[0].id, 2);
+        assert_eq!(
+            tabs[1].windows[0].user_vars.get(KSM_TASK_VAR),
+            Some(&"test".to_string())
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmd_run_matches_existing_tab_by_title() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_app_with_tasks(&mock_executor);
+        let server_tab = mock_executor
+            .add_session_tab("task-project", Some("session:task-project - server".into()));
+
+        cmd_run_with_context(&app, Some("serve"), session, no_pick)?;
+
+        let launch_calls = mock_executor.get_launch_calls();
+        assert_eq!(launch_calls[0].launch_type, LaunchType::Window);
+        assert_eq!(mock_executor.get_active_tab_id(), Some(server_tab));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmd_run_without_task_uses_picker() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_app_with_tasks(&mock_executor);
+
+        cmd_run_with_context(&app, None, session, |names| {
+            assert_eq!(names, ["serve", "test"]);
+            Some("test".to_string())
+        })?;
+        assert_eq!(
+            mock_executor.get_launch_calls()[0].args,
+            vec!["/bin/sh", "-c", "cargo test"]
+        );
+
+        // Aborting the picker runs nothing
+        cmd_run_with_context(&app, None, session, |_| None)?;
+        assert_eq!(mock_executor.launch_call_count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmd_run_unknown_task_or_session_fails() {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_app_with_tasks(&mock_executor);
+
+        let result = cmd_run_with_context(&app, Some("deploy"), session, no_pick);
+        assert!(result.unwrap_err().to_string().contains("serve, test"));
+
+        let result = cmd_run_with_context(
+            &app,
+            Some("test"),
+            || Ok(SessionContext::unnamed()),
+            no_pick,
+        );
+        assert!(result.is_err());
+        assert_eq!(mock_executor.launch_call_count(), 0);
+    }
+}
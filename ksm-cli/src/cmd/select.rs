@@ -6,6 +6,7 @@ use std::fs;
 use std::io::Cursor;
 
 use crate::app::App;
+use crate::hooks::{HookContext, HookKind};
 use crate::utils::{expand_tilde, format_project_for_selection, parse_project_selection};
 
 pub fn cmd_select<E: CommandExecutor>(app: &App<E>) -> Result<()> {
@@ -15,7 +16,7 @@ pub fn cmd_select<E: CommandExecutor>(app: &App<E>) -> Result<()> {
     let projects = get_projects_from_directories(directories)?;
 
     if projects.is_empty() {
-        println!("No projects found");
+        app.report("No projects found");
         return Ok(());
     }
 
@@ -43,7 +44,7 @@ pub fn cmd_select<E: CommandExecutor>(app: &App<E>) -> Result<()> {
         Some(out) => out,
         None => {
             info!("Skim failed to start");
-            println!("Selection failed");
+            app.report("Selection failed");
             return Ok(());
         }
     };
@@ -72,21 +73,41 @@ pub fn cmd_select<E: CommandExecutor>(app: &App<E>) -> Result<()> {
                     project_name, project_path
                 );
 
+                let project = app
+                    .config
+                    .session_project(&project_name, Some(&project_path));
+
                 // Check if session exists
-                match app.kitty.match_session_tab(&project_name) {
-                    Ok(Some(existing_tab)) => {
+                match app.kitty.match_session_tab(&project_name)? {
+                    Some(existing_tab) => {
                         info!("Session already exists, focusing existing tab");
                         app.kitty.focus_tab(existing_tab.id)?;
-                        println!("Switched to existing session: {}", project_name);
+                        app.report(format!("Switched to existing session: {}", project_name));
+
+                        if let Some(project) = &project {
+                            let hook_context = HookContext::new(&project_name)
+                                .with_path(&project_path)
+                                .with_tab(existing_tab.id);
+                            app.run_hooks(HookKind::Focus, project.on_focus(), &hook_context);
+                        }
                     }
-                    _ => {
+                    None => {
                         info!("No existing session found, creating new one");
-                        app.kitty
-                            .create_session_tab_by_path(&project_path, &project_name)?;
-                        println!(
+                        let project_env =
+                            app.config.session_env(&project_name, Some(&project_path));
+                        let on_create = project.as_ref().map(|p| p.on_create()).unwrap_or_default();
+                        let windows = project.as_ref().map(|p| p.windows()).unwrap_or_default();
+                        app.kitty.create_session_tab_by_path(
+                            &project_path,
+                            &project_name,
+                            &project_env,
+                            on_create,
+                            windows,
+                        )?;
+                        app.report(format!(
                             "Created and switched to new session: {} ({})",
                             project_name, project_path
-                        );
+                        ));
                     }
                 }
 
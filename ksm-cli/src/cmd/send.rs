@@ -0,0 +1,256 @@
+use anyhow::{Result, anyhow};
+use kitty_lib::{CommandExecutor, KittenSendTextCommand, KittyTab, KittyWindow, WindowId};
+use log::{info, warn};
+
+use crate::app::App;
+use crate::session::{SessionContext, SessionUtils};
+
+/// Send text to the active window of a session's last active tab
+pub fn cmd_send<E: CommandExecutor>(
+    app: &App<E>,
+    session_name: &str,
+    text: &str,
+    no_enter: bool,
+) -> Result<()> {
+    let session_tabs = find_session_tabs(app, session_name)?;
+
+    let window_id = SessionUtils::last_active_tab(session_name, &session_tabs)
+        .and_then(active_window)
+        .map(|window| window.id)
+        .ok_or_else(|| anyhow!("Session '{}' has no windows", session_name))?;
+
+    info!(
+        "Sending text to window {} of session '{}'",
+        window_id, session_name
+    );
+
+    let command = KittenSendTextCommand::new(with_enter(text, no_enter))
+        .for_window_id(window_id)
+        .from_stdin();
+    app.kitty.send_text(command)
+}
+
+/// Send text to every window of the current session (or specified session)
+pub fn cmd_broadcast<E: CommandExecutor>(
+    app: &App<E>,
+    session_name: Option<&str>,
+    text: &str,
+    exclude_active: bool,
+    no_enter: bool,
+) -> Result<()> {
+    cmd_broadcast_with_context(app, session_name, text, exclude_active, no_enter, || {
+        app.kitty.detect_session()
+    })
+}
+
+/// Broadcast text with injectable session detection
+pub fn cmd_broadcast_with_context<E: CommandExecutor, F>(
+    app: &App<E>,
+    session_name: Option<&str>,
+    text: &str,
+    exclude_active: bool,
+    no_enter: bool,
+    detect_session: F,
+) -> Result<()>
+where
+    F: FnOnce() -> Result<SessionContext>,
+{
+    let session_name = match session_name {
+        Some(name) => name.to_string(),
+        None => {
+            let context = detect_session()?;
+            if !context.is_explicit {
+                warn!(
+                    "No active session detected. Use --session <name> to specify a session, or run from within a session context."
+                );
+                return Ok(());
+            }
+            context.session_name
+        }
+    };
+
+    let session_tabs = find_session_tabs(app, &session_name)?;
+
+    // One send-text call for the whole session, matching every window by id
+    let window_ids: Vec<WindowId> = session_tabs
+        .iter()
+        .flat_map(|tab| tab.windows.iter().map(|window| window.id))
+        .collect();
+    let match_expression = window_ids
+        .iter()
+        .map(|id| format!("id:{}", id))
+        .collect::<Vec<_>>()
+        .join(" or ");
+
+    info!(
+        "Broadcasting text to {} window(s) of session '{}'",
+        window_ids.len(),
+        session_name
+    );
+
+    let mut command = KittenSendTextCommand::new(with_enter(text, no_enter))
+        .with_match(match_expression)
+        .from_stdin();
+    if exclude_active {
+        command = command.exclude_active();
+    }
+    app.kitty.send_text(command)
+}
+
+/// The window of a tab that has (or last had) focus: the active window, then the most recent
+/// one in the tab's `active_window_history`, then its first window
+fn active_window(tab: &KittyTab) -> Option<&KittyWindow> {
+    let window = |id: &WindowId| tab.windows.iter().find(|window| window.id == *id);
+
+    tab.windows
+        .iter()
+        .find(|window| window.is_active)
+        .or_else(|| tab.active_window_history.iter().rev().find_map(window))
+        .or(tab.windows.first())
+}
+
+fn find_session_tabs<E: CommandExecutor>(
+    app: &App<E>,
+    session_name: &str,
+) -> Result<Vec<KittyTab>> {
+    let session_tabs = app
+        .kitty
+        .get_session_tabs(&SessionContext::new(session_name))?;
+
+    if session_tabs.is_empty() {
+        return Err(anyhow!(
+            "Session '{}' not found or has no tabs",
+            session_name
+        ));
+    }
+
+    Ok(session_tabs)
+}
+
+/// Terminate the text with a carriage return so shells run it, unless told not to
+fn with_enter(text: &str, no_enter: bool) -> String {
+    if no_enter {
+        text.to_string()
+    } else {
+        format!("{}\r", text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::test_utils::create_test_app_with_executor;
+    use kitty_lib::MockExecutor;
+
+    #[test]
+    fn test_cmd_send_targets_one_session_window() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_test_app_with_executor(&mock_executor);
+        mock_executor.add_session_tab("send-project", None);
+        mock_executor.add_session_tab("send-project", None);
+
+        cmd_send(&app, "send-project", "make build", false)?;
+
+        let calls = mock_executor.get_send_text_calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].match_pattern, Some("id:1".to_string()));
+        assert!(calls[0].from_stdin);
+        assert_eq!(mock_executor.get_sent_text(1), vec!["make build\r"]);
+        assert!(mock_executor.get_sent_text(2).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmd_send_targets_the_active_window_of_the_tab() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_test_app_with_executor(&mock_executor);
+        let tab_id = mock_executor.add_session_tab("send-project", None);
+        let second = {
+            let mut layout = mock_executor.layout.borrow_mut();
+            let second = layout.add_window(tab_id, &[]).unwrap();
+            layout.focus_window(second);
+            second
+        };
+
+        cmd_send(&app, "send-project", "make build", false)?;
+
+        assert_eq!(
+            mock_executor.get_send_text_calls()[0].match_pattern,
+            Some(format!("id:{}", second))
+        );
+        assert_eq!(mock_executor.get_sent_text(second), vec!["make build\r"]);
+        assert!(mock_executor.get_sent_text(1).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_active_window_falls_back_to_focus_history() {
+        let mut tab: KittyTab = serde_json::from_str(
+            r#"{"id": 1, "title": "session:api", "active_window_history": [3, 2, 9],
+                "windows": [
+                    {"id": 2, "title": "zsh", "pid": 1, "cwd": "/", "cmdline": []},
+                    {"id": 3, "title": "vim", "pid": 2, "cwd": "/", "cmdline": []}
+                ]}"#,
+        )
+        .unwrap();
+
+        // Window 9 has closed, so the most recent window still in the tab wins
+        assert_eq!(active_window(&tab).map(|w| w.id), Some(WindowId(2)));
+
+        tab.windows[1].is_active = true;
+        assert_eq!(active_window(&tab).map(|w| w.id), Some(WindowId(3)));
+
+        tab.windows[1].is_active = false;
+        tab.active_window_history.clear();
+        assert_eq!(active_window(&tab).map(|w| w.id), Some(WindowId(2)));
+    }
+
+    #[test]
+    fn test_cmd_broadcast_reaches_every_session_window() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_test_app_with_executor(&mock_executor);
+        mock_executor.add_session_tab("broadcast-project", None);
+        mock_executor.add_session_tab("other-project", None);
+        mock_executor.add_session_tab("broadcast-project", None);
+
+        cmd_broadcast_with_context(&app, None, "git pull", false, false, || {
+            Ok(SessionContext::new("broadcast-project"))
+        })?;
+
+        let calls = mock_executor.get_send_text_calls();
+        assert_eq!(calls.len(), 1);
+        assert_eq!(calls[0].match_pattern, Some("id:1 or id:3".to_string()));
+        assert_eq!(mock_executor.get_sent_text(1), vec!["git pull\r"]);
+        assert_eq!(mock_executor.get_sent_text(3), vec!["git pull\r"]);
+        assert!(mock_executor.get_sent_text(2).is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmd_broadcast_exclude_active_and_no_enter() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_test_app_with_executor(&mock_executor);
+        mock_executor.add_session_tab("broadcast-active", None);
+        mock_executor.add_session_tab("broadcast-active", None);
+
+        cmd_broadcast(&app, Some("broadcast-active"), "clear", true, true)?;
+
+        assert!(mock_executor.get_sent_text(1).is_empty());
+        assert_eq!(mock_executor.get_sent_text(2), vec!["clear"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cmd_send_unknown_session_fails() {
+        let mock_executor = MockExecutor::new();
+        let (app, _temp_dir) = create_test_app_with_executor(&mock_executor);
+
+        assert!(cmd_send(&app, "missing", "ls", false).is_err());
+        assert!(cmd_broadcast(&app, Some("missing"), "ls", false, false).is_err());
+        assert_eq!(mock_executor.send_text_call_count(), 0);
+    }
+}
@@ -15,22 +15,22 @@ pub fn select_auto_profile(auto_profile_rules: &[AutoProfileRule]) -> Result<Opt
 
 fn rule_matches(rule: &AutoProfileRule) -> Result<bool> {
     // Check hostname regex
-    if let Some(ref hostname_regex) = rule.hostname_regex {
-        if let Ok(hostname) = hostname::get() {
-            let regex = Regex::new(hostname_regex)?;
-            if regex.is_match(&hostname.to_string_lossy()) {
-                return Ok(true);
-            }
+    if let Some(ref hostname_regex) = rule.hostname_regex
+        && let Ok(hostname) = hostname::get()
+    {
+        let regex = Regex::new(hostname_regex)?;
+        if regex.is_match(&hostname.to_string_lossy()) {
+            return Ok(true);
         }
     }
 
     // Check environment variables
     if let Some(ref env_vars) = rule.env {
         for (key, expected_value) in env_vars {
-            if let Ok(actual_value) = env::var(key) {
-                if &actual_value == expected_value {
-                    return Ok(true);
-                }
+            if let Ok(actual_value) = env::var(key)
+                && &actual_value == expected_value
+            {
+                return Ok(true);
             }
         }
     }
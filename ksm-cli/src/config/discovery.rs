@@ -24,10 +24,10 @@ impl Config {
                         for entry in paths {
                             match entry {
                                 Ok(path) => {
-                                    if path.is_dir() {
-                                        if let Some(path_str) = path.to_str() {
-                                            expanded_dirs.push(path_str.to_string());
-                                        }
+                                    if path.is_dir()
+                                        && let Some(path_str) = path.to_str()
+                                    {
+                                        expanded_dirs.push(path_str.to_string());
                                     }
                                 }
                                 Err(e) => {
@@ -87,10 +87,10 @@ impl Config {
         current_depth: u32,
     ) -> Result<()> {
         // Check max_depth limit
-        if let Some(max_depth) = search_config.max_depth {
-            if current_depth >= max_depth {
-                return Ok(());
-            }
+        if let Some(max_depth) = search_config.max_depth
+            && current_depth >= max_depth
+        {
+            return Ok(());
         }
 
         let git_dir = dir.join(".git");
@@ -105,32 +105,31 @@ impl Config {
         if let Ok(entries) = fs::read_dir(dir) {
             for entry in entries.flatten() {
                 let path = entry.path();
-                if path.is_dir() {
-                    if let Some(dir_name) = path.file_name() {
-                        let dir_name_str = dir_name.to_string_lossy();
-
-                        // Skip hidden directories
-                        if dir_name_str.starts_with('.') {
-                            continue;
-                        }
-
-                        // Check exclude patterns
-                        if let Some(ref exclude_patterns) = search_config.exclude {
-                            if exclude_patterns
-                                .iter()
-                                .any(|pattern| dir_name_str.contains(pattern))
-                            {
-                                continue;
-                            }
-                        }
+                if path.is_dir()
+                    && let Some(dir_name) = path.file_name()
+                {
+                    let dir_name_str = dir_name.to_string_lossy();
+
+                    // Skip hidden directories
+                    if dir_name_str.starts_with('.') {
+                        continue;
+                    }
 
-                        Self::find_git_projects_recursive_helper(
-                            &path,
-                            git_projects,
-                            search_config,
-                            current_depth + 1,
-                        )?;
+                    // Check exclude patterns
+                    if let Some(ref exclude_patterns) = search_config.exclude
+                        && exclude_patterns
+                            .iter()
+                            .any(|pattern| dir_name_str.contains(pattern))
+                    {
+                        continue;
                     }
+
+                    Self::find_git_projects_recursive_helper(
+                        &path,
+                        git_projects,
+                        search_config,
+                        current_depth + 1,
+                    )?;
                 }
             }
         }
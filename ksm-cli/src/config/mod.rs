@@ -1,9 +1,12 @@
 mod auto_profile;
 mod discovery;
+mod project_env;
+#[cfg(test)]
+pub(crate) mod test_helpers;
 pub mod types;
 
 use types::*;
-pub use types::{KeyedProject, ProjectDefinition};
+pub use types::{KeyedProject, KittyConfig, ProjectDefinition, ProjectWindow, TaskDefinition};
 
 use anyhow::Result;
 use log::{debug, error, info};
@@ -24,6 +27,7 @@ pub struct Config {
     profiles: HashMap<String, ProfileConfig>,
     #[allow(dead_code)]
     auto_profile_rules: Vec<AutoProfileRule>,
+    kitty: KittyConfig,
 
     // Runtime state
     selected_profiles: Vec<String>,
@@ -71,6 +75,7 @@ impl Config {
         let base_session = data.session.unwrap_or_default();
         let profiles = data.profiles.unwrap_or_default();
         let auto_profile_rules = data.auto_profile.map(|ap| ap.rules).unwrap_or_default();
+        let kitty = data.kitty.unwrap_or_default();
 
         // Determine which profiles to use
         let selected_profiles = if let Some(manual_profiles) = manual_profile {
@@ -110,6 +115,7 @@ impl Config {
             base_session,
             profiles,
             auto_profile_rules,
+            kitty,
             selected_profiles,
         })
     }
@@ -136,31 +142,31 @@ impl Config {
                 let profile_chain = self.build_profile_chain(profile_name);
 
                 for chain_profile_name in profile_chain {
-                    if let Some(chain_profile) = self.profiles.get(&chain_profile_name) {
-                        if let Some(ref search) = chain_profile.search {
-                            // Merge arrays by concatenation
-                            if let Some(ref profile_dirs) = search.dirs {
-                                result
-                                    .dirs
-                                    .get_or_insert_with(Vec::new)
-                                    .extend(profile_dirs.clone());
-                            }
-                            if let Some(ref profile_vsc) = search.vsc {
-                                result
-                                    .vsc
-                                    .get_or_insert_with(Vec::new)
-                                    .extend(profile_vsc.clone());
-                            }
-                            // Override scalar values
-                            if search.max_depth.is_some() {
-                                result.max_depth = search.max_depth;
-                            }
-                            if let Some(ref profile_exclude) = search.exclude {
-                                result
-                                    .exclude
-                                    .get_or_insert_with(Vec::new)
-                                    .extend(profile_exclude.clone());
-                            }
+                    if let Some(chain_profile) = self.profiles.get(&chain_profile_name)
+                        && let Some(ref search) = chain_profile.search
+                    {
+                        // Merge arrays by concatenation
+                        if let Some(ref profile_dirs) = search.dirs {
+                            result
+                                .dirs
+                                .get_or_insert_with(Vec::new)
+                                .extend(profile_dirs.clone());
+                        }
+                        if let Some(ref profile_vsc) = search.vsc {
+                            result
+                                .vsc
+                                .get_or_insert_with(Vec::new)
+                                .extend(profile_vsc.clone());
+                        }
+                        // Override scalar values
+                        if search.max_depth.is_some() {
+                            result.max_depth = search.max_depth;
+                        }
+                        if let Some(ref profile_exclude) = search.exclude {
+                            result
+                                .exclude
+                                .get_or_insert_with(Vec::new)
+                                .extend(profile_exclude.clone());
                         }
                     }
                 }
@@ -178,11 +184,11 @@ impl Config {
                 let profile_chain = self.build_profile_chain(profile_name);
 
                 for chain_profile_name in profile_chain {
-                    if let Some(chain_profile) = self.profiles.get(&chain_profile_name) {
-                        if let Some(ref projects) = chain_profile.projects {
-                            // Later profiles override earlier ones
-                            result.extend(projects.clone());
-                        }
+                    if let Some(chain_profile) = self.profiles.get(&chain_profile_name)
+                        && let Some(ref projects) = chain_profile.projects
+                    {
+                        // Later profiles override earlier ones
+                        result.extend(projects.clone());
                     }
                 }
             }
@@ -199,11 +205,11 @@ impl Config {
                 let profile_chain = self.build_profile_chain(profile_name);
 
                 for chain_profile_name in profile_chain {
-                    if let Some(chain_profile) = self.profiles.get(&chain_profile_name) {
-                        if let Some(ref keys) = chain_profile.keys {
-                            // Later profiles override earlier ones
-                            result.extend(keys.clone());
-                        }
+                    if let Some(chain_profile) = self.profiles.get(&chain_profile_name)
+                        && let Some(ref keys) = chain_profile.keys
+                    {
+                        // Later profiles override earlier ones
+                        result.extend(keys.clone());
                     }
                 }
             }
@@ -220,31 +226,29 @@ impl Config {
                 let profile_chain = self.build_profile_chain(profile_name);
 
                 for chain_profile_name in profile_chain {
-                    if let Some(chain_profile) = self.profiles.get(&chain_profile_name) {
-                        if let Some(ref session) = chain_profile.session {
-                            // Merge navigation config
-                            if let Some(ref profile_navigation) = session.navigation {
-                                let base_navigation = result
-                                    .navigation
-                                    .get_or_insert_with(NavigationConfig::default);
-                                if profile_navigation.wrap_tabs.is_some() {
-                                    base_navigation.wrap_tabs = profile_navigation.wrap_tabs;
-                                }
+                    if let Some(chain_profile) = self.profiles.get(&chain_profile_name)
+                        && let Some(ref session) = chain_profile.session
+                    {
+                        // Merge navigation config
+                        if let Some(ref profile_navigation) = session.navigation {
+                            let base_navigation = result
+                                .navigation
+                                .get_or_insert_with(NavigationConfig::default);
+                            if profile_navigation.wrap_tabs.is_some() {
+                                base_navigation.wrap_tabs = profile_navigation.wrap_tabs;
                             }
+                        }
 
-                            // Merge unnamed session config
-                            if let Some(ref profile_unnamed) = session.unnamed_session {
-                                let base_unnamed = result
-                                    .unnamed_session
-                                    .get_or_insert_with(UnnamedSessionConfig::default);
-                                if profile_unnamed.treat_as_session.is_some() {
-                                    base_unnamed.treat_as_session =
-                                        profile_unnamed.treat_as_session;
-                                }
-                                if profile_unnamed.enable_navigation.is_some() {
-                                    base_unnamed.enable_navigation =
-                                        profile_unnamed.enable_navigation;
-                                }
+                        // Merge unnamed session config
+                        if let Some(ref profile_unnamed) = session.unnamed_session {
+                            let base_unnamed = result
+                                .unnamed_session
+                                .get_or_insert_with(UnnamedSessionConfig::default);
+                            if profile_unnamed.treat_as_session.is_some() {
+                                base_unnamed.treat_as_session = profile_unnamed.treat_as_session;
+                            }
+                            if profile_unnamed.enable_navigation.is_some() {
+                                base_unnamed.enable_navigation = profile_unnamed.enable_navigation;
                             }
                         }
                     }
@@ -260,6 +264,11 @@ impl Config {
         self.resolved_session()
     }
 
+    /// Get the settings for talking to kitty
+    pub fn kitty_config(&self) -> KittyConfig {
+        self.kitty.clone()
+    }
+
     /// Get whether tab navigation should wrap around by default
     pub fn default_wrap_tabs(&self) -> bool {
         self.resolved_session()
@@ -341,434 +350,6 @@ mod tests {
     use assert_fs::TempDir;
     use assert_fs::prelude::*;
     use std::path::PathBuf;
-
-    mod test_helpers {
-        use super::*;
-
-        pub struct ConfigBuilder {
-            global_version: Option<String>,
-            search_dirs: Vec<String>,
-            search_vsc: Vec<String>,
-            search_max_depth: Option<u32>,
-            search_exclude: Vec<String>,
-            base_projects: Vec<(String, String)>,
-            base_keys: Vec<(String, String)>,
-            profiles: Vec<ProfileBuilder>,
-            auto_profile_rules: Vec<AutoProfileRuleBuilder>,
-        }
-
-        pub struct ProfileBuilder {
-            name: String,
-            extends: Option<String>,
-            extends_disabled: bool,
-            search_dirs: Vec<String>,
-            search_vsc: Vec<String>,
-            search_max_depth: Option<u32>,
-            search_exclude: Vec<String>,
-            projects: Vec<(String, String)>,
-            detailed_projects: Vec<(String, String, String)>, // (name, path, description)
-            keys: Vec<(String, String)>,
-        }
-
-        pub struct AutoProfileRuleBuilder {
-            hostname_regex: Option<String>,
-            env: Vec<(String, String)>,
-            ssh_session: Option<bool>,
-            default: Option<bool>,
-            profile: String,
-        }
-
-        #[allow(dead_code)]
-        impl ConfigBuilder {
-            pub fn new() -> Self {
-                Self {
-                    global_version: Some("1.0".to_string()),
-                    search_dirs: Vec::new(),
-                    search_vsc: Vec::new(),
-                    search_max_depth: None,
-                    search_exclude: Vec::new(),
-                    base_projects: Vec::new(),
-                    base_keys: Vec::new(),
-                    profiles: Vec::new(),
-                    auto_profile_rules: Vec::new(),
-                }
-            }
-
-            pub fn version(mut self, version: &str) -> Self {
-                self.global_version = Some(version.to_string());
-                self
-            }
-
-            pub fn search_dirs(mut self, dirs: Vec<&str>) -> Self {
-                self.search_dirs = dirs.into_iter().map(|s| s.to_string()).collect();
-                self
-            }
-
-            pub fn search_vsc(mut self, vsc: Vec<&str>) -> Self {
-                self.search_vsc = vsc.into_iter().map(|s| s.to_string()).collect();
-                self
-            }
-
-            pub fn search_max_depth(mut self, depth: u32) -> Self {
-                self.search_max_depth = Some(depth);
-                self
-            }
-
-            pub fn search_exclude(mut self, exclude: Vec<&str>) -> Self {
-                self.search_exclude = exclude.into_iter().map(|s| s.to_string()).collect();
-                self
-            }
-
-            pub fn base_project(mut self, name: &str, path: &str) -> Self {
-                self.base_projects
-                    .push((name.to_string(), path.to_string()));
-                self
-            }
-
-            pub fn base_key(mut self, key: &str, project: &str) -> Self {
-                self.base_keys.push((key.to_string(), project.to_string()));
-                self
-            }
-
-            pub fn profile(mut self, profile: ProfileBuilder) -> Self {
-                self.profiles.push(profile);
-                self
-            }
-
-            pub fn auto_profile_rule(mut self, rule: AutoProfileRuleBuilder) -> Self {
-                self.auto_profile_rules.push(rule);
-                self
-            }
-
-            pub fn build_toml(&self) -> String {
-                let mut toml = String::new();
-
-                // Global section
-                if let Some(ref version) = self.global_version {
-                    toml.push_str(&format!("[global]\nversion = \"{}\"\n\n", version));
-                }
-
-                // Search section
-                if !self.search_dirs.is_empty()
-                    || !self.search_vsc.is_empty()
-                    || self.search_max_depth.is_some()
-                    || !self.search_exclude.is_empty()
-                {
-                    toml.push_str("[search]\n");
-                    if !self.search_dirs.is_empty() {
-                        let dirs_str = self
-                            .search_dirs
-                            .iter()
-                            .map(|d| format!("\"{}\"", d))
-                            .collect::<Vec<_>>()
-                            .join(", ");
-                        toml.push_str(&format!("dirs = [{}]\n", dirs_str));
-                    }
-                    if !self.search_vsc.is_empty() {
-                        let vsc_str = self
-                            .search_vsc
-                            .iter()
-                            .map(|v| format!("\"{}\"", v))
-                            .collect::<Vec<_>>()
-                            .join(", ");
-                        toml.push_str(&format!("vsc = [{}]\n", vsc_str));
-                    }
-                    if let Some(depth) = self.search_max_depth {
-                        toml.push_str(&format!("max_depth = {}\n", depth));
-                    }
-                    if !self.search_exclude.is_empty() {
-                        let exclude_str = self
-                            .search_exclude
-                            .iter()
-                            .map(|e| format!("\"{}\"", e))
-                            .collect::<Vec<_>>()
-                            .join(", ");
-                        toml.push_str(&format!("exclude = [{}]\n", exclude_str));
-                    }
-                    toml.push('\n');
-                }
-
-                // Base projects section
-                if !self.base_projects.is_empty() {
-                    toml.push_str("[projects]\n");
-                    for (name, path) in &self.base_projects {
-                        toml.push_str(&format!("{} = \"{}\"\n", name, path));
-                    }
-                    toml.push('\n');
-                }
-
-                // Base keys section
-                if !self.base_keys.is_empty() {
-                    toml.push_str("[keys]\n");
-                    for (key, project) in &self.base_keys {
-                        toml.push_str(&format!("{} = \"{}\"\n", key, project));
-                    }
-                    toml.push('\n');
-                }
-
-                // Profiles
-                for profile in &self.profiles {
-                    toml.push_str(&profile.build_toml());
-                }
-
-                // Auto profile rules
-                if !self.auto_profile_rules.is_empty() {
-                    toml.push_str("[auto_profile]\n\n");
-                    for rule in &self.auto_profile_rules {
-                        toml.push_str(&rule.build_toml());
-                    }
-                }
-
-                toml
-            }
-
-            pub fn write_to_temp_file(&self, temp: &TempDir, filename: &str) -> std::path::PathBuf {
-                let file_path = temp.path().join(filename);
-                temp.child(filename).write_str(&self.build_toml()).unwrap();
-                file_path
-            }
-        }
-
-        #[allow(dead_code)]
-        impl ProfileBuilder {
-            pub fn new(name: &str) -> Self {
-                Self {
-                    name: name.to_string(),
-                    extends: None,
-                    extends_disabled: false,
-                    search_dirs: Vec::new(),
-                    search_vsc: Vec::new(),
-                    search_max_depth: None,
-                    search_exclude: Vec::new(),
-                    projects: Vec::new(),
-                    detailed_projects: Vec::new(),
-                    keys: Vec::new(),
-                }
-            }
-
-            pub fn extends(mut self, profile: &str) -> Self {
-                self.extends = Some(profile.to_string());
-                self
-            }
-
-            pub fn extends_disabled(mut self) -> Self {
-                self.extends_disabled = true;
-                self
-            }
-
-            pub fn search_dirs(mut self, dirs: Vec<&str>) -> Self {
-                self.search_dirs = dirs.into_iter().map(|s| s.to_string()).collect();
-                self
-            }
-
-            pub fn search_vsc(mut self, vsc: Vec<&str>) -> Self {
-                self.search_vsc = vsc.into_iter().map(|s| s.to_string()).collect();
-                self
-            }
-
-            pub fn search_max_depth(mut self, depth: u32) -> Self {
-                self.search_max_depth = Some(depth);
-                self
-            }
-
-            pub fn search_exclude(mut self, exclude: Vec<&str>) -> Self {
-                self.search_exclude = exclude.into_iter().map(|s| s.to_string()).collect();
-                self
-            }
-
-            pub fn project(mut self, name: &str, path: &str) -> Self {
-                self.projects.push((name.to_string(), path.to_string()));
-                self
-            }
-
-            pub fn detailed_project(mut self, name: &str, path: &str, description: &str) -> Self {
-                self.detailed_projects.push((
-                    name.to_string(),
-                    path.to_string(),
-                    description.to_string(),
-                ));
-                self
-            }
-
-            pub fn key(mut self, key: &str, project: &str) -> Self {
-                self.keys.push((key.to_string(), project.to_string()));
-                self
-            }
-
-            fn build_toml(&self) -> String {
-                let mut toml = String::new();
-
-                // Profile header with extends
-                if self.extends_disabled {
-                    toml.push_str(&format!("[profiles.{}]\nextends = false\n\n", self.name));
-                } else if let Some(ref extends) = self.extends {
-                    toml.push_str(&format!(
-                        "[profiles.{}]\nextends = '{}'\n\n",
-                        self.name, extends
-                    ));
-                }
-
-                // Profile search section
-                if !self.search_dirs.is_empty()
-                    || !self.search_vsc.is_empty()
-                    || self.search_max_depth.is_some()
-                    || !self.search_exclude.is_empty()
-                {
-                    toml.push_str(&format!("[profiles.{}.search]\n", self.name));
-                    if !self.search_dirs.is_empty() {
-                        let dirs_str = self
-                            .search_dirs
-                            .iter()
-                            .map(|d| format!("\"{}\"", d))
-                            .collect::<Vec<_>>()
-                            .join(", ");
-                        toml.push_str(&format!("dirs = [{}]\n", dirs_str));
-                    }
-                    if !self.search_vsc.is_empty() {
-                        let vsc_str = self
-                            .search_vsc
-                            .iter()
-                            .map(|v| format!("\"{}\"", v))
-                            .collect::<Vec<_>>()
-                            .join(", ");
-                        toml.push_str(&format!("vsc = [{}]\n", vsc_str));
-                    }
-                    if let Some(depth) = self.search_max_depth {
-                        toml.push_str(&format!("max_depth = {}\n", depth));
-                    }
-                    if !self.search_exclude.is_empty() {
-                        let exclude_str = self
-                            .search_exclude
-                            .iter()
-                            .map(|e| format!("\"{}\"", e))
-                            .collect::<Vec<_>>()
-                            .join(", ");
-                        toml.push_str(&format!("exclude = [{}]\n", exclude_str));
-                    }
-                    toml.push('\n');
-                }
-
-                // Profile projects section
-                if !self.projects.is_empty() || !self.detailed_projects.is_empty() {
-                    toml.push_str(&format!("[profiles.{}.projects]\n", self.name));
-                    for (name, path) in &self.projects {
-                        toml.push_str(&format!("{} = \"{}\"\n", name, path));
-                    }
-                    toml.push('\n');
-
-                    // Detailed projects as tables
-                    for (name, path, description) in &self.detailed_projects {
-                        toml.push_str(&format!("[profiles.{}.projects.{}]\n", self.name, name));
-                        toml.push_str(&format!("path = \"{}\"\n", path));
-                        toml.push_str(&format!("description = \"{}\"\n\n", description));
-                    }
-                }
-
-                // Profile keys section
-                if !self.keys.is_empty() {
-                    toml.push_str(&format!("[profiles.{}.keys]\n", self.name));
-                    for (key, project) in &self.keys {
-                        toml.push_str(&format!("{} = \"{}\"\n", key, project));
-                    }
-                    toml.push('\n');
-                }
-
-                toml
-            }
-        }
-
-        #[allow(dead_code)]
-        impl AutoProfileRuleBuilder {
-            pub fn new(profile: &str) -> Self {
-                Self {
-                    hostname_regex: None,
-                    env: Vec::new(),
-                    ssh_session: None,
-                    default: None,
-                    profile: profile.to_string(),
-                }
-            }
-
-            pub fn hostname_regex(mut self, regex: &str) -> Self {
-                self.hostname_regex = Some(regex.to_string());
-                self
-            }
-
-            pub fn env_var(mut self, key: &str, value: &str) -> Self {
-                self.env.push((key.to_string(), value.to_string()));
-                self
-            }
-
-            pub fn ssh_session(mut self, is_ssh: bool) -> Self {
-                self.ssh_session = Some(is_ssh);
-                self
-            }
-
-            pub fn default_rule(mut self) -> Self {
-                self.default = Some(true);
-                self
-            }
-
-            fn build_toml(&self) -> String {
-                let mut toml = String::new();
-                toml.push_str("[[auto_profile.rules]]\n");
-
-                if let Some(ref regex) = self.hostname_regex {
-                    toml.push_str(&format!("hostname_regex = \"{}\"\n", regex));
-                }
-
-                if !self.env.is_empty() {
-                    toml.push_str("env = { ");
-                    let env_pairs: Vec<String> = self
-                        .env
-                        .iter()
-                        .map(|(k, v)| format!("{} = \"{}\"", k, v))
-                        .collect();
-                    toml.push_str(&env_pairs.join(", "));
-                    toml.push_str(" }\n");
-                }
-
-                if let Some(ssh) = self.ssh_session {
-                    toml.push_str(&format!("ssh_session = {}\n", ssh));
-                }
-
-                if let Some(default) = self.default {
-                    toml.push_str(&format!("default = {}\n", default));
-                }
-
-                toml.push_str(&format!("profile = \"{}\"\n\n", self.profile));
-                toml
-            }
-        }
-
-        // Convenience functions for common test patterns
-        pub fn simple_config() -> ConfigBuilder {
-            ConfigBuilder::new()
-                .base_project("dots", "~/dotfiles")
-                .base_key("P1", "dots")
-        }
-
-        pub fn config_with_profiles() -> ConfigBuilder {
-            ConfigBuilder::new()
-                .base_project("dots", "~/dotfiles")
-                .base_key("P1", "dots")
-                .profile(
-                    ProfileBuilder::new("work")
-                        .project("frontend", "~/work/frontend")
-                        .project("backend", "~/work/backend")
-                        .key("P2", "frontend")
-                        .key("P3", "backend"),
-                )
-        }
-
-        #[allow(dead_code)]
-        pub fn config_with_search() -> ConfigBuilder {
-            ConfigBuilder::new()
-                .search_dirs(vec!["~/project1", "~/project2"])
-                .search_vsc(vec!["~/dev", "~/work"])
-        }
-    }
-
     use test_helpers::*;
 
     #[test]
@@ -1166,8 +747,9 @@ mod tests {
             panic!("Expected Simple project definition for frontend");
         }
 
-        if let ProjectDefinition::Detailed { path, description } =
-            work_projects.get("backend").unwrap()
+        if let ProjectDefinition::Detailed {
+            path, description, ..
+        } = work_projects.get("backend").unwrap()
         {
             assert_eq!(path, "~/work/app/go_be");
             assert_eq!(description, &Some("current backend project".to_string()));
@@ -1175,6 +757,16 @@ mod tests {
             panic!("Expected Detailed project definition for backend");
         }
 
+        let backend_windows = work_projects.get("backend").unwrap().windows();
+        assert_eq!(backend_windows.len(), 1);
+        assert_eq!(backend_windows[0].command, vec!["nvim", "."]);
+        assert_eq!(
+            backend_windows[0].location,
+            Some(kitty_lib::WindowLocation::Vsplit)
+        );
+        assert_eq!(backend_windows[0].bias, Some(60.0));
+        assert!(!backend_windows[0].hold);
+
         // Keys should be overridden by work profile
         assert_eq!(work_keys.get("P1"), Some(&"dots".to_string())); // from personal (which got it from base)
         assert_eq!(work_keys.get("P2"), Some(&"frontend".to_string())); // overridden by work
@@ -1216,4 +808,128 @@ mod tests {
             panic!("Expected exclude patterns in remote profile");
         }
     }
+
+    #[test]
+    fn test_session_env_loads_env_file_then_inline_overrides() {
+        let temp = TempDir::new().unwrap();
+        let project_dir = temp.child("api");
+        project_dir.create_dir_all().unwrap();
+        project_dir
+            .child(".env")
+            .write_str("# shared settings\nexport AWS_PROFILE=shared\nDATABASE_URL=\"postgres://localhost/api\"\n")
+            .unwrap();
+
+        let config_content = format!(
+            r#"[global]
+version = "1.0"
+
+[projects.api]
+path = "{}"
+env_file = ".env"
+env = {{ AWS_PROFILE = "dev", RUST_LOG = "debug" }}
+"#,
+            project_dir.path().display()
+        );
+        temp.child("config.toml")
+            .write_str(&config_content)
+            .unwrap();
+        let config = Config::load_from_path(Some(temp.path().join("config.toml")), None).unwrap();
+
+        let env = config.session_env("api", None);
+        assert_eq!(
+            env,
+            vec![
+                ("AWS_PROFILE".to_string(), "shared".to_string()),
+                (
+                    "DATABASE_URL".to_string(),
+                    "postgres://localhost/api".to_string()
+                ),
+                ("AWS_PROFILE".to_string(), "dev".to_string()),
+                ("RUST_LOG".to_string(), "debug".to_string()),
+            ]
+        );
+
+        // Sessions are named after the project directory, so path lookup must agree
+        let by_path = config.session_env("api", Some(&project_dir.path().to_string_lossy()));
+        assert_eq!(by_path, env);
+    }
+
+    #[test]
+    fn test_session_env_matches_directory_name_and_skips_missing_env_file() {
+        let temp = TempDir::new().unwrap();
+        let config_content = r#"[global]
+version = "1.0"
+
+[projects]
+plain = "/srv/plain"
+
+[projects.backend]
+path = "/srv/go_be"
+env_file = "does-not-exist.env"
+env = { AWS_PROFILE = "dev" }
+"#;
+        temp.child("config.toml").write_str(config_content).unwrap();
+        let config = Config::load_from_path(Some(temp.path().join("config.toml")), None).unwrap();
+
+        // Session named after the directory resolves to the "backend" project
+        assert!(config.project_for_session("go_be").is_some());
+        assert_eq!(
+            config.session_env("go_be", None),
+            vec![("AWS_PROFILE".to_string(), "dev".to_string())]
+        );
+
+        assert!(config.session_env("plain", None).is_empty());
+        assert!(config.session_env("unknown", None).is_empty());
+    }
+
+    #[test]
+    fn test_parse_dotenv() {
+        let content = r#"
+# comment
+export FOO=bar
+SPACED = value with spaces # trailing comment
+DOUBLE="line\nbreak"
+SINGLE='literal $HOME'
+EMPTY=
+malformed line
+"#;
+        assert_eq!(
+            project_env::parse_dotenv(content),
+            vec![
+                ("FOO".to_string(), "bar".to_string()),
+                ("SPACED".to_string(), "value with spaces".to_string()),
+                ("DOUBLE".to_string(), "line\nbreak".to_string()),
+                ("SINGLE".to_string(), "literal $HOME".to_string()),
+                ("EMPTY".to_string(), "".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_kitty_config_section() {
+        let temp = TempDir::new().unwrap();
+        temp.child("config.toml")
+            .write_str(
+                r#"[global]
+version = "1.0"
+
+[kitty]
+timeout_ms = 1500
+retries = 1
+"#,
+            )
+            .unwrap();
+        let config = Config::load_from_path(Some(temp.path().join("config.toml")), None).unwrap();
+
+        let kitty = config.kitty_config();
+        assert_eq!(kitty.timeout_ms, Some(1500));
+        assert_eq!(kitty.retries, Some(1));
+        assert_eq!(kitty.retry_backoff_ms, None);
+
+        temp.child("plain.toml")
+            .write_str("[global]\nversion = \"1.0\"\n")
+            .unwrap();
+        let config = Config::load_from_path(Some(temp.path().join("plain.toml")), None).unwrap();
+        assert_eq!(config.kitty_config().timeout_ms, None);
+    }
 }
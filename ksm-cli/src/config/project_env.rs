@@ -0,0 +1,171 @@
+use anyhow::Result;
+use log::{debug, warn};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::types::ProjectDefinition;
+use crate::Config;
+use crate::utils::expand_tilde;
+
+impl Config {
+    /// Find the project backing a session, matched by project name or by the basename of its path
+    pub fn project_for_session(&self, session_name: &str) -> Option<ProjectDefinition> {
+        let projects = self.resolved_projects();
+
+        if let Some(project) = projects.get(session_name) {
+            return Some(project.clone());
+        }
+
+        let mut matches: Vec<(&String, &ProjectDefinition)> = projects
+            .iter()
+            .filter(|(_, project)| {
+                Path::new(&expand_tilde(project.path()))
+                    .file_name()
+                    .is_some_and(|name| name == session_name)
+            })
+            .collect();
+        // Stable pick when several projects share a directory name
+        matches.sort_by(|a, b| a.0.cmp(b.0));
+        matches.first().map(|(_, project)| (*project).clone())
+    }
+
+    /// Find the project whose configured path resolves to `project_path`
+    pub fn project_for_path(&self, project_path: &str) -> Option<ProjectDefinition> {
+        let target = expand_tilde(project_path);
+        let target = target.trim_end_matches('/');
+
+        self.resolved_projects()
+            .into_values()
+            .find(|project| expand_tilde(project.path()).trim_end_matches('/') == target)
+    }
+
+    /// Find the project for a session, looked up by project path and falling back to session name
+    pub fn session_project(
+        &self,
+        session_name: &str,
+        project_path: Option<&str>,
+    ) -> Option<ProjectDefinition> {
+        project_path
+            .and_then(|path| self.project_for_path(path))
+            .or_else(|| self.project_for_session(session_name))
+    }
+
+    /// Environment variables for a session, looked up by project path and falling back to session name
+    ///
+    /// The `env_file` is loaded first so that inline `env` entries can override it. A missing or
+    /// unreadable env file is logged and skipped so it never blocks opening the session.
+    pub fn session_env(
+        &self,
+        session_name: &str,
+        project_path: Option<&str>,
+    ) -> Vec<(String, String)> {
+        let Some(project) = self.session_project(session_name, project_path) else {
+            debug!(
+                "No project configuration found for session '{}'",
+                session_name
+            );
+            return Vec::new();
+        };
+
+        match project_env(&project) {
+            Ok(env) => env,
+            Err(e) => {
+                warn!(
+                    "Failed to load environment for session '{}': {}",
+                    session_name, e
+                );
+                project
+                    .env()
+                    .map(|env| env.iter().map(|(k, v)| (k.clone(), v.clone())).collect())
+                    .unwrap_or_default()
+            }
+        }
+    }
+}
+
+/// Resolve a project's environment: `env_file` entries first, then inline `env` overrides
+pub fn project_env(project: &ProjectDefinition) -> Result<Vec<(String, String)>> {
+    let mut env = Vec::new();
+
+    if let Some(env_file) = project.env_file() {
+        let env_path = resolve_env_file(project.path(), env_file);
+        debug!("Loading project env file: {:?}", env_path);
+        let content = fs::read_to_string(&env_path).map_err(|e| {
+            anyhow::anyhow!("Failed to read env file {}: {}", env_path.display(), e)
+        })?;
+        env.extend(parse_dotenv(&content));
+    }
+
+    if let Some(inline) = project.env() {
+        env.extend(inline.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+
+    Ok(env)
+}
+
+fn resolve_env_file(project_path: &str, env_file: &str) -> PathBuf {
+    let env_file = PathBuf::from(expand_tilde(env_file));
+    if env_file.is_absolute() {
+        env_file
+    } else {
+        PathBuf::from(expand_tilde(project_path)).join(env_file)
+    }
+}
+
+/// Parse dotenv content into ordered `KEY=VALUE` pairs
+///
+/// Supports comments, blank lines, an optional `export ` prefix, single and double quoted
+/// values (with `\n`, `\t`, `\"` and `\\` escapes in double quotes) and trailing ` #` comments
+/// on unquoted values.
+pub fn parse_dotenv(content: &str) -> Vec<(String, String)> {
+    let mut vars = Vec::new();
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let line = line.strip_prefix("export ").unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            warn!("Ignoring malformed env file line: {}", line);
+            continue;
+        };
+
+        let key = key.trim();
+        if key.is_empty() {
+            continue;
+        }
+
+        vars.push((key.to_string(), parse_dotenv_value(value.trim())));
+    }
+
+    vars
+}
+
+fn parse_dotenv_value(value: &str) -> String {
+    if let Some(inner) = value.strip_prefix('"') {
+        let mut result = String::new();
+        let mut chars = inner.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => result.push('\n'),
+                    Some('t') => result.push('\t'),
+                    Some(other) => result.push(other),
+                    None => break,
+                },
+                other => result.push(other),
+            }
+        }
+        result
+    } else if let Some(inner) = value.strip_prefix('\'') {
+        inner.split('\'').next().unwrap_or_default().to_string()
+    } else {
+        match value.find(" #") {
+            Some(pos) => value[..pos].trim_end().to_string(),
+            None => value.to_string(),
+        }
+    }
+}
@@ -0,0 +1,491 @@
+//! Builders for the TOML configs used in tests
+
+use assert_fs::TempDir;
+use assert_fs::prelude::*;
+
+pub struct ConfigBuilder {
+    global_version: Option<String>,
+    search_dirs: Vec<String>,
+    search_vsc: Vec<String>,
+    search_max_depth: Option<u32>,
+    search_exclude: Vec<String>,
+    base_projects: Vec<(String, String)>,
+    detailed_projects: Vec<ProjectBuilder>,
+    base_keys: Vec<(String, String)>,
+    profiles: Vec<ProfileBuilder>,
+    auto_profile_rules: Vec<AutoProfileRuleBuilder>,
+}
+
+pub struct ProfileBuilder {
+    name: String,
+    extends: Option<String>,
+    extends_disabled: bool,
+    search_dirs: Vec<String>,
+    search_vsc: Vec<String>,
+    search_max_depth: Option<u32>,
+    search_exclude: Vec<String>,
+    projects: Vec<(String, String)>,
+    detailed_projects: Vec<(String, String, String)>, // (name, path, description)
+    keys: Vec<(String, String)>,
+}
+
+pub struct ProjectBuilder {
+    name: String,
+    path: String,
+    env: Vec<(String, String)>,
+    tasks: Vec<(String, String)>, // (name, TOML value)
+}
+
+pub struct AutoProfileRuleBuilder {
+    hostname_regex: Option<String>,
+    env: Vec<(String, String)>,
+    ssh_session: Option<bool>,
+    default: Option<bool>,
+    profile: String,
+}
+
+#[allow(dead_code)]
+impl ConfigBuilder {
+    pub fn new() -> Self {
+        Self {
+            global_version: Some("1.0".to_string()),
+            search_dirs: Vec::new(),
+            search_vsc: Vec::new(),
+            search_max_depth: None,
+            search_exclude: Vec::new(),
+            base_projects: Vec::new(),
+            detailed_projects: Vec::new(),
+            base_keys: Vec::new(),
+            profiles: Vec::new(),
+            auto_profile_rules: Vec::new(),
+        }
+    }
+
+    pub fn version(mut self, version: &str) -> Self {
+        self.global_version = Some(version.to_string());
+        self
+    }
+
+    pub fn search_dirs(mut self, dirs: Vec<&str>) -> Self {
+        self.search_dirs = dirs.into_iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn search_vsc(mut self, vsc: Vec<&str>) -> Self {
+        self.search_vsc = vsc.into_iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn search_max_depth(mut self, depth: u32) -> Self {
+        self.search_max_depth = Some(depth);
+        self
+    }
+
+    pub fn search_exclude(mut self, exclude: Vec<&str>) -> Self {
+        self.search_exclude = exclude.into_iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn base_project(mut self, name: &str, path: &str) -> Self {
+        self.base_projects
+            .push((name.to_string(), path.to_string()));
+        self
+    }
+
+    pub fn project(mut self, project: ProjectBuilder) -> Self {
+        self.detailed_projects.push(project);
+        self
+    }
+
+    pub fn base_key(mut self, key: &str, project: &str) -> Self {
+        self.base_keys.push((key.to_string(), project.to_string()));
+        self
+    }
+
+    pub fn profile(mut self, profile: ProfileBuilder) -> Self {
+        self.profiles.push(profile);
+        self
+    }
+
+    pub fn auto_profile_rule(mut self, rule: AutoProfileRuleBuilder) -> Self {
+        self.auto_profile_rules.push(rule);
+        self
+    }
+
+    pub fn build_toml(&self) -> String {
+        let mut toml = String::new();
+
+        // Global section
+        if let Some(ref version) = self.global_version {
+            toml.push_str(&format!("[global]\nversion = \"{}\"\n\n", version));
+        }
+
+        // Search section
+        if !self.search_dirs.is_empty()
+            || !self.search_vsc.is_empty()
+            || self.search_max_depth.is_some()
+            || !self.search_exclude.is_empty()
+        {
+            toml.push_str("[search]\n");
+            if !self.search_dirs.is_empty() {
+                let dirs_str = self
+                    .search_dirs
+                    .iter()
+                    .map(|d| format!("\"{}\"", d))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                toml.push_str(&format!("dirs = [{}]\n", dirs_str));
+            }
+            if !self.search_vsc.is_empty() {
+                let vsc_str = self
+                    .search_vsc
+                    .iter()
+                    .map(|v| format!("\"{}\"", v))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                toml.push_str(&format!("vsc = [{}]\n", vsc_str));
+            }
+            if let Some(depth) = self.search_max_depth {
+                toml.push_str(&format!("max_depth = {}\n", depth));
+            }
+            if !self.search_exclude.is_empty() {
+                let exclude_str = self
+                    .search_exclude
+                    .iter()
+                    .map(|e| format!("\"{}\"", e))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                toml.push_str(&format!("exclude = [{}]\n", exclude_str));
+            }
+            toml.push('\n');
+        }
+
+        // Base projects section
+        if !self.base_projects.is_empty() {
+            toml.push_str("[projects]\n");
+            for (name, path) in &self.base_projects {
+                toml.push_str(&format!("{} = \"{}\"\n", name, path));
+            }
+            toml.push('\n');
+        }
+
+        // Detailed base projects as tables
+        for project in &self.detailed_projects {
+            toml.push_str(&project.build_toml());
+        }
+
+        // Base keys section
+        if !self.base_keys.is_empty() {
+            toml.push_str("[keys]\n");
+            for (key, project) in &self.base_keys {
+                toml.push_str(&format!("{} = \"{}\"\n", key, project));
+            }
+            toml.push('\n');
+        }
+
+        // Profiles
+        for profile in &self.profiles {
+            toml.push_str(&profile.build_toml());
+        }
+
+        // Auto profile rules
+        if !self.auto_profile_rules.is_empty() {
+            toml.push_str("[auto_profile]\n\n");
+            for rule in &self.auto_profile_rules {
+                toml.push_str(&rule.build_toml());
+            }
+        }
+
+        toml
+    }
+
+    pub fn write_to_temp_file(&self, temp: &TempDir, filename: &str) -> std::path::PathBuf {
+        let file_path = temp.path().join(filename);
+        temp.child(filename).write_str(&self.build_toml()).unwrap();
+        file_path
+    }
+}
+
+#[allow(dead_code)]
+impl ProfileBuilder {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            extends: None,
+            extends_disabled: false,
+            search_dirs: Vec::new(),
+            search_vsc: Vec::new(),
+            search_max_depth: None,
+            search_exclude: Vec::new(),
+            projects: Vec::new(),
+            detailed_projects: Vec::new(),
+            keys: Vec::new(),
+        }
+    }
+
+    pub fn extends(mut self, profile: &str) -> Self {
+        self.extends = Some(profile.to_string());
+        self
+    }
+
+    pub fn extends_disabled(mut self) -> Self {
+        self.extends_disabled = true;
+        self
+    }
+
+    pub fn search_dirs(mut self, dirs: Vec<&str>) -> Self {
+        self.search_dirs = dirs.into_iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn search_vsc(mut self, vsc: Vec<&str>) -> Self {
+        self.search_vsc = vsc.into_iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn search_max_depth(mut self, depth: u32) -> Self {
+        self.search_max_depth = Some(depth);
+        self
+    }
+
+    pub fn search_exclude(mut self, exclude: Vec<&str>) -> Self {
+        self.search_exclude = exclude.into_iter().map(|s| s.to_string()).collect();
+        self
+    }
+
+    pub fn project(mut self, name: &str, path: &str) -> Self {
+        self.projects.push((name.to_string(), path.to_string()));
+        self
+    }
+
+    pub fn detailed_project(mut self, name: &str, path: &str, description: &str) -> Self {
+        self.detailed_projects
+            .push((name.to_string(), path.to_string(), description.to_string()));
+        self
+    }
+
+    pub fn key(mut self, key: &str, project: &str) -> Self {
+        self.keys.push((key.to_string(), project.to_string()));
+        self
+    }
+
+    fn build_toml(&self) -> String {
+        let mut toml = String::new();
+
+        // Profile header with extends
+        if self.extends_disabled {
+            toml.push_str(&format!("[profiles.{}]\nextends = false\n\n", self.name));
+        } else if let Some(ref extends) = self.extends {
+            toml.push_str(&format!(
+                "[profiles.{}]\nextends = '{}'\n\n",
+                self.name, extends
+            ));
+        }
+
+        // Profile search section
+        if !self.search_dirs.is_empty()
+            || !self.search_vsc.is_empty()
+            || self.search_max_depth.is_some()
+            || !self.search_exclude.is_empty()
+        {
+            toml.push_str(&format!("[profiles.{}.search]\n", self.name));
+            if !self.search_dirs.is_empty() {
+                let dirs_str = self
+                    .search_dirs
+                    .iter()
+                    .map(|d| format!("\"{}\"", d))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                toml.push_str(&format!("dirs = [{}]\n", dirs_str));
+            }
+            if !self.search_vsc.is_empty() {
+                let vsc_str = self
+                    .search_vsc
+                    .iter()
+                    .map(|v| format!("\"{}\"", v))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                toml.push_str(&format!("vsc = [{}]\n", vsc_str));
+            }
+            if let Some(depth) = self.search_max_depth {
+                toml.push_str(&format!("max_depth = {}\n", depth));
+            }
+            if !self.search_exclude.is_empty() {
+                let exclude_str = self
+                    .search_exclude
+                    .iter()
+                    .map(|e| format!("\"{}\"", e))
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                toml.push_str(&format!("exclude = [{}]\n", exclude_str));
+            }
+            toml.push('\n');
+        }
+
+        // Profile projects section
+        if !self.projects.is_empty() || !self.detailed_projects.is_empty() {
+            toml.push_str(&format!("[profiles.{}.projects]\n", self.name));
+            for (name, path) in &self.projects {
+                toml.push_str(&format!("{} = \"{}\"\n", name, path));
+            }
+            toml.push('\n');
+
+            // Detailed projects as tables
+            for (name, path, description) in &self.detailed_projects {
+                toml.push_str(&format!("[profiles.{}.projects.{}]\n", self.name, name));
+                toml.push_str(&format!("path = \"{}\"\n", path));
+                toml.push_str(&format!("description = \"{}\"\n\n", description));
+            }
+        }
+
+        // Profile keys section
+        if !self.keys.is_empty() {
+            toml.push_str(&format!("[profiles.{}.keys]\n", self.name));
+            for (key, project) in &self.keys {
+                toml.push_str(&format!("{} = \"{}\"\n", key, project));
+            }
+            toml.push('\n');
+        }
+
+        toml
+    }
+}
+
+impl ProjectBuilder {
+    pub fn new(name: &str, path: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            path: path.to_string(),
+            env: Vec::new(),
+            tasks: Vec::new(),
+        }
+    }
+
+    pub fn env_var(mut self, key: &str, value: &str) -> Self {
+        self.env.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    /// Add a task, given as its TOML value, e.g. `"cargo test"` or `{ cmd = "make" }`
+    pub fn task(mut self, name: &str, value: &str) -> Self {
+        self.tasks.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    fn build_toml(&self) -> String {
+        let mut toml = String::new();
+        toml.push_str(&format!("[projects.{}]\n", self.name));
+        toml.push_str(&format!("path = \"{}\"\n", self.path));
+
+        if !self.env.is_empty() {
+            let env_pairs: Vec<String> = self
+                .env
+                .iter()
+                .map(|(k, v)| format!("{} = \"{}\"", k, v))
+                .collect();
+            toml.push_str(&format!("env = {{ {} }}\n", env_pairs.join(", ")));
+        }
+        toml.push('\n');
+
+        if !self.tasks.is_empty() {
+            toml.push_str(&format!("[projects.{}.tasks]\n", self.name));
+            for (name, value) in &self.tasks {
+                toml.push_str(&format!("{} = {}\n", name, value));
+            }
+            toml.push('\n');
+        }
+
+        toml
+    }
+}
+
+#[allow(dead_code)]
+impl AutoProfileRuleBuilder {
+    pub fn new(profile: &str) -> Self {
+        Self {
+            hostname_regex: None,
+            env: Vec::new(),
+            ssh_session: None,
+            default: None,
+            profile: profile.to_string(),
+        }
+    }
+
+    pub fn hostname_regex(mut self, regex: &str) -> Self {
+        self.hostname_regex = Some(regex.to_string());
+        self
+    }
+
+    pub fn env_var(mut self, key: &str, value: &str) -> Self {
+        self.env.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn ssh_session(mut self, is_ssh: bool) -> Self {
+        self.ssh_session = Some(is_ssh);
+        self
+    }
+
+    pub fn default_rule(mut self) -> Self {
+        self.default = Some(true);
+        self
+    }
+
+    fn build_toml(&self) -> String {
+        let mut toml = String::new();
+        toml.push_str("[[auto_profile.rules]]\n");
+
+        if let Some(ref regex) = self.hostname_regex {
+            toml.push_str(&format!("hostname_regex = \"{}\"\n", regex));
+        }
+
+        if !self.env.is_empty() {
+            toml.push_str("env = { ");
+            let env_pairs: Vec<String> = self
+                .env
+                .iter()
+                .map(|(k, v)| format!("{} = \"{}\"", k, v))
+                .collect();
+            toml.push_str(&env_pairs.join(", "));
+            toml.push_str(" }\n");
+        }
+
+        if let Some(ssh) = self.ssh_session {
+            toml.push_str(&format!("ssh_session = {}\n", ssh));
+        }
+
+        if let Some(default) = self.default {
+            toml.push_str(&format!("default = {}\n", default));
+        }
+
+        toml.push_str(&format!("profile = \"{}\"\n\n", self.profile));
+        toml
+    }
+}
+
+// Convenience functions for common test patterns
+pub fn simple_config() -> ConfigBuilder {
+    ConfigBuilder::new()
+        .base_project("dots", "~/dotfiles")
+        .base_key("P1", "dots")
+}
+
+pub fn config_with_profiles() -> ConfigBuilder {
+    ConfigBuilder::new()
+        .base_project("dots", "~/dotfiles")
+        .base_key("P1", "dots")
+        .profile(
+            ProfileBuilder::new("work")
+                .project("frontend", "~/work/frontend")
+                .project("backend", "~/work/backend")
+                .key("P2", "frontend")
+                .key("P3", "backend"),
+        )
+}
+
+#[allow(dead_code)]
+pub fn config_with_search() -> ConfigBuilder {
+    ConfigBuilder::new()
+        .search_dirs(vec!["~/project1", "~/project2"])
+        .search_vsc(vec!["~/dev", "~/work"])
+}
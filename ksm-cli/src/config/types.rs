@@ -1,9 +1,12 @@
+use kitty_lib::WindowLocation;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 pub type KeyedProject = (String, String);
 
 /// Project definition - can be a simple path string or detailed configuration
+// Project definitions are loaded once per run, so the size gap to `Simple` is not worth boxing
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(untagged)]
 pub enum ProjectDefinition {
@@ -15,9 +18,72 @@ pub enum ProjectDefinition {
         path: String,
         /// Optional description of the project
         description: Option<String>,
+        /// Environment variables set in every tab and window of the session
+        env: Option<BTreeMap<String, String>>,
+        /// Dotenv file to load into the session environment, relative to the project path
+        env_file: Option<String>,
+        /// Commands run in the first window when the session is created
+        on_create: Option<Vec<String>>,
+        /// Commands run locally whenever ksm switches to the existing session
+        on_focus: Option<Vec<String>>,
+        /// Commands run locally before `close-all-session-tabs` closes the session
+        on_close: Option<Vec<String>>,
+        /// Extra windows opened beside the shell when the session is created
+        windows: Option<Vec<ProjectWindow>>,
+        /// Named commands run in their own session tab with `ksm run <task>`
+        tasks: Option<BTreeMap<String, TaskDefinition>>,
     },
 }
 
+/// A named project task - either a command line or a command with its tab name
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum TaskDefinition {
+    /// Command run in a tab named after the task
+    Simple(String),
+    /// Command run in a specific tab
+    Detailed {
+        /// Command line, run through `/bin/sh -c`
+        cmd: String,
+        /// Tab name, defaults to the task name
+        tab: Option<String>,
+    },
+}
+
+impl TaskDefinition {
+    /// Get the command line of the task
+    pub fn cmd(&self) -> &str {
+        match self {
+            TaskDefinition::Simple(cmd) => cmd,
+            TaskDefinition::Detailed { cmd, .. } => cmd,
+        }
+    }
+
+    /// Get the tab name of the task, if it overrides the task name
+    pub fn tab(&self) -> Option<&str> {
+        match self {
+            TaskDefinition::Simple(_) => None,
+            TaskDefinition::Detailed { tab, .. } => tab.as_deref(),
+        }
+    }
+}
+
+/// A window running a program beside the shell of a new session, e.g. an editor
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ProjectWindow {
+    /// Program and arguments to run in the window
+    pub command: Vec<String>,
+    /// Optional window title
+    pub title: Option<String>,
+    /// Placement relative to the shell window (kitty's default when unset)
+    pub location: Option<WindowLocation>,
+    /// Share of the tab given to the window, as a percentage
+    pub bias: Option<f64>,
+    /// Keep the window open after the program exits
+    #[serde(default)]
+    pub hold: bool,
+}
+
 impl ProjectDefinition {
     /// Get the path from either Simple or Detailed project definition
     pub fn path(&self) -> &str {
@@ -34,6 +100,62 @@ impl ProjectDefinition {
             ProjectDefinition::Detailed { description, .. } => description.as_deref(),
         }
     }
+
+    /// Get the inline environment variables from Detailed project definition
+    pub fn env(&self) -> Option<&BTreeMap<String, String>> {
+        match self {
+            ProjectDefinition::Simple(_) => None,
+            ProjectDefinition::Detailed { env, .. } => env.as_ref(),
+        }
+    }
+
+    /// Get the dotenv file from Detailed project definition
+    pub fn env_file(&self) -> Option<&str> {
+        match self {
+            ProjectDefinition::Simple(_) => None,
+            ProjectDefinition::Detailed { env_file, .. } => env_file.as_deref(),
+        }
+    }
+
+    /// Get the session creation hooks from Detailed project definition
+    pub fn on_create(&self) -> &[String] {
+        match self {
+            ProjectDefinition::Simple(_) => &[],
+            ProjectDefinition::Detailed { on_create, .. } => on_create.as_deref().unwrap_or(&[]),
+        }
+    }
+
+    /// Get the session focus hooks from Detailed project definition
+    pub fn on_focus(&self) -> &[String] {
+        match self {
+            ProjectDefinition::Simple(_) => &[],
+            ProjectDefinition::Detailed { on_focus, .. } => on_focus.as_deref().unwrap_or(&[]),
+        }
+    }
+
+    /// Get the session close hooks from Detailed project definition
+    pub fn on_close(&self) -> &[String] {
+        match self {
+            ProjectDefinition::Simple(_) => &[],
+            ProjectDefinition::Detailed { on_close, .. } => on_close.as_deref().unwrap_or(&[]),
+        }
+    }
+
+    /// Get the named tasks from Detailed project definition
+    pub fn tasks(&self) -> Option<&BTreeMap<String, TaskDefinition>> {
+        match self {
+            ProjectDefinition::Simple(_) => None,
+            ProjectDefinition::Detailed { tasks, .. } => tasks.as_ref(),
+        }
+    }
+
+    /// Get the extra session windows from Detailed project definition
+    pub fn windows(&self) -> &[ProjectWindow] {
+        match self {
+            ProjectDefinition::Simple(_) => &[],
+            ProjectDefinition::Detailed { windows, .. } => windows.as_deref().unwrap_or(&[]),
+        }
+    }
 }
 
 /// KSM configuration data structure
@@ -54,6 +176,19 @@ pub struct SessionConfigData {
     pub profiles: Option<HashMap<String, ProfileConfig>>,
     /// Rules for automatic profile selection based on environment
     pub auto_profile: Option<AutoProfileConfig>,
+    /// How ksm talks to kitty (not inherited by profiles)
+    pub kitty: Option<KittyConfig>,
+}
+
+/// How ksm talks to kitty
+#[derive(Debug, Deserialize, Serialize, Clone, Default)]
+pub struct KittyConfig {
+    /// Milliseconds a kitty call may take before it is abandoned (default: 5000, 0 waits forever)
+    pub timeout_ms: Option<u64>,
+    /// Retries for idempotent calls like `ls` after transient failures (default: 2)
+    pub retries: Option<u32>,
+    /// Milliseconds before the first retry, doubled for each following one (default: 50)
+    pub retry_backoff_ms: Option<u64>,
 }
 
 /// Global configuration settings
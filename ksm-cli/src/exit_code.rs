@@ -0,0 +1,92 @@
+//! Process exit codes, so scripts and key bindings can tell why ksm failed
+
+use kitty_lib::KittyError;
+
+/// Any failure without a more specific code, including configuration errors
+pub const FAILURE: i32 = 1;
+/// The `kitten` binary was not found on `PATH`
+pub const KITTEN_NOT_FOUND: i32 = 3;
+/// kitty's remote control socket could not be reached
+pub const KITTY_UNREACHABLE: i32 = 4;
+/// kitty has remote control disabled
+pub const REMOTE_CONTROL_DISABLED: i32 = 5;
+/// No tab or window matched
+pub const NO_MATCH: i32 = 6;
+/// kitten failed for another reason
+pub const KITTEN_FAILED: i32 = 7;
+/// kitty rejected the remote control password or doesn't allow the command
+pub const PERMISSION_DENIED: i32 = 8;
+/// A kitty call timed out
+pub const TIMEOUT: i32 = 9;
+/// The running kitty is too old for what was asked
+pub const KITTY_TOO_OLD: i32 = 10;
+
+/// The exit code for an error, from the first [`KittyError`] in its chain
+pub fn for_error(error: &anyhow::Error) -> i32 {
+    let Some(kitty_error) = error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<KittyError>())
+    else {
+        return FAILURE;
+    };
+
+    match kitty_error {
+        KittyError::KittenNotFound => KITTEN_NOT_FOUND,
+        KittyError::SocketUnreachable { .. } | KittyError::NoSocket { .. } => KITTY_UNREACHABLE,
+        KittyError::RemoteControlDisabled { .. } => REMOTE_CONTROL_DISABLED,
+        KittyError::PermissionDenied { .. } => PERMISSION_DENIED,
+        KittyError::Timeout { .. } => TIMEOUT,
+        KittyError::NoMatch { .. } => NO_MATCH,
+        KittyError::Unsupported { .. } => KITTY_TOO_OLD,
+        _ => KITTEN_FAILED,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_for_error_finds_kitty_error_behind_context() {
+        let error = Err::<(), _>(KittyError::SocketUnreachable {
+            socket: "unix:/tmp/kitty".to_string(),
+            exit_code: Some(1),
+            stderr: "connection refused".to_string(),
+        })
+        .context("Failed to focus tab 3")
+        .unwrap_err();
+        assert_eq!(for_error(&error), KITTY_UNREACHABLE);
+
+        let error = anyhow::Error::new(KittyError::no_match("focus-tab", "tabs", "id:3"));
+        assert_eq!(for_error(&error), NO_MATCH);
+
+        let error = anyhow::Error::new(KittyError::NoSocket { tried: Vec::new() });
+        assert_eq!(for_error(&error), KITTY_UNREACHABLE);
+
+        let error = anyhow::Error::new(KittyError::PermissionDenied {
+            command: "close-tab".to_string(),
+            exit_code: Some(1),
+            stderr: "not allowed".to_string(),
+        });
+        assert_eq!(for_error(&error), PERMISSION_DENIED);
+
+        let error = anyhow::Error::new(KittyError::Timeout {
+            command: "ls".to_string(),
+            timeout: std::time::Duration::from_secs(5),
+        });
+        assert_eq!(for_error(&error), TIMEOUT);
+
+        let error = anyhow::Error::new(KittyError::KittenNotFound);
+        assert_eq!(for_error(&error), KITTEN_NOT_FOUND);
+
+        let error = anyhow::Error::new(KittyError::Unsupported {
+            capability: kitty_lib::Capability::LsMatch,
+            found: kitty_lib::KittyVersion::new(0, 26, 5),
+        });
+        assert_eq!(for_error(&error), KITTY_TOO_OLD);
+
+        let error = anyhow::anyhow!("Session 'api' not found or has no tabs");
+        assert_eq!(for_error(&error), FAILURE);
+    }
+}
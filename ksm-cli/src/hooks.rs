@@ -0,0 +1,296 @@
+use kitty_lib::TabId;
+use kitty_lib::executor::dry_run::shell_quote;
+use log::{debug, info, warn};
+use std::path::Path;
+use std::process::Command;
+
+/// Environment variable carrying the session name into hooks
+pub const KSM_SESSION_NAME_ENV: &str = "KSM_SESSION_NAME";
+
+/// Environment variable carrying the project path into hooks
+pub const KSM_SESSION_PATH_ENV: &str = "KSM_SESSION_PATH";
+
+/// Environment variable carrying the affected tab id into hooks
+pub const KSM_TAB_ID_ENV: &str = "KSM_TAB_ID";
+
+/// Environment variable carrying every affected tab id (space separated) into `on_close` hooks
+pub const KSM_TAB_IDS_ENV: &str = "KSM_TAB_IDS";
+
+/// The lifecycle event a hook is attached to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HookKind {
+    /// Typed into the first window when a session is created
+    Create,
+    /// Run locally when switching to an existing session
+    Focus,
+    /// Run locally before the session's tabs are closed
+    Close,
+}
+
+impl HookKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HookKind::Create => "on_create",
+            HookKind::Focus => "on_focus",
+            HookKind::Close => "on_close",
+        }
+    }
+}
+
+/// The session details exposed to hooks as environment variables
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookContext {
+    pub session_name: String,
+    pub session_path: Option<String>,
+    pub tab_ids: Vec<TabId>,
+}
+
+impl HookContext {
+    pub fn new(session_name: impl Into<String>) -> Self {
+        Self {
+            session_name: session_name.into(),
+            session_path: None,
+            tab_ids: Vec::new(),
+        }
+    }
+
+    pub fn with_path(mut self, session_path: impl Into<String>) -> Self {
+        self.session_path = Some(session_path.into());
+        self
+    }
+
+    pub fn with_tab(mut self, tab_id: impl Into<TabId>) -> Self {
+        self.tab_ids.push(tab_id.into());
+        self
+    }
+
+    pub fn with_tabs(mut self, tab_ids: impl IntoIterator<Item = impl Into<TabId>>) -> Self {
+        self.tab_ids.extend(tab_ids.into_iter().map(Into::into));
+        self
+    }
+
+    /// The hook environment: session name, path and tab id(s) when known
+    pub fn env(&self) -> Vec<(String, String)> {
+        let mut env = vec![(KSM_SESSION_NAME_ENV.to_string(), self.session_name.clone())];
+
+        if let Some(path) = &self.session_path {
+            env.push((KSM_SESSION_PATH_ENV.to_string(), path.clone()));
+        }
+
+        if let Some(first) = self.tab_ids.first() {
+            env.push((KSM_TAB_ID_ENV.to_string(), first.to_string()));
+        }
+
+        if self.tab_ids.len() > 1 {
+            let ids = self
+                .tab_ids
+                .iter()
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+                .join(" ");
+            env.push((KSM_TAB_IDS_ENV.to_string(), ids));
+        }
+
+        env
+    }
+}
+
+/// The line typed into a new session window to run its `on_create` commands
+///
+/// Each command runs in its own `sh -c` with the hook variables set for that command only,
+/// so they don't linger in the user's shell, and a failing command is reported in the window.
+pub fn window_command(commands: &[String], context: &HookContext) -> String {
+    let script = script_lines(HookKind::Create, commands, context).join("; ");
+    format!("/bin/sh -c {}\n", shell_quote(&script))
+}
+
+/// Run hooks locally, one `sh -c` per command, in the session directory when it exists
+///
+/// Failures are logged and reported on stderr but never abort the caller. Returns the number
+/// of commands that failed.
+pub fn run_hooks(kind: HookKind, commands: &[String], context: &HookContext) -> usize {
+    if commands.is_empty() {
+        return 0;
+    }
+
+    info!(
+        "Running {} {} hook(s) for session '{}'",
+        commands.len(),
+        kind.name(),
+        context.session_name
+    );
+
+    let mut failures = 0;
+    for hook in commands {
+        debug!("Running {} hook: {}", kind.name(), hook);
+
+        let mut command = Command::new("/bin/sh");
+        command.arg("-c").arg(hook).envs(context.env());
+
+        if let Some(path) = &context.session_path
+            && Path::new(path).is_dir()
+        {
+            command.current_dir(path);
+        }
+
+        let error = match command.status() {
+            Ok(status) if status.success() => continue,
+            Ok(status) => format!("exited with {}", status),
+            Err(e) => e.to_string(),
+        };
+
+        failures += 1;
+        warn!(
+            "{} hook '{}' for session '{}' failed: {}",
+            kind.name(),
+            hook,
+            context.session_name,
+            error
+        );
+        eprintln!("ksm: {} hook '{}' failed: {}", kind.name(), hook, error);
+    }
+
+    failures
+}
+
+/// Shell lines that run hooks the way [`run_hooks`] would, for a dry-run script
+///
+/// Each runs in a subshell so the `cd` and a failing hook don't affect the lines after it.
+pub fn script_lines(kind: HookKind, commands: &[String], context: &HookContext) -> Vec<String> {
+    let env = context
+        .env()
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, shell_quote(value)))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let cd = match &context.session_path {
+        Some(path) => format!("cd {} 2>/dev/null; ", shell_quote(path)),
+        None => String::new(),
+    };
+
+    commands
+        .iter()
+        .map(|hook| {
+            format!(
+                "({}{} /bin/sh -c {}) || echo 'ksm: {} hook failed' >&2",
+                cd,
+                env,
+                shell_quote(hook),
+                kind.name()
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::TempDir;
+    use assert_fs::prelude::*;
+    use std::fs;
+
+    #[test]
+    fn test_hook_context_env() {
+        let context = HookContext::new("api").with_path("/srv/api").with_tab(7);
+        assert_eq!(
+            context.env(),
+            vec![
+                ("KSM_SESSION_NAME".to_string(), "api".to_string()),
+                ("KSM_SESSION_PATH".to_string(), "/srv/api".to_string()),
+                ("KSM_TAB_ID".to_string(), "7".to_string()),
+            ]
+        );
+
+        let context = HookContext::new("api").with_tabs([3, 5]);
+        let env = context.env();
+        assert!(env.contains(&("KSM_TAB_ID".to_string(), "3".to_string())));
+        assert!(env.contains(&("KSM_TAB_IDS".to_string(), "3 5".to_string())));
+    }
+
+    #[test]
+    fn test_window_command_scopes_env_to_each_hook() {
+        let context = HookContext::new("api").with_path("/srv/api").with_tab(7);
+        let line = window_command(
+            &["npm install".to_string(), "git fetch".to_string()],
+            &context,
+        );
+
+        assert_eq!(
+            line,
+            "/bin/sh -c '(cd /srv/api 2>/dev/null; KSM_SESSION_NAME=api KSM_SESSION_PATH=/srv/api \
+             KSM_TAB_ID=7 /bin/sh -c '\\''npm install'\\'') || echo '\\''ksm: on_create hook failed'\\'' >&2; \
+             (cd /srv/api 2>/dev/null; KSM_SESSION_NAME=api KSM_SESSION_PATH=/srv/api \
+             KSM_TAB_ID=7 /bin/sh -c '\\''git fetch'\\'') || echo '\\''ksm: on_create hook failed'\\'' >&2'\n"
+        );
+    }
+
+    #[test]
+    fn test_window_command_runs_hooks_without_leaking_env() {
+        let temp = TempDir::new().unwrap();
+        let output = temp.child("hook.out");
+        let context = HookContext::new("api")
+            .with_path(temp.path().to_string_lossy())
+            .with_tab(42);
+        let commands = vec![format!(
+            "echo \"$KSM_SESSION_NAME $KSM_TAB_ID\" > {}",
+            output.path().display()
+        )];
+
+        // Type the line into a shell the way kitty would, then check what the shell sees after
+        let line = window_command(&commands, &context);
+        let shell = Command::new("/bin/sh")
+            .arg("-c")
+            .arg(format!("{}echo \"${{KSM_TAB_ID:-unset}}\"", line))
+            .env_remove(KSM_TAB_ID_ENV)
+            .output()
+            .unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&shell.stdout).trim(), "unset");
+        let written = fs::read_to_string(output.path()).unwrap();
+        assert_eq!(written.trim(), "api 42");
+    }
+
+    #[test]
+    fn test_script_lines_run_each_hook_like_run_hooks() {
+        let context = HookContext::new("api").with_path("/srv/my api").with_tab(7);
+        let lines = script_lines(
+            HookKind::Focus,
+            &["exit 3".to_string(), "echo \"$KSM_TAB_ID\"".to_string()],
+            &context,
+        );
+
+        assert_eq!(
+            lines,
+            [
+                "(cd '/srv/my api' 2>/dev/null; KSM_SESSION_NAME=api KSM_SESSION_PATH='/srv/my api' \
+                 KSM_TAB_ID=7 /bin/sh -c 'exit 3') || echo 'ksm: on_focus hook failed' >&2",
+                "(cd '/srv/my api' 2>/dev/null; KSM_SESSION_NAME=api KSM_SESSION_PATH='/srv/my api' \
+                 KSM_TAB_ID=7 /bin/sh -c 'echo \"$KSM_TAB_ID\"') || echo 'ksm: on_focus hook failed' >&2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run_hooks_exposes_env_and_continues_after_failure() {
+        let temp = TempDir::new().unwrap();
+        let output = temp.child("hook.out");
+
+        let commands = vec![
+            "exit 3".to_string(),
+            format!(
+                "echo \"$KSM_SESSION_NAME $KSM_TAB_ID $(pwd)\" > {}",
+                output.path().display()
+            ),
+        ];
+        let context = HookContext::new("api")
+            .with_path(temp.path().to_string_lossy())
+            .with_tab(42);
+
+        let failures = run_hooks(HookKind::Focus, &commands, &context);
+
+        assert_eq!(failures, 1);
+        let written = fs::read_to_string(output.path()).unwrap();
+        let canonical = fs::canonicalize(temp.path()).unwrap();
+        assert_eq!(written.trim(), format!("api 42 {}", canonical.display()));
+    }
+}
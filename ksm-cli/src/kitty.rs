@@ -1,14 +1,144 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
 use kitty_lib::commands::set_tab_title::KittenSetTabTitleCommand;
 use kitty_lib::{
-    CommandExecutor, KittenCloseTabCommand, KittenFocusTabCommand, KittenLaunchCommand,
-    KittenLsCommand, KittenNavigateTabCommand, KittyExecutor, KittyTab, TabNavigationDirection,
+    CallTiming, Capability, CommandExecutor, DryRunExecutor, KittenCloseTabCommand,
+    KittenCloseWindowCommand, KittenFocusTabCommand, KittenGetTextCommand, KittenLaunchCommand,
+    KittenLsCommand, KittenNavigateTabCommand, KittenSendTextCommand, KittenSetUserVarsCommand,
+    KittyCapabilities, KittyExecutor, KittyLaunchResponse, KittyResult, KittySnapshot, KittyTab,
+    LaunchType, LayeredExecutor, Middleware, RemoteControlPassword, RetryPolicy, SESSION_USER_VAR,
+    SocketDiscovery, TabId, TabNavigationDirection, WindowId,
 };
-use log::{debug, error, info};
-
-use crate::session::{SessionContext, SessionUtils};
+use log::{debug, error, info, warn};
+use std::env;
+use std::io;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::config::{KittyConfig, ProjectWindow};
+use crate::hooks::{self, HookContext};
+use crate::session::{SessionContext, SessionUtils, UNNAMED_SESSION};
 use crate::utils::format_session_tab_title;
 
+/// Environment variable naming the kitty socket to use instead of discovering one
+pub const SOCKET_ENV: &str = "KSM_KITTY_SOCKET";
+
+/// Environment variable naming a file holding kitty's remote control password
+pub const PASSWORD_FILE_ENV: &str = "KSM_KITTY_PASSWORD_FILE";
+
+/// Environment variable holding kitty's remote control password
+pub const PASSWORD_ENV: &str = "KSM_KITTY_PASSWORD";
+
+/// How to reach kitty, as given on the command line
+#[derive(Debug, Clone, Default)]
+pub struct KittyConnection {
+    /// Socket to use instead of `KSM_KITTY_SOCKET` or discovery
+    pub socket: Option<String>,
+    /// Password file to use instead of `KSM_KITTY_PASSWORD_FILE`
+    pub password_file: Option<PathBuf>,
+    /// Timeouts and retries from the config file
+    pub config: KittyConfig,
+}
+
+impl KittyConnection {
+    /// The socket override, from the command line or `KSM_KITTY_SOCKET`
+    fn socket(&self) -> Option<String> {
+        self.socket
+            .clone()
+            .or_else(|| env::var(SOCKET_ENV).ok())
+            .filter(|socket| !socket.is_empty())
+    }
+
+    /// The password source: the command line, then `KSM_KITTY_PASSWORD_FILE`, then
+    /// `KSM_KITTY_PASSWORD`, then whatever kitten or kitty.conf provide
+    fn password(&self) -> Option<RemoteControlPassword> {
+        if let Some(path) = self
+            .password_file
+            .clone()
+            .or_else(|| env::var_os(PASSWORD_FILE_ENV).map(PathBuf::from))
+        {
+            return Some(RemoteControlPassword::File(path));
+        }
+
+        if env::var_os(PASSWORD_ENV).is_some() {
+            return Some(RemoteControlPassword::Env(PASSWORD_ENV.to_string()));
+        }
+
+        RemoteControlPassword::discover(SocketDiscovery::from_env().kitty_conf())
+    }
+}
+
+/// An executor for `connection`, discovering the socket unless one is given
+pub fn kitty_executor(connection: &KittyConnection) -> KittyExecutor {
+    let executor = match connection.socket() {
+        Some(socket) => {
+            debug!("Using kitty socket override: {}", socket);
+            KittyExecutor::with_socket(socket)
+        }
+        None => KittyExecutor::new(),
+    };
+
+    let executor = match connection.password() {
+        Some(password) => executor.with_password(password),
+        None => executor,
+    };
+
+    configure(executor, &connection.config)
+}
+
+/// Apply the `[kitty]` timeout and retry settings
+fn configure(executor: KittyExecutor, config: &KittyConfig) -> KittyExecutor {
+    let executor = match config.timeout_ms {
+        Some(0) => executor.without_timeout(),
+        Some(timeout_ms) => executor.with_timeout(Duration::from_millis(timeout_ms)),
+        None => executor,
+    };
+
+    if config.retries.is_none() && config.retry_backoff_ms.is_none() {
+        return executor;
+    }
+
+    let default = RetryPolicy::default();
+    executor.with_retry(RetryPolicy::new(
+        config.retries.unwrap_or(default.retries),
+        config
+            .retry_backoff_ms
+            .map(Duration::from_millis)
+            .unwrap_or(default.backoff),
+    ))
+}
+
+/// The `ksm --timings` report: one line per kitty call, then the number of round-trips
+///
+/// A call that took other than one round-trip, because it was retried, probed kitty's version
+/// or was only printed by a dry run, says how many it took.
+pub fn timings_report(timings: &[CallTiming]) -> String {
+    let mut report = String::new();
+    for timing in timings {
+        let round_trips = match timing.round_trips() {
+            0 => "  (not sent)".to_string(),
+            1 => String::new(),
+            n => format!("  ({} round-trips)", n),
+        };
+        report.push_str(&format!(
+            "{:>9.1}ms  {}{}{}\n",
+            timing.elapsed.as_secs_f64() * 1000.0,
+            timing.call,
+            round_trips,
+            if timing.succeeded { "" } else { "  (failed)" }
+        ));
+    }
+
+    let total: Duration = timings.iter().map(|timing| timing.elapsed).sum();
+    let round_trips: usize = timings.iter().map(CallTiming::round_trips).sum();
+    report.push_str(&format!(
+        "{} kitty round-trip{} in {:.1}ms\n",
+        round_trips,
+        if round_trips == 1 { "" } else { "s" },
+        total.as_secs_f64() * 1000.0
+    ));
+    report
+}
+
 pub struct Kitty<E: CommandExecutor> {
     kitty: E,
 }
@@ -21,86 +151,128 @@ impl Default for Kitty<KittyExecutor> {
 
 impl Kitty<KittyExecutor> {
     pub fn new() -> Self {
+        Self::connect(&KittyConnection::default())
+    }
+
+    /// Talk to kitty as `connection` asks, falling back to the environment and discovery
+    pub fn connect(connection: &KittyConnection) -> Self {
         Self {
-            kitty: KittyExecutor::new(),
+            kitty: kitty_executor(connection),
         }
     }
 }
 
+impl Kitty<DryRunExecutor> {
+    /// Read kitty's state as `connection` asks, but print the commands that would change it
+    /// to stdout as a shell script instead of running them
+    pub fn dry_run(connection: &KittyConnection) -> Result<Self> {
+        let executor = DryRunExecutor::new(kitty_executor(connection))
+            .print_to(io::stdout())
+            .context("Failed to print the dry-run script")?;
+        Ok(Self { kitty: executor })
+    }
+}
+
 impl<E: CommandExecutor> Kitty<E> {
     pub fn with_executor(executor: E) -> Self {
         Self { kitty: executor }
     }
 
-    pub fn match_session_tab(&self, project_name: &str) -> Result<Option<KittyTab>> {
-        debug!("Matching session tab for project: {}", project_name);
+    /// Run every kitty call through `middleware`, e.g. the `CallTimer` behind `ksm --timings`
+    pub fn layer<M: Middleware>(self, middleware: M) -> Kitty<LayeredExecutor<E, M>> {
+        Kitty::with_executor(LayeredExecutor::new(self.kitty, middleware))
+    }
 
-        // First try matching by tab title with session: prefix
-        let session_title_pattern = format!("session:{}", project_name);
-        let ls_command_title = KittenLsCommand::new().match_tab_title(&session_title_pattern);
-
-        if let Ok(os_windows) = self.kitty.ls(ls_command_title) {
-            for os_window in os_windows {
-                if let Some(tab) = os_window.tabs.into_iter().next() {
-                    info!(
-                        "Found existing session tab for project '{}' with id: {} using tab title matching",
-                        project_name, tab.id
-                    );
-                    return Ok(Some(tab));
-                }
-            }
-        }
+    /// Detect the session of the window ksm was invoked from, through this instance's executor
+    pub fn detect_session(&self) -> Result<SessionContext> {
+        Ok(SessionContext::detect_with(&self.kitty)?)
+    }
 
-        // Fall back to environment variable matching for backward compatibility
-        let ls_command_env =
-            KittenLsCommand::new().match_tab_env("KITTY_SESSION_PROJECT", project_name);
-        let os_windows = self.kitty.ls(ls_command_env)?;
+    /// Take a snapshot of kitty's layout to answer several questions from a single `ls`
+    pub fn snapshot(&self) -> Result<KittySnapshot> {
+        Ok(KittySnapshot::fetch(&self.kitty)?)
+    }
+
+    pub fn match_session_tab(&self, project_name: &str) -> Result<Option<KittyTab>> {
+        debug!("Matching session tab for project: {}", project_name);
 
-        if os_windows.is_empty() {
+        let snapshot = self.snapshot()?;
+        let Some(tab) = snapshot.session_tab(project_name) else {
             debug!("No matching session found for project: {}", project_name);
             return Ok(None);
-        }
+        };
 
-        for os_window in os_windows {
-            if let Some(tab) = os_window.tabs.into_iter().next() {
-                info!(
-                    "Found existing session tab for project '{}' with id: {} using environment variable matching",
-                    project_name, tab.id
-                );
-                return Ok(Some(tab));
-            }
+        if let Some((_, marker)) = snapshot.session_of(tab.id) {
+            info!(
+                "Found existing session tab for project '{}' with id: {} ({:?})",
+                project_name, tab.id, marker
+            );
         }
-
-        debug!(
-            "No tabs found in matching windows for project: {}",
-            project_name
-        );
-        Ok(None)
+        Ok(Some(tab.clone()))
     }
 
-    pub fn focus_tab(&self, tab_id: u32) -> Result<()> {
+    pub fn focus_tab(&self, tab_id: impl Into<TabId>) -> Result<()> {
+        let tab_id = tab_id.into();
         info!("Focusing tab with id: {}", tab_id);
 
         let focus_command = KittenFocusTabCommand::new(tab_id);
-        let result = self.kitty.focus_tab(focus_command)?;
-
-        if !result.is_success() {
-            let error_msg = result
-                .error_message
-                .unwrap_or_else(|| "Unknown error".to_string());
-            error!("Failed to focus tab {}: {}", tab_id, error_msg);
-            return Err(anyhow::anyhow!(
-                "Failed to focus tab {}: {}",
-                tab_id,
-                error_msg
-            ));
-        }
+        self.kitty
+            .focus_tab(focus_command)
+            .with_context(|| format!("Failed to focus tab {}", tab_id))?;
 
         info!("Successfully focused tab: {}", tab_id);
         Ok(())
     }
 
-    pub fn create_session_tab_by_path(&self, project_path: &str, project_name: &str) -> Result<()> {
+    /// Create a session tab for a project, typing any `on_create` commands into its first window
+    /// and opening the configured extra windows beside it
+    pub fn create_session_tab_by_path(
+        &self,
+        project_path: &str,
+        project_name: &str,
+        project_env: &[(String, String)],
+        on_create: &[String],
+        windows: &[ProjectWindow],
+    ) -> Result<()> {
+        self.create_session_tab(
+            project_path,
+            project_name,
+            project_env,
+            on_create,
+            windows,
+            false,
+        )
+    }
+
+    /// Like [`Self::create_session_tab_by_path`], but every window is launched with
+    /// `--keep-focus` so focus never leaves the current tab
+    pub fn create_background_session_tab(
+        &self,
+        project_path: &str,
+        project_name: &str,
+        project_env: &[(String, String)],
+        on_create: &[String],
+        windows: &[ProjectWindow],
+    ) -> Result<()> {
+        self.create_session_tab(
+            project_path,
+            project_name,
+            project_env,
+            on_create,
+            windows,
+            true,
+        )
+    }
+
+    fn create_session_tab(
+        &self,
+        project_path: &str,
+        project_name: &str,
+        project_env: &[(String, String)],
+        on_create: &[String],
+        windows: &[ProjectWindow],
+        keep_focus: bool,
+    ) -> Result<()> {
         info!(
             "Creating new session tab for project '{}' at path: {}",
             project_name, project_path
@@ -108,25 +280,37 @@ impl<E: CommandExecutor> Kitty<E> {
 
         let session_name = format_session_tab_title(project_name);
 
-        let launch_command = KittenLaunchCommand::new()
-            .launch_type("tab")
+        let mut launch_command = KittenLaunchCommand::new()
+            .launch_type(LaunchType::Tab)
             .cwd(project_path)
             .env("KITTY_SESSION_PROJECT", project_name)
+            .var(SESSION_USER_VAR, project_name)
+            .envs(project_env.iter().cloned())
             .tab_title(&session_name);
-        let result = self.kitty.launch(launch_command)?;
+        if keep_focus {
+            launch_command = launch_command.keep_focus();
+        }
 
-        if !result.is_success() {
-            let error_msg = result
-                .error_message
-                .unwrap_or_else(|| "Unknown error".to_string());
-            error!(
-                "Failed to create session tab for project '{}': {}",
-                project_name, error_msg
+        let launched = self.launch_marked(launch_command).with_context(|| {
+            format!(
+                "Failed to create session tab for project '{}'",
+                project_name
+            )
+        })?;
+        let shell_window_id = launched.window_id;
+
+        if !on_create.is_empty() {
+            self.run_on_create(project_path, project_name, on_create, &launched);
+        }
+        for window in windows {
+            self.launch_session_window(
+                project_path,
+                project_name,
+                project_env,
+                window,
+                shell_window_id,
+                keep_focus,
             );
-            return Err(anyhow::anyhow!(
-                "Failed to create session tab: {}",
-                error_msg
-            ));
         }
 
         info!(
@@ -136,14 +320,111 @@ impl<E: CommandExecutor> Kitty<E> {
         Ok(())
     }
 
+    /// Type the `on_create` commands into the new session's shell window
+    ///
+    /// Failures are logged so the session itself stays usable.
+    fn run_on_create(
+        &self,
+        project_path: &str,
+        project_name: &str,
+        on_create: &[String],
+        launched: &KittyLaunchResponse,
+    ) {
+        info!(
+            "Running {} on_create hook(s) in the new session window",
+            on_create.len()
+        );
+
+        let mut hook_context = HookContext::new(project_name).with_path(project_path);
+        if let Some(tab_id) = launched.tab_id {
+            hook_context = hook_context.with_tab(tab_id);
+        }
+
+        let send_command =
+            KittenSendTextCommand::new(hooks::window_command(on_create, &hook_context))
+                .from_stdin();
+        let send_command = match (launched.window_id, launched.tab_id) {
+            (Some(window_id), _) => send_command.for_window_id(window_id),
+            (None, Some(tab_id)) => send_command.with_tab_match(format!("id:{}", tab_id)),
+            (None, None) => {
+                error!(
+                    "kitty did not report the new window of session '{}', skipping on_create hooks",
+                    project_name
+                );
+                return;
+            }
+        };
+
+        if let Err(e) = self.kitty.send_text(send_command) {
+            error!(
+                "Failed to run on_create hooks in session '{}': {}",
+                project_name, e
+            );
+        }
+    }
+
+    /// Open an extra session window; failures are logged so the session itself stays usable
+    fn launch_session_window(
+        &self,
+        project_path: &str,
+        project_name: &str,
+        project_env: &[(String, String)],
+        window: &ProjectWindow,
+        next_to: Option<WindowId>,
+        keep_focus: bool,
+    ) {
+        info!(
+            "Opening window {:?} in session '{}'",
+            window.command, project_name
+        );
+
+        let mut launch_command = KittenLaunchCommand::new()
+            .launch_type(LaunchType::Window)
+            .cwd(project_path)
+            .env("KITTY_SESSION_PROJECT", project_name)
+            .var(SESSION_USER_VAR, project_name)
+            .envs(project_env.iter().cloned())
+            .args(window.command.iter().cloned());
+
+        if let Some(window_id) = next_to {
+            launch_command = launch_command.next_to(&format!("id:{}", window_id));
+        }
+        if let Some(title) = &window.title {
+            launch_command = launch_command.window_title(title);
+        }
+        if let Some(location) = window.location {
+            launch_command = launch_command.location(location);
+        }
+        if let Some(bias) = window.bias {
+            launch_command = launch_command.bias(bias);
+        }
+        if window.hold {
+            launch_command = launch_command.hold();
+        }
+        if keep_focus {
+            launch_command = launch_command.keep_focus();
+        }
+
+        if let Err(e) = self.launch_marked(launch_command) {
+            error!(
+                "Failed to open window {:?} in session '{}': {}",
+                window.command, project_name, e
+            );
+        }
+    }
+
     /// Create a new tab that automatically inherits the current session context
+    ///
+    /// `session_context` is the session the caller already detected, and `session_env` carries
+    /// the session's project environment so that inherited tabs see the same variables as the
+    /// tab that created the session.
     pub fn create_tab_with_session_inheritance(
         &self,
+        session_context: &SessionContext,
         cwd: Option<&str>,
         tab_title: Option<&str>,
+        session_env: &[(String, String)],
     ) -> Result<()> {
-        let session_context = SessionContext::detect();
-
         if session_context.is_explicit {
             info!(
                 "Creating tab with automatic session inheritance for session: {}",
@@ -154,9 +435,14 @@ impl<E: CommandExecutor> Kitty<E> {
         }
 
         let mut launch_command = KittenLaunchCommand::new()
-            .launch_type("tab")
+            .launch_type(LaunchType::Tab)
+            .envs(session_env.iter().cloned())
             .inherit_current_session();
 
+        if session_context.is_explicit {
+            launch_command = launch_command.var(SESSION_USER_VAR, session_context.name());
+        }
+
         if let Some(cwd) = cwd {
             launch_command = launch_command.cwd(cwd);
         }
@@ -169,21 +455,8 @@ impl<E: CommandExecutor> Kitty<E> {
             launch_command = launch_command.tab_title(&session_title);
         }
 
-        let result = self.kitty.launch(launch_command)?;
-
-        if !result.is_success() {
-            let error_msg = result
-                .error_message
-                .unwrap_or_else(|| "Unknown error".to_string());
-            error!(
-                "Failed to create tab with session inheritance: {}",
-                error_msg
-            );
-            return Err(anyhow::anyhow!(
-                "Failed to create tab with session inheritance: {}",
-                error_msg
-            ));
-        }
+        self.launch_marked(launch_command)
+            .context("Failed to create tab with session inheritance")?;
 
         info!("Successfully created tab with session inheritance");
         Ok(())
@@ -193,7 +466,7 @@ impl<E: CommandExecutor> Kitty<E> {
     pub fn create_unnamed_tab(&self, cwd: Option<&str>, tab_title: Option<&str>) -> Result<()> {
         info!("Creating unnamed tab (no session context)");
 
-        let mut launch_command = KittenLaunchCommand::new().launch_type("tab");
+        let mut launch_command = KittenLaunchCommand::new().launch_type(LaunchType::Tab);
 
         if let Some(cwd) = cwd {
             launch_command = launch_command.cwd(cwd);
@@ -203,18 +476,9 @@ impl<E: CommandExecutor> Kitty<E> {
             launch_command = launch_command.tab_title(title);
         }
 
-        let result = self.kitty.launch(launch_command)?;
-
-        if !result.is_success() {
-            let error_msg = result
-                .error_message
-                .unwrap_or_else(|| "Unknown error".to_string());
-            error!("Failed to create unnamed tab: {}", error_msg);
-            return Err(anyhow::anyhow!(
-                "Failed to create unnamed tab: {}",
-                error_msg
-            ));
-        }
+        self.kitty
+            .launch(launch_command)
+            .context("Failed to create unnamed tab")?;
 
         info!("Successfully created unnamed tab");
         Ok(())
@@ -222,13 +486,13 @@ impl<E: CommandExecutor> Kitty<E> {
 
     /// Navigate to the next tab within the current session context
     pub fn next_session_tab(&self, allow_wrap: bool) -> Result<()> {
-        let session_context = SessionContext::detect();
+        let session_context = self.detect_session()?;
         self.navigate_session_tab(session_context, TabNavigationDirection::Next, allow_wrap)
     }
 
     /// Navigate to the previous tab within the current session context
     pub fn prev_session_tab(&self, allow_wrap: bool) -> Result<()> {
-        let session_context = SessionContext::detect();
+        let session_context = self.detect_session()?;
         self.navigate_session_tab(
             session_context,
             TabNavigationDirection::Previous,
@@ -274,15 +538,9 @@ impl<E: CommandExecutor> Kitty<E> {
                 .with_wrap(allow_wrap),
         };
 
-        let result = self.kitty.navigate_tab(navigate_command)?;
-
-        if !result.is_success() {
-            let error_msg = result
-                .error_message
-                .unwrap_or_else(|| "Unknown error".to_string());
-            error!("Failed to navigate tabs: {}", error_msg);
-            return Err(anyhow::anyhow!("Failed to navigate tabs: {}", error_msg));
-        }
+        self.kitty
+            .navigate_tab(navigate_command)
+            .context("Failed to navigate tabs")?;
 
         info!("Successfully navigated to {:?} tab", direction);
         Ok(())
@@ -290,97 +548,38 @@ impl<E: CommandExecutor> Kitty<E> {
 
     /// Get all tabs for the current session context
     pub fn get_current_session_tabs(&self) -> Result<Vec<KittyTab>> {
-        let session_context = SessionContext::detect();
+        let session_context = self.detect_session()?;
         self.get_session_tabs(&session_context)
     }
 
     /// Get all tabs for a specific session
     pub fn get_session_tabs(&self, session_context: &SessionContext) -> Result<Vec<KittyTab>> {
-        if session_context.is_explicit {
-            let mut session_tabs = Vec::new();
-
-            // First try tab title matching
-            let session_title_pattern = format!("session:{}", session_context.name());
-            let ls_command_title = KittenLsCommand::new().match_tab_title(&session_title_pattern);
-
-            if let Ok(os_windows) = self.kitty.ls(ls_command_title) {
-                for os_window in os_windows {
-                    session_tabs.extend(os_window.tabs);
-                }
-            }
-
-            // Also include tabs matched by environment variable for backward compatibility
-            let ls_command_env = KittenLsCommand::new()
-                .match_tab_env("KITTY_SESSION_PROJECT", session_context.name());
-
-            if let Ok(os_windows) = self.kitty.ls(ls_command_env) {
-                for os_window in os_windows {
-                    for tab in os_window.tabs {
-                        // Only add if not already included (check by ID)
-                        if !session_tabs.iter().any(|existing| existing.id == tab.id) {
-                            session_tabs.push(tab);
-                        }
-                    }
-                }
-            }
-
-            // Sort by ID to maintain consistent ordering
-            session_tabs.sort_by_key(|t| t.id);
-            Ok(session_tabs)
-        } else {
-            // For unnamed session, get all tabs and filter out those with session env vars or tab titles
-            let ls_command = KittenLsCommand::new();
-            let os_windows = self.kitty.ls(ls_command)?;
-
-            let mut unnamed_tabs = Vec::new();
-            for os_window in os_windows {
-                for tab in os_window.tabs {
-                    let has_session_env = tab
-                        .windows
-                        .iter()
-                        .any(|w| w.env.contains_key("KITTY_SESSION_PROJECT"));
-                    let has_session_title = tab.title.starts_with("session:");
-
-                    if !has_session_env && !has_session_title {
-                        unnamed_tabs.push(tab);
-                    }
-                }
-            }
+        let snapshot = self.snapshot()?;
 
-            Ok(unnamed_tabs)
-        }
+        Ok(session_context
+            .tabs_in(&snapshot)
+            .into_iter()
+            .cloned()
+            .collect())
     }
 
-    /// Check if there are any session tabs (tabs with KITTY_SESSION_PROJECT set or session: title)
+    /// Check if there are any session tabs (marked by user variable, session: title or
+    /// KITTY_SESSION_PROJECT)
     pub fn has_session_tabs(&self) -> Result<bool> {
-        let ls_command = KittenLsCommand::new();
-        let os_windows = self.kitty.ls(ls_command)?;
-
-        for os_window in os_windows {
-            for tab in os_window.tabs {
-                // Check tab title first
-                if tab.title.starts_with("session:") {
-                    return Ok(true);
-                }
-
-                // Check environment variables for backward compatibility
-                for window in tab.windows {
-                    if window.env.contains_key("KITTY_SESSION_PROJECT") {
-                        return Ok(true);
-                    }
-                }
-            }
-        }
-
-        Ok(false)
+        Ok(self.snapshot()?.has_session_tabs())
     }
 
     /// Switch to a specific session, focusing the last active tab if available
     pub fn switch_to_session(&self, session_name: &str) -> Result<()> {
+        let snapshot = self.snapshot()?;
+        self.switch_to_session_in(&snapshot, session_name)
+    }
+
+    fn switch_to_session_in(&self, snapshot: &KittySnapshot, session_name: &str) -> Result<()> {
         info!("Switching to session: {}", session_name);
 
         // First check if the session exists
-        let session_tabs = self.get_session_tabs(&SessionContext::new(session_name))?;
+        let session_tabs = snapshot.session_tabs(session_name);
         if session_tabs.is_empty() {
             return Err(anyhow::anyhow!(
                 "Session '{}' not found or has no tabs",
@@ -406,7 +605,7 @@ impl<E: CommandExecutor> Kitty<E> {
         }
 
         // Fallback to first tab in the session
-        let first_tab = &session_tabs[0];
+        let first_tab = session_tabs[0];
         info!(
             "Focusing first tab {} in session '{}'",
             first_tab.id, session_name
@@ -420,113 +619,63 @@ impl<E: CommandExecutor> Kitty<E> {
     }
 
     /// Focus a tab and update last active tracking for its session
-    pub fn focus_tab_with_tracking(&self, tab_id: u32) -> Result<()> {
+    pub fn focus_tab_with_tracking(&self, tab_id: impl Into<TabId>) -> Result<()> {
+        let tab_id = tab_id.into();
         // Focus the tab first
         self.focus_tab(tab_id)?;
 
         // Find which session this tab belongs to and update tracking
-        let ls_command = KittenLsCommand::new();
-        let os_windows = self.kitty.ls(ls_command)?;
-
-        for os_window in os_windows {
-            for tab in os_window.tabs {
-                if tab.id == tab_id {
-                    // Check tab title first for session: prefix
-                    if tab.title.starts_with("session:") {
-                        if let Some(session_name) =
-                            crate::session::SessionContext::parse_session_from_title(&tab.title)
-                        {
-                            SessionUtils::set_last_active_tab(&session_name, tab_id);
-                            debug!(
-                                "Updated last active tab tracking: session '{}' -> tab {} (from tab title)",
-                                session_name, tab_id
-                            );
-                            return Ok(());
-                        }
-                    }
-
-                    // Fall back to checking environment variables
-                    for window in &tab.windows {
-                        if let Some(session_name) = window.env.get("KITTY_SESSION_PROJECT") {
-                            SessionUtils::set_last_active_tab(session_name, tab_id);
-                            debug!(
-                                "Updated last active tab tracking: session '{}' -> tab {} (from environment)",
-                                session_name, tab_id
-                            );
-                            return Ok(());
-                        }
-                    }
-                    // If no session context found, this is an unnamed session tab
-                    debug!("Focused tab {} in unnamed session context", tab_id);
-                    return Ok(());
-                }
-            }
+        let snapshot = self.snapshot()?;
+        if snapshot.tab(tab_id).is_none() {
+            debug!("Tab {} not found for session tracking update", tab_id);
+            return Ok(());
         }
 
-        debug!("Tab {} not found for session tracking update", tab_id);
+        match snapshot.session_of(tab_id) {
+            Some((session_name, marker)) => {
+                SessionUtils::set_last_active_tab(session_name, tab_id);
+                debug!(
+                    "Updated last active tab tracking: session '{}' -> tab {} (from {:?})",
+                    session_name, tab_id, marker
+                );
+            }
+            // If no session context found, this is an unnamed session tab
+            None => debug!("Focused tab {} in unnamed session context", tab_id),
+        }
         Ok(())
     }
 
     /// Get a list of all available sessions with their tab counts
     pub fn list_sessions(&self) -> Result<Vec<(String, usize)>> {
-        let ls_command = KittenLsCommand::new();
-        let os_windows = self.kitty.ls(ls_command)?;
-
-        let mut session_counts: std::collections::HashMap<String, usize> =
-            std::collections::HashMap::new();
-        let mut unnamed_count = 0;
-
-        for os_window in os_windows {
-            for tab in os_window.tabs {
-                let mut has_session = false;
-
-                // Check tab title first for session: prefix
-                if tab.title.starts_with("session:") {
-                    if let Some(session_name) =
-                        crate::session::SessionContext::parse_session_from_title(&tab.title)
-                    {
-                        *session_counts.entry(session_name).or_insert(0) += 1;
-                        has_session = true;
-                    }
-                }
-
-                // Fall back to environment variable for backward compatibility
-                if !has_session {
-                    for window in &tab.windows {
-                        if let Some(session_name) = window.env.get("KITTY_SESSION_PROJECT") {
-                            *session_counts.entry(session_name.clone()).or_insert(0) += 1;
-                            has_session = true;
-                            break;
-                        }
-                    }
-                }
-
-                if !has_session {
-                    unnamed_count += 1;
-                }
-            }
-        }
+        Ok(Self::sessions_in(&self.snapshot()?))
+    }
 
-        let mut sessions: Vec<(String, usize)> = session_counts.into_iter().collect();
-        sessions.sort_by(|a, b| a.0.cmp(&b.0));
+    /// Sessions in a snapshot with their tab counts, sorted by name, then the unnamed session
+    /// if it has tabs
+    fn sessions_in(snapshot: &KittySnapshot) -> Vec<(String, usize)> {
+        let mut sessions: Vec<(String, usize)> = snapshot
+            .sessions()
+            .map(|(name, count)| (name.to_string(), count))
+            .collect();
 
-        // Add unnamed session if it has tabs
+        let unnamed_count = snapshot.unnamed_tabs().len();
         if unnamed_count > 0 {
-            sessions.push(("unnamed".to_string(), unnamed_count));
+            sessions.push((UNNAMED_SESSION.to_string(), unnamed_count));
         }
 
-        Ok(sessions)
+        sessions
     }
 
     /// Switch to the next available session (cycling through sessions)
     pub fn next_session(&self) -> Result<()> {
-        let sessions = self.list_sessions()?;
+        let snapshot = self.snapshot()?;
+        let sessions = Self::sessions_in(&snapshot);
         if sessions.len() <= 1 {
             debug!("Only one or no sessions available, no switching needed");
             return Ok(());
         }
 
-        let current_session = SessionContext::detect();
+        let current_session = SessionContext::detect_in(&snapshot);
         let current_session_name = current_session.name();
 
         // Find the current session in the list
@@ -545,18 +694,19 @@ impl<E: CommandExecutor> Kitty<E> {
             current_session_name, next_session_name
         );
 
-        self.switch_to_session(next_session_name)
+        self.switch_to_session_in(&snapshot, next_session_name)
     }
 
     /// Switch to the previous available session (cycling through sessions)
     pub fn prev_session(&self) -> Result<()> {
-        let sessions = self.list_sessions()?;
+        let snapshot = self.snapshot()?;
+        let sessions = Self::sessions_in(&snapshot);
         if sessions.len() <= 1 {
             debug!("Only one or no sessions available, no switching needed");
             return Ok(());
         }
 
-        let current_session = SessionContext::detect();
+        let current_session = SessionContext::detect_in(&snapshot);
         let current_session_name = current_session.name();
 
         // Find the current session in the list
@@ -576,44 +726,134 @@ impl<E: CommandExecutor> Kitty<E> {
             current_session_name, prev_session_name
         );
 
-        self.switch_to_session(prev_session_name)
+        self.switch_to_session_in(&snapshot, prev_session_name)
+    }
+
+    /// Launch a tab or window, returning the ids kitty reports for it
+    pub fn launch(&self, command: KittenLaunchCommand) -> Result<KittyLaunchResponse> {
+        let launch_type = command.launch_type;
+        self.launch_marked(command)
+            .with_context(|| format!("Failed to launch {}", launch_type))
+    }
+
+    /// Launch with the session user variable, or without it on a kitty too old for user
+    /// variables; the title and `KITTY_SESSION_PROJECT` still tie the tab to its session
+    fn launch_marked(&self, command: KittenLaunchCommand) -> KittyResult<KittyLaunchResponse> {
+        let capabilities = self.capabilities();
+        if command.vars.is_empty() || capabilities.supports(Capability::UserVars) {
+            return self.kitty.launch(command);
+        }
+
+        if let Some(version) = capabilities.version() {
+            warn!(
+                "kitty {} has no user variables; marking the session by title and environment only",
+                version
+            );
+        }
+        self.kitty.launch(KittenLaunchCommand {
+            vars: Vec::new(),
+            ..command
+        })
+    }
+
+    /// What the running kitty supports, or everything when its version can't be found out
+    fn capabilities(&self) -> KittyCapabilities {
+        self.kitty.capabilities().unwrap_or_else(|e| {
+            debug!("Could not find out kitty's version: {}", e);
+            KittyCapabilities::unknown()
+        })
+    }
+
+    /// Find the id of the tab that currently has keyboard focus
+    pub fn focused_tab_id(&self) -> Result<Option<TabId>> {
+        Ok(self.focused_tab()?.map(|tab| tab.id))
+    }
+
+    /// Find the tab that currently has keyboard focus
+    pub fn focused_tab(&self) -> Result<Option<KittyTab>> {
+        Ok(self.snapshot()?.focused_tab().cloned())
+    }
+
+    /// Send text to the windows selected by a send-text command
+    pub fn send_text(&self, command: KittenSendTextCommand) -> Result<()> {
+        self.kitty.send_text(command).context("Failed to send text")
+    }
+
+    /// Read text from the window selected by a get-text command
+    pub fn get_text(&self, command: KittenGetTextCommand) -> Result<String> {
+        self.kitty
+            .get_text(command)
+            .context("Failed to get window text")
+    }
+
+    /// Mark every window of a tab with the session user variable, so the tab stays in the
+    /// session whatever its title becomes
+    pub fn tag_session_tab(&self, tab: &KittyTab, session_name: &str) -> Result<()> {
+        if tab.windows.is_empty() {
+            return Ok(());
+        }
+
+        let window_match = tab
+            .windows
+            .iter()
+            .map(|window| format!("id:{}", window.id))
+            .collect::<Vec<_>>()
+            .join(" or ");
+        debug!(
+            "Tagging tab {} with session '{}' ({})",
+            tab.id, session_name, window_match
+        );
+
+        self.capabilities().require(Capability::UserVars)?;
+
+        let command = KittenSetUserVarsCommand::new()
+            .var(SESSION_USER_VAR, session_name)
+            .with_match(window_match);
+        self.kitty.set_user_vars(command).with_context(|| {
+            format!(
+                "Failed to tag tab {} with session '{}'",
+                tab.id, session_name
+            )
+        })
     }
 
     /// Execute a kitty ls command
     pub fn ls(&self, command: KittenLsCommand) -> Result<Vec<kitty_lib::KittyOsWindow>> {
-        self.kitty.ls(command)
+        Ok(self.kitty.ls(command)?)
     }
 
     /// Close a specific tab by ID
-    pub fn close_tab(&self, tab_id: u32) -> Result<kitty_lib::KittyCommandResult<()>> {
+    pub fn close_window(&self, window_id: impl Into<WindowId>) -> Result<()> {
+        let window_id = window_id.into();
+        info!("Closing window with id: {}", window_id);
+        let close_command = KittenCloseWindowCommand::new(window_id);
+        self.kitty
+            .close_window(close_command)
+            .with_context(|| format!("Failed to close window {}", window_id))?;
+
+        info!("Successfully closed window: {}", window_id);
+        Ok(())
+    }
+
+    pub fn close_tab(&self, tab_id: impl Into<TabId>) -> Result<()> {
+        let tab_id = tab_id.into();
         info!("Closing tab with id: {}", tab_id);
         let close_command = KittenCloseTabCommand::new(tab_id);
-        let result = self.kitty.close_tab(close_command)?;
-
-        if result.is_success() {
-            info!("Successfully closed tab: {}", tab_id);
-        } else {
-            let default_error = "Unknown error".to_string();
-            let error_msg = result.error_message.as_ref().unwrap_or(&default_error);
-            error!("Failed to close tab {}: {}", tab_id, error_msg);
-        }
+        self.kitty
+            .close_tab(close_command)
+            .with_context(|| format!("Failed to close tab {}", tab_id))?;
 
-        Ok(result)
+        info!("Successfully closed tab: {}", tab_id);
+        Ok(())
     }
 
     /// Set the title of the current tab
     pub fn set_tab_title(&self, title: &str) -> Result<()> {
         info!("Setting tab title to: '{}'", title);
         let command = KittenSetTabTitleCommand::new(title);
-        let result = self.kitty.set_tab_title(command)?;
-
-        if !result.is_success() {
-            let error_msg = result
-                .error_message
-                .unwrap_or_else(|| "Unknown error".to_string());
-            error!("Failed to set tab title: {}", error_msg);
-            return Err(anyhow::anyhow!("Failed to set tab title: {}", error_msg));
-        }
+        self.kitty
+            .set_tab_title(command)
+            .context("Failed to set tab title")?;
 
         info!("Successfully set tab title");
         Ok(())
@@ -625,21 +865,123 @@ mod tests {
     use super::*;
     use anyhow::Result;
     use kitty_lib::{
-        KittyCommandResult, KittyLaunchResponse, KittyOsWindow, KittyTab, KittyWindow, MockExecutor,
+        KittyError, KittyLaunchResponse, KittyOsWindow, KittyTab, KittyWindow, MockExecutor,
+        OsWindowId, WindowLocation,
     };
     use std::collections::HashMap;
 
+    #[test]
+    fn test_next_tab_replays_scripted_kitten_calls() -> Result<()> {
+        let replay = kitty_lib::ReplayExecutor::load(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/replay/synthetic-next-tab.json"
+        ))?;
+        let kitty = Kitty::with_executor(&replay);
+
+        kitty.navigate_session_tab(
+            SessionContext::new("api"),
+            TabNavigationDirection::Next,
+            true,
+        )?;
+
+        replay.assert_finished();
+        Ok(())
+    }
+
+    #[test]
+    fn test_replay_launches_like_the_recorded_old_kitty() -> Result<()> {
+        use kitty_lib::{KittenCommand, RecordedCall, RecordedOutcome, Recording};
+
+        // Recorded against kitty 0.26.5, which has no user variables, so --var was dropped
+        let launch = KittenLaunchCommand::new().tab_title("session:api");
+        let recording = Recording {
+            socket: "unix:/tmp/kitty".to_string(),
+            kitty_version: Some(kitty_lib::KittyVersion::new(0, 26, 5)),
+            calls: vec![RecordedCall {
+                command: "launch".to_string(),
+                args: KittenCommand::args(&launch)?,
+                stdin: None,
+                outcome: RecordedOutcome::Success {
+                    stdout: "12\n".to_string(),
+                },
+            }],
+        };
+        let replay = kitty_lib::ReplayExecutor::new(recording);
+        let kitty = Kitty::with_executor(&replay);
+
+        let launched = kitty.launch(launch.var(SESSION_USER_VAR, "api"))?;
+
+        assert_eq!(launched.window_id, Some(WindowId(12)));
+        replay.assert_finished();
+        Ok(())
+    }
+
+    #[test]
+    fn test_layered_kitty_times_every_replayed_call() -> Result<()> {
+        let recording = kitty_lib::Recording::load(concat!(
+            env!("CARGO_MANIFEST_DIR"),
+            "/tests/fixtures/replay/synthetic-next-tab.json"
+        ))?;
+        let replay = kitty_lib::ReplayExecutor::new(recording.clone());
+        let timer = kitty_lib::CallTimer::new();
+        let kitty = Kitty::with_executor(&replay).layer(&timer);
+
+        kitty.navigate_session_tab(
+            SessionContext::new("api"),
+            TabNavigationDirection::Next,
+            true,
+        )?;
+
+        replay.assert_finished();
+        let recorded: Vec<_> = recording
+            .calls
+            .iter()
+            .map(|call| (call.command.clone(), call.args.clone()))
+            .collect();
+        let timed: Vec<_> = timer
+            .timings()
+            .into_iter()
+            .map(|timing| (timing.call.command.to_string(), timing.call.args))
+            .collect();
+        assert_eq!(timed, recorded);
+        Ok(())
+    }
+
+    #[test]
+    fn test_timings_report_counts_retries_and_probes_as_round_trips() {
+        let timing = |command: KittenFocusTabCommand, millis, round_trips, succeeded| CallTiming {
+            call: kitty_lib::Call {
+                round_trips,
+                ..kitty_lib::Call::of(&command)
+            },
+            elapsed: Duration::from_millis(millis),
+            succeeded,
+        };
+
+        let report = timings_report(&[
+            timing(KittenFocusTabCommand::new(3), 12, 1, true),
+            timing(KittenFocusTabCommand::new(4), 3, 3, false),
+            timing(KittenFocusTabCommand::new(5), 0, 0, true),
+        ]);
+
+        assert_eq!(
+            report,
+            "     12.0ms  focus-tab --match=id:3\n      3.0ms  focus-tab --match=id:4  (3 round-trips)  (failed)\n      0.0ms  focus-tab --match=id:5  (not sent)\n4 kitty round-trips in 15.0ms\n"
+        );
+        assert_eq!(timings_report(&[]), "0 kitty round-trips in 0.0ms\n");
+    }
+
     #[test]
     fn test_kitty_with_mock_executor() -> Result<()> {
         let mock_executor = MockExecutor::with_default_socket();
 
         // Setup mock response for ls command
         let mock_tab = KittyTab {
-            id: 42,
+            id: TabId(42),
             index: Some(0),
-            title: "Test Tab".to_string(),
+            title: "session:test-project".to_string(),
             windows: vec![KittyWindow {
-                id: 1,
+                id: WindowId(1),
                 title: "Test Window".to_string(),
                 pid: 12345,
                 cwd: "/tmp/test".to_string(),
@@ -649,22 +991,36 @@ mod tests {
                 state: Some("active".to_string()),
                 num: Some(0),
                 recent: Some(0),
+                user_vars: HashMap::new(),
+                is_active: true,
+                is_focused: false,
+                foreground_processes: Vec::new(),
+                at_prompt: true,
+                last_reported_cmdline: None,
+                columns: 80,
+                lines: 24,
             }],
             is_active: false,
             is_focused: false,
             state: Some("active".to_string()),
             recent: Some(0),
+            layout: Some("tall".to_string()),
+            enabled_layouts: vec!["tall".to_string(), "stack".to_string()],
+            active_window_history: vec![WindowId(1)],
         };
         let mock_os_window = KittyOsWindow {
-            id: 1,
+            id: OsWindowId(1),
             tabs: vec![mock_tab],
             title: Some("Test OS Window".to_string()),
             state: Some("active".to_string()),
+            is_focused: false,
+            last_focused: false,
+            platform_window_id: None,
         };
         mock_executor.expect_ls_response(Ok(vec![mock_os_window]));
 
         // Setup mock response for focus command
-        mock_executor.expect_focus_tab_response(Ok(KittyCommandResult::success_empty()));
+        mock_executor.expect_focus_tab_response(Ok(()));
 
         let kitty = Kitty::with_executor(&mock_executor);
 
@@ -704,28 +1060,49 @@ mod tests {
         let result = kitty.match_session_tab("nonexistent-project")?;
         assert!(result.is_none());
 
-        // Verify calls were made (now makes 2 calls: tab title match + env var match)
-        assert_eq!(mock_executor.ls_call_count(), 2);
+        // Verify a single snapshot answered the lookup
+        assert_eq!(mock_executor.ls_call_count(), 1);
 
         Ok(())
     }
 
+    #[test]
+    fn test_kitty_match_session_tab_reports_ls_failure() {
+        let mock_executor = MockExecutor::with_default_socket();
+        mock_executor.expect_ls_response(Err(KittyError::RemoteControlDisabled {
+            exit_code: Some(1),
+            stderr: "Error: Remote control is disabled".to_string(),
+        }));
+
+        let kitty = Kitty::with_executor(&mock_executor);
+
+        let error = kitty.match_session_tab("api").unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<KittyError>(),
+            Some(KittyError::RemoteControlDisabled { .. })
+        ));
+    }
+
     #[test]
     fn test_kitty_mock_create_session() -> Result<()> {
         let mock_executor = MockExecutor::with_default_socket();
 
         // Setup mock response for launch command
-        mock_executor.expect_launch_response(Ok(KittyCommandResult::success(
-            KittyLaunchResponse {
-                tab_id: None,
-                window_id: None,
-            },
-        )));
+        mock_executor.expect_launch_response(Ok(KittyLaunchResponse {
+            tab_id: None,
+            window_id: None,
+        }));
 
         let kitty = Kitty::with_executor(&mock_executor);
 
         // Test create_session_tab_by_path
-        kitty.create_session_tab_by_path("/tmp/test-project", "test-project")?;
+        kitty.create_session_tab_by_path(
+            "/tmp/test-project",
+            "test-project",
+            &[("AWS_PROFILE".to_string(), "dev".to_string())],
+            &[],
+            &[],
+        )?;
 
         // Verify call was made
         assert_eq!(mock_executor.launch_call_count(), 1);
@@ -736,12 +1113,118 @@ mod tests {
         assert_eq!(launch_calls[0].cwd, Some("/tmp/test-project".to_string()));
         assert_eq!(
             launch_calls[0].env,
-            Some("KITTY_SESSION_PROJECT=test-project".to_string())
+            vec![
+                (
+                    "KITTY_SESSION_PROJECT".to_string(),
+                    "test-project".to_string()
+                ),
+                ("AWS_PROFILE".to_string(), "dev".to_string()),
+            ]
+        );
+        assert_eq!(
+            launch_calls[0].tab_title,
+            Some("session:test-project".to_string())
         );
+        assert!(launch_calls[0].args.is_empty()); // Default shell
+        assert_eq!(mock_executor.send_text_call_count(), 0); // No hooks to run
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_create_session_tab_without_user_vars_on_old_kitty() -> Result<()> {
+        let mock_executor = MockExecutor::new();
+        mock_executor.set_kitty_version(kitty_lib::KittyVersion::new(0, 26, 5));
+        let kitty = Kitty::with_executor(&mock_executor);
+
+        kitty.create_session_tab_by_path("/tmp/test-project", "test-project", &[], &[], &[])?;
+
+        // The version is checked first, so kitty never sees a launch it would reject
+        let launch_calls = mock_executor.get_launch_calls();
+        assert_eq!(launch_calls.len(), 1);
+        assert!(launch_calls[0].vars.is_empty());
         assert_eq!(
             launch_calls[0].tab_title,
             Some("session:test-project".to_string())
         );
+        assert_eq!(mock_executor.layout.borrow().get_all_tabs().len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_kitty_mock_create_session_runs_on_create_in_first_window() -> Result<()> {
+        let mock_executor = MockExecutor::with_default_socket();
+        let kitty = Kitty::with_executor(&mock_executor);
+
+        kitty.create_session_tab_by_path(
+            "/tmp/test-project",
+            "test-project",
+            &[],
+            &["npm install".to_string()],
+            &[],
+        )?;
+
+        // The shell starts first, then the hooks are typed into it with the new tab's id
+        let launch_calls = mock_executor.get_launch_calls();
+        assert_eq!(launch_calls.len(), 1);
+        assert!(launch_calls[0].args.is_empty());
+        assert_eq!(launch_calls[0].env_value("KSM_SESSION_NAME"), None);
+
+        let tab = &mock_executor.get_tabs_for_session("test-project")[0];
+        let send_calls = mock_executor.get_send_text_calls();
+        assert_eq!(send_calls.len(), 1);
+        assert_eq!(
+            send_calls[0].match_pattern,
+            Some(format!("id:{}", tab.windows[0].id))
+        );
+        assert!(send_calls[0].from_stdin);
+        assert_eq!(
+            send_calls[0].text,
+            hooks::window_command(
+                &["npm install".to_string()],
+                &HookContext::new("test-project")
+                    .with_path("/tmp/test-project")
+                    .with_tab(tab.id)
+            )
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_kitty_mock_create_session_opens_windows_beside_shell() -> Result<()> {
+        let mock_executor = MockExecutor::with_default_socket();
+        let kitty = Kitty::with_executor(&mock_executor);
+
+        let editor = ProjectWindow {
+            command: vec!["nvim".to_string(), ".".to_string()],
+            title: Some("editor".to_string()),
+            location: Some(WindowLocation::Vsplit),
+            bias: Some(60.0),
+            hold: false,
+        };
+
+        kitty.create_session_tab_by_path(
+            "/tmp/test-project",
+            "test-project",
+            &[("AWS_PROFILE".to_string(), "dev".to_string())],
+            &[],
+            &[editor],
+        )?;
+
+        let launch_calls = mock_executor.get_launch_calls();
+        assert_eq!(launch_calls.len(), 2);
+        assert_eq!(launch_calls[1].launch_type, LaunchType::Window);
+        assert_eq!(launch_calls[1].next_to, Some("id:1".to_string()));
+        assert_eq!(launch_calls[1].location, Some(WindowLocation::Vsplit));
+        assert_eq!(launch_calls[1].bias, Some(60.0));
+        assert_eq!(launch_calls[1].args, vec!["nvim", "."]);
+        assert_eq!(launch_calls[1].env_value("AWS_PROFILE"), Some("dev"));
+
+        let tabs = mock_executor.get_tabs_for_session("test-project");
+        assert_eq!(tabs.len(), 1);
+        assert_eq!(tabs[0].windows.len(), 2);
+        assert_eq!(tabs[0].windows[1].title, "editor");
 
         Ok(())
     }
@@ -760,18 +1243,18 @@ mod tests {
 
         // Test navigation
         kitty.navigate_session_tab(session_context.clone(), TabNavigationDirection::Next, true)?;
-        assert_eq!(mock_executor.get_active_tab_id(), Some(2));
+        assert_eq!(mock_executor.get_active_tab_id(), Some(TabId(2)));
 
         kitty.navigate_session_tab(
             session_context.clone(),
             TabNavigationDirection::Previous,
             true,
         )?;
-        assert_eq!(mock_executor.get_active_tab_id(), Some(1));
+        assert_eq!(mock_executor.get_active_tab_id(), Some(TabId(1)));
 
         // Test no-wrap behavior
         kitty.navigate_session_tab(session_context, TabNavigationDirection::Previous, false)?;
-        assert_eq!(mock_executor.get_active_tab_id(), Some(1)); // Should stay on first tab
+        assert_eq!(mock_executor.get_active_tab_id(), Some(TabId(1))); // Should stay on first tab
 
         // Verify calls were made
         assert_eq!(mock_executor.navigate_tab_call_count(), 3);
@@ -851,26 +1334,31 @@ mod tests {
         let mock_executor = MockExecutor::with_default_socket();
 
         // Setup mock response for launch command
-        mock_executor.expect_launch_response(Ok(KittyCommandResult::success(
-            KittyLaunchResponse {
-                tab_id: None,
-                window_id: None,
-            },
-        )));
+        mock_executor.expect_launch_response(Ok(KittyLaunchResponse {
+            tab_id: None,
+            window_id: None,
+        }));
 
         let kitty = Kitty::with_executor(&mock_executor);
 
         // Test creating tab with session inheritance
-        kitty.create_tab_with_session_inheritance(Some("/tmp/test"), Some("Test Tab"))?;
+        kitty.create_tab_with_session_inheritance(
+            &SessionContext::new("test-project"),
+            Some("/tmp/test"),
+            Some("Test Tab"),
+            &[("AWS_PROFILE".to_string(), "dev".to_string())],
+        )?;
 
-        // Verify call was made
+        // Verify call was made, without listing tabs to detect the session again
         assert_eq!(mock_executor.launch_call_count(), 1);
+        assert_eq!(mock_executor.ls_call_count(), 0);
 
         // Verify call details
         let launch_calls = mock_executor.get_launch_calls();
         assert_eq!(launch_calls.len(), 1);
         assert_eq!(launch_calls[0].cwd, Some("/tmp/test".to_string()));
         assert_eq!(launch_calls[0].tab_title, Some("Test Tab".to_string()));
+        assert_eq!(launch_calls[0].env_value("AWS_PROFILE"), Some("dev"));
         assert!(launch_calls[0].inherit_session); // Should be true
 
         Ok(())
@@ -881,12 +1369,10 @@ mod tests {
         let mock_executor = MockExecutor::with_default_socket();
 
         // Setup mock response for launch command
-        mock_executor.expect_launch_response(Ok(KittyCommandResult::success(
-            KittyLaunchResponse {
-                tab_id: None,
-                window_id: None,
-            },
-        )));
+        mock_executor.expect_launch_response(Ok(KittyLaunchResponse {
+            tab_id: None,
+            window_id: None,
+        }));
 
         let kitty = Kitty::with_executor(&mock_executor);
 
@@ -982,7 +1468,7 @@ mod tests {
         let tab_id = mock_executor.add_session_tab("project1", Some("Project 1 Tab".to_string()));
 
         // Setup mock response for focus command
-        mock_executor.expect_focus_tab_response(Ok(KittyCommandResult::success_empty()));
+        mock_executor.expect_focus_tab_response(Ok(()));
 
         let kitty = Kitty::with_executor(&mock_executor);
 
@@ -1024,27 +1510,233 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_session_survives_retitle_through_user_var() -> Result<()> {
+        let mock_executor = MockExecutor::with_default_socket();
+        let kitty = Kitty::with_executor(&mock_executor);
+
+        kitty.create_session_tab_by_path("/tmp/api", "api", &[], &[], &[])?;
+        let launch_calls = mock_executor.get_launch_calls();
+        assert_eq!(
+            launch_calls[0].vars,
+            vec![(SESSION_USER_VAR.to_string(), "api".to_string())]
+        );
+
+        // A program retitles the tab and its environment marker is gone, e.g. after detaching
+        let tab_id = mock_executor.get_all_tabs()[0].id;
+        mock_executor.set_tab_title_by_id(tab_id, "vim src/main.rs");
+        for window in &mut mock_executor.layout.borrow_mut().os_windows[0].tabs[0].windows {
+            window.env.clear();
+        }
+
+        let session_tabs = kitty.get_session_tabs(&SessionContext::new("api"))?;
+        assert_eq!(session_tabs.len(), 1);
+        assert_eq!(kitty.list_sessions()?, vec![("api".to_string(), 1)]);
+        assert_eq!(
+            kitty.match_session_tab("api")?.map(|tab| tab.id),
+            Some(tab_id)
+        );
+        assert!(
+            kitty
+                .get_session_tabs(&SessionContext::unnamed())?
+                .is_empty()
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_session_tab_marks_every_window() -> Result<()> {
+        let mock_executor = MockExecutor::with_default_socket();
+        let tab_id = mock_executor.add_unnamed_tab(Some("session:web".to_string()));
+        mock_executor.add_window(tab_id);
+
+        let kitty = Kitty::with_executor(&mock_executor);
+        let tab = mock_executor.get_all_tabs()[0].clone();
+        kitty.tag_session_tab(&tab, "web")?;
+
+        let calls = mock_executor.get_set_user_vars_calls();
+        assert_eq!(calls[0].match_pattern.as_deref(), Some("id:1 or id:2"));
+
+        mock_executor.set_tab_title_by_id(tab_id, "htop");
+        let tabs = kitty.get_session_tabs(&SessionContext::new("web"))?;
+        assert_eq!(tabs.len(), 1);
+        assert!(
+            tabs[0]
+                .windows
+                .iter()
+                .all(|w| w.user_vars.get(SESSION_USER_VAR).map(String::as_str) == Some("web"))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tag_session_tab_is_not_tried_on_old_kitty() {
+        let mock_executor = MockExecutor::with_default_socket();
+        mock_executor.set_kitty_version(kitty_lib::KittyVersion::new(0, 26, 5));
+        mock_executor.add_unnamed_tab(Some("session:web".to_string()));
+
+        let kitty = Kitty::with_executor(&mock_executor);
+        let tab = mock_executor.get_all_tabs()[0].clone();
+        let error = kitty.tag_session_tab(&tab, "web").unwrap_err();
+
+        assert!(matches!(
+            error.downcast_ref::<KittyError>(),
+            Some(KittyError::Unsupported {
+                capability: Capability::UserVars,
+                ..
+            })
+        ));
+        assert_eq!(mock_executor.set_user_vars_call_count(), 0);
+    }
+
+    #[test]
+    fn test_detect_session_uses_injected_executor() -> Result<()> {
+        use crate::session::{KITTY_SESSION_PROJECT_ENV, KITTY_WINDOW_ID_ENV};
+        use crate::utils::test_utils::EnvGuard;
+
+        let window_guard = EnvGuard::new(KITTY_WINDOW_ID_ENV);
+        let session_guard = EnvGuard::new(KITTY_SESSION_PROJECT_ENV);
+        window_guard.set("4242");
+        session_guard.remove();
+
+        let mock_executor = MockExecutor::with_default_socket();
+        let kitty = Kitty::with_executor(&mock_executor);
+
+        // The invoking window lives in a background OS window
+        mock_executor.add_session_tab("focused", None);
+        let background_tab = mock_executor.add_session_tab("background", None);
+        {
+            let mut layout = mock_executor.layout.borrow_mut();
+            layout.detach_tab(background_tab);
+            layout.tab_mut(background_tab).unwrap().windows[0].id = WindowId(4242);
+        }
+
+        let context = kitty.detect_session()?;
+        assert_eq!(context.name(), "background");
+        assert_eq!(mock_executor.ls_call_count(), 1);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_session_falls_back_to_the_self_window() -> Result<()> {
+        use crate::session::{KITTY_SESSION_PROJECT_ENV, KITTY_WINDOW_ID_ENV};
+        use crate::utils::test_utils::EnvGuard;
+
+        let window_guard = EnvGuard::new(KITTY_WINDOW_ID_ENV);
+        let session_guard = EnvGuard::new(KITTY_SESSION_PROJECT_ENV);
+        window_guard.remove();
+        session_guard.remove();
+
+        let mock_executor = MockExecutor::with_default_socket();
+        let kitty = Kitty::with_executor(&mock_executor);
+
+        // kitty marks the caller's window, which is neither the first nor the focused tab
+        mock_executor.add_session_tab("first", None);
+        let invoking_tab = mock_executor.add_session_tab("invoking", None);
+        let focused_tab = mock_executor.add_session_tab("focused", None);
+        mock_executor.set_active_tab(focused_tab);
+        {
+            let mut layout = mock_executor.layout.borrow_mut();
+            let window_id = layout.tab(invoking_tab).unwrap().windows[0].id;
+            layout.set_self_window(window_id);
+        }
+
+        assert_eq!(kitty.detect_session()?.name(), "invoking");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_session_falls_back_to_the_focused_os_window() -> Result<()> {
+        use crate::session::{KITTY_SESSION_PROJECT_ENV, KITTY_WINDOW_ID_ENV};
+        use crate::utils::test_utils::EnvGuard;
+
+        let window_guard = EnvGuard::new(KITTY_WINDOW_ID_ENV);
+        let session_guard = EnvGuard::new(KITTY_SESSION_PROJECT_ENV);
+        window_guard.remove();
+        session_guard.remove();
+
+        let mock_executor = MockExecutor::with_default_socket();
+        let kitty = Kitty::with_executor(&mock_executor);
+
+        // Neither the invoking window nor kitty's self marker is known
+        mock_executor.add_session_tab("first", None);
+        let focused_tab = {
+            let mut layout = mock_executor.layout.borrow_mut();
+            let focused_tab = layout.add_os_window(Some("focused"), None);
+            layout.set_active_tab(focused_tab);
+            focused_tab
+        };
+        assert_ne!(mock_executor.get_all_tabs()[0].id, focused_tab);
+
+        assert_eq!(kitty.detect_session()?.name(), "focused");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_detect_session_fails_when_kitty_cannot_be_asked() {
+        let mock_executor = MockExecutor::with_default_socket();
+        mock_executor.expect_ls_response(Err(KittyError::SocketUnreachable {
+            socket: "unix:/tmp/kitty".to_string(),
+            exit_code: Some(1),
+            stderr: "Failed to connect".to_string(),
+        }));
+        let kitty = Kitty::with_executor(&mock_executor);
+
+        let error = kitty.detect_session().unwrap_err();
+        assert_eq!(
+            crate::exit_code::for_error(&error),
+            crate::exit_code::KITTY_UNREACHABLE
+        );
+    }
+
     #[test]
     fn test_next_session() -> Result<()> {
+        use crate::session::{KITTY_SESSION_PROJECT_ENV, KITTY_WINDOW_ID_ENV};
+        use crate::utils::test_utils::EnvGuard;
+
         let mock_executor = MockExecutor::with_default_socket();
 
         // Add tabs to different sessions
         mock_executor.add_session_tab("alpha", Some("Alpha Tab".to_string()));
-        let _beta_tab_id = mock_executor.add_session_tab("beta", Some("Beta Tab".to_string()));
-        mock_executor.add_session_tab("gamma", Some("Gamma Tab".to_string()));
+        let beta_tab_id = mock_executor.add_session_tab("beta", Some("Beta Tab".to_string()));
+        let gamma_tab_id = mock_executor.add_session_tab("gamma", Some("Gamma Tab".to_string()));
 
         let kitty = Kitty::with_executor(&mock_executor);
 
-        // Note: Since SessionContext::detect() uses real env vars, we can't easily test this
-        // without modifying the detection logic. This test would need dependency injection
-        // to be fully testable. For now, we'll test the list_sessions functionality.
-
         let sessions = kitty.list_sessions()?;
         assert_eq!(sessions.len(), 3);
         assert_eq!(sessions[0].0, "alpha");
         assert_eq!(sessions[1].0, "beta");
         assert_eq!(sessions[2].0, "gamma");
 
+        // Invoked from the beta tab, next_session moves on to gamma
+        let beta_window_id = mock_executor
+            .layout
+            .borrow()
+            .tab(beta_tab_id)
+            .unwrap()
+            .windows[0]
+            .id;
+        let window_guard = EnvGuard::new(KITTY_WINDOW_ID_ENV);
+        let session_guard = EnvGuard::new(KITTY_SESSION_PROJECT_ENV);
+        window_guard.set(&beta_window_id.to_string());
+        session_guard.remove();
+
+        let ls_calls_before = mock_executor.ls_call_count();
+        kitty.next_session()?;
+
+        // Listing, detection and switching all came from one snapshot
+        assert_eq!(mock_executor.ls_call_count(), ls_calls_before + 1);
+        assert_eq!(
+            mock_executor.get_focus_tab_calls().last().map(|c| c.tab_id),
+            Some(gamma_tab_id)
+        );
+
         Ok(())
     }
 
@@ -1052,16 +1744,28 @@ mod tests {
     fn test_session_utils_last_active_tracking() {
         // Test setting and getting last active tabs
         SessionUtils::set_last_active_tab("test-session", 42);
-        assert_eq!(SessionUtils::get_last_active_tab("test-session"), Some(42));
+        assert_eq!(
+            SessionUtils::get_last_active_tab("test-session"),
+            Some(TabId(42))
+        );
 
         // Test updating existing session
         SessionUtils::set_last_active_tab("test-session", 84);
-        assert_eq!(SessionUtils::get_last_active_tab("test-session"), Some(84));
+        assert_eq!(
+            SessionUtils::get_last_active_tab("test-session"),
+            Some(TabId(84))
+        );
 
         // Test different session
         SessionUtils::set_last_active_tab("other-session", 21);
-        assert_eq!(SessionUtils::get_last_active_tab("other-session"), Some(21));
-        assert_eq!(SessionUtils::get_last_active_tab("test-session"), Some(84)); // Should not affect
+        assert_eq!(
+            SessionUtils::get_last_active_tab("other-session"),
+            Some(TabId(21))
+        );
+        assert_eq!(
+            SessionUtils::get_last_active_tab("test-session"),
+            Some(TabId(84))
+        ); // Should not affect
 
         // Test non-existent session
         assert_eq!(SessionUtils::get_last_active_tab("non-existent"), None);
@@ -1069,7 +1773,10 @@ mod tests {
         // Test clearing
         SessionUtils::clear_last_active_tab("test-session");
         assert_eq!(SessionUtils::get_last_active_tab("test-session"), None);
-        assert_eq!(SessionUtils::get_last_active_tab("other-session"), Some(21)); // Should not affect
+        assert_eq!(
+            SessionUtils::get_last_active_tab("other-session"),
+            Some(TabId(21))
+        ); // Should not affect
 
         // Test tracked sessions
         SessionUtils::set_last_active_tab("session1", 1);
@@ -1079,4 +1786,94 @@ mod tests {
         assert!(tracked.contains(&"session2".to_string()));
         assert!(tracked.contains(&"other-session".to_string())); // From previous test
     }
+
+    #[test]
+    fn test_kitty_executor_socket_override() {
+        use crate::utils::test_utils::EnvGuard;
+
+        let socket_guard = EnvGuard::new(SOCKET_ENV);
+        socket_guard.set("unix:/tmp/ksm-from-env");
+
+        let from_flag = KittyConnection {
+            socket: Some("unix:/tmp/ksm-from-flag".to_string()),
+            ..KittyConnection::default()
+        };
+        assert_eq!(
+            kitty_executor(&from_flag).socket(),
+            Some("unix:/tmp/ksm-from-flag")
+        );
+        assert_eq!(
+            kitty_executor(&KittyConnection::default()).socket(),
+            Some("unix:/tmp/ksm-from-env")
+        );
+
+        socket_guard.remove();
+    }
+
+    #[test]
+    fn test_configure_applies_kitty_config() {
+        let executor = KittyExecutor::with_socket("unix:/tmp/kitty");
+
+        let configured = configure(executor.clone(), &KittyConfig::default());
+        assert_eq!(configured.timeout(), executor.timeout());
+        assert_eq!(configured.retry_policy(), RetryPolicy::default());
+
+        let config = KittyConfig {
+            timeout_ms: Some(0),
+            retries: Some(0),
+            retry_backoff_ms: None,
+        };
+        let configured = configure(executor.clone(), &config);
+        assert_eq!(configured.timeout(), None);
+        assert_eq!(configured.retry_policy().retries, 0);
+
+        let config = KittyConfig {
+            timeout_ms: Some(750),
+            retries: None,
+            retry_backoff_ms: Some(200),
+        };
+        let configured = configure(executor, &config);
+        assert_eq!(configured.timeout(), Some(Duration::from_millis(750)));
+        assert_eq!(
+            configured.retry_policy(),
+            RetryPolicy::new(RetryPolicy::default().retries, Duration::from_millis(200))
+        );
+    }
+
+    #[test]
+    fn test_kitty_connection_password_sources() {
+        use crate::utils::test_utils::EnvGuard;
+
+        let file_guard = EnvGuard::new(PASSWORD_FILE_ENV);
+        let password_guard = EnvGuard::new(PASSWORD_ENV);
+        file_guard.set("/run/ksm/env-password");
+        password_guard.set("secret");
+
+        let connection = KittyConnection {
+            password_file: Some(PathBuf::from("/run/ksm/flag-password")),
+            ..KittyConnection::default()
+        };
+        assert_eq!(
+            connection.password(),
+            Some(RemoteControlPassword::File(PathBuf::from(
+                "/run/ksm/flag-password"
+            )))
+        );
+
+        let connection = KittyConnection::default();
+        assert_eq!(
+            connection.password(),
+            Some(RemoteControlPassword::File(PathBuf::from(
+                "/run/ksm/env-password"
+            )))
+        );
+
+        file_guard.remove();
+        assert_eq!(
+            connection.password(),
+            Some(RemoteControlPassword::Env(PASSWORD_ENV.to_string()))
+        );
+
+        password_guard.remove();
+    }
 }
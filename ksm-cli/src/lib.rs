@@ -2,6 +2,8 @@ pub mod app;
 pub mod cli;
 pub mod cmd;
 pub mod config;
+pub mod exit_code;
+pub mod hooks;
 pub mod kitty;
 pub mod session;
 pub mod utils;
@@ -1,23 +1,35 @@
 use anyhow::Result;
 use clap::Parser;
+use kitty_lib::{CallTimer, CommandExecutor};
 use log::{debug, info};
 use std::path::PathBuf;
+use std::rc::Rc;
 
 use ksm::app::App;
 use ksm::cli::{Cli, Commands};
 use ksm::cmd::{
-    cmd_close_all_session_tabs, cmd_key, cmd_keys, cmd_list, cmd_new_tab, cmd_next_tab,
-    cmd_prev_tab, cmd_rename_tab, cmd_select,
+    cmd_broadcast, cmd_capture, cmd_close_all_session_tabs, cmd_exec, cmd_key, cmd_keys, cmd_list,
+    cmd_new_tab, cmd_next_tab, cmd_prev_tab, cmd_rename_tab, cmd_run, cmd_select, cmd_send,
 };
 use ksm::config::Config;
+use ksm::exit_code;
+use ksm::kitty::{Kitty, KittyConnection, timings_report};
 
 #[cfg(not(tarpaulin_include))]
-fn main() -> Result<()> {
+fn main() {
     // Initialize logger
     env_logger::init();
 
     info!("Starting ksm session manager");
 
+    if let Err(error) = run() {
+        eprintln!("Error: {:#}", error);
+        std::process::exit(exit_code::for_error(&error));
+    }
+}
+
+#[cfg(not(tarpaulin_include))]
+fn run() -> Result<()> {
     let cli = Cli::parse();
     debug!("Parsed CLI arguments: {:?}", cli);
 
@@ -25,45 +37,66 @@ fn main() -> Result<()> {
     let config_path = cli.config.map(PathBuf::from);
     let config = Config::load_from_path(config_path, cli.profile)?;
 
-    // Create App instance with config and Kitty
-    let app = App::new(config);
+    // Create App instance with config and Kitty on the requested socket
+    let connection = KittyConnection {
+        socket: cli.socket,
+        password_file: cli.password_file.map(PathBuf::from),
+        config: config.kitty_config(),
+    };
+    let timer = cli.timings.then(|| Rc::new(CallTimer::new()));
+    let result = if cli.dry_run {
+        let kitty = Kitty::dry_run(&connection)?.layer(timer.clone());
+        run_command(&App::with_kitty(config, kitty).with_dry_run(), cli.command)
+    } else {
+        let kitty = Kitty::connect(&connection).layer(timer.clone());
+        run_command(&App::with_kitty(config, kitty), cli.command)
+    };
 
-    match cli.command {
+    // Also on failure, where the timings tend to matter most
+    if let Some(timer) = timer {
+        eprint!("{}", timings_report(&timer.timings()));
+    }
+    result
+}
+
+#[cfg(not(tarpaulin_include))]
+fn run_command<E: CommandExecutor>(app: &App<E>, command: Option<Commands>) -> Result<()> {
+    match command {
         Some(Commands::List) => {
             info!("Listing sessions");
-            cmd_list(&app)
+            cmd_list(app)
         }
         Some(Commands::Key { key, path }) => {
             info!("Switching to project by key: {}", key);
-            cmd_key(&app, &key, path)?;
+            cmd_key(app, &key, path)?;
             if !path {
-                println!("Switched to session by key: {}", key);
+                app.report(format!("Switched to session by key: {}", key));
             }
             Ok(())
         }
         Some(Commands::Keys) => {
             info!("Listing all Keys");
-            cmd_keys(&app)
+            cmd_keys(app)
         }
         Some(Commands::Select) => {
             info!("Interactive project selection");
-            cmd_select(&app)
+            cmd_select(app)
         }
         Some(Commands::NextTab { no_wrap }) => {
             info!("Navigating to next tab in session");
             let no_wrap_option = if no_wrap { Some(true) } else { None };
-            cmd_next_tab(&app, no_wrap_option)?;
+            cmd_next_tab(app, no_wrap_option)?;
             Ok(())
         }
         Some(Commands::PrevTab { no_wrap }) => {
             info!("Navigating to previous tab in session");
             let no_wrap_option = if no_wrap { Some(true) } else { None };
-            cmd_prev_tab(&app, no_wrap_option)?;
+            cmd_prev_tab(app, no_wrap_option)?;
             Ok(())
         }
         Some(Commands::NewTab { cwd, title }) => {
             info!("Creating new tab with session inheritance");
-            cmd_new_tab(&app, cwd.as_deref(), title.as_deref())?;
+            cmd_new_tab(app, cwd.as_deref(), title.as_deref())?;
             Ok(())
         }
         Some(Commands::CloseAllSessionTabs { session, force }) => {
@@ -71,17 +104,62 @@ fn main() -> Result<()> {
                 "Closing all tabs in session{}",
                 if force { " (forced)" } else { "" }
             );
-            cmd_close_all_session_tabs(&app, session.as_deref(), force)?;
+            cmd_close_all_session_tabs(app, session.as_deref(), force)?;
+            Ok(())
+        }
+        Some(Commands::Exec {
+            session,
+            tab,
+            window,
+            keep_focus,
+            command,
+        }) => {
+            info!("Running {:?} in session: {}", command, session);
+            cmd_exec(app, &session, tab.as_deref(), window, keep_focus, &command)?;
+            Ok(())
+        }
+        Some(Commands::Run { task }) => {
+            info!("Running session task: {:?}", task);
+            cmd_run(app, task.as_deref())?;
+            Ok(())
+        }
+        Some(Commands::Send {
+            session,
+            text,
+            no_enter,
+        }) => {
+            info!("Sending text to session: {}", session);
+            cmd_send(app, &session, &text, no_enter)?;
+            Ok(())
+        }
+        Some(Commands::Broadcast {
+            text,
+            session,
+            exclude_active,
+            no_enter,
+        }) => {
+            info!("Broadcasting text to session windows");
+            cmd_broadcast(app, session.as_deref(), &text, exclude_active, no_enter)?;
+            Ok(())
+        }
+        Some(Commands::Capture {
+            session,
+            out,
+            extent,
+            ansi,
+        }) => {
+            info!("Capturing session window text");
+            cmd_capture(app, session.as_deref(), out.as_deref(), extent, ansi)?;
             Ok(())
         }
         Some(Commands::RenameTab { description }) => {
             info!("Renaming current tab to: {}", description);
-            cmd_rename_tab(&app, &description)?;
+            cmd_rename_tab(app, &description)?;
             Ok(())
         }
         None => {
             info!("No command specified, listing sessions");
-            cmd_list(&app)
+            cmd_list(app)
         }
     }
 }
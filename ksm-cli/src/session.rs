@@ -1,8 +1,15 @@
+use kitty_lib::{
+    CommandExecutor, KittyResult, KittySnapshot, KittyTab, SESSION_USER_VAR, SessionMarker, TabId,
+    WindowId, classify_tab, parse_session_title,
+};
+use log::warn;
 use std::env;
 
 /// The source of session identification
 #[derive(Debug, Clone, PartialEq)]
 pub enum SessionSource {
+    /// Session detected from the `ksm_session` user variable of a window in the tab
+    UserVar,
     /// Session detected from tab title with session: prefix
     TabTitle,
     /// Session detected from KITTY_SESSION_PROJECT environment variable
@@ -11,13 +18,27 @@ pub enum SessionSource {
     Default,
 }
 
+impl From<SessionMarker> for SessionSource {
+    fn from(marker: SessionMarker) -> Self {
+        match marker {
+            SessionMarker::UserVar => SessionSource::UserVar,
+            SessionMarker::TabTitle => SessionSource::TabTitle,
+            SessionMarker::Environment => SessionSource::Environment,
+        }
+    }
+}
+
 /// The environment variable used to identify the session/project context
 pub const KITTY_SESSION_PROJECT_ENV: &str = "KITTY_SESSION_PROJECT";
 
+/// The environment variable kitty sets to the id of the window a program runs in
+pub const KITTY_WINDOW_ID_ENV: &str = "KITTY_WINDOW_ID";
+
 /// The default session name for tabs created outside of any specific session
 pub const UNNAMED_SESSION: &str = "unnamed";
 
-/// Represents the current session context detected from tab titles or environment variables
+/// Represents the current session context detected from user variables, tab titles or environment
+/// variables
 #[derive(Debug, Clone, PartialEq)]
 pub struct SessionContext {
     /// The session/project name, or "unnamed" if no session context is found
@@ -29,32 +50,100 @@ pub struct SessionContext {
 }
 
 impl SessionContext {
-    /// Detects the current session context from tab title or environment variables
+    /// Detects the current session context through a fresh `KittyExecutor`, honouring
+    /// `KSM_KITTY_SOCKET` and the password variables
+    ///
+    /// Prefer `Kitty::detect_session`, which goes through the app's executor. When kitty
+    /// cannot be reached this warns and lets the `KITTY_SESSION_PROJECT` environment variable
+    /// decide on its own.
     pub fn detect() -> Self {
-        // Try tab title first
-        if let Some(session_name) = Self::detect_from_tab_title() {
-            return Self {
-                session_name,
-                is_explicit: true,
-                source: SessionSource::TabTitle,
-            };
-        }
+        let executor = crate::kitty::kitty_executor(&crate::kitty::KittyConnection::default());
+        Self::detect_with(&executor).unwrap_or_else(|e| {
+            warn!("Could not ask kitty for the current session: {}", e);
+            Self::detect_in(&KittySnapshot::default())
+        })
+    }
+
+    /// Detects the session of the window that invoked ksm, asking kitty through `executor`
+    ///
+    /// The invoking window comes from `KITTY_WINDOW_ID`. Fails when kitty can't be asked, so
+    /// an unreachable socket or a rejected password isn't mistaken for having no session.
+    pub fn detect_with<E: CommandExecutor>(executor: &E) -> KittyResult<Self> {
+        let snapshot = KittySnapshot::fetch(executor)?;
+        Ok(Self::detect_in(&snapshot))
+    }
+
+    /// Detects the session of the window that invoked ksm from an existing snapshot
+    pub fn detect_in(snapshot: &KittySnapshot) -> Self {
+        let window_id = env::var(KITTY_WINDOW_ID_ENV)
+            .ok()
+            .and_then(|id| id.trim().parse().ok());
+        let env_session = env::var(KITTY_SESSION_PROJECT_ENV).ok();
+
+        Self::from_snapshot(snapshot, window_id, env_session.as_deref())
+    }
+
+    /// Resolve the session context from a kitty snapshot
+    ///
+    /// The invoking tab is the one holding `window_id`, else the one holding the window kitty
+    /// marks `is_self`, else the active tab of the focused (or last focused) OS window. Within it
+    /// the invoking window's session user variable wins, then the tab's markers, then
+    /// `env_session`, then the `KITTY_SESSION_PROJECT` environment of the tab's windows.
+    pub fn from_snapshot(
+        snapshot: &KittySnapshot,
+        window_id: Option<WindowId>,
+        env_session: Option<&str>,
+    ) -> Self {
+        let tab = Self::invoking_tab(snapshot, window_id);
+
+        let window_var = tab
+            .and_then(|tab| {
+                tab.windows.iter().find(|window| match window_id {
+                    Some(id) => window.id == id,
+                    None => window.is_self,
+                })
+            })
+            .and_then(|window| window.user_vars.get(SESSION_USER_VAR))
+            .filter(|name| !name.is_empty())
+            .map(|name| (name.clone(), SessionSource::UserVar));
+
+        let tab_session = tab.and_then(|tab| snapshot.session_of(tab.id));
+        let (tab_marker, tab_env) = match tab_session {
+            Some((name, SessionMarker::Environment)) => {
+                (None, Some((name.to_string(), SessionSource::Environment)))
+            }
+            Some((name, marker)) => (Some((name.to_string(), marker.into())), None),
+            None => (None, None),
+        };
+
+        let env_session = env_session
+            .filter(|name| !name.is_empty())
+            .map(|name| (name.to_string(), SessionSource::Environment));
 
-        // Fall back to environment variable
-        match env::var(KITTY_SESSION_PROJECT_ENV) {
-            Ok(session_name) if !session_name.is_empty() => Self {
+        match window_var.or(tab_marker).or(env_session).or(tab_env) {
+            Some((session_name, source)) => Self {
                 session_name,
                 is_explicit: true,
-                source: SessionSource::Environment,
-            },
-            _ => Self {
-                session_name: UNNAMED_SESSION.to_string(),
-                is_explicit: false,
-                source: SessionSource::Default,
+                source,
             },
+            None => Self::unnamed(),
         }
     }
 
+    /// Find the tab ksm was invoked from, falling back to the focused OS window's active tab
+    fn invoking_tab(snapshot: &KittySnapshot, window_id: Option<WindowId>) -> Option<&KittyTab> {
+        let by_window = match window_id {
+            Some(id) => snapshot.tab_for_window(id),
+            None => snapshot.self_tab(),
+        };
+        if by_window.is_some() {
+            return by_window;
+        }
+
+        let focused_os_window = snapshot.focused_os_window()?;
+        snapshot.active_tab(focused_os_window.id)
+    }
+
     /// Creates a new explicit session context with the given name
     pub fn new(session_name: impl Into<String>) -> Self {
         Self {
@@ -83,46 +172,25 @@ impl SessionContext {
         &self.session_name
     }
 
-    /// Detects session from the current tab title by parsing the session: prefix
-    fn detect_from_tab_title() -> Option<String> {
-        use kitty_lib::commands::ls::KittenLsCommand;
-        use kitty_lib::executor::{CommandExecutor, KittyExecutor};
-
-        // Create a KittyExecutor to get the current tab info
-        let executor = KittyExecutor::new();
-
-        // Get current tab info
-        let ls_result = executor.ls(KittenLsCommand::new()).ok()?;
-
-        // Find the active tab across all OS windows
-        for os_window in ls_result {
-            for tab in os_window.tabs {
-                if tab.is_active {
-                    // Parse the tab title for session: prefix
-                    if let Some(session_name) = Self::parse_session_from_title(&tab.title) {
-                        return Some(session_name);
-                    }
-                }
-            }
+    /// The tabs of this session in a snapshot; for the unnamed session, the tabs outside every
+    /// session
+    pub fn tabs_in<'a>(&self, snapshot: &'a KittySnapshot) -> Vec<&'a KittyTab> {
+        if self.is_explicit {
+            snapshot.session_tabs(self.name())
+        } else {
+            snapshot.unnamed_tabs()
         }
+    }
 
-        None
+    /// Identify the session a tab belongs to: its windows' session user variable first, then a
+    /// `session:` title prefix, then the `KITTY_SESSION_PROJECT` environment of its windows
+    pub fn session_for_tab(tab: &KittyTab) -> Option<(String, SessionSource)> {
+        classify_tab(tab).map(|(session_name, marker)| (session_name, marker.into()))
     }
 
     /// Parses a session name from a tab title with the format "session:<name>[ - description]"
     pub fn parse_session_from_title(title: &str) -> Option<String> {
-        if let Some(after_prefix) = title.strip_prefix("session:") {
-            // Split on " - " to handle optional description
-            let session_name = after_prefix.split(" - ").next()?;
-
-            if !session_name.is_empty() {
-                Some(session_name.to_string())
-            } else {
-                None
-            }
-        } else {
-            None
-        }
+        parse_session_title(title).map(str::to_string)
     }
 }
 
@@ -130,9 +198,9 @@ use std::collections::HashMap;
 use std::sync::{OnceLock, RwLock};
 
 /// Last active tab tracking for sessions
-static LAST_ACTIVE_TABS: OnceLock<RwLock<HashMap<String, u32>>> = OnceLock::new();
+static LAST_ACTIVE_TABS: OnceLock<RwLock<HashMap<String, TabId>>> = OnceLock::new();
 
-fn get_last_active_tabs() -> &'static RwLock<HashMap<String, u32>> {
+fn get_last_active_tabs() -> &'static RwLock<HashMap<String, TabId>> {
     LAST_ACTIVE_TABS.get_or_init(|| RwLock::new(HashMap::new()))
 }
 
@@ -161,7 +229,8 @@ impl SessionUtils {
     }
 
     /// Record the last active tab for a session
-    pub fn set_last_active_tab(session_name: &str, tab_id: u32) {
+    pub fn set_last_active_tab(session_name: &str, tab_id: impl Into<TabId>) {
+        let tab_id = tab_id.into();
         if let Ok(mut tabs) = get_last_active_tabs().write() {
             tabs.insert(session_name.to_string(), tab_id);
             log::debug!(
@@ -173,13 +242,26 @@ impl SessionUtils {
     }
 
     /// Get the last active tab for a session
-    pub fn get_last_active_tab(session_name: &str) -> Option<u32> {
+    pub fn get_last_active_tab(session_name: &str) -> Option<TabId> {
         get_last_active_tabs()
             .read()
             .ok()
             .and_then(|tabs| tabs.get(session_name).copied())
     }
 
+    /// Pick the session tab focused most recently, falling back to the first one
+    pub fn last_active_tab<'a>(
+        session_name: &str,
+        session_tabs: &'a [kitty_lib::KittyTab],
+    ) -> Option<&'a kitty_lib::KittyTab> {
+        let last_active = Self::get_last_active_tab(session_name);
+
+        session_tabs
+            .iter()
+            .find(|tab| Some(tab.id) == last_active)
+            .or_else(|| session_tabs.first())
+    }
+
     /// Clear the last active tab tracking for a session (useful when session is deleted)
     pub fn clear_last_active_tab(session_name: &str) {
         if let Ok(mut tabs) = get_last_active_tabs().write() {
@@ -203,6 +285,7 @@ impl SessionUtils {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use kitty_lib::KittyOsWindow;
     use std::collections::HashMap as StdHashMap;
     use std::env;
     use std::sync::{Arc, Mutex};
@@ -289,6 +372,130 @@ mod tests {
         assert!(context.is_unnamed());
     }
 
+    fn tab_with(title: &str, var: Option<&str>, env: Option<&str>) -> KittyTab {
+        let mut window: kitty_lib::KittyWindow = serde_json::from_value(serde_json::json!({
+            "id": 1, "title": "shell", "pid": 1, "cwd": "/tmp", "cmdline": ["zsh"]
+        }))
+        .unwrap();
+        if let Some(name) = var {
+            window
+                .user_vars
+                .insert(SESSION_USER_VAR.to_string(), name.to_string());
+        }
+        if let Some(name) = env {
+            window
+                .env
+                .insert(KITTY_SESSION_PROJECT_ENV.to_string(), name.to_string());
+        }
+
+        serde_json::from_value(serde_json::json!({
+            "id": 1, "title": title, "windows": [window]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_session_for_tab_prefers_user_var() {
+        // A retitled tab keeps its session through the user variable
+        let tab = tab_with("vim src/main.rs", Some("api"), Some("legacy"));
+        assert_eq!(
+            SessionContext::session_for_tab(&tab),
+            Some(("api".to_string(), SessionSource::UserVar))
+        );
+
+        let tab = tab_with("session:web - server", None, Some("legacy"));
+        assert_eq!(
+            SessionContext::session_for_tab(&tab),
+            Some(("web".to_string(), SessionSource::TabTitle))
+        );
+
+        let tab = tab_with("zsh", Some(""), Some("legacy"));
+        assert_eq!(
+            SessionContext::session_for_tab(&tab),
+            Some(("legacy".to_string(), SessionSource::Environment))
+        );
+
+        assert_eq!(
+            SessionContext::session_for_tab(&tab_with("zsh", None, None)),
+            None
+        );
+    }
+
+    /// Two OS windows: the focused one shows the `web` session, the other the `api` session
+    /// whose active tab was retitled by a program
+    fn two_os_windows() -> Vec<KittyOsWindow> {
+        serde_json::from_value(serde_json::json!([
+            {
+                "id": 1, "is_focused": false, "last_focused": false,
+                "tabs": [{
+                    "id": 10, "title": "vim", "is_active": true, "is_focused": false,
+                    "windows": [
+                        {"id": 100, "title": "vim", "pid": 1, "cwd": "/srv/api", "cmdline": ["vim"],
+                         "user_vars": {"ksm_session": "api"}},
+                        {"id": 101, "title": "zsh", "pid": 2, "cwd": "/srv/api", "cmdline": ["zsh"],
+                         "user_vars": {"ksm_session": "api-tests"}}
+                    ]
+                }]
+            },
+            {
+                "id": 2, "is_focused": true, "last_focused": true,
+                "tabs": [
+                    {"id": 20, "title": "session:web", "is_active": true, "is_focused": true,
+                     "windows": [{"id": 200, "title": "zsh", "pid": 3, "cwd": "/srv/web", "cmdline": ["zsh"]}]},
+                    {"id": 21, "title": "scratch", "is_active": false, "is_focused": false,
+                     "windows": [{"id": 210, "title": "zsh", "pid": 4, "cwd": "/tmp", "cmdline": ["zsh"]}]}
+                ]
+            }
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_from_snapshot_uses_invoking_window() {
+        let snapshot = KittySnapshot::new(two_os_windows());
+
+        // The invoking window's own marker wins over the rest of its tab
+        let context = SessionContext::from_snapshot(&snapshot, Some(WindowId(101)), Some("stale"));
+        assert_eq!(context.session_name, "api-tests");
+        assert_eq!(context.source, SessionSource::UserVar);
+
+        // An unfocused OS window is still read correctly
+        let context = SessionContext::from_snapshot(&snapshot, Some(WindowId(100)), None);
+        assert_eq!(context.session_name, "api");
+
+        // A window in an unmarked tab falls back to the environment
+        let context = SessionContext::from_snapshot(&snapshot, Some(WindowId(210)), Some("legacy"));
+        assert_eq!(context.session_name, "legacy");
+        assert_eq!(context.source, SessionSource::Environment);
+
+        let context = SessionContext::from_snapshot(&snapshot, Some(WindowId(210)), None);
+        assert!(!context.is_explicit);
+    }
+
+    #[test]
+    fn test_from_snapshot_falls_back_to_focused_os_window() {
+        let mut os_windows = two_os_windows();
+        let snapshot = KittySnapshot::new(os_windows.clone());
+
+        // Unknown window: use the active tab of the focused OS window, not the first active tab
+        let context = SessionContext::from_snapshot(&snapshot, Some(WindowId(999)), None);
+        assert_eq!(context.session_name, "web");
+        assert_eq!(context.source, SessionSource::TabTitle);
+
+        // kitty itself unfocused: use the last focused OS window
+        os_windows[1].is_focused = false;
+        let snapshot = KittySnapshot::new(os_windows.clone());
+        let context = SessionContext::from_snapshot(&snapshot, None, None);
+        assert_eq!(context.session_name, "web");
+
+        // Nothing to go on: the environment, then unnamed
+        os_windows[1].last_focused = false;
+        let snapshot = KittySnapshot::new(os_windows);
+        let context = SessionContext::from_snapshot(&snapshot, None, Some("env-only"));
+        assert_eq!(context.session_name, "env-only");
+        assert!(SessionContext::from_snapshot(&KittySnapshot::default(), None, None).is_unnamed());
+    }
+
     #[test]
     fn test_session_context_new() {
         let context = SessionContext::new("my-project");
@@ -379,25 +586,25 @@ mod tests {
         SessionUtils::set_last_active_tab("test-session-1", 100);
         assert_eq!(
             SessionUtils::get_last_active_tab("test-session-1"),
-            Some(100)
+            Some(TabId(100))
         );
 
         // Test updating existing session
         SessionUtils::set_last_active_tab("test-session-1", 200);
         assert_eq!(
             SessionUtils::get_last_active_tab("test-session-1"),
-            Some(200)
+            Some(TabId(200))
         );
 
         // Test different session
         SessionUtils::set_last_active_tab("test-session-2", 300);
         assert_eq!(
             SessionUtils::get_last_active_tab("test-session-2"),
-            Some(300)
+            Some(TabId(300))
         );
         assert_eq!(
             SessionUtils::get_last_active_tab("test-session-1"),
-            Some(200)
+            Some(TabId(200))
         ); // Should not affect
 
         // Test non-existent session
@@ -408,7 +615,7 @@ mod tests {
         assert_eq!(SessionUtils::get_last_active_tab("test-session-1"), None);
         assert_eq!(
             SessionUtils::get_last_active_tab("test-session-2"),
-            Some(300)
+            Some(TabId(300))
         ); // Should not affect
 
         // Test getting tracked sessions
@@ -451,7 +658,7 @@ mod tests {
                 // Verify it was set correctly
                 assert_eq!(
                     SessionUtils::get_last_active_tab(&session_name),
-                    Some(tab_id)
+                    Some(TabId(tab_id))
                 );
 
                 // Clear it
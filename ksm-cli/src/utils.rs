@@ -124,9 +124,9 @@ pub fn navigate_tab<E: CommandExecutor>(
 pub mod test_utils {
     use crate::app::App;
     use crate::config::Config;
+    use crate::config::test_helpers::ConfigBuilder;
     use crate::kitty::Kitty;
     use assert_fs::TempDir;
-    use assert_fs::prelude::*;
     use kitty_lib::MockExecutor;
     use std::env;
 
@@ -164,9 +164,8 @@ pub mod test_utils {
 
     impl Drop for EnvGuard {
         fn drop(&mut self) {
-            match &self.original_value {
-                Some(value) => unsafe { env::set_var(&self.var_name, value) },
-                None => {} // Keep it unset
+            if let Some(value) = &self.original_value {
+                unsafe { env::set_var(&self.var_name, value) };
             }
             // Add delay after restoration to prevent race conditions
             std::thread::sleep(std::time::Duration::from_millis(200));
@@ -177,21 +176,20 @@ pub mod test_utils {
     /// Returns the app and temp_dir. The executor is embedded in the app.
     pub fn create_test_app_with_executor(
         mock_executor: &MockExecutor,
+    ) -> (App<&MockExecutor>, TempDir) {
+        create_test_app_with_config(mock_executor, ConfigBuilder::new())
+    }
+
+    /// Creates a test app with MockExecutor and the config `config` builds
+    pub fn create_test_app_with_config(
+        mock_executor: &MockExecutor,
+        config: ConfigBuilder,
     ) -> (App<&MockExecutor>, TempDir) {
         let kitty = Kitty::with_executor(mock_executor);
         let temp_dir = TempDir::new().unwrap();
 
-        let config_content = r#"[global]
-version = "1.0"
-
-[search]
-dirs = []
-vsc = []
-"#;
-        let config_file = temp_dir.child("test_config.toml");
-        config_file.write_str(config_content).unwrap();
-
-        let config = Config::load_from_path(Some(config_file.path().to_path_buf()), None).unwrap();
+        let config_file = config.write_to_temp_file(&temp_dir, "test_config.toml");
+        let config = Config::load_from_path(Some(config_file), None).unwrap();
         let app = App::with_kitty(config, kitty);
 
         (app, temp_dir)
@@ -224,7 +222,7 @@ vsc = []
 #[cfg(test)]
 mod tests {
     use super::*;
-    use kitty_lib::MockExecutor;
+    use kitty_lib::{MockExecutor, TabId};
     use std::env;
 
     #[test]
@@ -353,12 +351,12 @@ mod tests {
 
         // Test next tab navigation with default (config default is true)
         navigate_tab(&app, NavigationDirection::Next, None)?;
-        assert_eq!(mock_executor.get_active_tab_id(), Some(2));
+        assert_eq!(mock_executor.get_active_tab_id(), Some(TabId(2)));
 
         // Test next tab with explicit no-wrap from last tab
         mock_executor.set_active_tab(3);
         navigate_tab(&app, NavigationDirection::Next, Some(true))?;
-        assert_eq!(mock_executor.get_active_tab_id(), Some(3)); // Should stay on last tab
+        assert_eq!(mock_executor.get_active_tab_id(), Some(TabId(3))); // Should stay on last tab
 
         Ok(())
     }
@@ -380,11 +378,11 @@ mod tests {
 
         // Test previous tab navigation with default (config default is true)
         navigate_tab(&app, NavigationDirection::Previous, None)?;
-        assert_eq!(mock_executor.get_active_tab_id(), Some(1));
+        assert_eq!(mock_executor.get_active_tab_id(), Some(TabId(1)));
 
         // Test previous tab with explicit no-wrap from first tab
         navigate_tab(&app, NavigationDirection::Previous, Some(true))?;
-        assert_eq!(mock_executor.get_active_tab_id(), Some(1)); // Should stay on first tab
+        assert_eq!(mock_executor.get_active_tab_id(), Some(TabId(1))); // Should stay on first tab
 
         Ok(())
     }